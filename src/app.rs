@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::path::PathBuf;
 
 use ansi_term::Color;
 use rustyline::error::ReadlineError;
@@ -6,13 +7,16 @@ use rustyline::highlight::Highlighter;
 use rustyline::Editor;
 use rustyline_derive::{Completer, Helper, Hinter, Validator};
 
-use crate::cmd::{parse_command, to_executor, CommandInput, CommandOutput};
+use crate::cmd::{alias, history, parse_command, to_executor, CommandInput, CommandOutput};
 use crate::config::Config;
 use crate::error::Fallacy;
-use crate::state::State;
+use crate::state::{Journal, State};
+use crate::utils::today;
 
 pub struct App {
     config: Config,
+    /// Where the config was loaded from, to save aliases to.
+    config_path: Option<PathBuf>,
     state: State,
     editor: Editor<PromptHighlighter>,
 }
@@ -38,14 +42,9 @@ impl App {
     /// Initialize a new Reason app.
     pub fn init() -> Result<Self, Box<dyn std::error::Error>> {
         // Load reason configuration.
-        let mut config: Config = match home::home_dir() {
-            Some(mut p) => {
-                p.push(".config/reason/config.toml");
-                match confy::load_path(p) {
-                    Ok(config) => config,
-                    Err(e) => return Err(e.into()),
-                }
-            }
+        let config_path = home::home_dir().map(|home| home.join(".config/reason/config.toml"));
+        let mut config: Config = match config_path {
+            Some(ref p) => confy::load_path(p)?,
             None => {
                 eprintln!("Failed to find your home directory. Using default configuration.");
                 Config::default()
@@ -56,13 +55,20 @@ impl App {
         config.validate()?;
 
         // Load metadata state.
-        let state = State::load(&config.storage.paper_metadata)?;
+        let mut state = State::load(&config.storage.paper_metadata)?;
+
+        // Load undo snapshots, or start afresh.
+        let storage = &config.storage;
+        state.journal = match storage.undo_journal {
+            Some(ref path) => Journal::load(path, storage.undo_depth)?,
+            None => Journal::new(storage.undo_depth),
+        };
 
         // Setup readline.
         let builder = rustyline::config::Builder::default();
         let rlconfig = builder
             .max_history_size(config.storage.max_history_size)
-            .auto_add_history(true)
+            .history_ignore_dups(true)
             .build();
         let mut editor = Editor::with_config(rlconfig);
         editor.set_helper(Some(PromptHighlighter {}));
@@ -85,6 +91,7 @@ impl App {
 
         Ok(Self {
             config,
+            config_path,
             state,
             editor,
         })
@@ -106,12 +113,39 @@ impl App {
             return Ok(());
         }
 
+        // Remind of papers not read by their due date.
+        if self.config.general.remind_overdue {
+            let today = today();
+            let overdue = self
+                .state
+                .papers
+                .iter()
+                .filter(|paper| !paper.archived && paper.is_overdue(today))
+                .count();
+            if overdue > 0 {
+                println!(
+                    "{} {} overdue. `ls overdue` lists {}.",
+                    overdue,
+                    if overdue != 1 {
+                        "papers are"
+                    } else {
+                        "paper is"
+                    },
+                    if overdue != 1 { "them" } else { "it" },
+                );
+            }
+        }
+
         // Run the main loop.
         let mut run = true;
         while run {
-            let readline = self.editor.readline(">> ");
+            let readline = self
+                .editor
+                .readline(">> ")
+                .map(|line| self.expand_history(line));
             match readline {
-                Ok(line) => match self.execute(&line) {
+                Ok(Err(e)) => println!("{}", e),
+                Ok(Ok(line)) => match self.execute(&line) {
                     Ok(msg) => print!("{}", msg),
                     Err(Fallacy::ExitReason) => run = false,
                     Err(e) => println!("{}", e),
@@ -129,6 +163,11 @@ impl App {
             if let Err(e) = self.state.store(&self.config.storage.paper_metadata) {
                 eprintln!("Could not save paper metadata: {}", e);
             }
+            if let Some(ref path) = self.config.storage.undo_journal {
+                if let Err(e) = self.state.journal.store(path) {
+                    eprintln!("Could not save undo journal: {}", e);
+                }
+            }
         }
 
         Ok(())
@@ -143,6 +182,11 @@ impl App {
         if let Err(e) = self.state.store(&self.config.storage.paper_metadata) {
             eprintln!("Error during teardown: {}", e);
         }
+        if let Some(ref path) = self.config.storage.undo_journal {
+            if let Err(e) = self.state.journal.store(path) {
+                eprintln!("Error during teardown: {}", e);
+            }
+        }
 
         // Save command history to history file.
         let history_path = &self.config.storage.command_history;
@@ -160,19 +204,104 @@ impl App {
                 "Error during teardown: {}",
                 Fallacy::RLHistoryStoreFailed(history_path.to_owned(), e)
             );
-            return;
         }
     }
 
+    /// Expand `!!` and `!N` at the start of the line and remember the
+    /// line in history.
+    fn expand_history(&mut self, line: String) -> Result<String, Fallacy> {
+        // Lines read without a terminal keep their newline.
+        let line = line.trim_end_matches(['\n', '\r']).to_owned();
+        let entries: Vec<&str> = self.editor.history().iter().map(String::as_str).collect();
+        let line = match history::expand(&line, &entries)? {
+            Some(expanded) => {
+                println!("{}", expanded);
+                expanded
+            }
+            None => line,
+        };
+        self.editor.add_history_entry(line.as_str());
+        Ok(line)
+    }
+
+    /// List or clear the command history of the line editor.
+    fn history(&mut self, args: &[String]) -> Result<CommandOutput, Fallacy> {
+        match args.get(1).map(String::as_str) {
+            None => {
+                let entries: Vec<&str> = self.editor.history().iter().map(String::as_str).collect();
+                Ok(CommandOutput::Message(history::list(&entries)))
+            }
+            Some("clear") if args.len() == 2 => {
+                self.editor.history_mut().clear();
+                let history_path = &self.config.storage.command_history;
+                if let Err(e) = self.editor.save_history(history_path) {
+                    return Err(Fallacy::RLHistoryStoreFailed(history_path.to_owned(), e));
+                }
+                Ok(CommandOutput::Message(
+                    "Cleared command history.\n".to_owned(),
+                ))
+            }
+            _ => Err(Fallacy::InvalidCommand(
+                "`history` expects nothing or 'clear'.".to_owned(),
+            )),
+        }
+    }
+
+    /// List, show, define, or remove aliases.
+    fn alias(&mut self, args: &[String]) -> Result<CommandOutput, Fallacy> {
+        let aliases = &mut self.config.aliases;
+        let message = match args.get(1).map(String::as_str) {
+            None => return Ok(CommandOutput::Message(alias::list(aliases))),
+            Some("-d") | Some("--delete") if args.len() == 3 => {
+                if aliases.remove(&args[2]).is_none() {
+                    return Err(Fallacy::AliasUnknown(args[2].clone()));
+                }
+                format!("Removed alias '{}'.\n", args[2])
+            }
+            Some(name) if args.len() == 2 => match aliases.get(name) {
+                Some(value) => return Ok(CommandOutput::Message(format!("{}\n", value))),
+                None => return Err(Fallacy::AliasUnknown(name.to_owned())),
+            },
+            Some(name) => {
+                // A single argument is the command line itself, as in
+                // `alias backlog 'ls unread'`.
+                let value = match &args[2..] {
+                    [value] => value.clone(),
+                    values => alias::quote(values),
+                };
+                alias::check(name, &value, aliases)?;
+                aliases.insert(name.to_owned(), value);
+                format!("Defined alias '{}'.\n", name)
+            }
+        };
+
+        // Save only the aliases, keeping the rest of the file as written.
+        match self.config_path {
+            Some(ref path) => {
+                let mut stored: Config = confy::load_path(path)?;
+                stored.aliases = self.config.aliases.clone();
+                if let Err(e) = confy::store_path(path, stored) {
+                    return Err(Fallacy::AliasStoreFailed(e));
+                }
+            }
+            None => println!("Aliases are forgotten when reason exits, without a config file."),
+        }
+        Ok(CommandOutput::Message(message))
+    }
+
     /// Runs a command entered by the user and returns a success or error message.
     /// The command may mutate the current state object.
     pub fn execute(&mut self, command: &str) -> Result<String, Fallacy> {
-        // Parse the command.
+        // Parse the command and expand aliases.
         let commands = parse_command(command)?;
+        let commands = alias::expand(commands, &self.config.aliases)?;
 
         // Run the command.
-        self.run_command(commands)
-            .map(|output| output.into_string(&self.state, &self.config))
+        let output = self.run_command(commands);
+
+        // Commands may have added, removed, or changed papers.
+        self.state.resolve_citekeys();
+        output.map(|output| output.into_string(&self.state, &self.config))
     }
 
     fn run_command(&mut self, mut commands: Vec<Vec<String>>) -> Result<CommandOutput, Fallacy> {
@@ -190,6 +319,14 @@ impl App {
                 if commands[0][0] == "#" {
                     return Ok(CommandOutput::None);
                 }
+                // The line editor keeps history.
+                if commands[0][0] == "history" {
+                    return self.history(&commands[0]);
+                }
+                // The config keeps aliases.
+                if commands[0][0] == "alias" {
+                    return self.alias(&commands[0]);
+                }
                 let executor = to_executor(commands[0][0].clone())?;
                 let input = CommandInput {
                     args: commands.remove(0),
@@ -218,3 +355,46 @@ impl App {
         Ok(output)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, ReadingProgress};
+
+    #[test]
+    fn define_and_run_aliases() {
+        let paper = |title: &str, author: &str, venue: &str, progress| Paper {
+            title: title.to_owned(),
+            authors: vec![author.to_owned()],
+            venue: venue.to_owned(),
+            progress,
+            ..Default::default()
+        };
+        let mut app = App {
+            config: Config::default(),
+            config_path: None,
+            state: State::from_papers(vec![
+                paper("Reason", "Jae-Won Chung", "OSDI", ReadingProgress::Unread),
+                paper("Zeus", "Jae-Won Chung", "NSDI", ReadingProgress::Read),
+                paper("Shadowtutor", "Ahn", "OSDI", ReadingProgress::Read),
+            ]),
+            editor: Editor::new(),
+        };
+
+        // A single argument is the command line itself.
+        app.execute("alias backlog 'ls unread'").unwrap();
+        assert_eq!(app.config.aliases["backlog"], "ls unread");
+        let listed = app.execute("backlog at OSDI").unwrap();
+        assert!(listed.contains("Reason") && !listed.contains("Shadowtutor"));
+
+        // Several arguments keep their quotes.
+        app.execute(r#"alias dl ls by "Jae-Won Chung""#).unwrap();
+        let listed = app.execute("dl at NSDI").unwrap();
+        assert!(listed.contains("Zeus") && !listed.contains("Reason"));
+
+        assert!(matches!(
+            app.execute("alias oops 'lss unread'"),
+            Err(Fallacy::AliasNotCommand(name, first)) if name == "oops" && first == "lss"
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+//! A small BibTeX reader that is just enough to turn entries exported
+//! from reference managers into papers, and a writer for the other way.
+
+use std::collections::HashMap;
+
+use crate::error::Fallacy;
+
+/// A single BibTeX entry, like `@inproceedings{key, title = {...}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// The entry type in lowercase, e.g. 'article'.
+    pub kind: String,
+    /// The citation key.
+    pub key: String,
+    /// Field names in lowercase, with values still in LaTeX.
+    /// `@string` macros are expanded and `#` concatenations are joined.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Entry {
+    /// The LaTeX value of the given field, if present.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse every entry in the input. `@comment`, `@preamble`, and `@string`
+/// are not entries. A malformed entry does not prevent later entries from
+/// being parsed.
+pub fn parse(input: &str) -> Vec<Result<Entry, Fallacy>> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        strings: HashMap::new(),
+    };
+    let mut entries = Vec::new();
+    while parser.skip_to_entry() {
+        let kind = parser.identifier().to_lowercase();
+        let result = match kind.as_ref() {
+            "comment" | "preamble" => {
+                parser.skip_block();
+                continue;
+            }
+            "string" => match parser.string_macro() {
+                Ok(()) => continue,
+                Err(e) => Err(e),
+            },
+            "" => Err(Fallacy::BibtexInvalidEntry(
+                "expected an entry type after '@'".to_owned(),
+            )),
+            _ => parser.entry(kind),
+        };
+        entries.push(result);
+    }
+    entries
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    strings: HashMap<String, String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Move past the next '@' that starts an entry. Returns false at the end
+    /// of input. Text between entries is a comment in BibTeX.
+    fn skip_to_entry(&mut self) -> bool {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '@' {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn identifier(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || "_-:.+/".contains(c))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Skip a braced or parenthesized block, like the body of `@comment`.
+    fn skip_block(&mut self) {
+        self.skip_whitespace();
+        let close = match self.peek() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return,
+        };
+        let open = self.chars[self.pos];
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the closing delimiter of an entry body.
+    fn open_body(&mut self, context: &str) -> Result<char, Fallacy> {
+        self.skip_whitespace();
+        let close = match self.peek() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => {
+                return Err(Fallacy::BibtexInvalidEntry(format!(
+                    "expected '{{' after '@{}'",
+                    context
+                )))
+            }
+        };
+        self.pos += 1;
+        Ok(close)
+    }
+
+    /// Parse `@string{name = value}`.
+    fn string_macro(&mut self) -> Result<(), Fallacy> {
+        let close = self.open_body("string")?;
+        let name = self.identifier().to_lowercase();
+        self.skip_whitespace();
+        if self.peek() != Some('=') {
+            return Err(Fallacy::BibtexInvalidField(
+                name,
+                "expected '=' in @string".to_owned(),
+            ));
+        }
+        self.pos += 1;
+        let value = self.value(&name)?;
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+        }
+        self.strings.insert(name, value);
+        Ok(())
+    }
+
+    fn entry(&mut self, kind: String) -> Result<Entry, Fallacy> {
+        let close = self.open_body(&kind)?;
+
+        // The citation key.
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != ',' && c != close) {
+            self.pos += 1;
+        }
+        let key: String = self.chars[start..self.pos].iter().collect();
+        let key = key.trim().to_owned();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(Fallacy::BibtexInvalidEntry(format!(
+                "missing or invalid citation key in '@{}'",
+                kind
+            )));
+        }
+
+        // Fields.
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => {
+                    return Err(Fallacy::BibtexInvalidEntry(format!(
+                        "entry '{}' is not closed",
+                        key
+                    )))
+                }
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some(_) => {}
+            }
+            let name = self.identifier().to_lowercase();
+            if name.is_empty() {
+                return Err(Fallacy::BibtexInvalidEntry(format!(
+                    "unexpected '{}' in entry '{}'",
+                    self.peek().unwrap(),
+                    key
+                )));
+            }
+            self.skip_whitespace();
+            if self.peek() != Some('=') {
+                return Err(Fallacy::BibtexInvalidField(name, "expected '='".to_owned()));
+            }
+            self.pos += 1;
+            let value = self.value(&name)?;
+            fields.push((name, value));
+        }
+
+        Ok(Entry { kind, key, fields })
+    }
+
+    /// Parse a field value: braced or quoted text, a number, or a macro
+    /// name, possibly concatenated with '#'.
+    fn value(&mut self, field: &str) -> Result<String, Fallacy> {
+        let invalid =
+            |reason: &str| Fallacy::BibtexInvalidField(field.to_owned(), reason.to_owned());
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => {
+                    self.pos += 1;
+                    value.push_str(
+                        &self
+                            .delimited('}')
+                            .ok_or_else(|| invalid("unbalanced braces"))?,
+                    );
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    value.push_str(
+                        &self
+                            .delimited('"')
+                            .ok_or_else(|| invalid("unterminated quote"))?,
+                    );
+                }
+                Some(c) if c.is_alphanumeric() => {
+                    let name = self.identifier();
+                    if name.chars().all(|c| c.is_ascii_digit()) {
+                        value.push_str(&name);
+                    } else {
+                        match self.strings.get(&name.to_lowercase()) {
+                            Some(expanded) => value.push_str(expanded),
+                            // Unknown macros, like month names, are kept as is.
+                            None => value.push_str(&name),
+                        }
+                    }
+                }
+                _ => return Err(invalid("missing value")),
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.pos += 1;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Read until the closing delimiter outside of nested braces. The
+    /// opening delimiter is already consumed. Returns `None` on end of input.
+    fn delimited(&mut self, close: char) -> Option<String> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => self.pos += 1,
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                _ if c == close && depth == 0 => {
+                    let text = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Some(text);
+                }
+                '}' => return None,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+/// Split a LaTeX value on a separator word like 'and', outside of braces.
+pub fn split_words(value: &str, separator: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    for word in value.split_whitespace() {
+        if depth == 0 && word.eq_ignore_ascii_case(separator) {
+            parts.push(String::new());
+            continue;
+        }
+        for c in word.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        let part = parts.last_mut().unwrap();
+        if !part.is_empty() {
+            part.push(' ');
+        }
+        part.push_str(word);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Turn a LaTeX value into plain text: accents become accented letters,
+/// escaped characters are unescaped, braces are removed, and whitespace is
+/// collapsed.
+pub fn to_text(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '{' | '}' => {}
+            '~' => text.push(' '),
+            '\\' => {
+                // The command name: a single symbol or a run of letters.
+                let start = i;
+                if i < chars.len() && !chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                } else {
+                    while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                }
+                let command: String = chars[start..i].iter().collect();
+                if let Some(symbol) = symbol(&command) {
+                    text.push_str(symbol);
+                    // Letter commands swallow the following space.
+                    if command.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                        while i < chars.len() && chars[i] == ' ' {
+                            i += 1;
+                        }
+                    }
+                } else if is_accent(&command) {
+                    // The accented letter, like '\"o', '\"{o}', or '\'{\i}'.
+                    while i < chars.len() && chars[i] == ' ' {
+                        i += 1;
+                    }
+                    let braced = i < chars.len() && chars[i] == '{';
+                    if braced {
+                        i += 1;
+                    }
+                    if i + 1 < chars.len() && chars[i] == '\\' && "ij".contains(chars[i + 1]) {
+                        i += 1;
+                    }
+                    if let Some(&base) = chars.get(i) {
+                        i += 1;
+                        text.push(accent(&command, base));
+                    }
+                    if braced && chars.get(i) == Some(&'}') {
+                        i += 1;
+                    }
+                } else {
+                    // Formatting commands like '\emph' keep only their argument.
+                    while i < chars.len() && chars[i] == ' ' {
+                        i += 1;
+                    }
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn symbol(command: &str) -> Option<&'static str> {
+    Some(match command {
+        "&" => "&",
+        "%" => "%",
+        "_" => "_",
+        "$" => "$",
+        "#" => "#",
+        "{" => "{",
+        "}" => "}",
+        " " => " ",
+        "ss" => "ß",
+        "o" => "ø",
+        "O" => "Ø",
+        "aa" => "å",
+        "AA" => "Å",
+        "ae" => "æ",
+        "AE" => "Æ",
+        "oe" => "œ",
+        "OE" => "Œ",
+        "l" => "ł",
+        "L" => "Ł",
+        "i" => "i",
+        "j" => "j",
+        _ => return None,
+    })
+}
+
+fn is_accent(command: &str) -> bool {
+    matches!(
+        command,
+        "\"" | "'" | "`" | "^" | "~" | "=" | "." | "c" | "v" | "u" | "H" | "r"
+    )
+}
+
+/// Compose an accent with a letter. Letters that have no precomposed
+/// form with the accent are left as they are.
+fn accent(command: &str, base: char) -> char {
+    let table = match command {
+        "\"" => "aäeëiïoöuüyÿAÄEËIÏOÖUÜYŸ",
+        "'" => "aáeéiíoóuúyýcćnńsśzźAÁEÉIÍOÓUÚYÝCĆNŃSŚZŹ",
+        "`" => "aàeèiìoòuùAÀEÈIÌOÒUÙ",
+        "^" => "aâeêiîoôuûAÂEÊIÎOÔUÛ",
+        "~" => "aãnñoõAÃNÑOÕ",
+        "=" => "aāeēiīoōuūAĀEĒIĪOŌUŪ",
+        "." => "zżeėZŻEĖ",
+        "c" => "cçsşCÇSŞ",
+        "v" => "cčsšzžrřeěnňCČSŠZŽRŘEĚNŇ",
+        "u" => "aăgğAĂGĞ",
+        "H" => "oőuűOŐUŰ",
+        "r" => "aåuůAÅUŮ",
+        _ => "",
+    };
+    let table: Vec<char> = table.chars().collect();
+    table
+        .chunks(2)
+        .find(|pair| pair[0] == base)
+        .map(|pair| pair[1])
+        .unwrap_or(base)
+}
+
+impl std::fmt::Display for Entry {
+    /// Write the entry with one field per line. Values are written as they
+    /// are, so they should already be LaTeX.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "@{}{{{},", self.kind, self.key)?;
+        for (name, value) in self.fields.iter() {
+            writeln!(f, "  {} = {{{}}},", name, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Turn plain text into a LaTeX value by escaping special characters.
+pub fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '~' => ret.push_str("\\textasciitilde{}"),
+            '^' => ret.push_str("\\textasciicircum{}"),
+            '\\' => ret.push_str("\\textbackslash{}"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let input = r#"
+            @string{osdi = "USENIX OSDI"}
+            @comment{ignored {entirely}}
+            @InProceedings{chung2022reason,
+              title = {{Reason}: A Shell for {R}esearch Papers},
+              booktitle = osdi # " 2022",
+              year = 2022,
+            }
+            @misc(broken, title = "unterminated)
+        "#;
+        let entries = parse(input);
+        assert_eq!(entries.len(), 2);
+        let entry = entries[0].as_ref().unwrap();
+        assert_eq!(entry.kind, "inproceedings");
+        assert_eq!(entry.key, "chung2022reason");
+        assert_eq!(entry.get("booktitle"), Some("USENIX OSDI 2022"));
+        assert_eq!(entry.get("year"), Some("2022"));
+        assert_eq!(
+            to_text(entry.get("title").unwrap()),
+            "Reason: A Shell for Research Papers"
+        );
+        assert!(matches!(
+            &entries[1],
+            Err(Fallacy::BibtexInvalidField(field, _)) if field == "title"
+        ));
+    }
+
+    #[test]
+    fn latex_text() {
+        assert_eq!(to_text(r#"G{\"o}del and Erd\H{o}s"#), "Gödel and Erdős");
+        assert_eq!(to_text(r"Fran\c{c}ois Mus\'{\i}c \ss"), "François Musíc ß");
+        assert_eq!(to_text(r"R\&D at 100\% \emph{now}"), "R&D at 100% now");
+        assert_eq!(
+            split_words("Smith, John and {Barnes and Noble} AND Doe", "and"),
+            vec!["Smith, John", "{Barnes and Noble}", "Doe"]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Aliases live in the config, which only the app can change, so `alias`
+//! itself is run by the app. This module has its manual and the parts that
+//! do not change the config.
+use std::collections::BTreeMap;
+
+use crate::cmd::prelude::*;
+use crate::cmd::{parse_command, to_executor};
+
+pub static MAN: &str = include_str!("../../man/alias.md");
+
+/// `alias` changes the config, which commands cannot. Alone, it is run by
+/// the app before getting here.
+pub fn execute(
+    _input: CommandInput,
+    _state: &mut State,
+    _config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    Err(Fallacy::InvalidCommand(
+        "`alias` cannot be used with pipes.".to_owned(),
+    ))
+}
+
+/// Check that `name` can be an alias of `value`: the name is a single word
+/// that is not a command, the value is a single command that starts with a
+/// command or another alias, and expanding it does not lead back to the name.
+pub fn check(name: &str, value: &str, aliases: &BTreeMap<String, String>) -> Result<(), Fallacy> {
+    if name.is_empty() || name.chars().any(char::is_whitespace) || name.starts_with(['!', '#']) {
+        return Err(Fallacy::InvalidCommand(format!(
+            "Invalid alias name: '{}'. Names are single words that do not start with '!' or '#'.",
+            name
+        )));
+    }
+    if to_executor(name.to_owned()).is_ok() {
+        return Err(Fallacy::AliasShadowsCommand(name.to_owned()));
+    }
+    let mut aliases = aliases.clone();
+    aliases.insert(name.to_owned(), value.to_owned());
+    let expanded = expand_one(vec![name.to_owned()], &aliases)?;
+    match to_executor(expanded[0].clone()) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(Fallacy::AliasNotCommand(
+            name.to_owned(),
+            expanded[0].clone(),
+        )),
+    }
+}
+
+/// Expand aliases at the start of each command, with the rest of the
+/// arguments appended, as in `backlog at OSDI`.
+pub fn expand(
+    commands: Vec<Vec<String>>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<Vec<String>>, Fallacy> {
+    if aliases.is_empty() {
+        return Ok(commands);
+    }
+    commands
+        .into_iter()
+        .map(|command| expand_one(command, aliases))
+        .collect()
+}
+
+fn expand_one(
+    mut command: Vec<String>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<String>, Fallacy> {
+    let mut seen: Vec<String> = Vec::new();
+    while let Some(value) = command.first().and_then(|name| aliases.get(name)) {
+        let name = command.remove(0);
+        if seen.contains(&name) {
+            return Err(Fallacy::AliasRecursive(seen.remove(0)));
+        }
+        let mut expanded = parse_command(value)?;
+        if expanded.len() != 1 || expanded[0].is_empty() {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Alias '{}' should be a single command without pipes, but is '{}'.",
+                name, value
+            )));
+        }
+        seen.push(name);
+        let mut expanded = expanded.remove(0);
+        expanded.append(&mut command);
+        command = expanded;
+    }
+    Ok(command)
+}
+
+/// Join parsed arguments back into a command line that parses to the same
+/// arguments. Arguments that are empty or have whitespace, quotes, or pipes
+/// are quoted.
+pub fn quote(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote_one(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The argument in double quotes if it needs them. A backslash escapes a
+/// quote after it, so one before a quote is doubled, and trailing backslashes
+/// go after the closing quote.
+fn quote_one(arg: &str) -> String {
+    let special = |c: char| c.is_whitespace() || matches!(c, '\'' | '"' | '|');
+    if !arg.is_empty() && !arg.contains(special) {
+        return arg.to_owned();
+    }
+    let body = arg.trim_end_matches('\\');
+    let mut ret = String::from('"');
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' if chars.peek() == Some(&'\'') => ret.push_str("\\\\"),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret + &arg[body.len()..]
+}
+
+/// One alias per line, like `backlog = ls unread`.
+pub fn list(aliases: &BTreeMap<String, String>) -> String {
+    if aliases.is_empty() {
+        return "No aliases are defined.\n".to_owned();
+    }
+    let width = aliases
+        .keys()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    aliases
+        .iter()
+        .map(|(name, value)| format!("{:width$} = {}\n", name, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn args(line: &str) -> Vec<Vec<String>> {
+        parse_command(line).unwrap()
+    }
+
+    #[test]
+    fn expand_aliases() {
+        let defined = aliases(&[
+            ("backlog", "ls unread"),
+            ("osdi", "backlog at OSDI"),
+            ("deep", "ls 'deep learning'"),
+        ]);
+        let expand = |line: &str| expand(args(line), &defined).unwrap();
+        assert_eq!(expand("backlog at OSDI"), args("ls unread at OSDI"));
+        assert_eq!(expand("osdi in 2022"), args("ls unread at OSDI in 2022"));
+        assert_eq!(
+            expand("deep | backlog"),
+            args("ls 'deep learning' | ls unread")
+        );
+        // Only the command name is expanded.
+        assert_eq!(expand("ls backlog"), args("ls backlog"));
+
+        let looping = aliases(&[("a", "b x"), ("b", "a y")]);
+        assert!(matches!(
+            super::expand(args("a"), &looping),
+            Err(Fallacy::AliasRecursive(name)) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn check_definitions() {
+        let defined = aliases(&[("backlog", "ls unread")]);
+        assert!(check("osdi", "backlog at OSDI", &defined).is_ok());
+        assert!(matches!(
+            check("ls", "ls -l", &defined),
+            Err(Fallacy::AliasShadowsCommand(_))
+        ));
+        assert!(matches!(
+            check("again", "again -l", &defined),
+            Err(Fallacy::AliasRecursive(_))
+        ));
+        assert!(matches!(
+            check("two", "ls | wc", &defined),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+        assert!(matches!(
+            check("two words", "ls", &defined),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+        assert!(matches!(
+            check("quoted", "'ls unread'", &defined),
+            Err(Fallacy::AliasNotCommand(_, first)) if first == "ls unread"
+        ));
+    }
+
+    #[test]
+    fn quoted_values() {
+        let defined = BTreeMap::new();
+        for line in [
+            r#"alias dl ls by "Jae-Won Chung""#,
+            r#"alias osd ls at "OSDI|NSDI""#,
+            r#"alias q ls 'say "hi"' "it's""#,
+            r#"alias e ls "" at ''"#,
+        ] {
+            let parsed = args(line).remove(0);
+            let value = quote(&parsed[2..]);
+            assert!(check(&parsed[1], &value, &defined).is_ok(), "{}", value);
+            assert_eq!(args(&value), vec![parsed[2..].to_vec()]);
+        }
+        // Backslashes are kept, even right before a quote or at the end.
+        let parsed: Vec<String> = ["ls", r"C:\", r#"a\"b"#, r"a\'b", r"x y\", r"\"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(args(&quote(&parsed)), vec![parsed]);
+
+        let value = quote(&args(r#"ls by "Jae-Won Chung""#).remove(0));
+        assert_eq!(value, r#"ls by "Jae-Won Chung""#);
+        let defined = aliases(&[("dl", &value)]);
+        assert_eq!(
+            expand(args("dl in 2023"), &defined).unwrap(),
+            vec![vec!["ls", "by", "Jae-Won Chung", "in", "2023"]]
+        );
+    }
+
+    #[test]
+    fn list_aliases() {
+        assert_eq!(list(&BTreeMap::new()), "No aliases are defined.\n");
+        let defined = aliases(&[("backlog", "ls unread"), ("re", "ls @reason")]);
+        assert_eq!(
+            list(&defined),
+            "backlog = ls unread\nre      = ls @reason\n"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::cmd::dry_run;
+use crate::cmd::prelude::*;
+use crate::utils::{confirm, take_dry_run, take_yes};
+
+pub static MAN: &str = include_str!("../../man/archive.md");
+
+fn papers(num: usize) -> String {
+    format!("{} {}", num, if num != 1 { "papers" } else { "paper" })
+}
+
+/// Archive or unarchive the piped papers.
+fn set_archived(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+    command: &'static str,
+) -> Result<CommandOutput, Fallacy> {
+    let archived = command == "archive";
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+    let preview = take_dry_run(&mut input.args);
+
+    // Only piped papers, so that a filter does not archive too much.
+    let paper_list = match input.papers {
+        Some(list) => list,
+        None => return Err(Fallacy::ArchiveNoPapers(command)),
+    };
+    let indices = state.resolve(&paper_list)?;
+
+    let num_paper = indices.len();
+    if preview {
+        let change = format!("{} {}", command, papers(num_paper));
+        return Ok(dry_run(&change, paper_list));
+    }
+
+    // Ask for confirmation.
+    if num_paper > 1 && !yes {
+        let verb = if archived { "Archive" } else { "Unarchive" };
+        confirm(format!("{} {} papers?", verb, num_paper), false)?;
+    }
+
+    state.snapshot(command, &indices);
+    for ind in indices {
+        state.papers[ind].archived = archived;
+    }
+
+    Ok(CommandOutput::Message(format!(
+        "{} {}.\n",
+        if archived { "Archived" } else { "Unarchived" },
+        papers(num_paper)
+    )))
+}
+
+/// Hide papers from `ls`.
+#[allow(clippy::module_inception)]
+pub mod archive {
+    use super::*;
+
+    pub fn execute(
+        input: CommandInput,
+        state: &mut State,
+        config: &Config,
+    ) -> Result<CommandOutput, Fallacy> {
+        set_archived(input, state, config, "archive")
+    }
+}
+
+/// Show archived papers in `ls` again.
+pub mod unarchive {
+    use super::*;
+
+    pub fn execute(
+        input: CommandInput,
+        state: &mut State,
+        config: &Config,
+    ) -> Result<CommandOutput, Fallacy> {
+        set_archived(input, state, config, "unarchive")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, PaperList};
+
+    #[test]
+    fn hide_and_reach() {
+        let paper = |title: &str, nickname: &str| Paper {
+            title: title.to_owned(),
+            nickname: Some(nickname.to_owned()),
+            venue: "OSDI".to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Reason", "reason"),
+            paper("Shadowtutor", "shadow"),
+            paper("Other", "other"),
+        ]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+        let config = Config::default();
+        let run = |execute: crate::cmd::ExecuteFn,
+                   args: &[&str],
+                   papers: Option<Vec<u64>>,
+                   state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: papers.map(PaperList),
+            };
+            execute(input, state, &config)
+        };
+        let ls = |args: &[&str], state: &mut State| match run(
+            crate::cmd::ls::execute,
+            args,
+            None,
+            state,
+        ) {
+            Ok(CommandOutput::Papers(PaperList(listed))) => listed,
+            _ => panic!(),
+        };
+        let wc = |args: &[&str], papers: Option<Vec<u64>>, state: &mut State| match run(
+            crate::cmd::wc::execute,
+            args,
+            papers,
+            state,
+        ) {
+            Ok(CommandOutput::Message(message)) => message,
+            _ => panic!(),
+        };
+
+        // Only piped papers are archived.
+        assert!(matches!(
+            run(archive::execute, &["archive", "Reason"], None, &mut state),
+            Err(Fallacy::ArchiveNoPapers("archive"))
+        ));
+        run(
+            archive::execute,
+            &["archive", "-y"],
+            Some(ids[..2].to_vec()),
+            &mut state,
+        )
+        .unwrap();
+        assert!(state.papers[0].archived && state.papers[1].archived);
+
+        assert_eq!(ls(&["ls"], &mut state), vec![ids[2]]);
+        assert_eq!(ls(&["ls", "at", "OSDI"], &mut state), vec![ids[2]]);
+        assert_eq!(ls(&["ls", "all"], &mut state), ids);
+        assert_eq!(ls(&["ls", "archived"], &mut state), ids[..2].to_vec());
+        assert_eq!(ls(&["ls", "archived", "Shadow"], &mut state), vec![ids[1]]);
+        assert_eq!(
+            ls(&["ls", "not", "(", "archived", ")"], &mut state),
+            vec![ids[2]]
+        );
+        // Exact nicknames still reach archived papers.
+        assert_eq!(ls(&["ls", "@reason"], &mut state), vec![ids[0]]);
+        assert_eq!(
+            ls(&["ls", "@reason", "@other"], &mut state),
+            vec![ids[0], ids[2]]
+        );
+        assert!(ls(&["ls", "as", "reason"], &mut state).is_empty());
+
+        assert_eq!(
+            wc(&["wc"], None, &mut state),
+            "1 papers, plus 2 archived.\n"
+        );
+        assert_eq!(wc(&["wc", "Other"], None, &mut state), "1 papers.\n");
+        assert_eq!(
+            wc(&["wc"], Some(ids.clone()), &mut state),
+            "3 papers, 2 of them archived.\n"
+        );
+
+        run(
+            unarchive::execute,
+            &["unarchive"],
+            Some(vec![ids[1]]),
+            &mut state,
+        )
+        .unwrap();
+        assert_eq!(ls(&["ls"], &mut state), vec![ids[1], ids[2]]);
+    }
+}
@@ -9,8 +9,13 @@ pub fn execute(
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
     // Convert arguments to a filter.
-    let filter_inst =
-        FilterInst::from_args(&input.args[1..], true, config.filter.case_insensitive_regex)?;
+    let filter_inst = FilterInst::from_args(
+        &input.args[1..],
+        true,
+        &config.filter,
+        &state.saved_filters,
+        &state.papers,
+    )?;
 
     // Record the filter instruction.
     state.filters.record(filter_inst);
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::cmd::prelude::*;
+use crate::paper::Paper;
+use crate::pdfinfo;
+use crate::utils::{confirm, take_yes};
+
+pub static MAN: &str = include_str!("../../man/check.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    if input.papers.is_some() {
+        return Err(Fallacy::InvalidCommand(
+            "`check` looks at the whole paperbase and does not take papers through pipe."
+                .to_owned(),
+        ));
+    }
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+    let (mut adopt, mut clear) = (false, false);
+    for arg in input.args[1..].iter() {
+        match arg.as_str() {
+            "--adopt" => adopt = true,
+            "--clear" => clear = true,
+            _ => {
+                return Err(Fallacy::InvalidCommand(format!(
+                    "Unknown argument to `check`: '{}'. Expected --adopt or --clear.",
+                    arg
+                )))
+            }
+        }
+    }
+
+    let orphans = orphans(state, config)?;
+    let missing_files = missing_files(&state.papers, config);
+    let missing_notes = missing_notes(&state.papers, config);
+    let duplicate_nicknames = duplicate_nicknames(&state.papers);
+    let duplicate_ids = duplicate_ids(&state.papers);
+
+    let mut report = String::new();
+    let mut section = |title: &str, lines: Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        report.push_str(&format!("{} ({}):\n", title, lines.len()));
+        for line in lines {
+            report.push_str(&format!("  {}\n", line));
+        }
+    };
+    section(
+        "Orphaned files",
+        orphans
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+    );
+    section(
+        "Missing files",
+        missing_files
+            .iter()
+            .map(|(ind, path)| format!("{}: {}", state.papers[*ind].mention(), path.display()))
+            .collect(),
+    );
+    section(
+        "Missing notes",
+        missing_notes
+            .iter()
+            .map(|(ind, path)| format!("{}: {}", state.papers[*ind].mention(), path.display()))
+            .collect(),
+    );
+    section(
+        "Duplicate nicknames",
+        duplicate_nicknames
+            .iter()
+            .map(|(name, titles)| format!("@{}: {}", name, titles.join(", ")))
+            .collect(),
+    );
+    section(
+        "Duplicate IDs",
+        duplicate_ids
+            .iter()
+            .map(|(id, titles)| format!("{}: {}", id, titles.join(", ")))
+            .collect(),
+    );
+
+    // Clear dangling paths. This can be undone.
+    if clear && !(missing_files.is_empty() && missing_notes.is_empty()) {
+        let mut indices: Vec<usize> = missing_files
+            .iter()
+            .chain(missing_notes.iter())
+            .map(|(ind, _)| *ind)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        state.snapshot("check", &indices);
+        for &ind in indices.iter() {
+            let paper = &mut state.papers[ind];
+            paper.files = paper
+                .files
+                .iter()
+                .filter(|file| config.storage.file_dir.join(file).exists())
+                .cloned()
+                .collect();
+            let storage = &config.storage;
+            if !paper
+                .wikipath
+                .iter()
+                .all(|path| storage.wiki_dir.join(path).exists())
+            {
+                paper.wikipath = None;
+            }
+            if !paper
+                .notepath
+                .iter()
+                .all(|path| storage.note_dir.join(path).exists())
+            {
+                paper.notepath = None;
+            }
+        }
+        let num_path = missing_files.len() + missing_notes.len();
+        report.push_str(&format!(
+            "Cleared {} dangling {}.\n",
+            num_path,
+            if num_path != 1 { "paths" } else { "path" },
+        ));
+    }
+
+    // Adopt orphan PDFs as new papers.
+    if adopt {
+        let pdfs: Vec<_> = orphans
+            .iter()
+            .filter(|path| pdfinfo::is_pdf(path))
+            .collect();
+        if pdfs.len() > 1 && !yes {
+            confirm(format!("Add {} papers for orphan PDFs?", pdfs.len()), false)?;
+        }
+        for path in pdfs.iter() {
+            state.add_paper(skeleton(path, config));
+        }
+        report.push_str(&format!(
+            "Adopted {} orphan {}. Fill in their fields with `ed` or `set`.\n",
+            pdfs.len(),
+            if pdfs.len() != 1 { "PDFs" } else { "PDF" },
+        ));
+    }
+
+    let count =
+        |num: usize, what: &str| format!("{} {}{}", num, what, if num != 1 { "s" } else { "" });
+    report.push_str(&format!(
+        "Found {}, {}, {}, {}, and {}.\n",
+        count(orphans.len(), "orphaned file"),
+        count(missing_files.len(), "missing file"),
+        count(missing_notes.len(), "missing note"),
+        count(duplicate_nicknames.len(), "duplicate nickname"),
+        count(duplicate_ids.len(), "duplicate ID"),
+    ));
+    Ok(CommandOutput::Message(report))
+}
+
+/// Files under the file and wiki directories that no paper refers to,
+/// even in the trash. Hidden files, wiki indices, and the files reason
+/// keeps its own state in are not orphans.
+fn orphans(state: &State, config: &Config) -> Result<Vec<PathBuf>, Fallacy> {
+    let storage = &config.storage;
+    let mut known: HashSet<PathBuf> = HashSet::new();
+    for paper in state
+        .papers
+        .iter()
+        .chain(state.trash.iter().map(|t| &t.paper))
+    {
+        known.extend(paper.files(config).iter().map(|path| normalize(path)));
+        known.extend(
+            paper
+                .wikipath
+                .iter()
+                .map(|path| normalize(&storage.wiki_dir.join(path))),
+        );
+        known.extend(
+            paper
+                .notepath
+                .iter()
+                .map(|path| normalize(&storage.note_dir.join(path))),
+        );
+    }
+    known.extend(
+        [
+            storage.paper_metadata.clone(),
+            storage.command_history.clone(),
+            storage.wiki_dir.join("index.wiki"),
+            storage.wiki_dir.join("index.md"),
+        ]
+        .iter()
+        .chain(storage.undo_journal.iter())
+        .map(|path| normalize(path)),
+    );
+
+    let mut orphans = BTreeSet::new();
+    for dir in [&storage.file_dir, &storage.wiki_dir].iter() {
+        for path in walk(dir)? {
+            if !known.contains(&normalize(&path)) {
+                orphans.insert(path);
+            }
+        }
+    }
+    Ok(orphans.into_iter().collect())
+}
+
+/// Every file under the directory, skipping hidden ones. A missing
+/// directory has no files.
+fn walk(dir: &Path) -> Result<Vec<PathBuf>, Fallacy> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_none_or(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// The canonical form of a path that exists, so that the same file is
+/// recognized however it is written.
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Attached files that do not exist, with the index of their paper.
+fn missing_files(papers: &[Paper], config: &Config) -> Vec<(usize, PathBuf)> {
+    papers
+        .iter()
+        .enumerate()
+        .flat_map(|(ind, paper)| paper.files(config).into_iter().map(move |path| (ind, path)))
+        .filter(|(_, path)| !path.exists())
+        .collect()
+}
+
+/// Wiki and note paths that do not exist, with the index of their paper.
+fn missing_notes(papers: &[Paper], config: &Config) -> Vec<(usize, PathBuf)> {
+    let storage = &config.storage;
+    let mut missing = Vec::new();
+    for (ind, paper) in papers.iter().enumerate() {
+        let paths = paper
+            .wikipath
+            .iter()
+            .map(|path| storage.wiki_dir.join(path))
+            .chain(
+                paper
+                    .notepath
+                    .iter()
+                    .map(|path| storage.note_dir.join(path)),
+            );
+        missing.extend(paths.filter(|path| !path.exists()).map(|path| (ind, path)));
+    }
+    missing
+}
+
+/// Nicknames and aliases used by more than one paper, with the titles of
+/// those papers.
+fn duplicate_nicknames(papers: &[Paper]) -> Vec<(String, Vec<String>)> {
+    let mut users: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for paper in papers {
+        let mut names: Vec<_> = paper.nicknames().collect();
+        names.sort_unstable();
+        names.dedup();
+        for name in names {
+            users.entry(name).or_default().push(paper.title.clone());
+        }
+    }
+    users
+        .into_iter()
+        .filter(|(_, titles)| titles.len() > 1)
+        .map(|(name, titles)| (name.to_owned(), titles))
+        .collect()
+}
+
+/// IDs shared by more than one paper, with the titles of those papers.
+fn duplicate_ids(papers: &[Paper]) -> Vec<(u64, Vec<String>)> {
+    let mut users: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    for paper in papers {
+        users.entry(paper.id).or_default().push(paper.title.clone());
+    }
+    users
+        .into_iter()
+        .filter(|(_, titles)| titles.len() > 1)
+        .collect()
+}
+
+/// A paper for an orphan PDF, with the title, authors, and year guessed
+/// from it. The title falls back to the file name.
+fn skeleton(path: &Path, config: &Config) -> Paper {
+    let guess = pdfinfo::guess(path);
+    let title = guess.title.unwrap_or_else(|| {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        stem.replace(['_', '-'], " ")
+    });
+    // Files in the file directory are stored relative to it.
+    let file = path
+        .strip_prefix(&config.storage.file_dir)
+        .unwrap_or(path)
+        .to_owned();
+    Paper {
+        title,
+        authors: guess.authors,
+        year: guess.year.unwrap_or_default(),
+        files: vec![file],
+        added: Some(chrono::Local::now()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report_clear_and_adopt() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().join("files");
+        config.storage.wiki_dir = dir.path().join("wiki");
+        config.storage.note_dir = dir.path().join("notes");
+        config.storage.paper_metadata = dir.path().join("files/metadata.yaml");
+        for dir in ["files/old", "wiki", "notes"] {
+            std::fs::create_dir_all(dir_path(&config, dir)).unwrap();
+        }
+        let write = |name: &str, content: &str| {
+            std::fs::write(dir_path(&config, name), content).unwrap();
+        };
+        write("files/a.pdf", "%PDF-1.4");
+        write("files/old/trashed.pdf", "%PDF-1.4");
+        write("files/old/orphan_paper.pdf", "%PDF-1.4");
+        write("files/notes.txt", "");
+        write("files/.hidden", "");
+        write("files/metadata.yaml", "");
+        write("wiki/a.wiki", "");
+        write("wiki/index.wiki", "");
+        write("wiki/stray.wiki", "");
+
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "A".to_owned(),
+                nickname: Some("a".to_owned()),
+                files: vec!["a.pdf".into(), "gone.pdf".into()],
+                wikipath: Some("a.wiki".into()),
+                ..Default::default()
+            },
+            Paper {
+                title: "B".to_owned(),
+                aliases: vec!["a".to_owned()],
+                wikipath: Some("b.wiki".into()),
+                ..Default::default()
+            },
+            Paper {
+                title: "C".to_owned(),
+                files: vec!["old/trashed.pdf".into()],
+                ..Default::default()
+            },
+        ]);
+        state.trash(&[2]);
+        let id = state.papers[1].id;
+        state.papers[1].id = state.papers[0].id;
+
+        let check = |args: &[&str], state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, state, &config) {
+                Ok(CommandOutput::Message(report)) => report,
+                _ => panic!(),
+            }
+        };
+
+        // Only report.
+        let report = check(&["check"], &mut state);
+        assert!(report.contains("Orphaned files (3):\n"), "{}", report);
+        assert!(report.contains("orphan_paper.pdf\n"));
+        assert!(report.contains("stray.wiki\n"));
+        assert!(report.contains("Missing files (1):\n  @a: "));
+        assert!(report.contains("Missing notes (1):\n  B: "));
+        assert!(report.contains("Duplicate nicknames (1):\n  @a: A, B\n"));
+        assert!(report.contains("Duplicate IDs (1):\n"));
+        assert!(report.ends_with(
+            "Found 3 orphaned files, 1 missing file, 1 missing note, \
+             1 duplicate nickname, and 1 duplicate ID.\n"
+        ));
+        assert_eq!(state.papers[0].files.len(), 2);
+        state.papers[1].id = id;
+
+        // Dangling paths are cleared, which can be undone.
+        let report = check(&["check", "--clear"], &mut state);
+        assert!(report.contains("Cleared 2 dangling paths.\n"));
+        assert_eq!(state.papers[0].files, vec![PathBuf::from("a.pdf")]);
+        assert_eq!(state.papers[0].wikipath, Some("a.wiki".into()));
+        assert_eq!(state.papers[1].wikipath, None);
+        assert!(check(&["check"], &mut state).contains("0 missing files, 0 missing notes"));
+        state.undo().unwrap();
+        assert_eq!(state.papers[0].files.len(), 2);
+
+        // Only PDFs are adopted.
+        let report = check(&["check", "-y", "--adopt"], &mut state);
+        assert!(report.contains("Adopted 1 orphan PDF."));
+        let adopted = state.papers.last().unwrap();
+        assert_eq!(adopted.title, "orphan paper");
+        assert_eq!(adopted.files, vec![PathBuf::from("old/orphan_paper.pdf")]);
+        assert!(check(&["check"], &mut state).contains("Found 2 orphaned files"));
+    }
+
+    fn dir_path(config: &Config, name: &str) -> PathBuf {
+        config.storage.file_dir.parent().unwrap().join(name)
+    }
+}
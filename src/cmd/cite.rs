@@ -0,0 +1,262 @@
+use crate::bibtex;
+use crate::cmd::prelude::*;
+use crate::paper::Paper;
+
+pub static MAN: &str = include_str!("../../man/cite.md");
+
+/// Placeholders for fields a paper lacks, so that it still gets cited.
+const NO_AUTHORS: &str = "Anonymous";
+const NO_VENUE: &str = "Unknown venue";
+const NO_YEAR: &str = "n.d.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Style {
+    Acm,
+    Ieee,
+    Apa,
+}
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // The ACM style is used unless told otherwise.
+    let style = match input.args.get(1).map(String::as_str) {
+        Some("ieee") => Style::Ieee,
+        Some("apa") => Style::Apa,
+        _ => Style::Acm,
+    };
+    if matches!(
+        input.args.get(1).map(String::as_str),
+        Some("acm") | Some("ieee") | Some("apa")
+    ) {
+        input.args.remove(1);
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let max_authors = config.output.cite_max_authors;
+    let mut message = String::new();
+    for ind in state.resolve(&paper_list)? {
+        message.push_str(&cite(&state.papers[ind], style, max_authors));
+        message.push('\n');
+    }
+
+    Ok(CommandOutput::Message(message))
+}
+
+/// The citation of a paper in the given style.
+fn cite(paper: &Paper, style: Style, max_authors: usize) -> String {
+    let title = paper.title.trim().trim_end_matches('.');
+    let venue = match plain_venue(&paper.venue) {
+        venue if venue.is_empty() => NO_VENUE.to_owned(),
+        venue => venue,
+    };
+    let year = match paper.year.trim() {
+        "" => NO_YEAR,
+        year => year,
+    };
+    let authors = authors(paper, style, max_authors);
+    match style {
+        // Jaehoon Kim, Minsu Park, and Ana Lee. 2021. Title. In OSDI.
+        Style::Acm => format!(
+            "{} {} {} In {}",
+            sentence(&authors),
+            sentence(year),
+            sentence(title),
+            sentence(&venue)
+        ),
+        // J. Kim, M. Park, and A. Lee, "Title," in OSDI, 2021.
+        Style::Ieee => sentence(&format!(
+            "{}, \"{},\" in {}, {}",
+            authors, title, venue, year
+        )),
+        // Kim, J., Park, M., & Lee, A. (2021). Title. OSDI.
+        Style::Apa => format!(
+            "{} ({}). {} {}",
+            authors,
+            year,
+            sentence(title),
+            sentence(&venue)
+        ),
+    }
+}
+
+/// The text ending with a period, without doubling one already there.
+fn sentence(text: &str) -> String {
+    if text.ends_with('.') {
+        text.to_owned()
+    } else {
+        format!("{}.", text)
+    }
+}
+
+/// The author list in the given style, cut to the first author and
+/// "et al." when there are more than `max_authors`.
+fn authors(paper: &Paper, style: Style, max_authors: usize) -> String {
+    let name = match style {
+        Style::Acm => full_name,
+        Style::Ieee => initials_first,
+        Style::Apa => surname_first,
+    };
+    let names: Vec<String> = paper
+        .authors
+        .iter()
+        .filter(|author| !author.trim().is_empty())
+        .map(|author| name(author))
+        .collect();
+    // APA separates inverted names with commas even when there are two.
+    let (and, last_and) = match style {
+        Style::Apa => (", & ", ", & "),
+        _ => (" and ", ", and "),
+    };
+    match names.len() {
+        0 => NO_AUTHORS.to_owned(),
+        1 => names[0].clone(),
+        n if n > max_authors.max(1) => match style {
+            Style::Apa => format!("{}, et al.", names[0]),
+            _ => format!("{} et al.", names[0]),
+        },
+        2 => format!("{}{}{}", names[0], and, names[1]),
+        n => format!("{}{}{}", names[..n - 1].join(", "), last_and, names[n - 1]),
+    }
+}
+
+/// The given names and surname of an author, which is either 'First Last'
+/// or 'Last, First'.
+fn split_name(author: &str) -> (&str, &str) {
+    match author.split_once(',') {
+        Some((last, first)) => (first.trim(), last.trim()),
+        None => match author.trim().rsplit_once(char::is_whitespace) {
+            Some((first, last)) => (first.trim(), last),
+            None => ("", author.trim()),
+        },
+    }
+}
+
+/// Given names as initials, like 'J.-W.' for 'Jae-Won'.
+fn initials(given: &str) -> String {
+    given
+        .split_whitespace()
+        .map(|name| {
+            name.split('-')
+                .filter_map(|part| part.chars().next())
+                .map(|c| format!("{}.", c))
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 'Jaehoon Kim'.
+fn full_name(author: &str) -> String {
+    match split_name(author) {
+        ("", last) => last.to_owned(),
+        (first, last) => format!("{} {}", first, last),
+    }
+}
+
+/// 'J. Kim'.
+fn initials_first(author: &str) -> String {
+    match split_name(author) {
+        ("", last) => last.to_owned(),
+        (first, last) => format!("{} {}", initials(first), last),
+    }
+}
+
+/// 'Kim, J.'.
+fn surname_first(author: &str) -> String {
+    match split_name(author) {
+        ("", last) => last.to_owned(),
+        (first, last) => format!("{}, {}", last, initials(first)),
+    }
+}
+
+/// The venue as plain text, without LaTeX commands or markdown italics
+/// like '*OSDI*' or '_OSDI_'.
+fn plain_venue(venue: &str) -> String {
+    bibtex::to_text(venue)
+        .trim_matches(|c| c == '*' || c == '_')
+        .trim()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::PaperList;
+
+    #[test]
+    fn styles() {
+        let paper = |title: &str, authors: &[&str], venue: &str, year: &str| Paper {
+            title: title.to_owned(),
+            authors: authors.iter().map(|s| s.to_string()).collect(),
+            venue: venue.to_owned(),
+            year: year.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper(
+                "Reason: A Paper Manager",
+                &["Jae-Won Kim", "Park, Minsu", "Ana Lee"],
+                "\\emph{OSDI}",
+                "2021",
+            ),
+            paper("Shell Papers.", &["Jaehoon Kim", "Minsu Park"], "*ATC*", ""),
+            paper("Untitled Notes", &[], "", "2020"),
+        ]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+        let mut config = Config::default();
+        let mut cite = |args: &[&str], papers: Vec<u64>, config: &Config| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: Some(PaperList(papers)),
+            };
+            match execute(input, &mut state, config) {
+                Ok(CommandOutput::Message(message)) => message,
+                _ => panic!(),
+            }
+        };
+
+        assert_eq!(
+            cite(&["cite"], ids.clone(), &config),
+            "Jae-Won Kim, Minsu Park, and Ana Lee. 2021. Reason: A Paper Manager. In OSDI.\n\
+             Jaehoon Kim and Minsu Park. n.d. Shell Papers. In ATC.\n\
+             Anonymous. 2020. Untitled Notes. In Unknown venue.\n"
+        );
+        assert_eq!(
+            cite(&["cite", "ieee"], ids[..2].to_vec(), &config),
+            "J.-W. Kim, M. Park, and A. Lee, \"Reason: A Paper Manager,\" in OSDI, 2021.\n\
+             J. Kim and M. Park, \"Shell Papers,\" in ATC, n.d.\n"
+        );
+        assert_eq!(
+            cite(&["cite", "apa"], ids[..2].to_vec(), &config),
+            "Kim, J.-W., Park, M., & Lee, A. (2021). Reason: A Paper Manager. OSDI.\n\
+             Kim, J., & Park, M. (n.d.). Shell Papers. ATC.\n"
+        );
+
+        // Long author lists are cut short.
+        config.output.cite_max_authors = 2;
+        assert_eq!(
+            cite(&["cite", "ieee"], vec![ids[0]], &config),
+            "J.-W. Kim et al., \"Reason: A Paper Manager,\" in OSDI, 2021.\n"
+        );
+        assert_eq!(
+            cite(&["cite", "apa"], vec![ids[0]], &config),
+            "Kim, J.-W., et al. (2021). Reason: A Paper Manager. OSDI.\n"
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use std::fs::File;
-use std::io::{Cursor, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{stdout, Cursor, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::cmd::import::{summarize, Outcome};
 use crate::cmd::prelude::*;
-use crate::paper::{Paper, PaperList};
-use crate::utils::{as_filename, ask_for, confirm, make_unique_path, select};
+use crate::config::NetworkConfig;
+use crate::paper::Paper;
+use crate::utils::{
+    as_filename, ask_for, confirm, expand_tilde, make_unique_path, select, unescape_xml,
+};
 
 use soup::prelude::*;
 use tempfile::NamedTempFile;
@@ -22,24 +27,89 @@ pub fn execute(
         return Err(Fallacy::CurlNoSource);
     }
 
-    // Parse source and route to appropriate function.
-    let url = args.remove(1);
-    let paper = if url.contains("arxiv") {
-        from_arxiv(url.as_ref(), config)?
-    } else if url.contains("usenix") {
-        from_usenix(url.as_ref(), config)?
-    } else if url.contains("file://") {
-        from_disk(url.as_ref(), config)?
+    // `curl -f links.txt` imports every source in the file.
+    let batch = args[1] == "-f";
+    if batch {
+        args.remove(1);
+        if args.len() <= 1 {
+            return Err(Fallacy::CurlNoSource);
+        }
+    }
+
+    let source = args.remove(1);
+    let pdf = match args.get(1).map(String::as_str) {
+        None => None,
+        Some("pdf") => Some(true),
+        Some("nopdf") => Some(false),
+        Some(option) => return Err(Fallacy::CurlUnknownOption(option.to_owned())),
+    };
+    if batch {
+        return import_file(Path::new(&source), pdf, state, config);
+    }
+    let ind = import_source(&source, pdf, true, state, config)?;
+    Ok(CommandOutput::Papers(state.paper_list([ind])))
+}
+
+/// Import every source listed in the file at `path`, one per line, and
+/// summarize how each went. Blank lines and lines starting with '#' are
+/// skipped. Papers already in the paperbase are skipped, not updated.
+pub fn import_file(
+    path: &Path,
+    pdf: Option<bool>,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let path = expand_tilde(path)?;
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| Fallacy::ImportReadFailed(path.clone(), e))?;
+    let sources: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let mut outcomes = Vec::with_capacity(sources.len());
+    for (i, source) in sources.iter().enumerate() {
+        println!("[{}/{}] {}", i + 1, sources.len(), source);
+        let result = import_source(source, pdf, false, state, config);
+        let title = result.map(|ind| state.papers[ind].title.clone());
+        outcomes.push((source.to_string(), Outcome::of(title)));
+    }
+    Ok(CommandOutput::Message(summarize(&outcomes)))
+}
+
+/// Import a paper from a source and return its index. If `update` is set,
+/// papers that are already in the paperbase may be updated. Otherwise, they
+/// are reported as duplicates.
+fn import_source(
+    source: &str,
+    pdf: Option<bool>,
+    update: bool,
+    state: &mut State,
+    config: &Config,
+) -> Result<usize, Fallacy> {
+    if let Some(id) = source.strip_prefix("s2:") {
+        return import_s2(&s2_paper_id(id), update, state, config);
+    }
+    if let Some(id) = ArxivId::parse(source) {
+        return import_arxiv(&id, pdf, update, state, config);
+    }
+    if let Some(doi) = parse_doi_source(source)? {
+        return import_doi(&doi, pdf, update, state, config);
+    }
+    if source.contains("arxiv") {
+        return Err(Fallacy::CurlInvalidSourceUrl(source.to_owned()));
+    }
+    let paper = if source.contains("usenix") {
+        from_usenix(source, config)?
+    } else if source.contains("file://") {
+        from_disk(source, config)?
     } else {
-        from_pdf(url.as_ref(), config)?
+        from_pdf(source, config)?
     };
 
     // Add paper to state.
-    state.papers.push(paper);
-
-    Ok(CommandOutput::Papers(PaperList(vec![
-        state.papers.len() - 1,
-    ])))
+    add_new(state, paper)
 }
 
 fn from_disk(url: &str, config: &Config) -> Result<Paper, Fallacy> {
@@ -95,117 +165,861 @@ fn from_disk(url: &str, config: &Config) -> Result<Paper, Fallacy> {
         authors,
         venue,
         year,
-        filepath: Some(filepath),
+        files: vec![filepath],
         ..Default::default()
     })
 }
 
-fn from_arxiv(url: &str, config: &Config) -> Result<Paper, Fallacy> {
-    // NOTE: There's the arXiv export API, but we need to parse XML to use that.
-    //       xml-rs seems good enough, but I'd rather not add another dependency
-    //       just for this. As of now our use case is simple and parsing HTML
-    //       with soup seems tractable. However, if things get more complicated,
-    //       consider switching to using the API.
+/// An arXiv identifier, either new-style like '1706.03762' or old-style like
+/// 'cs/0112017', maybe with a version like 'v2'.
+#[derive(Debug, PartialEq)]
+struct ArxivId {
+    id: String,
+    version: Option<String>,
+}
 
-    println!("Fetching from arXiv.");
+impl ArxivId {
+    /// Parse a bare identifier like '2304.01234v2' or 'arxiv:cs/0112017', or
+    /// an arXiv URL like 'https://arxiv.org/abs/1706.03762'.
+    fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let bare = if source.len() > 6 && source[..6].eq_ignore_ascii_case("arxiv:") {
+            &source[6..]
+        } else if let Ok(url) = url::Url::parse(source) {
+            if !url.host_str()?.ends_with("arxiv.org") {
+                return None;
+            }
+            let path = url.path().trim_start_matches('/');
+            let path = path
+                .strip_prefix("abs/")
+                .or_else(|| path.strip_prefix("pdf/"))?;
+            return Self::parse_bare(path.trim_end_matches(".pdf"));
+        } else {
+            source
+        };
+        Self::parse_bare(bare)
+    }
 
-    // Parse and validate url.
-    // https://arxiv.org/abs/2208.06102
-    // https://arxiv.org/pdf/2208.06102.pdf
-    let parsed_url = url::Url::parse(url)?;
-    if parsed_url.cannot_be_a_base() {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
+    fn parse_bare(id: &str) -> Option<Self> {
+        let shape =
+            regex::Regex::new(r"^(\d{4}\.\d{4,5}|[a-z]+(?:-[a-z]+)*(?:\.[A-Z]{2})?/\d{7})(v\d+)?$")
+                .unwrap();
+        let captures = shape.captures(id)?;
+        Some(Self {
+            id: captures[1].to_owned(),
+            version: captures.get(2).map(|v| v.as_str().to_owned()),
+        })
     }
-    // Calling unwrap() doesn't panic if !parsed_url.cannot_be_a_base().
-    let mut segments: Vec<_> = parsed_url.path_segments().unwrap().collect();
-    if segments.len() != 2
-        || !parsed_url.has_host()
-        || !parsed_url.host_str().unwrap().ends_with("arxiv.org")
-        || (segments[0] != "abs" && segments[0] != "pdf")
-    {
-        confirm(
-            "URL of form https://arxiv.org/abs/{identifier} expected. Just continue?".to_string(),
-            true,
-        )?;
+
+    /// The DOI arXiv registers for every paper.
+    fn doi(&self) -> String {
+        format!("10.48550/arXiv.{}", self.id)
     }
-    // Convert https://arvix.org/pdf urls to https://arxiv.org/abs urls.
-    if segments[0] == "pdf" {
-        segments[0] = "abs";
-        segments[1] = segments[1].trim_end_matches(".pdf");
+
+    /// The identifier with its version, if any.
+    fn versioned(&self) -> String {
+        format!("{}{}", self.id, self.version.as_deref().unwrap_or_default())
     }
-    let pieces: Vec<_> = segments[1].split('.').collect();
-    if pieces.len() != 2 || pieces[0].len() != 4 || pieces[1].len() != 5 {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
+
+    /// The year the paper was submitted, from the 'YYMM' in the identifier.
+    /// arXiv started in 1991.
+    fn year(&self) -> String {
+        let digits = self.id.rsplit('/').next().unwrap_or_default();
+        match digits[..2].parse::<u32>() {
+            Ok(yy) if yy >= 91 => format!("19{:02}", yy),
+            Ok(yy) => format!("20{:02}", yy),
+            Err(_) => String::new(),
+        }
     }
-    if !pieces.iter().all(|p| p.chars().all(|c| c.is_numeric())) {
-        return Err(Fallacy::CurlInvalidSourceUrl(url.to_owned()));
+}
+
+/// Import a paper from arXiv. If the paper is already in the paperbase,
+/// offer to update it instead if `update` is set.
+/// `pdf` says whether to download the PDF, and defaults to the config.
+/// Papers that already have files get one only when asked explicitly.
+fn import_arxiv(
+    id: &ArxivId,
+    pdf: Option<bool>,
+    update: bool,
+    state: &mut State,
+    config: &Config,
+) -> Result<usize, Fallacy> {
+    if !update {
+        skip_existing(state, &id.doi())?;
     }
-    let venue = "arXiv".to_owned();
-    let year = format!("20{}", &pieces[0][..2]);
+    let paper = from_arxiv(id, config)?;
 
-    // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    let ind = match find_existing(state, &paper) {
+        Some(ind) => {
+            let has_files = !state.papers[ind].files.is_empty();
+            update_after_confirm(state, ind, paper)?;
+            if pdf.unwrap_or(config.storage.download_pdf && !has_files) {
+                attach_arxiv_pdf(id, state, ind, config);
+            }
+            ind
+        }
+        None => {
+            let ind = add_new(state, paper)?;
+            if pdf.unwrap_or(config.storage.download_pdf) {
+                attach_arxiv_pdf(id, state, ind, config);
+            }
+            ind
+        }
+    };
+    Ok(ind)
+}
 
-    // Parse title.
-    let res = client
-        .get(format!("https://arxiv.org/abs/{}", segments[1]))
-        .send()?;
-    let soup = Soup::from_reader(res)?;
-    let title = match soup.class("title").find() {
+/// Fail if a paper with the DOI is already in the paperbase. This saves
+/// fetching metadata only to throw it away.
+fn skip_existing(state: &State, doi: &str) -> Result<(), Fallacy> {
+    let probe = Paper {
+        doi: Some(doi.to_owned()),
+        ..Default::default()
+    };
+    match find_existing(state, &probe) {
+        Some(ind) => Err(Fallacy::PaperDuplicateDoi(
+            doi.to_owned(),
+            state.papers[ind].title.clone(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Download the PDF of an arXiv paper and attach it to the paper at `ind`.
+/// The metadata is already imported, so failures are only reported.
+fn attach_arxiv_pdf(id: &ArxivId, state: &mut State, ind: usize, config: &Config) {
+    // Name the file after the nickname or the citation key.
+    state.resolve_citekeys();
+    let paper = &state.papers[ind];
+    let name = paper.nickname.clone().unwrap_or_else(|| paper.citekey());
+    let url = format!("https://arxiv.org/pdf/{}.pdf", id.versioned());
+    match download_pdf(&url, &as_filename(&name), &paper.files, config) {
+        Ok(filepath) => {
+            let files = &mut state.papers[ind].files;
+            if !files.contains(&filepath) {
+                files.push(filepath);
+            }
+        }
+        Err(e) => println!(
+            "Failed to download the PDF: {}\nThe paper was imported without it. Run `curl {} pdf` to try again.",
+            e,
+            id.versioned()
+        ),
+    }
+}
+
+/// Fetch paper metadata from the arXiv API.
+fn from_arxiv(id: &ArxivId, config: &Config) -> Result<Paper, Fallacy> {
+    println!("Fetching from arXiv.");
+
+    let client = http_client(config)?;
+    let api_url = format!(
+        "https://export.arxiv.org/api/query?id_list={}",
+        id.versioned()
+    );
+    let feed = get(&client, &api_url, &config.network)?.text()?;
+    parse_arxiv_feed(&feed, id)
+}
+
+/// Build a paper out of the Atom feed the arXiv API returns for `id`.
+fn parse_arxiv_feed(feed: &str, id: &ArxivId) -> Result<Paper, Fallacy> {
+    // Unknown identifiers give no entry or an entry describing the error.
+    let not_found = || Fallacy::CurlArxivNotFound(id.versioned());
+    let entry = xml_elements(feed, "entry")
+        .into_iter()
+        .next()
+        .ok_or_else(not_found)?;
+    if xml_elements(entry, "id")
+        .first()
+        .is_none_or(|entry_id| entry_id.contains("/api/errors"))
+    {
+        return Err(not_found());
+    }
+
+    let text = |tag: &str| {
+        xml_elements(entry, tag)
+            .first()
+            .map(|text| {
+                unescape_xml(text)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .filter(|text| !text.is_empty())
+    };
+    let title = match text("title") {
         Some(title) => title,
         None => {
             return Err(Fallacy::CurlCannotFindTitle(
-                "No class named 'title' found.".to_owned(),
+                "The arXiv entry has no title.".to_owned(),
             ))
         }
     };
-    let title = match title.children().last() {
-        Some(title) => title.text(),
-        None => {
-            return Err(Fallacy::CurlCannotFindTitle(
-                "Class 'title' has no children.".to_owned(),
-            ))
+    let authors: Vec<String> = xml_elements(entry, "name")
+        .into_iter()
+        .map(|name| unescape_xml(name).trim().to_owned())
+        .collect();
+    if authors.is_empty() {
+        return Err(Fallacy::CurlCannotFindAuthor(
+            "The arXiv entry has no authors.".to_owned(),
+        ));
+    }
+    // The date of the first version, like '2017-06-12T17:57:34Z'.
+    let year = match text("published") {
+        Some(published) if published.len() >= 4 => published[..4].to_owned(),
+        _ => id.year(),
+    };
+
+    Ok(Paper {
+        title,
+        authors,
+        venue: "arXiv".to_owned(),
+        year,
+        // arXiv registers a DOI for every paper.
+        doi: Some(id.doi()),
+        url: Some(format!("https://arxiv.org/abs/{}", id.versioned())),
+        abstract_text: text("summary"),
+        ..Default::default()
+    })
+}
+
+/// Download a PDF into the file directory as '`name`.pdf'.
+///
+/// The file is first written to '`name`.pdf.part', and a download that was
+/// cut off continues from there next time. If '`name`.pdf' already exists and
+/// is not one of `attached`, ask whether to overwrite it. Otherwise the
+/// download gets a new name.
+fn download_pdf(
+    url: &str,
+    name: &str,
+    attached: &[PathBuf],
+    config: &Config,
+) -> Result<PathBuf, Fallacy> {
+    let dir = &config.storage.file_dir;
+    let mut filepath = dir.join(format!("{}.pdf", name));
+    if filepath.exists() {
+        if attached.contains(&filepath) {
+            println!("The PDF is already at {:?}.", filepath);
+            return Ok(filepath);
         }
+        let overwrite = confirm(format!("{:?} already exists. Overwrite?", filepath), false);
+        if overwrite.is_err() {
+            filepath = make_unique_path(dir, name, ".pdf");
+        }
+    }
+    let partpath = filepath.with_extension("pdf.part");
+
+    // Retries continue from where the last attempt left off.
+    let client = http_client(config)?;
+    let network = &config.network;
+    retrying(url, network.retries, BACKOFF_BASE, || {
+        fetch_to_file(&client, url, &partpath, network)
+    })?;
+    std::fs::rename(&partpath, &filepath)?;
+    Ok(filepath)
+}
+
+/// Download `url` into `path`, continuing from where an earlier download
+/// left off if `path` exists. Shows progress for large files.
+fn fetch_to_file(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    path: &Path,
+    network: &NetworkConfig,
+) -> Result<(), Fallacy> {
+    let mut offset = path.metadata().map(|meta| meta.len()).unwrap_or(0);
+    // arXiv download is pretty slow.
+    let timeout = Duration::from_secs(network.timeout_secs.max(90));
+    let mut request = client.get(url).timeout(timeout);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut res = match send(request, url) {
+        // The part was complete or the file changed since. Start over.
+        Err(Fallacy::CurlHttpStatus(_, 416)) => {
+            std::fs::remove_file(path)?;
+            return fetch_to_file(client, url, path, network);
+        }
+        res => res?,
     };
+    // Servers that do not support ranges send everything again.
+    let resumed = res.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !resumed {
+        offset = 0;
+    } else {
+        println!("Resuming the download.");
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(path)?;
 
-    // Parse author list.
-    let authors = match soup.class("authors").find() {
-        Some(authors) => authors,
+    let total = res.content_length().map(|len| len + offset);
+    let show_progress =
+        stdout().is_terminal() && total.is_some_and(|total| total >= PROGRESS_THRESHOLD);
+    let mut done = offset;
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = res
+            .read(&mut buffer)
+            .map_err(|e| Fallacy::CurlDownloadInterrupted(url.to_owned(), e.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        done += read as u64;
+        if show_progress {
+            let total = total.unwrap_or_default();
+            print!(
+                "\rDownloading: {:.1}/{:.1} MB ({}%)",
+                done as f64 / 1e6,
+                total as f64 / 1e6,
+                done * 100 / total
+            );
+            stdout().flush()?;
+        }
+    }
+    if show_progress {
+        println!();
+    }
+    Ok(())
+}
+
+/// Downloads larger than this many bytes show progress.
+const PROGRESS_THRESHOLD: u64 = 1_000_000;
+
+/// Parse a DOI source like 'doi:10.1145/3341301.3359646' or
+/// 'https://doi.org/10.1145/3341301.3359646'. Returns `None` if the source
+/// is not a DOI at all.
+fn parse_doi_source(source: &str) -> Result<Option<String>, Fallacy> {
+    let lower = source.to_ascii_lowercase();
+    let looks_like_doi = lower.starts_with("doi:")
+        || lower.starts_with("10.")
+        || [
+            "https://doi.org/",
+            "http://doi.org/",
+            "https://dx.doi.org/",
+            "http://dx.doi.org/",
+        ]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix));
+    if !looks_like_doi {
+        return Ok(None);
+    }
+    Paper::parse_doi(source).map(Some)
+}
+
+/// Import a paper by its DOI. DOIs arXiv registered go to arXiv, and the rest
+/// to Crossref. If the paper is already in the paperbase, offer to update it
+/// instead if `update` is set.
+fn import_doi(
+    doi: &str,
+    pdf: Option<bool>,
+    update: bool,
+    state: &mut State,
+    config: &Config,
+) -> Result<usize, Fallacy> {
+    let arxiv_prefix = "10.48550/arxiv.";
+    if doi.to_ascii_lowercase().starts_with(arxiv_prefix) {
+        if let Some(id) = ArxivId::parse_bare(&doi[arxiv_prefix.len()..]) {
+            return import_arxiv(&id, pdf, update, state, config);
+        }
+    }
+
+    if !update {
+        skip_existing(state, doi)?;
+    }
+    let paper = from_crossref(doi, config)?;
+    match find_existing(state, &paper) {
+        Some(ind) => {
+            update_after_confirm(state, ind, paper)?;
+            Ok(ind)
+        }
+        None => add_new(state, paper),
+    }
+}
+
+/// Fetch paper metadata from the Crossref REST API. DOIs Crossref does not
+/// know, like those of many datasets, are looked up through doi.org instead.
+fn from_crossref(doi: &str, config: &Config) -> Result<Paper, Fallacy> {
+    println!("Fetching from Crossref.");
+
+    let client = http_client(config)?;
+    let api_url = format!("https://api.crossref.org/works/{}", doi);
+    let work = match get(&client, &api_url, &config.network) {
+        Ok(res) => {
+            let mut body = parse_json(&api_url, &res.text()?)?;
+            body["message"].take()
+        }
+        Err(Fallacy::CurlHttpStatus(_, 404)) => {
+            println!("Crossref does not know the DOI. Asking doi.org.");
+            let url = format!("https://doi.org/{}", doi);
+            let res = retrying(&url, config.network.retries, BACKOFF_BASE, || {
+                let request = client
+                    .get(&url)
+                    .header("Accept", "application/vnd.citationstyles.csl+json");
+                send(request, &url)
+            })?;
+            parse_json(&url, &res.text()?)?
+        }
+        Err(e) => return Err(e),
+    };
+    parse_crossref_work(&work, doi)
+}
+
+fn parse_json(url: &str, body: &str) -> Result<serde_json::Value, Fallacy> {
+    serde_json::from_str(body)
+        .map_err(|e| Fallacy::CurlInvalidResponse(url.to_owned(), e.to_string()))
+}
+
+/// Build a paper out of a Crossref work or the similar CSL JSON of doi.org.
+/// Fields like the title are lists in Crossref and strings in CSL JSON.
+fn parse_crossref_work(work: &serde_json::Value, doi: &str) -> Result<Paper, Fallacy> {
+    let text = |field: &str| {
+        let value = match &work[field] {
+            serde_json::Value::Array(values) => values.first()?.as_str(),
+            value => value.as_str(),
+        };
+        value
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty())
+    };
+
+    let title = match text("title") {
+        Some(title) => title,
         None => {
-            return Err(Fallacy::CurlCannotFindAuthor(
-                "No class named 'authors' found.".to_owned(),
-            ))
+            return Err(Fallacy::CurlCannotFindTitle(format!(
+                "DOI '{}' has no title.",
+                doi
+            )))
         }
     };
-    let authors: Vec<String> = authors.tag("a").find_all().map(|a| a.text()).collect();
-
-    // Download paper PDF.
-    let url = format!("https://arxiv.org/pdf/{}.pdf", segments[1]);
-    let mut cursor = Cursor::new(
-        client
-            .get(url)
-            .timeout(Duration::from_secs(90)) // arXiv download is pretty slow
-            .send()?
-            .bytes()?,
-    );
-    let filename = as_filename(&title);
-    let filepath = make_unique_path(&config.storage.file_dir, &filename, ".pdf");
-    let mut file = File::create(&filepath)?;
-    std::io::copy(&mut cursor, &mut file)?;
+
+    // Books often have only editors, and organizations only a name. CSL JSON
+    // calls the name 'literal'.
+    let people = |field: &str| -> Vec<String> {
+        work[field]
+            .as_array()
+            .map(|people| {
+                people
+                    .iter()
+                    .filter_map(|person| {
+                        let given = person["given"].as_str().unwrap_or_default();
+                        match person["family"].as_str() {
+                            Some(family) => Some(format!("{} {}", given, family)),
+                            None => person["name"]
+                                .as_str()
+                                .or_else(|| person["literal"].as_str())
+                                .map(str::to_owned),
+                        }
+                    })
+                    .map(|name| name.trim().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut authors = people("author");
+    if authors.is_empty() {
+        authors = people("editor");
+    }
+
+    // Books and datasets are in no container, so fall back to the publisher.
+    let venue = text("container-title")
+        .or_else(|| text("publisher"))
+        .unwrap_or_default();
+
+    let year = ["issued", "published-print", "published-online", "created"]
+        .iter()
+        .find_map(|field| work[*field]["date-parts"][0][0].as_u64())
+        .map(|year| year.to_string())
+        .unwrap_or_default();
+
+    // Abstracts are in JATS XML, like '<jats:p>...</jats:p>'.
+    let abstract_text = text("abstract").map(|text| {
+        let mut plain = String::new();
+        let mut in_tag = false;
+        for c in text.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                c if !in_tag => plain.push(c),
+                _ => {}
+            }
+        }
+        unescape_xml(&plain.split_whitespace().collect::<Vec<_>>().join(" "))
+    });
 
     Ok(Paper {
         title,
         authors,
         venue,
         year,
-        filepath: Some(filepath),
+        doi: Some(doi.to_owned()),
+        url: Some(format!("https://doi.org/{}", doi)),
+        abstract_text,
+        ..Default::default()
+    })
+}
+
+/// Fields of the Semantic Scholar Graph API turned into papers.
+const S2_FIELDS: &str = "title,authors,venue,year,externalIds,abstract,citationCount,url";
+
+/// Turn an identifier given after 's2:' into a paper ID of the Semantic
+/// Scholar Graph API. DOIs and arXiv IDs get their prefixes, and the rest,
+/// like Semantic Scholar's own paper IDs, are kept as is.
+fn s2_paper_id(id: &str) -> String {
+    if let Ok(doi) = Paper::parse_doi(id) {
+        let arxiv_prefix = "10.48550/arxiv.";
+        if doi.to_ascii_lowercase().starts_with(arxiv_prefix) {
+            return format!("ARXIV:{}", &doi[arxiv_prefix.len()..]);
+        }
+        return format!("DOI:{}", doi);
+    }
+    if let Some(arxiv) = ArxivId::parse(id) {
+        return format!("ARXIV:{}", arxiv.id);
+    }
+    id.to_owned()
+}
+
+/// The Semantic Scholar paper ID to look the paper up with. This is its DOI
+/// or arXiv ID, or the ID in its Semantic Scholar URL.
+pub fn s2_id_of(paper: &Paper) -> Option<String> {
+    if let Some(doi) = paper.doi.as_ref() {
+        return Some(s2_paper_id(doi));
+    }
+    let url = paper.url.as_ref()?;
+    if url.contains("semanticscholar.org/paper/") {
+        // Like 'https://www.semanticscholar.org/paper/Title-Words/{paperId}'.
+        return url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .map(str::to_owned);
+    }
+    ArxivId::parse(url).map(|arxiv| format!("ARXIV:{}", arxiv.id))
+}
+
+/// Import a paper from Semantic Scholar. If the paper is already in the
+/// paperbase, offer to update it instead if `update` is set.
+fn import_s2(id: &str, update: bool, state: &mut State, config: &Config) -> Result<usize, Fallacy> {
+    if !update {
+        if let Some(doi) = id.strip_prefix("DOI:") {
+            skip_existing(state, doi)?;
+        }
+    }
+    let paper = from_s2(id, config)?;
+    match find_existing(state, &paper) {
+        Some(ind) => {
+            update_after_confirm(state, ind, paper)?;
+            Ok(ind)
+        }
+        None => add_new(state, paper),
+    }
+}
+
+/// Fetch a paper from the Semantic Scholar Graph API.
+fn from_s2(id: &str, config: &Config) -> Result<Paper, Fallacy> {
+    println!("Fetching from Semantic Scholar.");
+    let work = get_s2(id, S2_FIELDS, config)?;
+    parse_s2_paper(&work, id)
+}
+
+/// How many papers cite the paper, according to Semantic Scholar.
+pub fn fetch_citation_count(paper: &Paper, config: &Config) -> Result<u32, Fallacy> {
+    let id = s2_id_of(paper).ok_or(Fallacy::RefreshNoIdentifier)?;
+    let work = get_s2(&id, "citationCount", config)?;
+    work["citationCount"]
+        .as_u64()
+        .map(|count| count as u32)
+        .ok_or_else(|| {
+            Fallacy::CurlInvalidResponse(id, "The paper has no citation count.".to_owned())
+        })
+}
+
+/// Fetch the given fields of a paper from the Semantic Scholar Graph API.
+fn get_s2(id: &str, fields: &str, config: &Config) -> Result<serde_json::Value, Fallacy> {
+    let client = http_client(config)?;
+    let url = format!(
+        "https://api.semanticscholar.org/graph/v1/paper/{}?fields={}",
+        id, fields
+    );
+    match get(&client, &url, &config.network) {
+        Ok(res) => parse_json(&url, &res.text()?),
+        Err(Fallacy::CurlHttpStatus(_, 404)) => Err(Fallacy::CurlS2NotFound(id.to_owned())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Build a paper out of a paper object of the Semantic Scholar Graph API.
+fn parse_s2_paper(work: &serde_json::Value, id: &str) -> Result<Paper, Fallacy> {
+    let text = |field: &str| {
+        work[field]
+            .as_str()
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty())
+    };
+
+    let title = match text("title") {
+        Some(title) => title,
+        None => {
+            return Err(Fallacy::CurlCannotFindTitle(format!(
+                "Semantic Scholar paper '{}' has no title.",
+                id
+            )))
+        }
+    };
+    let authors = work["authors"]
+        .as_array()
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| author["name"].as_str())
+                .map(|name| name.trim().to_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Preprints have no venue, but an arXiv ID.
+    let external = &work["externalIds"];
+    let arxiv = external["ArXiv"].as_str().and_then(ArxivId::parse_bare);
+    let venue = text("venue")
+        .or_else(|| arxiv.as_ref().map(|_| "arXiv".to_owned()))
+        .unwrap_or_default();
+    let doi = external["DOI"]
+        .as_str()
+        .map(str::to_owned)
+        .or_else(|| arxiv.as_ref().map(ArxivId::doi));
+
+    Ok(Paper {
+        title,
+        authors,
+        venue,
+        year: work["year"]
+            .as_u64()
+            .map(|year| year.to_string())
+            .unwrap_or_default(),
+        doi,
+        url: text("url"),
+        abstract_text: text("abstract"),
+        citation_count: work["citationCount"].as_u64().map(|count| count as u32),
         ..Default::default()
     })
 }
 
+/// Add a freshly fetched paper to the paperbase and return its index.
+fn add_new(state: &mut State, mut paper: Paper) -> Result<usize, Fallacy> {
+    state.check_unique(&paper, &[])?;
+    paper.added = Some(chrono::Local::now());
+    Ok(state.add_paper(paper))
+}
+
+/// Ask whether to update the paper at `ind`, which is the same as the fetched
+/// one, and update it if so.
+fn update_after_confirm(state: &mut State, ind: usize, fetched: Paper) -> Result<(), Fallacy> {
+    confirm(
+        format!(
+            "'{}' is already in the paperbase. Update it instead?",
+            state.papers[ind].title
+        ),
+        true,
+    )?;
+    state.snapshot("curl", &[ind]);
+    update_existing(&mut state.papers[ind], fetched);
+    Ok(())
+}
+
+/// Find the paper in the paperbase with the same DOI, if any.
+fn find_existing(state: &State, paper: &Paper) -> Option<usize> {
+    let doi = paper.doi.as_ref()?;
+    state.papers.iter().position(|other| {
+        other
+            .doi
+            .as_ref()
+            .is_some_and(|other| other.eq_ignore_ascii_case(doi))
+    })
+}
+
+/// Update a paper in the paperbase with freshly fetched metadata. What only
+/// the user sets, like nicknames and labels, is kept, and so are the venue
+/// and year of a paper that was since published elsewhere.
+fn update_existing(existing: &mut Paper, fetched: Paper) {
+    existing.title = fetched.title;
+    existing.authors = fetched.authors;
+    if existing.venue.is_empty() {
+        existing.venue = fetched.venue;
+    }
+    if existing.year.is_empty() {
+        existing.year = fetched.year;
+    }
+    if fetched.url.is_some() {
+        existing.url = fetched.url;
+    }
+    if fetched.abstract_text.is_some() {
+        existing.abstract_text = fetched.abstract_text;
+    }
+    if fetched.citation_count.is_some() {
+        existing.citation_count = fetched.citation_count;
+    }
+}
+
+/// The HTTP client used to fetch papers. APIs like Crossref serve clients
+/// that leave a contact address in the User-Agent more reliably.
+fn http_client(config: &Config) -> Result<reqwest::blocking::Client, Fallacy> {
+    let mut user_agent = format!(
+        "{}/{} ({}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_REPOSITORY"),
+    );
+    if let Some(mailto) = config.network.mailto.as_ref() {
+        user_agent.push_str(&format!("; mailto:{}", mailto));
+    }
+    user_agent.push(')');
+    let network = &config.network;
+    let mut builder = reqwest::blocking::ClientBuilder::new()
+        .user_agent(user_agent)
+        .timeout(Duration::from_secs(network.timeout_secs));
+    if let Some(proxy) = network.proxy() {
+        let proxy = reqwest::Proxy::all(&proxy).map_err(|_| Fallacy::CurlInvalidProxy(proxy))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+/// How long to wait before the first retry. Later retries wait twice as
+/// long as the one before.
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// Servers asking to wait longer than this are not waited for.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Make an attempt, and up to `retries` more with exponential backoff if it
+/// fails transiently, like with timeouts or 503s.
+fn retrying<T>(
+    url: &str,
+    retries: u32,
+    base: Duration,
+    mut attempt: impl FnMut() -> Result<T, Fallacy>,
+) -> Result<T, Fallacy> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let error = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(e) if !is_transient(&e) => return Err(e),
+            Err(e) => e,
+        };
+        if attempts > retries {
+            return Err(Fallacy::CurlGaveUp(
+                url.to_owned(),
+                attempts,
+                Box::new(error),
+            ));
+        }
+        let delay = match error {
+            Fallacy::CurlRateLimited(_, Some(secs)) => Duration::from_secs(secs),
+            _ => base * 2u32.pow(attempts - 1),
+        };
+        if delay > MAX_BACKOFF {
+            return Err(Fallacy::CurlGaveUp(
+                url.to_owned(),
+                attempts,
+                Box::new(error),
+            ));
+        }
+        println!("{} Retrying in {}s.", error, delay.as_secs());
+        std::thread::sleep(delay);
+    }
+}
+
+/// Whether a request that failed this way may succeed if tried again.
+fn is_transient(error: &Fallacy) -> bool {
+    match error {
+        Fallacy::CurlTimedOut(_)
+        | Fallacy::CurlRateLimited(..)
+        | Fallacy::CurlDownloadInterrupted(..) => true,
+        Fallacy::CurlHttpStatus(_, status) => *status == 408 || *status >= 500,
+        Fallacy::CurlGetFailed(e) => e.is_connect() || e.is_request() || e.is_body(),
+        _ => false,
+    }
+}
+
+/// Send a GET request, retrying transient failures.
+fn get(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    network: &NetworkConfig,
+) -> Result<reqwest::blocking::Response, Fallacy> {
+    retrying(url, network.retries, BACKOFF_BASE, || {
+        send(client.get(url), url)
+    })
+}
+
+/// Send a request, failing on error statuses like 404 or 503.
+fn send(
+    request: reqwest::blocking::RequestBuilder,
+    url: &str,
+) -> Result<reqwest::blocking::Response, Fallacy> {
+    let res = request.send().map_err(|e| request_error(url, e))?;
+    // Servers that rate limit say when to come back.
+    if res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = res
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok()?.parse().ok());
+        return Err(Fallacy::CurlRateLimited(url.to_owned(), retry_after));
+    }
+    res.error_for_status().map_err(|e| request_error(url, e))
+}
+
+fn request_error(url: &str, error: reqwest::Error) -> Fallacy {
+    if error.is_timeout() {
+        return Fallacy::CurlTimedOut(url.to_owned());
+    }
+    match error.status() {
+        Some(status) => Fallacy::CurlHttpStatus(url.to_owned(), status.as_u16()),
+        None => Fallacy::CurlGetFailed(error),
+    }
+}
+
+/// The contents of every `<tag>` element in `xml`, in order. This is only
+/// meant for simple documents like the Atom feeds of the arXiv API, where
+/// elements of the same name do not nest.
+fn xml_elements<'x>(xml: &'x str, tag: &str) -> Vec<&'x str> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        // Skip longer tag names like `<titles>`.
+        match rest.chars().next() {
+            Some('>') => {}
+            Some(c) if c.is_whitespace() => {}
+            _ => continue,
+        }
+        let (attrs, body) = match rest.find('>') {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => break,
+        };
+        // Self-closing elements like `<link href="..."/>` have no contents.
+        if attrs.ends_with('/') {
+            rest = body;
+            continue;
+        }
+        match body.find(&close) {
+            Some(end) => {
+                elements.push(&body[..end]);
+                rest = &body[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
 fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
     println!("Fetching from usenix.org.");
 
@@ -239,13 +1053,11 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
     let year = format!("20{}", &conf[conf.len() - 2..]);
 
     // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    let client = http_client(config)?;
 
     // Parse title.
-    let res = client.get(url).send()?;
+    let source_url = url;
+    let res = get(&client, url, &config.network)?;
     let soup = Soup::from_reader(res)?;
     let title = match soup.attr("id", "page-title").find() {
         Some(title) => title.text(),
@@ -316,7 +1128,7 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
 
     // Maybe download paper PDF.
     let filepath = if let Some(url) = url {
-        let mut cursor = Cursor::new(client.get(url).send()?.bytes()?);
+        let mut cursor = Cursor::new(get(&client, &url, &config.network)?.bytes()?);
         let filename = as_filename(&title);
         let filepath = make_unique_path(&config.storage.file_dir, &filename, ".pdf");
         let mut file = File::create(&filepath)?;
@@ -333,7 +1145,8 @@ fn from_usenix(url: &str, config: &Config) -> Result<Paper, Fallacy> {
         authors,
         venue,
         year,
-        filepath,
+        files: filepath.into_iter().collect(),
+        url: Some(source_url.to_owned()),
         ..Default::default()
     })
 }
@@ -348,13 +1161,10 @@ fn from_pdf(url: &str, config: &Config) -> Result<Paper, Fallacy> {
     }
 
     // Initialize HTTP client.
-    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let client = reqwest::blocking::ClientBuilder::new()
-        .user_agent(user_agent)
-        .build()?;
+    let client = http_client(config)?;
 
     // Download PDF file.
-    let mut cursor = Cursor::new(client.get(url).send()?.bytes()?);
+    let mut cursor = Cursor::new(get(&client, url, &config.network)?.bytes()?);
     let mut tmpfile = NamedTempFile::new_in(&config.storage.file_dir)?;
     std::io::copy(&mut cursor, &mut tmpfile)?;
     tmpfile.flush()?;
@@ -401,7 +1211,356 @@ fn from_pdf(url: &str, config: &Config) -> Result<Paper, Fallacy> {
         authors,
         venue,
         year,
-        filepath: Some(filepath),
+        files: vec![filepath],
+        url: Some(url.to_owned()),
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arxiv_ids() {
+        let id = |id: &str, version: Option<&str>| {
+            Some(ArxivId {
+                id: id.to_owned(),
+                version: version.map(str::to_owned),
+            })
+        };
+        assert_eq!(ArxivId::parse("1706.03762"), id("1706.03762", None));
+        assert_eq!(
+            ArxivId::parse("arxiv:2304.01234v2"),
+            id("2304.01234", Some("v2"))
+        );
+        assert_eq!(ArxivId::parse("arXiv:cs/0112017"), id("cs/0112017", None));
+        assert_eq!(
+            ArxivId::parse("math.AG/0309136v1"),
+            id("math.AG/0309136", Some("v1"))
+        );
+        assert_eq!(
+            ArxivId::parse("https://arxiv.org/pdf/2003.10735v3.pdf"),
+            id("2003.10735", Some("v3"))
+        );
+        assert_eq!(
+            ArxivId::parse("https://arxiv.org/abs/hep-th/9901001"),
+            id("hep-th/9901001", None)
+        );
+        assert_eq!(ArxivId::parse("2020"), None);
+        assert_eq!(ArxivId::parse("https://example.com/abs/1706.03762"), None);
+        assert_eq!(ArxivId::parse("cs/0112017").unwrap().year(), "2001");
+        assert_eq!(ArxivId::parse("hep-th/9901001").unwrap().year(), "1999");
+    }
+
+    #[test]
+    fn arxiv_feed() {
+        let id = ArxivId::parse("1706.03762v7").unwrap();
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="html">ArXiv Query: id_list=1706.03762v7</title>
+  <entry>
+    <id>http://arxiv.org/abs/1706.03762v7</id>
+    <published>2017-06-12T17:57:34Z</published>
+    <title>Attention Is All
+      You Need</title>
+    <summary>  The dominant sequence transduction models &amp; more.
+    </summary>
+    <author>
+      <name>Ashish Vaswani</name>
+    </author>
+    <author>
+      <name>&#321;ukasz Kaiser</name>
+      <arxiv:affiliation>Google</arxiv:affiliation>
+    </author>
+    <link href="http://arxiv.org/abs/1706.03762v7" rel="alternate"/>
+  </entry>
+</feed>"#;
+        let paper = parse_arxiv_feed(feed, &id).unwrap();
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!(paper.authors, vec!["Ashish Vaswani", "Łukasz Kaiser"]);
+        assert_eq!(paper.year, "2017");
+        assert_eq!(paper.venue, "arXiv");
+        assert_eq!(paper.doi.as_deref(), Some("10.48550/arXiv.1706.03762"));
+        assert_eq!(
+            paper.url.as_deref(),
+            Some("https://arxiv.org/abs/1706.03762v7")
+        );
+        assert_eq!(
+            paper.abstract_text.as_deref(),
+            Some("The dominant sequence transduction models & more.")
+        );
+
+        let error = r#"<feed><entry><id>http://arxiv.org/api/errors#incorrect_id_format</id>
+<title>Error</title></entry></feed>"#;
+        assert!(matches!(
+            parse_arxiv_feed(error, &id),
+            Err(Fallacy::CurlArxivNotFound(_))
+        ));
+        assert!(matches!(
+            parse_arxiv_feed("<feed></feed>", &id),
+            Err(Fallacy::CurlArxivNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn update_duplicate() {
+        let mut state = State::from_papers(vec![Paper {
+            title: "Attention".to_owned(),
+            nickname: Some("transformer".to_owned()),
+            venue: "NeurIPS".to_owned(),
+            doi: Some("10.48550/arxiv.1706.03762".to_owned()),
+            ..Default::default()
+        }]);
+        let fetched = Paper {
+            title: "Attention Is All You Need".to_owned(),
+            venue: "arXiv".to_owned(),
+            year: "2017".to_owned(),
+            doi: Some("10.48550/arXiv.1706.03762".to_owned()),
+            ..Default::default()
+        };
+        let ind = find_existing(&state, &fetched).unwrap();
+        update_existing(&mut state.papers[ind], fetched);
+        let paper = &state.papers[0];
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!(paper.nickname.as_deref(), Some("transformer"));
+        assert_eq!(
+            (paper.venue.as_str(), paper.year.as_str()),
+            ("NeurIPS", "2017")
+        );
+    }
+
+    #[test]
+    fn doi_sources() {
+        let doi = |source: &str| parse_doi_source(source).unwrap();
+        assert_eq!(
+            doi("doi:10.1145/3341301.3359646").as_deref(),
+            Some("10.1145/3341301.3359646")
+        );
+        assert_eq!(
+            doi("https://doi.org/10.5281/zenodo.1234").as_deref(),
+            Some("10.5281/zenodo.1234")
+        );
+        assert_eq!(
+            doi("10.1145/3341301.3359646").as_deref(),
+            Some("10.1145/3341301.3359646")
+        );
+        assert_eq!(doi("https://usenix.org/conference/atc21"), None);
+        assert!(matches!(
+            parse_doi_source("doi:3341301"),
+            Err(Fallacy::PaperInvalidDoi(_))
+        ));
+    }
+
+    #[test]
+    fn crossref_works() {
+        let doi = "10.1145/3341301.3359646";
+        let article: serde_json::Value = serde_json::from_str(
+            r#"{
+            "title": ["PipeDream: Generalized Pipeline Parallelism for DNN Training"],
+            "author": [
+                {"given": "Deepak", "family": "Narayanan"},
+                {"name": "The PipeDream Team"}
+            ],
+            "container-title": ["Proceedings of the 27th ACM Symposium on Operating Systems Principles"],
+            "publisher": "ACM",
+            "issued": {"date-parts": [[2019, 10, 27]]},
+            "abstract": "<jats:p>DNN training is  &amp; slow.</jats:p>"
+        }"#,
+        )
+        .unwrap();
+        let paper = parse_crossref_work(&article, doi).unwrap();
+        assert_eq!(
+            paper.title,
+            "PipeDream: Generalized Pipeline Parallelism for DNN Training"
+        );
+        assert_eq!(
+            paper.authors,
+            vec!["Deepak Narayanan", "The PipeDream Team"]
+        );
+        assert_eq!(
+            paper.venue,
+            "Proceedings of the 27th ACM Symposium on Operating Systems Principles"
+        );
+        assert_eq!(paper.year, "2019");
+        assert_eq!(paper.doi.as_deref(), Some(doi));
+        assert_eq!(
+            paper.abstract_text.as_deref(),
+            Some("DNN training is & slow.")
+        );
+
+        // Books have editors and no container.
+        let book: serde_json::Value = serde_json::from_str(
+            r#"{
+            "title": ["Readings in Database Systems"],
+            "container-title": [],
+            "editor": [{"given": "Michael", "family": "Stonebraker"}],
+            "publisher": "MIT Press",
+            "issued": {"date-parts": [[null]]},
+            "published-print": {"date-parts": [[2005]]}
+        }"#,
+        )
+        .unwrap();
+        let paper = parse_crossref_work(&book, doi).unwrap();
+        assert_eq!(paper.authors, vec!["Michael Stonebraker"]);
+        assert_eq!(paper.venue, "MIT Press");
+        assert_eq!(paper.year, "2005");
+
+        // CSL JSON of a dataset from doi.org.
+        let dataset: serde_json::Value = serde_json::from_str(
+            r#"{
+            "type": "dataset",
+            "title": "Traces of a production cluster",
+            "author": [{"literal": "Someone"}, {"family": "Doe", "given": "Jane"}],
+            "publisher": "Zenodo",
+            "issued": {"date-parts": [[2021, 3]]}
+        }"#,
+        )
+        .unwrap();
+        let paper = parse_crossref_work(&dataset, "10.5281/zenodo.1234").unwrap();
+        assert_eq!(paper.title, "Traces of a production cluster");
+        assert_eq!(paper.authors, vec!["Someone", "Jane Doe"]);
+        assert_eq!(paper.venue, "Zenodo");
+        assert_eq!(paper.year, "2021");
+
+        let untitled = serde_json::json!({"title": []});
+        assert!(matches!(
+            parse_crossref_work(&untitled, doi),
+            Err(Fallacy::CurlCannotFindTitle(_))
+        ));
+    }
+
+    #[test]
+    fn attached_pdf_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        let filepath = dir.path().join("vaswani2017attention.pdf");
+        std::fs::write(&filepath, "%PDF").unwrap();
+
+        // The file is already attached, so nothing is downloaded.
+        let url = "https://arxiv.org/pdf/1706.03762.pdf";
+        let downloaded = download_pdf(
+            url,
+            "vaswani2017attention",
+            std::slice::from_ref(&filepath),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(downloaded, filepath);
+        assert_eq!(std::fs::read_to_string(&filepath).unwrap(), "%PDF");
+    }
+
+    #[test]
+    fn batch_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        let mut state = State::from_papers(vec![Paper {
+            title: "Attention Is All You Need".to_owned(),
+            doi: Some("10.48550/arXiv.1706.03762".to_owned()),
+            ..Default::default()
+        }]);
+
+        let links = dir.path().join("links.txt");
+        std::fs::write(
+            &links,
+            "# To triage\n\n  arxiv:1706.03762v7  \nfile:///nonexistent/paper.pdf\n",
+        )
+        .unwrap();
+        let summary = match import_file(&links, Some(false), &mut state, &config).unwrap() {
+            CommandOutput::Message(summary) => summary,
+            _ => panic!("batch import should summarize"),
+        };
+        assert_eq!(state.papers.len(), 1);
+        assert!(summary.contains("duplicate"));
+        assert!(summary.contains("failed"));
+        assert!(!summary.contains("To triage"));
+        assert!(summary.ends_with("0 added, 1 duplicate skipped, 1 failed.\n"));
+
+        assert!(matches!(
+            import_file(&dir.path().join("missing.txt"), None, &mut state, &config),
+            Err(Fallacy::ImportReadFailed(..))
+        ));
+    }
+
+    #[test]
+    fn s2_papers() {
+        assert_eq!(
+            s2_paper_id("10.1145/3341301.3359646"),
+            "DOI:10.1145/3341301.3359646"
+        );
+        assert_eq!(s2_paper_id("arxiv:1706.03762v7"), "ARXIV:1706.03762");
+        assert_eq!(s2_paper_id("10.48550/arXiv.1706.03762"), "ARXIV:1706.03762");
+        assert_eq!(s2_paper_id("CorpusId:13756489"), "CorpusId:13756489");
+
+        let work = serde_json::json!({
+            "paperId": "204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "url": "https://www.semanticscholar.org/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "title": "Attention is All you Need",
+            "venue": "",
+            "year": 2017,
+            "externalIds": {"ArXiv": "1706.03762", "CorpusId": 13756489},
+            "abstract": null,
+            "citationCount": 104000,
+            "authors": [{"authorId": "40348417", "name": "Ashish Vaswani"}]
+        });
+        let mut paper = parse_s2_paper(&work, "ARXIV:1706.03762").unwrap();
+        assert_eq!(paper.title, "Attention is All you Need");
+        assert_eq!(paper.authors, vec!["Ashish Vaswani"]);
+        assert_eq!(paper.venue, "arXiv");
+        assert_eq!(paper.year, "2017");
+        assert_eq!(paper.doi.as_deref(), Some("10.48550/arXiv.1706.03762"));
+        assert_eq!(paper.abstract_text, None);
+        assert_eq!(paper.citation_count, Some(104000));
+
+        // Papers are looked up by DOI first, then by URL.
+        assert_eq!(s2_id_of(&paper).as_deref(), Some("ARXIV:1706.03762"));
+        paper.doi = None;
+        assert_eq!(
+            s2_id_of(&paper).as_deref(),
+            Some("204e3073870fae3d05bcbc2f6a8e263d9b72e776")
+        );
+        paper.url = None;
+        assert_eq!(s2_id_of(&paper), None);
+    }
+
+    #[test]
+    fn retries() {
+        let url = "https://api.crossref.org/works/10.1145/3341301.3359646";
+
+        // Transient failures are retried until they succeed.
+        let mut attempts = 0;
+        let result = retrying(url, 2, Duration::ZERO, || {
+            attempts += 1;
+            match attempts {
+                1 => Err(Fallacy::CurlTimedOut(url.to_owned())),
+                2 => Err(Fallacy::CurlHttpStatus(url.to_owned(), 503)),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // Giving up tells how many attempts were made and the last error.
+        let mut attempts = 0;
+        let result: Result<(), _> = retrying(url, 2, Duration::ZERO, || {
+            attempts += 1;
+            Err(Fallacy::CurlTimedOut(url.to_owned()))
+        });
+        match result {
+            Err(Fallacy::CurlGaveUp(_, 3, last)) => {
+                assert!(matches!(*last, Fallacy::CurlTimedOut(_)))
+            }
+            _ => panic!("should give up after three attempts"),
+        }
+
+        // Other failures are not retried.
+        let mut attempts = 0;
+        let result: Result<(), _> = retrying(url, 2, Duration::ZERO, || {
+            attempts += 1;
+            Err(Fallacy::CurlHttpStatus(url.to_owned(), 404))
+        });
+        assert!(matches!(result, Err(Fallacy::CurlHttpStatus(_, 404))));
+        assert_eq!(attempts, 1);
+    }
+}
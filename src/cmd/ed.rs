@@ -1,30 +1,46 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::Write;
 
 use crate::cmd::prelude::*;
-use crate::utils::confirm;
+use crate::paper::Paper;
+use crate::utils::spawn::{editor_command, spawn};
+use crate::utils::{confirm, take_yes};
 
 pub static MAN: &str = include_str!("../../man/ed.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+
+    // `ed meta` edits metadata instead of notes.
+    let meta = input.args.get(1).map(String::as_str) == Some("meta");
+    if meta && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
     // Build paper list from input.
-    let selected = match input.papers {
+    let paper_list = match input.papers {
         // Papers are given through pipe.
-        Some(list) => list.0,
+        Some(list) => list,
         // Papers are specified as filter.
         None => {
             match crate::cmd::ls::execute(input, state, config)? {
-                CommandOutput::Papers(paper_list) => paper_list.0,
+                CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!("internal ls invocation returned wrong output variant"),
             }
         }
     };
 
+    let selected = state.resolve(&paper_list)?;
+
+    if meta {
+        edit_metadata(&selected, state, config)?;
+        return Ok(CommandOutput::Papers(paper_list));
+    }
+
     // Build a vector of note paths.
     let num_papers = selected.len();
     let mut notes = Vec::new();
@@ -33,45 +49,214 @@ pub fn execute(
     }
 
     // Ask for confirmation.
-    if num_papers > 1 {
+    if num_papers > 1 && !yes {
         confirm(format!("Open notes for {} paper?", num_papers), true)?;
     }
 
     // Open notes.
     if config.output.editor_batch {
-        spawn(build_editor_command(notes.as_ref(), config), true);
+        spawn(editor_command(notes.as_ref(), config), true);
     } else {
         for note in notes {
-            spawn(build_editor_command(&[note], config), false);
+            spawn(editor_command(&[note], config), false);
         }
     }
 
     Ok(CommandOutput::None)
 }
 
-fn spawn(mut command: Command, block: bool) {
-    match command.spawn() {
-        Ok(mut handle) => {
-            if !block {
-                return;
-            }
-            if let Err(e) = handle.wait() {
-                println!("Failed to wait subprocess: {}", e);
+const METADATA_HEADER: &str = "\
+# Edit the fields of the papers below, and save and quit to apply them.
+# Papers are matched by 'id'. Papers cannot be added or removed here.
+";
+
+/// Open the metadata of the papers as a YAML document in the editor, and
+/// apply the edited fields. When the document is invalid, the editor can be
+/// opened again with the edits kept.
+fn edit_metadata(selected: &[usize], state: &mut State, config: &Config) -> Result<(), Fallacy> {
+    let papers: Vec<&Paper> = selected.iter().map(|&ind| &state.papers[ind]).collect();
+    let yaml =
+        serde_yaml::to_string(&papers).map_err(|e| Fallacy::EdInvalidMetadata(e.to_string()))?;
+    let mut file = tempfile::Builder::new()
+        .prefix("reason-")
+        .suffix(".yaml")
+        .tempfile()?;
+    write!(file, "{}{}", METADATA_HEADER, yaml)?;
+    let path = file.path().to_owned();
+
+    let edited = loop {
+        spawn(editor_command(std::slice::from_ref(&path), config), true);
+        let text = std::fs::read_to_string(&path)?;
+        match parse_metadata(&text, selected, state) {
+            Ok(edited) => break edited,
+            Err(message) => {
+                println!("{}", message);
+                if confirm("Reopen the editor?".to_owned(), true).is_err() {
+                    return Err(Fallacy::EdInvalidMetadata(message));
+                }
             }
         }
-        Err(e) => {
-            if matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                println!("Invalid editor command: '{:?}'", e);
-            } else {
-                println!("Failed to spawn subprocess: '{:?}'", e);
+    };
+
+    let changed: Vec<_> = selected
+        .iter()
+        .copied()
+        .zip(edited)
+        .filter(|(ind, paper)| {
+            serde_yaml::to_value(&state.papers[*ind]).ok() != serde_yaml::to_value(paper).ok()
+        })
+        .collect();
+    if changed.is_empty() {
+        println!("No changes.");
+        return Ok(());
+    }
+    let indices: Vec<_> = changed.iter().map(|(ind, _)| *ind).collect();
+    state.snapshot("ed", &indices);
+    for (ind, paper) in changed {
+        state.papers[ind] = paper;
+    }
+    println!("Changed {} papers.", indices.len());
+    Ok(())
+}
+
+/// Parse the edited document into papers in the order of `selected`, or
+/// describe what is wrong with it, with line numbers.
+fn parse_metadata(text: &str, selected: &[usize], state: &State) -> Result<Vec<Paper>, String> {
+    let mut papers: Vec<Paper> = serde_yaml::from_str(text).map_err(|e| match e.location() {
+        Some(location) => format!("Line {}: {}", location.line(), e),
+        None => e.to_string(),
+    })?;
+
+    // Entries are matched by ID, and should be the same ones.
+    let mut ids: Vec<u64> = papers.iter().map(|paper| paper.id).collect();
+    let mut expected: Vec<u64> = selected.iter().map(|&ind| state.papers[ind].id).collect();
+    ids.sort_unstable();
+    expected.sort_unstable();
+    if ids != expected {
+        return Err("Papers cannot be added or removed, and their 'id' cannot change.".to_owned());
+    }
+    papers.sort_by_key(|paper| {
+        selected
+            .iter()
+            .position(|&ind| state.papers[ind].id == paper.id)
+    });
+
+    // Entries start with '- ' at the top level, in the order they were
+    // written.
+    let entry_lines: Vec<usize> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("- "))
+        .map(|(num, _)| num + 1)
+        .collect();
+    let at_line = |paper: &Paper, message: String| {
+        let pos = text_position(text, paper.id);
+        match pos.and_then(|pos| entry_lines.get(pos)) {
+            Some(line) => format!("Line {}: {}", line, message),
+            None => message,
+        }
+    };
+
+    for (i, paper) in papers.iter().enumerate() {
+        if paper.title.trim().is_empty() {
+            return Err(at_line(paper, "The title cannot be empty.".to_owned()));
+        }
+        paper
+            .validate()
+            .map_err(|e| at_line(paper, e.to_string()))?;
+        state
+            .check_unique(paper, selected)
+            .map_err(|e| at_line(paper, e.to_string()))?;
+
+        // Nicknames are unique among all papers, edited or not.
+        let others = state
+            .papers
+            .iter()
+            .enumerate()
+            .filter(|(ind, _)| !selected.contains(ind))
+            .map(|(_, other)| other)
+            .chain(papers.iter().skip(i + 1));
+        for other in others {
+            if let Some(name) = paper
+                .nicknames()
+                .find(|name| other.nicknames().any(|other| other == *name))
+            {
+                return Err(at_line(
+                    paper,
+                    format!("'{}' is already a nickname of '{}'.", name, other.title),
+                ));
             }
         }
     }
+    Ok(papers)
+}
+
+/// Which entry of the document has the paper of the ID, counting from 0.
+fn text_position(text: &str, id: u64) -> Option<usize> {
+    let papers: Vec<serde_yaml::Value> = serde_yaml::from_str(text).ok()?;
+    papers
+        .iter()
+        .position(|paper| paper.get("id").and_then(serde_yaml::Value::as_u64) == Some(id))
 }
 
-fn build_editor_command(notes: &[PathBuf], config: &Config) -> Command {
-    let command = &config.output.editor_command;
-    let mut ret = Command::new(&command[0]);
-    ret.args(&command[1..]).args(notes);
-    ret
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edit_metadata() {
+        let paper = |title: &str, nickname: &str| Paper {
+            title: title.to_owned(),
+            nickname: Some(nickname.to_owned()),
+            authors: vec!["Jaehoon Kim".to_owned()],
+            venue: "OSDI".to_owned(),
+            year: "2022".to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Reason", "reason"),
+            paper("Other", "other"),
+            paper("Third", "third"),
+        ]);
+        let mut config = Config::default();
+        let ed = |script: &str, config: &mut Config, state: &mut State| {
+            config.output.editor_command =
+                vec!["sh".to_owned(), "-c".to_owned(), script.to_owned()];
+            let input = CommandInput {
+                args: vec!["ed".to_owned(), "meta".to_owned()],
+                papers: Some(crate::paper::PaperList(vec![1, 2])),
+            };
+            execute(input, state, config)
+        };
+
+        // Field edits are applied.
+        ed(
+            "sed -i 's/title: Reason/title: Edited/; s/year: .2022./year: \"2021\"/' \"$0\"",
+            &mut config,
+            &mut state,
+        )
+        .unwrap();
+        assert_eq!(state.papers[0].title, "Edited");
+        assert_eq!(state.papers[1].year, "2021");
+        assert_eq!(state.journal.len(), 1);
+
+        // Invalid documents tell where the error is.
+        let selected = [0, 1];
+        let papers: Vec<_> = selected.iter().map(|&ind| &state.papers[ind]).collect();
+        let yaml = serde_yaml::to_string(&papers).unwrap();
+        let message = parse_metadata(
+            &yaml.replace("nickname: other", "nickname: third"),
+            &selected,
+            &state,
+        )
+        .unwrap_err();
+        assert!(message.starts_with("Line ") && message.contains("'third'"));
+        let first = &yaml[..yaml.find("\n- id: 2").unwrap()];
+        let message = parse_metadata(first, &selected, &state).unwrap_err();
+        assert!(message.contains("cannot be added or removed"));
+        let message = parse_metadata(&format!("{}- [", yaml), &selected, &state).unwrap_err();
+        assert!(message.starts_with("Line "));
+        assert!(parse_metadata(&yaml, &selected, &state).is_ok());
+        assert_eq!(state.journal.len(), 1);
+    }
 }
@@ -0,0 +1,302 @@
+use std::path::Path;
+
+use crate::cmd::prelude::*;
+use crate::utils::expand_tilde;
+
+pub static MAN: &str = include_str!("../../man/export.md");
+
+/// What `export` writes.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Bibtex,
+    Html,
+}
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Export accepts a format, a file, and optionally `force`, in order.
+    let (format, path) = match (input.args.get(1), input.args.get(2)) {
+        (Some(format), Some(path)) => (format.clone(), expand_tilde(Path::new(path))?),
+        _ => return Err(Fallacy::ExportNoFile),
+    };
+    let format = match format.as_str() {
+        "bibtex" => Format::Bibtex,
+        "html" => Format::Html,
+        _ => return Err(Fallacy::ExportUnknownFormat(format)),
+    };
+    let force = input.args.get(3).map(String::as_str) == Some("force");
+    if path.exists() && !force {
+        return Err(Fallacy::ExportFileExists(path));
+    }
+    input.args.drain(1..if force { 4 } else { 3 });
+
+    // HTML tables take columns like `ls --cols`.
+    let mut columns = config.output.table_columns.clone();
+    if matches!(input.args.get(1).map(String::as_str), Some("-c" | "--cols")) {
+        match input.args.get(2) {
+            Some(value) => columns = crate::cmd::ls::parse_columns(value)?,
+            None => {
+                return Err(Fallacy::InvalidCommand(
+                    "`export --cols` expects a value.".to_owned(),
+                ))
+            }
+        }
+        if input.papers.is_none() {
+            input.args.drain(1..3);
+        }
+    }
+
+    // Build paper list from input.
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!("ls did not return CommandOutput::Papers."),
+            }
+        }
+    };
+    let selected = state.resolve(&paper_list)?;
+
+    let contents = match format {
+        Format::Bibtex => crate::cmd::printf::bibtex_entries(&selected, state),
+        Format::Html => html_page(&selected, state, &columns) + "\n",
+    };
+    std::fs::write(&path, contents).map_err(|e| Fallacy::ExportWriteFailed(path.clone(), e))?;
+
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    Ok(CommandOutput::Message(format!(
+        "Exported {} papers to '{}'.",
+        selected.len(),
+        path.display()
+    )))
+}
+
+/// A standalone page with a table of the papers, which can be sorted by
+/// clicking a header and searched with the box above it. The reading state
+/// is shown as a badge, in its own column unless 'state' is one.
+fn html_page(selected: &[usize], state: &State, columns: &[String]) -> String {
+    let mut columns = columns.to_vec();
+    if !columns.iter().any(|col| col == "state") {
+        columns.push("state".to_owned());
+    }
+
+    let mut page = String::from(HTML_HEAD);
+    page.push_str("<thead><tr>");
+    for col in columns.iter() {
+        page.push_str(&format!("<th>{}</th>", escape_html(col)));
+    }
+    page.push_str("</tr></thead>\n<tbody>\n");
+    for &ind in selected {
+        let paper = &state.papers[ind];
+        page.push_str("<tr>");
+        for col in columns.iter() {
+            let value = escape_html(&paper.field_as_string(col));
+            if col == "state" {
+                let kind = paper.progress.kind();
+                page.push_str(&format!(
+                    "<td data-sort=\"{}\"><span class=\"badge {}\">{}</span></td>",
+                    kind, kind, value
+                ));
+            } else {
+                page.push_str(&format!("<td>{}</td>", value));
+            }
+        }
+        page.push_str("</tr>\n");
+    }
+    page.push_str("</tbody>\n");
+    page.push_str(HTML_TAIL);
+    page
+}
+
+/// Escape text for HTML elements and attribute values.
+fn escape_html(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Reason</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+input { margin-bottom: 1em; padding: 0.3em; width: 20em; }
+table { border-collapse: collapse; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em 0.8em; text-align: left; }
+th { cursor: pointer; user-select: none; }
+th.asc::after { content: " \25B2"; }
+th.desc::after { content: " \25BC"; }
+.badge { border-radius: 0.8em; padding: 0.1em 0.6em; font-size: 0.9em; }
+.unread { background: #eee; }
+.current { background: #fde68a; }
+.read { background: #bbf7d0; }
+</style>
+</head>
+<body>
+<input id="search" type="search" placeholder="Search papers">
+<table id="papers">
+"#;
+
+const HTML_TAIL: &str = r#"</table>
+<script>
+const table = document.getElementById("papers");
+const body = table.tBodies[0];
+document.getElementById("search").addEventListener("input", (event) => {
+  const query = event.target.value.toLowerCase();
+  for (const row of body.rows) {
+    row.hidden = !row.textContent.toLowerCase().includes(query);
+  }
+});
+const value = (row, col) => {
+  const cell = row.cells[col];
+  return cell.dataset.sort || cell.textContent;
+};
+for (const [col, th] of [...table.tHead.rows[0].cells].entries()) {
+  th.addEventListener("click", () => {
+    const asc = !th.classList.contains("asc");
+    for (const other of table.tHead.rows[0].cells) {
+      other.classList.remove("asc", "desc");
+    }
+    th.classList.add(asc ? "asc" : "desc");
+    const rows = [...body.rows].sort((a, b) => {
+      const x = value(a, col), y = value(b, col);
+      const order = x !== "" && y !== "" && !isNaN(x) && !isNaN(y)
+        ? x - y
+        : x.localeCompare(y);
+      return asc ? order : -order;
+    });
+    body.append(...rows);
+  });
+}
+</script>
+</body>
+</html>"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn export_bibtex() {
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "Reason: A Shell for Papers & Notes".to_owned(),
+                authors: vec!["Jaehyeon Park".to_owned(), "Jaehoon Kim".to_owned()],
+                venue: "arXiv".to_owned(),
+                year: "2022".to_owned(),
+                url: Some("https://arxiv.org/abs/2201.00001".to_owned()),
+                ..Default::default()
+            },
+            Paper {
+                title: "Less is 100% More".to_owned(),
+                authors: vec!["Jaehoon Kim".to_owned()],
+                venue: "IEEE Transactions on Computers".to_owned(),
+                year: "2021".to_owned(),
+                ..Default::default()
+            },
+            Paper {
+                title: "Snake_case".to_owned(),
+                authors: vec!["Jaehoon Kim".to_owned()],
+                venue: "OSDI".to_owned(),
+                year: "2020".to_owned(),
+                ..Default::default()
+            },
+        ]);
+        state.resolve_citekeys();
+        let config = Config::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.bib");
+        let mut export = |force: bool| {
+            let mut args = vec!["export", "bibtex", path.to_str().unwrap()];
+            if force {
+                args.push("force");
+            }
+            let input = CommandInput {
+                args: args.into_iter().map(String::from).collect(),
+                papers: None,
+            };
+            execute(input, &mut state, &config)
+        };
+
+        export(false).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("@misc{park2022reason,"));
+        assert!(contents.contains("  title = {Reason: A Shell for Papers \\& Notes},"));
+        assert!(contents.contains("  author = {Jaehyeon Park and Jaehoon Kim},"));
+        assert!(contents.contains("  eprint = {2201.00001},"));
+        assert!(contents.contains("@article{"));
+        assert!(contents.contains("  title = {Less is 100\\% More},"));
+        assert!(contents.contains("@inproceedings{"));
+        assert!(contents.contains("  title = {Snake\\_case},"));
+
+        // Exported entries can be read back.
+        let entries = crate::bibtex::parse(&contents);
+        assert_eq!(entries.len(), 3);
+        let paper = Paper::from_bibtex_entry(entries[0].as_ref().unwrap()).unwrap();
+        assert_eq!(paper.title, "Reason: A Shell for Papers & Notes");
+        assert_eq!(paper.venue, "arXiv");
+
+        // Files are only overwritten with `force`.
+        assert!(matches!(export(false), Err(Fallacy::ExportFileExists(_))));
+        export(true).unwrap();
+    }
+
+    #[test]
+    fn export_html() {
+        let mut state = State::from_papers(vec![Paper {
+            title: "<Reason> & \"Notes\"".to_owned(),
+            authors: vec!["Jaehyeon Park".to_owned()],
+            progress: crate::paper::ReadingProgress::Read,
+            ..Default::default()
+        }]);
+        let config = Config::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.html");
+        let input = CommandInput {
+            args: [
+                "export",
+                "html",
+                path.to_str().unwrap(),
+                "--cols",
+                "title,year",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            papers: None,
+        };
+
+        // The absolute path is reported.
+        match execute(input, &mut state, &config) {
+            Ok(CommandOutput::Message(message)) => {
+                let path = std::fs::canonicalize(&path).unwrap();
+                assert!(message.contains(&path.display().to_string()))
+            }
+            _ => panic!(),
+        }
+        let page = std::fs::read_to_string(&path).unwrap();
+        assert!(page.contains("<th>title</th><th>year</th><th>state</th>"));
+        assert!(page.contains("<td>&lt;Reason&gt; &amp; &quot;Notes&quot;</td>"));
+        assert!(page.contains("<span class=\"badge read\">read</span>"));
+        assert!(!page.contains("http"));
+    }
+}
@@ -0,0 +1,132 @@
+use regex::RegexBuilder;
+
+use crate::cmd::prelude::*;
+use crate::filter::FilterDiagnostic;
+use crate::paper::PaperList;
+
+pub static MAN: &str = include_str!("../../man/grep.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // The pattern comes right after the command.
+    if input.args.len() < 2 {
+        return Err(Fallacy::GrepNoPattern);
+    }
+    let pattern = input.args.remove(1);
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(config.filter.case_insensitive_regex)
+        .build()
+        .map_err(|e| {
+            let diag = FilterDiagnostic::new(
+                std::slice::from_ref(&pattern),
+                0,
+                "Invalid regex".to_owned(),
+            );
+            Fallacy::FilterRegexInvalid("note", diag, e)
+        })?;
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => match crate::cmd::ls::execute(input, state, config)? {
+            CommandOutput::Papers(paper_list) => paper_list,
+            // `ls` always returns CommandOutput::Papers.
+            _ => panic!(),
+        },
+    };
+
+    // Print matching lines and keep the papers they are from.
+    let mut matched = Vec::new();
+    for ind in state.resolve(&paper_list)? {
+        let paper = &mut state.papers[ind];
+        let wiki = match paper.wikipath(config, false)? {
+            Some(wiki) => wiki,
+            None => continue,
+        };
+        let text = match std::fs::read(&wiki).map(String::from_utf8) {
+            Ok(Ok(text)) if !text.contains('\0') => text,
+            Ok(_) => {
+                println!("Skipped the wiki of {}: not a text file", paper.mention());
+                continue;
+            }
+            Err(e) => {
+                println!("Skipped the wiki of {}: {}", paper.mention(), e);
+                continue;
+            }
+        };
+        let mut found = false;
+        for (num, line) in text.lines().enumerate() {
+            if regex.is_match(line) {
+                println!("{}:{}: {}", paper.mention(), num + 1, line);
+                found = true;
+            }
+        }
+        if found {
+            matched.push(paper.id);
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(matched)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn search_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.wiki"), "= A =\nCache Coherence\n").unwrap();
+        std::fs::write(dir.path().join("b.wiki"), "= B =\nconsensus\n").unwrap();
+        std::fs::write(dir.path().join("c.wiki"), [b'c', 0, 0xff]).unwrap();
+        let mut config = Config::default();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let paper = |nickname: &str, wiki: Option<&str>| Paper {
+            title: nickname.to_uppercase(),
+            nickname: Some(nickname.to_owned()),
+            wikipath: wiki.map(std::path::PathBuf::from),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("a", Some("a.wiki")),
+            paper("b", Some("b.wiki")),
+            paper("c", Some("c.wiki")),
+            paper("d", Some("missing.wiki")),
+            paper("e", None),
+        ]);
+        let ids: Vec<u64> = state.papers.iter().map(|p| p.id).collect();
+        let mut grep = |args: &[&str], papers: Option<Vec<u64>>| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: papers.map(PaperList),
+            };
+            execute(input, &mut state, &config)
+        };
+
+        // The whole library, case-insensitively by default.
+        match grep(&["grep", "cache coherence|consensus"], None) {
+            Ok(CommandOutput::Papers(list)) => assert_eq!(list.0, vec![ids[0], ids[1]]),
+            _ => panic!(),
+        }
+        // Only within the filter or the piped papers.
+        match grep(&["grep", "c", "A"], None) {
+            Ok(CommandOutput::Papers(list)) => assert_eq!(list.0, vec![ids[0]]),
+            _ => panic!(),
+        }
+        match grep(&["grep", "consensus"], Some(vec![ids[0], ids[2]])) {
+            Ok(CommandOutput::Papers(list)) => assert!(list.0.is_empty()),
+            _ => panic!(),
+        }
+
+        assert!(matches!(
+            grep(&["grep", "(unclosed"], None),
+            Err(Fallacy::FilterRegexInvalid("note", _, _))
+        ));
+        assert!(matches!(grep(&["grep"], None), Err(Fallacy::GrepNoPattern)));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::cmd::prelude::*;
+use crate::paper::PaperList;
+
+pub static MAN: &str = include_str!("../../man/head.md");
+
+/// How many papers `head` and `tail` keep when no count is given.
+const DEFAULT_COUNT: usize = 10;
+
+/// The piped papers and the count given after the command, if any.
+fn take_input(
+    input: CommandInput,
+    command: &'static str,
+) -> Result<(PaperList, Option<usize>), Fallacy> {
+    let papers = match input.papers {
+        Some(papers) => papers,
+        None => return Err(Fallacy::HeadNoPapers(command)),
+    };
+    let count = match input.args.get(1..).unwrap_or_default() {
+        [] => None,
+        [count] => match count.parse() {
+            Ok(count) => Some(count),
+            Err(_) => return Err(Fallacy::HeadInvalidCount(count.clone())),
+        },
+        _ => {
+            return Err(Fallacy::InvalidCommand(format!(
+                "`{}` takes at most one count, like `{} 3`.",
+                command, command
+            )))
+        }
+    };
+    Ok((papers, count))
+}
+
+/// Keep the first few papers.
+#[allow(clippy::module_inception)]
+pub mod head {
+    use super::*;
+
+    pub fn execute(
+        input: CommandInput,
+        _state: &mut State,
+        _config: &Config,
+    ) -> Result<CommandOutput, Fallacy> {
+        let (PaperList(mut ids), count) = take_input(input, "head")?;
+        ids.truncate(count.unwrap_or(DEFAULT_COUNT));
+        Ok(CommandOutput::Papers(PaperList(ids)))
+    }
+}
+
+/// Keep the last few papers.
+pub mod tail {
+    use super::*;
+
+    pub fn execute(
+        input: CommandInput,
+        _state: &mut State,
+        _config: &Config,
+    ) -> Result<CommandOutput, Fallacy> {
+        let (PaperList(mut ids), count) = take_input(input, "tail")?;
+        let count = count.unwrap_or(DEFAULT_COUNT);
+        ids.drain(..ids.len().saturating_sub(count));
+        Ok(CommandOutput::Papers(PaperList(ids)))
+    }
+}
+
+/// Shuffle papers, keeping a few of them if a count is given.
+pub mod shuf {
+    use super::*;
+
+    pub fn execute(
+        input: CommandInput,
+        _state: &mut State,
+        _config: &Config,
+    ) -> Result<CommandOutput, Fallacy> {
+        let (PaperList(mut ids), count) = take_input(input, "shuf")?;
+        shuffle(&mut ids, RandomState::new().build_hasher().finish());
+        if let Some(count) = count {
+            ids.truncate(count);
+        }
+        Ok(CommandOutput::Papers(PaperList(ids)))
+    }
+}
+
+/// Fisher-Yates shuffle with a xorshift generator. Any seed but zero is
+/// fine, which is replaced.
+fn shuffle(ids: &mut [u64], seed: u64) {
+    let mut state = seed.max(1);
+    for i in (1..ids.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        ids.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        papers: Option<Vec<u64>>,
+    ) -> Result<Vec<u64>, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: papers.map(PaperList),
+        };
+        match execute(input, &mut State::default(), &Config::default())? {
+            CommandOutput::Papers(PaperList(ids)) => Ok(ids),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn head_and_tail() {
+        let ids: Vec<u64> = (1..=12).collect();
+        let some = || Some(ids.clone());
+        assert_eq!(
+            run(head::execute, &["head", "3"], some()).unwrap(),
+            [1, 2, 3]
+        );
+        assert_eq!(run(head::execute, &["head"], some()).unwrap(), ids[..10]);
+        assert_eq!(
+            run(tail::execute, &["tail", "2"], some()).unwrap(),
+            [11, 12]
+        );
+        assert_eq!(run(tail::execute, &["tail"], some()).unwrap(), ids[2..]);
+        assert_eq!(run(tail::execute, &["tail", "20"], some()).unwrap(), ids);
+        assert!(run(head::execute, &["head", "0"], some())
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            run(head::execute, &["head", "3"], None),
+            Err(Fallacy::HeadNoPapers("head"))
+        ));
+        for count in ["-1", "three", "2.5"] {
+            assert!(matches!(
+                run(tail::execute, &["tail", count], some()),
+                Err(Fallacy::HeadInvalidCount(_))
+            ));
+        }
+        assert!(matches!(
+            run(head::execute, &["head", "1", "2"], some()),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+    }
+
+    #[test]
+    fn shuffled() {
+        let ids: Vec<u64> = (1..=20).collect();
+        let mut shuffled = run(shuf::execute, &["shuf"], Some(ids.clone())).unwrap();
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, ids);
+        let picked = run(shuf::execute, &["shuf", "3"], Some(ids.clone())).unwrap();
+        assert_eq!(picked.len(), 3);
+        assert!(picked.iter().all(|id| ids.contains(id)));
+
+        // The order depends only on the seed.
+        let (mut a, mut b) = (ids.clone(), ids.clone());
+        shuffle(&mut a, 42);
+        shuffle(&mut b, 42);
+        assert_eq!(a, b);
+        assert_ne!(a, ids);
+    }
+}
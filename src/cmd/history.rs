@@ -0,0 +1,91 @@
+//! Command history is kept by the line editor, so `history` itself is run
+//! by the app. This module has its manual and the parts that do not need
+//! the editor.
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/history.md");
+
+/// `history` reads the line editor, which commands cannot reach. Alone, it
+/// is run by the app before getting here.
+pub fn execute(
+    _input: CommandInput,
+    _state: &mut State,
+    _config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    Err(Fallacy::InvalidCommand(
+        "`history` cannot be used with pipes.".to_owned(),
+    ))
+}
+
+/// Number the entries from 1, oldest first.
+pub fn list(entries: &[&str]) -> String {
+    let width = entries.len().to_string().len();
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| format!("{:>width$}  {}\n", index + 1, entry))
+        .collect()
+}
+
+/// Replace a leading `!!` with the last entry, or `!N` with the N-th entry.
+/// The rest of the line is kept, as in `!! | mark`. Returns `None` if the
+/// line does not start with either.
+pub fn expand(line: &str, entries: &[&str]) -> Result<Option<String>, Fallacy> {
+    let line = line.trim_start();
+    let rest = match line.strip_prefix('!') {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+    let (entry, rest) = if let Some(rest) = rest.strip_prefix('!') {
+        match entries.last() {
+            Some(entry) => (*entry, rest),
+            None => return Err(Fallacy::HistoryNoEntry("!".to_owned())),
+        }
+    } else {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return Ok(None);
+        }
+        let (number, rest) = rest.split_at(digits);
+        match number.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= entries.len() => (entries[n - 1], rest),
+            _ => return Err(Fallacy::HistoryNoEntry(number.to_owned())),
+        }
+    };
+    Ok(Some(format!("{}{}", entry, rest)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expand_entries() {
+        let entries = ["ls at OSDI", "mark @reason", "wc"];
+        let expand = |line: &str| expand(line, &entries);
+        assert_eq!(expand("!!").unwrap().as_deref(), Some("wc"));
+        assert_eq!(expand("!1").unwrap().as_deref(), Some("ls at OSDI"));
+        assert_eq!(
+            expand("!1 | wc by year").unwrap().as_deref(),
+            Some("ls at OSDI | wc by year")
+        );
+        assert_eq!(expand("ls !1").unwrap(), None);
+        assert_eq!(expand("!reason").unwrap(), None);
+        for line in ["!0", "!4", "!99999999999999999999999"] {
+            assert!(matches!(expand(line), Err(Fallacy::HistoryNoEntry(_))));
+        }
+        assert!(matches!(
+            super::expand("!!", &[]),
+            Err(Fallacy::HistoryNoEntry(n)) if n == "!"
+        ));
+    }
+
+    #[test]
+    fn list_entries() {
+        let entries: Vec<String> = (1..=10).map(|n| format!("ls {}", n)).collect();
+        let entries: Vec<&str> = entries.iter().map(String::as_str).collect();
+        let listed = list(&entries);
+        assert!(listed.starts_with(" 1  ls 1\n"));
+        assert!(listed.ends_with("10  ls 10\n"));
+    }
+}
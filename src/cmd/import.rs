@@ -0,0 +1,207 @@
+use std::path::Path;
+
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+
+use crate::bibtex;
+use crate::cmd::prelude::*;
+use crate::paper::Paper;
+use crate::utils::expand_tilde;
+
+pub static MAN: &str = include_str!("../../man/import.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let args = input.args;
+    let path = match args.get(1) {
+        Some(path) => Path::new(path),
+        None => return Err(Fallacy::ImportNoFile),
+    };
+
+    // BibTeX files are read entry by entry, and anything else line by line.
+    let is_bibtex = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bib"));
+    if is_bibtex {
+        if let Some(arg) = args.get(2) {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Unexpected argument '{}' after a BibTeX file.",
+                arg
+            )));
+        }
+        return import_bibtex(path, state);
+    }
+
+    let pdf = match args.get(2).map(String::as_str) {
+        None => None,
+        Some("pdf") => Some(true),
+        Some("nopdf") => Some(false),
+        Some(option) => return Err(Fallacy::CurlUnknownOption(option.to_owned())),
+    };
+    crate::cmd::curl::import_file(path, pdf, state, config)
+}
+
+/// Add a paper for every entry in the BibTeX file at `path`. Entries that
+/// are malformed, lack required fields, or are already in the paperbase are
+/// reported and skipped.
+fn import_bibtex(path: &Path, state: &mut State) -> Result<CommandOutput, Fallacy> {
+    let path = expand_tilde(path)?;
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| Fallacy::ImportReadFailed(path.clone(), e))?;
+
+    let mut outcomes = Vec::new();
+    for (i, entry) in bibtex::parse(&contents).into_iter().enumerate() {
+        // Entries that could not be parsed have no citation key to show.
+        let (source, result) = match entry {
+            Ok(entry) => {
+                let result = Paper::from_bibtex_entry(&entry).and_then(|paper| {
+                    paper.validate()?;
+                    state.check_unique(&paper, &[])?;
+                    let title = paper.title.clone();
+                    state.add_paper(paper);
+                    Ok(title)
+                });
+                (entry.key, result)
+            }
+            Err(e) => (format!("entry #{}", i + 1), Err(e)),
+        };
+        outcomes.push((source, Outcome::of(result)));
+    }
+    Ok(CommandOutput::Message(summarize(&outcomes)))
+}
+
+/// How importing a single source in a batch went.
+pub enum Outcome {
+    /// Added a paper with this title.
+    Added(String),
+    /// Skipped because a paper with this title is already there.
+    Duplicate(String),
+    /// Failed for this reason.
+    Failed(String),
+}
+
+impl Outcome {
+    /// Tell apart duplicates from other failures to add a paper.
+    pub fn of(result: Result<String, Fallacy>) -> Self {
+        match result {
+            Ok(title) => Self::Added(title),
+            Err(
+                Fallacy::PaperDuplicateDoi(_, title)
+                | Fallacy::PaperDuplicateAlias(_, title)
+                | Fallacy::PaperDuplicateCitekey(_, title),
+            ) => Self::Duplicate(title),
+            Err(e) => Self::Failed(e.to_string()),
+        }
+    }
+}
+
+/// A table with a row for every source, followed by the counts.
+pub fn summarize(outcomes: &[(String, Outcome)]) -> String {
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(
+        ["Source", "Result", "Details"]
+            .iter()
+            .map(|name| Cell::new(name).add_attribute(Attribute::Bold)),
+    );
+    let (mut added, mut duplicates, mut failed) = (0, 0, 0);
+    for (source, outcome) in outcomes {
+        let (result, details) = match outcome {
+            Outcome::Added(title) => {
+                added += 1;
+                ("added", title)
+            }
+            Outcome::Duplicate(title) => {
+                duplicates += 1;
+                ("duplicate", title)
+            }
+            Outcome::Failed(reason) => {
+                failed += 1;
+                ("failed", reason)
+            }
+        };
+        table.add_row(vec![source.as_str(), result, details.as_str()]);
+    }
+    let mut summary = if outcomes.is_empty() {
+        String::new()
+    } else {
+        table.to_string() + "\n"
+    };
+    summary += &format!(
+        "{} added, {} duplicate{} skipped, {} failed.\n",
+        added,
+        duplicates,
+        if duplicates != 1 { "s" } else { "" },
+        failed
+    );
+    summary
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bibtex_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let bib = dir.path().join("zotero.bib");
+        std::fs::write(
+            &bib,
+            r#"
+@inproceedings{narayanan2019pipedream,
+  title = {{PipeDream}: Generalized Pipeline Parallelism for {DNN} Training},
+  author = {Narayanan, Deepak and Harlap, Aaron},
+  booktitle = {SOSP},
+  year = {2019},
+}
+@misc{untitled2020,
+  title = {A Paper Without Authors},
+  howpublished = {Online},
+  year = {2020},
+}
+@article{vaswani2017attention,
+  title = {Attention Is All You Need},
+  author = {Vaswani, Ashish},
+  journal = {NeurIPS},
+  year = {2017},
+  doi = {10.48550/arXiv.1706.03762},
+}
+@article{broken, title = "unterminated
+"#,
+        )
+        .unwrap();
+        let mut state = State::from_papers(vec![Paper {
+            title: "Attention".to_owned(),
+            doi: Some("10.48550/arxiv.1706.03762".to_owned()),
+            ..Default::default()
+        }]);
+
+        let summary = match import_bibtex(&bib, &mut state).unwrap() {
+            CommandOutput::Message(summary) => summary,
+            _ => panic!("BibTeX import should summarize"),
+        };
+        assert_eq!(state.papers.len(), 2);
+        assert_eq!(
+            state.papers[1].title,
+            "PipeDream: Generalized Pipeline Parallelism for DNN Training"
+        );
+        assert_eq!(
+            state.papers[1].citekey.as_deref(),
+            Some("narayanan2019pipedream")
+        );
+        // Skipped entries are reported by their citation keys.
+        assert!(summary.contains("untitled2020"));
+        assert!(summary.contains("vaswani2017attention"));
+        assert!(summary.ends_with("1 added, 1 duplicate skipped, 2 failed.\n"));
+
+        // Importing again adds nothing.
+        let summary = match import_bibtex(&bib, &mut state).unwrap() {
+            CommandOutput::Message(summary) => summary,
+            _ => panic!("BibTeX import should summarize"),
+        };
+        assert_eq!(state.papers.len(), 2);
+        assert!(summary.ends_with("0 added, 2 duplicates skipped, 2 failed.\n"));
+    }
+}
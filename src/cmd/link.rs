@@ -0,0 +1,114 @@
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/link.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let indices = state.resolve(&paper_list)?;
+    if indices.len() != 2 {
+        return Err(Fallacy::LinkNotTwoPapers(indices.len()));
+    }
+    let (first, second) = (indices[0], indices[1]);
+    if first == second {
+        return Err(Fallacy::LinkSelf);
+    }
+
+    // Links go both ways.
+    let (first_id, second_id) = (state.papers[first].id, state.papers[second].id);
+    let mut changed = false;
+    for (ind, other) in [(first, second_id), (second, first_id)] {
+        let related = &mut state.papers[ind].related;
+        if !related.contains(&other) {
+            related.push(other);
+            changed = true;
+        }
+    }
+
+    Ok(CommandOutput::Message(format!(
+        "{} '{}' and '{}'.\n",
+        if changed { "Linked" } else { "Already linked" },
+        state.papers[first].title,
+        state.papers[second].title,
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, PaperList};
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        papers: Option<Vec<u64>>,
+        state: &mut State,
+    ) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: papers.map(PaperList),
+        };
+        execute(input, state, &Config::default())
+    }
+
+    #[test]
+    fn link_and_rm() {
+        let paper = |title: &str, nickname: &str| Paper {
+            title: title.to_owned(),
+            nickname: Some(nickname.to_owned()),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Deep Residual Learning", "resnet"),
+            paper("Aggregated Residual Transformations", "resnext"),
+            paper("Densely Connected Networks", "densenet"),
+        ]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+
+        run(execute, &["link"], Some(vec![ids[0], ids[1]]), &mut state).unwrap();
+        run(execute, &["link"], Some(vec![ids[2], ids[0]]), &mut state).unwrap();
+        match run(execute, &["link"], Some(vec![ids[1], ids[0]]), &mut state) {
+            Ok(CommandOutput::Message(message)) => assert!(message.starts_with("Already linked")),
+            _ => panic!(),
+        }
+        assert_eq!(state.papers[0].related, vec![ids[1], ids[2]]);
+        assert_eq!(state.papers[1].related, vec![ids[0]]);
+        let details = state.papers[0].details(&state);
+        assert!(details.contains("@resnext") && details.contains("@densenet"));
+
+        assert!(matches!(
+            run(execute, &["link"], Some(vec![ids[0]]), &mut state),
+            Err(Fallacy::LinkNotTwoPapers(1))
+        ));
+        assert!(matches!(
+            run(execute, &["link"], Some(vec![ids[1], ids[1]]), &mut state),
+            Err(Fallacy::LinkSelf)
+        ));
+
+        // Removing a paper drops links to it.
+        run(
+            crate::cmd::rm::execute,
+            &["rm", "Aggregated"],
+            None,
+            &mut state,
+        )
+        .unwrap();
+        assert_eq!(state.papers[0].related, vec![ids[2]]);
+        assert_eq!(state.papers[1].related, vec![ids[0]]);
+    }
+}
@@ -1,23 +1,289 @@
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
+
+use crate::cmd::excerpt_footer;
 use crate::cmd::prelude::*;
-use crate::paper::PaperList;
+use crate::cmd::sort::{sort_papers, SortKey};
+use crate::config::TABLE_COLUMNS;
+use crate::filter::FilterExpr;
+use crate::paper::Paper;
 use crate::state::FilterInst;
 
 pub static MAN: &str = include_str!("../../man/ls.md");
 
+/// How `ls` prints the selected papers.
+#[derive(Debug, PartialEq)]
+enum Format {
+    /// The usual table, or details for a single paper.
+    Table,
+    /// Every field of each paper, one block per paper.
+    Long,
+    /// Every field of every paper, as pretty-printed JSON.
+    Json,
+    /// Common fields as RFC 4180 CSV, with a header row.
+    Csv,
+}
+
+/// Columns of CSV output, as the header row names them.
+const CSV_COLUMNS: [&str; 7] = [
+    "title", "nickname", "authors", "venue", "year", "status", "tags",
+];
+
+/// Options given to `ls`.
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    /// Table columns to use instead of `output.table_columns`.
+    columns: Option<Vec<String>>,
+}
+
+/// Take options like '-o json' off the front of the arguments, right after
+/// the command. '--' ends options, so that filters can start with '-'.
+fn take_options(args: &mut Vec<String>) -> Result<Options, Fallacy> {
+    let mut options = Options {
+        format: Format::Table,
+        columns: None,
+    };
+    while let Some(option) = args.get(1) {
+        match option.as_str() {
+            "-o" | "--output" | "-c" | "--cols" => {
+                let value = match args.get(2) {
+                    Some(value) => value,
+                    None => {
+                        return Err(Fallacy::InvalidCommand(format!(
+                            "`ls {}` expects a value.",
+                            option
+                        )))
+                    }
+                };
+                if matches!(option.as_str(), "-o" | "--output") {
+                    options.format = match value.as_str() {
+                        "table" => Format::Table,
+                        "long" => Format::Long,
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        other => return Err(Fallacy::LsUnknownFormat(other.to_owned())),
+                    };
+                } else {
+                    options.columns = Some(parse_columns(value)?);
+                }
+                args.drain(1..3);
+            }
+            "-l" | "--long" => {
+                options.format = Format::Long;
+                args.remove(1);
+            }
+            "--" => {
+                args.remove(1);
+                break;
+            }
+            _ => break,
+        }
+    }
+    Ok(options)
+}
+
+/// Which part of the selected papers `ls` returns.
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    Head(usize),
+    Tail(usize),
+}
+
+/// Take a trailing 'head N' or 'tail N' off the arguments.
+fn take_limit(args: &mut Vec<String>) -> Option<Limit> {
+    let len = args.len();
+    if len < 3 {
+        return None;
+    }
+    let count = args[len - 1].parse().ok()?;
+    let limit = match args[len - 2].as_str() {
+        "head" => Limit::Head(count),
+        "tail" => Limit::Tail(count),
+        _ => return None,
+    };
+    args.truncate(len - 2);
+    Some(limit)
+}
+
+/// What to group papers by.
+#[derive(Debug, Clone, Copy)]
+pub enum GroupKey {
+    Venue,
+    Year,
+    Tag,
+    Author,
+    Status,
+}
+
+impl GroupKey {
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "venue" => Some(Self::Venue),
+            "year" => Some(Self::Year),
+            "tag" | "label" => Some(Self::Tag),
+            "author" | "first_author" => Some(Self::Author),
+            "status" | "state" => Some(Self::Status),
+            _ => None,
+        }
+    }
+
+    /// Groups the paper belongs to. Papers with several tags are in several
+    /// groups, in the order of their names.
+    pub fn groups_of(self, paper: &Paper) -> Vec<String> {
+        match self {
+            Self::Venue => vec![paper.venue.clone()],
+            Self::Year => vec![paper.year.clone()],
+            Self::Tag => {
+                let mut labels: Vec<_> = paper.labels.iter().cloned().collect();
+                labels.sort_unstable();
+                labels
+            }
+            Self::Author => paper.authors.first().cloned().into_iter().collect(),
+            Self::Status => vec![paper.progress.kind().to_owned()],
+        }
+    }
+}
+
+/// Group papers, keeping their order within each group. Groups are in order
+/// of first appearance if papers were sorted, and by name otherwise. Papers
+/// without a group go to '(none)', which comes last.
+pub fn group_papers(
+    selected: &[usize],
+    papers: &[Paper],
+    key: GroupKey,
+    sorted: bool,
+) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut none = Vec::new();
+    for &ind in selected {
+        let names: Vec<_> = key
+            .groups_of(&papers[ind])
+            .into_iter()
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            none.push(ind);
+        }
+        for name in names {
+            match groups.iter_mut().find(|(group, _)| *group == name) {
+                Some((_, members)) => members.push(ind),
+                None => groups.push((name, vec![ind])),
+            }
+        }
+    }
+    if !sorted {
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    if !none.is_empty() {
+        groups.push(("(none)".to_owned(), none));
+    }
+    groups
+}
+
+/// Take a trailing 'group KEY' off the arguments.
+fn take_group(args: &mut Vec<String>) -> Option<GroupKey> {
+    let len = args.len();
+    if len < 3 || args[len - 2] != "group" {
+        return None;
+    }
+    let key = GroupKey::parse(&args[len - 1])?;
+    args.truncate(len - 2);
+    Some(key)
+}
+
+/// Take a trailing 'sort KEY' or 'sort KEY asc|desc|rev' off the arguments.
+fn take_sort(args: &mut Vec<String>) -> Option<(SortKey, bool)> {
+    let len = args.len();
+    for suffix in [2, 3] {
+        if len <= suffix || args[len - suffix] != "sort" {
+            continue;
+        }
+        let key: SortKey = match args[len - suffix + 1].parse() {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        let descending = match args.get(len - suffix + 2).map(String::as_str) {
+            None => key.default_descending(),
+            Some("asc") => false,
+            Some("desc") | Some("rev") => true,
+            Some(_) => continue,
+        };
+        args.truncate(len - suffix);
+        return Some((key, descending));
+    }
+    None
+}
+
+/// Parse comma-separated table columns like 'title,nickname,year'.
+pub fn parse_columns(columns: &str) -> Result<Vec<String>, Fallacy> {
+    columns
+        .split(',')
+        .map(|col| {
+            let col = col.trim().to_lowercase();
+            if TABLE_COLUMNS.contains(&col.as_str()) {
+                Ok(col)
+            } else {
+                Err(Fallacy::LsUnknownColumn(col))
+            }
+        })
+        .collect()
+}
+
+/// The filter of `cd` combined with the given filter arguments, without
+/// changing the filter of `cd`.
+fn current_filter(args: &[String], state: &State, config: &Config) -> Result<FilterExpr, Fallacy> {
+    let filter_inst = FilterInst::from_args(
+        args,
+        false,
+        &config.filter,
+        &state.saved_filters,
+        &state.papers,
+    )?;
+    Ok(state.filters.observe(filter_inst))
+}
+
+/// How many archived papers match the filter arguments but are hidden
+/// from `ls`.
+pub fn hidden_archived(args: &[String], state: &State, config: &Config) -> Result<usize, Fallacy> {
+    let filter = current_filter(args, state, config)?;
+    Ok(state
+        .papers
+        .iter()
+        .filter(|paper| {
+            paper.archived && !filter.shows_archived(paper) && filter.matches(paper, config)
+        })
+        .count())
+}
+
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    // Convert arguments to a filter
-    let filter_inst = FilterInst::from_args(
-        &input.args[1..],
-        false,
-        config.filter.case_insensitive_regex,
-    )?;
+    // Other commands select papers through `ls`, and only `ls` itself
+    // takes options.
+    let (options, limit, group, order) = if input.args[0] == "ls" {
+        let options = take_options(&mut input.args)?;
+        let limit = take_limit(&mut input.args);
+        let group = take_group(&mut input.args);
+        (options, limit, group, take_sort(&mut input.args))
+    } else {
+        let options = Options {
+            format: Format::Table,
+            columns: None,
+        };
+        (options, None, None, None)
+    };
 
-    // Filter state + argument filter (without modifying the filter state).
-    let filter = state.filters.observe(filter_inst);
+    // Trashed papers are only listed when asked for.
+    if input.args[0] == "ls" && input.args.get(1).map(String::as_str) == Some("trashed") {
+        return list_trash(&input.args[2..], state, config);
+    }
+
+    let filter = current_filter(&input.args[1..], state, config)?;
+
+    // Citation keys depend on other papers, so resolve them before matching.
+    state.resolve_citekeys();
 
     // Filter papers.
     let mut selected = Vec::new();
@@ -28,11 +294,401 @@ pub fn execute(
     // Our filter is not empty.
     else {
         for (ind, paper) in state.papers.iter().enumerate() {
-            if filter.matches(paper) {
+            if filter.matches(paper, config) {
                 selected.push(ind);
             }
         }
     }
+    // Archived papers are hidden unless asked for.
+    selected.retain(|&ind| {
+        let paper = &state.papers[ind];
+        !paper.archived || filter.shows_archived(paper)
+    });
+
+    if let Some((key, descending)) = order {
+        sort_papers(&mut selected, &state.papers, &[(key, descending)]);
+    }
+
+    // Keep only the first or last few papers.
+    let total = selected.len();
+    match limit {
+        Some(Limit::Head(count)) => selected.truncate(count),
+        Some(Limit::Tail(count)) => {
+            selected.drain(..total.saturating_sub(count));
+        }
+        None => {}
+    }
+
+    // Grouped tables.
+    if let (Some(key), Format::Table, None) = (group, &options.format, &options.columns) {
+        let groups = group_papers(&selected, &state.papers, key, order.is_some())
+            .into_iter()
+            .map(|(name, members)| (name, state.paper_list(members)))
+            .collect();
+        return Ok(CommandOutput::Groups(groups));
+    }
+
+    match options.format {
+        // Tables with other columns are printed right away.
+        Format::Table => match (options.columns, limit) {
+            (Some(columns), _) => {
+                let shown = selected.len();
+                let mut table = state
+                    .paper_list(selected)
+                    .into_table(state, config, &columns);
+                if limit.is_some() {
+                    table += &excerpt_footer(shown, total);
+                }
+                Ok(CommandOutput::Message(table))
+            }
+            (None, Some(_)) => Ok(CommandOutput::Excerpt(state.paper_list(selected), total)),
+            (None, None) => Ok(CommandOutput::Papers(state.paper_list(selected))),
+        },
+        Format::Long => {
+            let blocks: Vec<_> = selected
+                .into_iter()
+                .map(|ind| state.papers[ind].detailed_display(config))
+                .collect();
+            Ok(CommandOutput::Message(blocks.join("\n")))
+        }
+        Format::Json => {
+            let papers: Vec<&Paper> = selected.into_iter().map(|ind| &state.papers[ind]).collect();
+            Ok(CommandOutput::Message(
+                serde_json::to_string_pretty(&papers)? + "\n",
+            ))
+        }
+        Format::Csv => {
+            let mut csv = csv_record(CSV_COLUMNS.iter().map(|&col| col.to_owned()));
+            for ind in selected {
+                csv += &csv_record(csv_row(&state.papers[ind]));
+            }
+            Ok(CommandOutput::Message(csv))
+        }
+    }
+}
+
+/// List trashed papers that match the filter, most recently removed first.
+/// They are not in the paperbase, so they are shown as a table that cannot
+/// be piped to other commands.
+fn list_trash(
+    args: &[String],
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let papers: Vec<Paper> = state.trash.iter().map(|t| t.paper.clone()).collect();
+    let filter_inst =
+        FilterInst::from_args(args, false, &config.filter, &state.saved_filters, &papers)?;
+    let filter = state.filters.observe(filter_inst);
+
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(
+        ["title", "first author", "venue", "year", "removed"].map(|s| {
+            Cell::new(s)
+                .set_alignment(CellAlignment::Center)
+                .add_attribute(Attribute::Bold)
+        }),
+    );
+    let mut shown = 0;
+    for trashed in state.trash.iter().rev() {
+        let paper = &trashed.paper;
+        if !filter.is_empty() && !filter.matches(paper, config) {
+            continue;
+        }
+        table.add_row(vec![
+            paper.title.clone(),
+            paper.authors.first().cloned().unwrap_or_default(),
+            paper.venue.clone(),
+            paper.year.clone(),
+            trashed.removed_at.format("%Y-%m-%d %H:%M").to_string(),
+        ]);
+        shown += 1;
+    }
+    if shown == 0 {
+        return Ok(CommandOutput::Message("No trashed papers.\n".to_owned()));
+    }
+    Ok(CommandOutput::Message(format!("{}\n", table)))
+}
+
+/// Fields of a paper in the order of `CSV_COLUMNS`. Authors and tags are
+/// joined by ';'.
+fn csv_row(paper: &Paper) -> impl Iterator<Item = String> {
+    let mut labels: Vec<_> = paper.labels.iter().map(String::as_str).collect();
+    labels.sort_unstable();
+    vec![
+        paper.title.clone(),
+        paper.nickname.clone().unwrap_or_default(),
+        paper.authors.join(";"),
+        paper.venue.clone(),
+        paper.year.clone(),
+        paper.progress.to_string(),
+        labels.join(";"),
+    ]
+    .into_iter()
+}
+
+/// A CSV record ending with CRLF. Fields with commas, quotes, or line breaks
+/// are quoted, and quotes inside are doubled.
+fn csv_record(fields: impl Iterator<Item = String>) -> String {
+    let fields: Vec<_> = fields
+        .map(|field| {
+            if field.contains(&[',', '"', '\r', '\n'][..]) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    fields.join(",") + "\r\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::PaperList;
+
+    fn run(args: &[&str], state: &mut State) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, &Config::default())
+    }
+
+    #[test]
+    fn json_output() {
+        let mut state = State::from_papers(vec![
+            Paper::from_args(
+                [
+                    "touch",
+                    "Reason",
+                    "by",
+                    "Chung",
+                    "at",
+                    "OSDI",
+                    "in",
+                    "2022",
+                    "is",
+                    "shell",
+                    "doi",
+                    "10.1145/3492321.3519584",
+                    "notes=shell, papers",
+                ]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            )
+            .unwrap(),
+            Paper {
+                title: "-Negative Results".to_owned(),
+                ..Default::default()
+            },
+        ]);
+        state.papers[0].added = Some(chrono::Local::now());
+
+        let json = match run(&["ls", "-o", "json", "Reason"], &mut state) {
+            Ok(CommandOutput::Message(json)) => json,
+            _ => panic!(),
+        };
+        let papers: Vec<Paper> = serde_json::from_str(&json).unwrap();
+        assert_eq!(papers.len(), 1);
+        let (parsed, original) = (&papers[0], &state.papers[0]);
+        assert_eq!(parsed.id, original.id);
+        assert_eq!(parsed.title, original.title);
+        assert_eq!(parsed.labels, original.labels);
+        assert_eq!(parsed.doi, original.doi);
+        assert_eq!(parsed.extra, original.extra);
+        assert_eq!(parsed.added, original.added);
+
+        // Filters after '--' may start with '-'.
+        match run(&["ls", "--output", "json", "--", "-Negative"], &mut state) {
+            Ok(CommandOutput::Message(json)) => assert!(json.contains("Negative Results")),
+            _ => panic!(),
+        }
+        assert!(matches!(
+            run(&["ls", "-o", "xml"], &mut state),
+            Err(Fallacy::LsUnknownFormat(_))
+        ));
+    }
 
-    Ok(CommandOutput::Papers(PaperList(selected)))
+    #[test]
+    fn csv_output() {
+        let mut state = State::from_papers(vec![Paper {
+            title: "Reason: A \"Shell\", for Papers".to_owned(),
+            nickname: Some("reason".to_owned()),
+            authors: vec!["Jae-Won Chung".to_owned(), "Jiwon Chung".to_owned()],
+            venue: "OSDI".to_owned(),
+            year: "2022".to_owned(),
+            labels: ["shell".to_owned(), "notes\nand papers".to_owned()].into(),
+            ..Default::default()
+        }]);
+        match run(&["ls", "-o", "csv"], &mut state) {
+            Ok(CommandOutput::Message(csv)) => assert_eq!(
+                csv,
+                "title,nickname,authors,venue,year,status,tags\r\n\
+                 \"Reason: A \"\"Shell\"\", for Papers\",reason,Jae-Won Chung;Jiwon Chung,\
+                 OSDI,2022,unread,\"notes\nand papers;shell\"\r\n"
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn table_columns() {
+        let paper = |title: &str, label: &str| Paper {
+            title: title.to_owned(),
+            authors: vec!["Chung".to_owned()],
+            venue: "OSDI".to_owned(),
+            labels: [label.to_owned()].into(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("Reason", "shell"), paper("Zeus", "energy")]);
+        match run(&["ls", "--cols", "Title, tags"], &mut state) {
+            Ok(CommandOutput::Message(table)) => {
+                assert!(table.contains("tags") && table.contains("energy"));
+                assert!(!table.contains("venue") && !table.contains("OSDI"));
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(
+            run(&["ls", "-c", "title,pages"], &mut state),
+            Err(Fallacy::LsUnknownColumn(col)) if col == "pages"
+        ));
+    }
+
+    #[test]
+    fn long_listing() {
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "Reason".to_owned(),
+                files: vec!["reason.pdf".into()],
+                notepath: Some("reason.md".into()),
+                ..Default::default()
+            },
+            Paper {
+                title: "Zeus".to_owned(),
+                ..Default::default()
+            },
+        ]);
+        let config = Config::default();
+        let input = CommandInput {
+            args: vec!["ls".to_owned(), "-l".to_owned()],
+            papers: None,
+        };
+        match execute(input, &mut state, &config) {
+            Ok(CommandOutput::Message(blocks)) => {
+                assert!(blocks.contains("Reason") && blocks.contains("Zeus"));
+                let file = config.storage.file_dir.join("reason.pdf");
+                assert!(blocks.contains(&file.display().to_string()));
+                assert!(blocks.contains("notes"));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn head_and_tail() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state =
+            State::from_papers(["A1", "A2", "A3", "B"].iter().map(|t| paper(t)).collect());
+        let mut ls = |args: &[&str]| match run(args, &mut state) {
+            Ok(CommandOutput::Excerpt(PaperList(ids), total)) => (ids, total),
+            _ => panic!(),
+        };
+        assert_eq!(ls(&["ls", "A", "head", "2"]), (vec![1, 2], 3));
+        assert_eq!(ls(&["ls", "A", "tail", "2"]), (vec![2, 3], 3));
+        assert_eq!(ls(&["ls", "head", "10"]), (vec![1, 2, 3, 4], 4));
+        assert_eq!(ls(&["ls", "tail", "0"]), (vec![], 4));
+
+        // Only the shown papers are piped, and the footer counts all matches.
+        let output = run(&["ls", "A", "head", "1"], &mut state).unwrap();
+        let input = CommandInput::from_output(
+            vec!["wc".to_owned()],
+            CommandOutput::Excerpt(PaperList(vec![1]), 3),
+        );
+        assert_eq!(input.papers.unwrap().0, vec![1]);
+        assert!(output
+            .into_string(&state, &Config::default())
+            .ends_with("Showing 1 of 3 papers.\n"));
+    }
+
+    #[test]
+    fn sort_suffix() {
+        let paper = |title: &str, year: &str, author: &str| Paper {
+            title: title.to_owned(),
+            year: year.to_owned(),
+            authors: vec![author.to_owned()],
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Zeus", "2023", "Jae-Won Chung"),
+            paper("Reason", "2022", "Chung, Jae-Won"),
+            paper("Sort of Titles", "", "Ann Abbot"),
+        ]);
+        let mut ls = |args: &[&str]| match run(args, &mut state) {
+            Ok(CommandOutput::Papers(PaperList(ids))) => ids,
+            Ok(CommandOutput::Excerpt(PaperList(ids), _)) => ids,
+            _ => panic!(),
+        };
+        assert_eq!(ls(&["ls", "sort", "title"]), vec![2, 3, 1]);
+        // Papers without a year come last in either direction.
+        assert_eq!(ls(&["ls", "sort", "year"]), vec![2, 1, 3]);
+        assert_eq!(ls(&["ls", "sort", "year", "desc", "head", "1"]), vec![1]);
+        assert_eq!(ls(&["ls", "sort", "year", "rev"]), vec![1, 2, 3]);
+        assert_eq!(ls(&["ls", "sort", "author", "desc"]), vec![1, 2, 3]);
+        // 'sort' without a key is part of the filter.
+        assert_eq!(ls(&["ls", "sort"]), vec![3]);
+        assert_eq!(ls(&["ls", "r", "sort", "title", "asc"]), vec![2, 3]);
+    }
+
+    #[test]
+    fn group_by() {
+        let paper = |title: &str, venue: &str, labels: &[&str]| Paper {
+            title: title.to_owned(),
+            venue: venue.to_owned(),
+            labels: labels.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Zeus", "NSDI", &["energy"]),
+            paper("Reason", "", &["shell", "energy"]),
+            paper("Perseus", "SOSP", &[]),
+            paper("Oobleck", "SOSP", &["training"]),
+        ]);
+        let mut ls = |args: &[&str]| match run(args, &mut state) {
+            Ok(CommandOutput::Groups(groups)) => groups
+                .into_iter()
+                .map(|(name, PaperList(ids))| (name, ids))
+                .collect::<Vec<_>>(),
+            _ => panic!(),
+        };
+        let owned = |groups: &[(&str, &[u64])]| {
+            groups
+                .iter()
+                .map(|(name, ids)| (name.to_string(), ids.to_vec()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ls(&["ls", "group", "venue"]),
+            owned(&[("NSDI", &[1]), ("SOSP", &[3, 4]), ("(none)", &[2])])
+        );
+        // Sorted papers keep groups in order of first appearance.
+        assert_eq!(
+            ls(&["ls", "sort", "title", "group", "tag"]),
+            owned(&[
+                ("training", &[4]),
+                ("energy", &[2, 1]),
+                ("shell", &[2]),
+                ("(none)", &[3])
+            ])
+        );
+
+        // Papers in several groups are piped once, in group order.
+        let output = run(&["ls", "group", "tag"], &mut state).unwrap();
+        let input = CommandInput::from_output(vec!["wc".to_owned()], output);
+        assert_eq!(input.papers.unwrap().0, vec![1, 2, 4, 3]);
+    }
 }
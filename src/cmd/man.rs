@@ -1,43 +1,251 @@
+use std::io::{stdout, IsTerminal};
+
+use ansi_term::Style;
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
+
 use crate::cmd::prelude::*;
+use crate::utils::fuzzy_score;
+use crate::utils::spawn::page;
 
 pub static MAN: &str = include_str!("../../man/man.md");
 
 pub fn execute(
     input: CommandInput,
     _state: &mut State,
-    _config: &Config,
+    config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    // List the subjects without an argument.
+    if input.args.len() == 1 || (input.args.len() == 2 && input.args[1] == "topics") {
+        return Ok(CommandOutput::Message(topics()));
+    }
+
     // Man accepts exactly one argument.
     if input.args.len() != 2 {
         return Err(Fallacy::ManInvalidArgument);
     }
 
     // Fetch the man string.
-    let entry = input.args[1].as_ref();
-    let man_str = match entry {
-        "command" => crate::cmd::MAN,
-        "cd" => crate::cmd::cd::MAN,
-        "curl" => crate::cmd::curl::MAN,
-        "ed" => crate::cmd::ed::MAN,
-        "exit" => crate::cmd::exit::MAN,
-        "ls" => crate::cmd::ls::MAN,
-        "man" => crate::cmd::man::MAN,
-        "mark" => crate::cmd::mark::MAN,
-        "open" => crate::cmd::open::MAN,
-        "printf" => crate::cmd::printf::MAN,
-        "pwd" => crate::cmd::pwd::MAN,
-        "rm" => crate::cmd::rm::MAN,
-        "set" => crate::cmd::set::MAN,
-        "sort" => crate::cmd::sort::MAN,
-        "touch" => crate::cmd::touch::MAN,
-        "vimwiki" => crate::cmd::vimwiki::MAN,
-        "wc" => crate::cmd::wc::MAN,
-        "config" => crate::config::MAN,
-        "filter" => crate::filter::MAN,
-        "paper" => crate::paper::MAN,
-        _ => return Err(Fallacy::ManUnknownSubject(entry.to_owned())),
+    let entry = input.args[1].as_str();
+    let man_str = match subjects().iter().find(|(subject, _)| *subject == entry) {
+        Some((_, man_str)) => *man_str,
+        None => return Err(Fallacy::ManUnknownSubject(entry.to_owned(), suggest(entry))),
     };
 
+    // Page through the manual in a terminal, or print it otherwise.
+    if stdout().is_terminal() {
+        let text = if config.output.use_color() {
+            render(man_str)
+        } else {
+            man_str.to_owned()
+        };
+        if page(&text, config) {
+            return Ok(CommandOutput::None);
+        }
+    }
+
     // Build CommandOutput
     Ok(CommandOutput::Message(man_str.to_owned()))
 }
+
+/// Every subject with its manual page.
+fn subjects() -> [(&'static str, &'static str); 44] {
+    [
+        ("command", crate::cmd::MAN),
+        ("alias", crate::cmd::alias::MAN),
+        ("archive", crate::cmd::archive::MAN),
+        ("cd", crate::cmd::cd::MAN),
+        ("check", crate::cmd::check::MAN),
+        ("cite", crate::cmd::cite::MAN),
+        ("curl", crate::cmd::curl::MAN),
+        ("ed", crate::cmd::ed::MAN),
+        ("exit", crate::cmd::exit::MAN),
+        ("export", crate::cmd::export::MAN),
+        ("grep", crate::cmd::grep::MAN),
+        ("head", crate::cmd::head::MAN),
+        ("history", crate::cmd::history::MAN),
+        ("import", crate::cmd::import::MAN),
+        ("link", crate::cmd::link::MAN),
+        ("ls", crate::cmd::ls::MAN),
+        ("man", crate::cmd::man::MAN),
+        ("mark", crate::cmd::mark::MAN),
+        ("mv", crate::cmd::mv::MAN),
+        ("open", crate::cmd::open::MAN),
+        ("pick", crate::cmd::pick::MAN),
+        ("printf", crate::cmd::printf::MAN),
+        ("pwd", crate::cmd::pwd::MAN),
+        ("random", crate::cmd::random::MAN),
+        ("rate", crate::cmd::rate::MAN),
+        ("recent", crate::cmd::recent::MAN),
+        ("refresh", crate::cmd::refresh::MAN),
+        ("rm", crate::cmd::rm::MAN),
+        ("set", crate::cmd::set::MAN),
+        ("shuf", crate::cmd::head::MAN),
+        ("sort", crate::cmd::sort::MAN),
+        ("stat", crate::cmd::stat::MAN),
+        ("sync", crate::cmd::sync::MAN),
+        ("tag", crate::cmd::tag::MAN),
+        ("tail", crate::cmd::head::MAN),
+        ("touch", crate::cmd::touch::MAN),
+        ("unarchive", crate::cmd::archive::MAN),
+        ("undo", crate::cmd::undo::MAN),
+        ("uniq", crate::cmd::uniq::MAN),
+        ("vimwiki", crate::cmd::vimwiki::MAN),
+        ("wc", crate::cmd::wc::MAN),
+        ("config", crate::config::MAN),
+        ("filter", crate::filter::MAN),
+        ("paper", crate::paper::MAN),
+    ]
+}
+
+/// A table of subjects and what their manual pages are about.
+fn topics() -> String {
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(["subject", "description"].map(|s| {
+        Cell::new(s)
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }));
+    for (subject, man_str) in subjects() {
+        table.add_row(vec![subject.to_owned(), summary(man_str)]);
+    }
+    format!("{}\nRun `man [subject]` to read one.\n", table)
+}
+
+/// The first sentence of the page after its usage lines and headers.
+fn summary(man_str: &str) -> String {
+    let paragraph: Vec<_> = man_str
+        .lines()
+        .skip_while(|line| !line.trim().is_empty())
+        .skip_while(|line| line.trim().is_empty() || line.starts_with('#'))
+        .take_while(|line| !line.trim().is_empty() && !line.starts_with("```"))
+        .map(str::trim)
+        .collect();
+    let paragraph = paragraph.join(" ").replace("**", "");
+    match paragraph.find(". ") {
+        Some(end) => paragraph[..end + 1].to_owned(),
+        None => paragraph,
+    }
+}
+
+/// The subject most like the given one, if any is close enough. Both
+/// ways are scored so that short typos are not matched inside long names.
+fn suggest(entry: &str) -> String {
+    let entry = entry.to_lowercase();
+    subjects()
+        .iter()
+        .map(|(subject, _)| {
+            let score = fuzzy_score(&entry, subject).min(fuzzy_score(subject, &entry));
+            (score, *subject)
+        })
+        .filter(|(score, _)| *score >= 0.5)
+        .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(a.1)))
+        .map(|(_, subject)| subject.to_owned())
+        .unwrap_or_default()
+}
+
+/// Style markdown headers and bold text with ANSI escapes. Code blocks
+/// are left as they are.
+fn render(text: &str) -> String {
+    let heading = Style::new().bold().underline();
+    let mut rendered = String::with_capacity(text.len());
+    let mut in_code = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code {
+            let title = line.trim_start_matches('#');
+            if title.len() < line.len() && title.starts_with(' ') {
+                rendered.push_str(&heading.paint(title.trim()).to_string());
+                rendered.push('\n');
+                continue;
+            }
+            rendered.push_str(&embolden(line));
+            rendered.push('\n');
+            continue;
+        }
+        rendered.push_str(line);
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// Replace `**bold**` spans with bold text.
+fn embolden(line: &str) -> String {
+    let mut pieces = line.split("**");
+    let mut ret = pieces.next().unwrap_or_default().to_owned();
+    let rest: Vec<_> = pieces.collect();
+    // An odd number of '**' leaves the last one unmatched.
+    for (index, piece) in rest.iter().enumerate() {
+        if index % 2 == 0 && index + 1 < rest.len() {
+            ret.push_str(&Style::new().bold().paint(*piece).to_string());
+        } else if index % 2 == 0 {
+            ret.push_str("**");
+            ret.push_str(piece);
+        } else {
+            ret.push_str(piece);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn topics_and_suggestions() {
+        let mut state = State::from_papers(Vec::new());
+        let config = Config::default();
+        let man = |args: &[&str], state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            execute(input, state, &config)
+        };
+        for args in [&["man"][..], &["man", "topics"]] {
+            match man(args, &mut state) {
+                Ok(CommandOutput::Message(table)) => {
+                    assert!(table.contains("Move papers from the paperbase to the trash."));
+                    assert!(table.contains("vimwiki"));
+                }
+                _ => panic!(),
+            }
+        }
+        assert_eq!(
+            summary(crate::cmd::cd::MAN),
+            "`cd [filter]` adds the specified filter to the default filter."
+        );
+        assert!(summary(crate::filter::MAN).starts_with("Filters are a collection of regexes"));
+
+        match man(&["man", "mrak"], &mut state) {
+            Err(Fallacy::ManUnknownSubject(_, close)) => assert_eq!(close, "mark"),
+            _ => panic!(),
+        }
+        match man(&["man", "zzzzzz"], &mut state) {
+            Err(Fallacy::ManUnknownSubject(_, close)) => assert!(close.is_empty()),
+            _ => panic!(),
+        }
+        assert!(matches!(
+            man(&["man", "ls", "cd"], &mut state),
+            Err(Fallacy::ManInvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn render_markdown() {
+        let bold = |s: &str| Style::new().bold().paint(s).to_string();
+        let heading = |s: &str| Style::new().bold().underline().paint(s).to_string();
+        let text = "## Default filters\nA **bold** move, **half\n```\n# not a header\n```\n";
+        assert_eq!(
+            render(text),
+            format!(
+                "{}\nA {} move, **half\n```\n# not a header\n```\n",
+                heading("Default filters"),
+                bold("bold")
+            )
+        );
+        assert_eq!(render("#hashtag\n"), "#hashtag\n");
+    }
+}
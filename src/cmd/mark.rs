@@ -1,18 +1,117 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+
+use crate::cmd::dry_run;
 use crate::cmd::prelude::*;
-use crate::paper::ReadingProgress;
-use crate::utils::confirm;
+use crate::paper::{PartialProgress, ReadingProgress};
+use crate::utils::{confirm, take_dry_run, take_yes, today};
 
 pub static MAN: &str = include_str!("../../man/mark.md");
 
+/// Take partial progress like '45%' or 'page 12/30' off the front of the
+/// arguments, right after the command.
+fn take_partial(args: &mut Vec<String>) -> Result<Option<PartialProgress>, Fallacy> {
+    let (spec, len) = match args.get(1).map(String::as_str) {
+        Some(percent) if percent.ends_with('%') => (percent.to_owned(), 1),
+        Some("page") => match args.get(2) {
+            Some(pages) => (format!("page {}", pages), 2),
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    match spec.parse() {
+        Ok(partial) => {
+            args.drain(1..1 + len);
+            Ok(Some(partial))
+        }
+        Err(_) => Err(Fallacy::InvalidCommand(format!(
+            "Invalid reading progress: '{}'. Expected a form like '45%' or 'page 12/30'.",
+            spec
+        ))),
+    }
+}
+
+/// Parse a day like '2024-03-10', 'today', 'yesterday', or '-3d' relative to
+/// `today`. Returns `None` if the argument does not look like a day at all,
+/// so that it can be part of the filter.
+fn parse_day(arg: &str, today: NaiveDate) -> Option<Result<NaiveDate, Fallacy>> {
+    let invalid = || Fallacy::MarkInvalidDate(arg.to_owned());
+    let day = match arg {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        _ if arg.starts_with('-') && arg.ends_with('d') => {
+            // Larger counts are far beyond any date, and overflow `Duration`.
+            let days = match arg[1..arg.len() - 1].parse::<i64>() {
+                Ok(days) if days.abs() <= 100_000 => days,
+                _ => return Some(Err(invalid())),
+            };
+            today.checked_sub_signed(Duration::days(days))
+        }
+        _ if arg.len() > 4
+            && arg[..4].chars().all(|c| c.is_ascii_digit())
+            && arg.chars().all(|c| c.is_ascii_digit() || c == '-') =>
+        {
+            NaiveDate::parse_from_str(arg, "%Y-%m-%d").ok()
+        }
+        _ => return None,
+    };
+    match day {
+        Some(day) if day > today => Some(Err(Fallacy::MarkFutureDate(arg.to_owned()))),
+        Some(day) => Some(Ok(day)),
+        None => Some(Err(invalid())),
+    }
+}
+
+/// Take the day papers were read, like 'read 2024-03-10' or 'yesterday', off
+/// the front of the arguments, right after the command and partial progress.
+fn take_date(args: &mut Vec<String>, today: NaiveDate) -> Result<Option<NaiveDate>, Fallacy> {
+    let skip = (args.get(1).map(String::as_str) == Some("read")) as usize;
+    let day = match args.get(1 + skip).and_then(|arg| parse_day(arg, today)) {
+        Some(day) => day?,
+        None => return Ok(None),
+    };
+    args.drain(1..2 + skip);
+    Ok(Some(day))
+}
+
+/// The moment on `day` at the current time of day. Today is right now.
+fn moment_on(day: NaiveDate) -> DateTime<Local> {
+    let now = Local::now();
+    Local
+        .from_local_datetime(&day.and_time(now.time()))
+        .earliest()
+        .unwrap_or(now)
+}
+
+/// '1 paper' or 'N papers'.
+fn papers(num: usize) -> String {
+    format!("{} {}", num, if num != 1 { "papers" } else { "paper" })
+}
+
 /// Mark an article as finished and highlight it as Red.
+#[allow(clippy::module_inception)]
 pub mod mark {
     use super::*;
 
     pub fn execute(
-        input: CommandInput,
+        mut input: CommandInput,
         state: &mut State,
         config: &Config,
     ) -> Result<CommandOutput, Fallacy> {
+        let yes = take_yes(&mut input.args) || config.general.assume_yes;
+        let preview = take_dry_run(&mut input.args);
+
+        // Partial progress marks papers as currently reading instead, unless
+        // it reaches the end.
+        let progress = match take_partial(&mut input.args)? {
+            Some(partial) if !partial.is_complete() => ReadingProgress::InProgress(Some(partial)),
+            _ => ReadingProgress::Read,
+        };
+        // Papers read some other day are recorded as read then.
+        let when = match take_date(&mut input.args, today())? {
+            Some(day) => moment_on(day),
+            None => Local::now(),
+        };
+
         let paper_list = match input.papers {
             // Papers are given through pipe.
             Some(list) => list,
@@ -26,20 +125,34 @@ pub mod mark {
             }
         };
 
+        let indices = state.resolve(&paper_list)?;
+
+        let num_paper = indices.len();
+        if preview {
+            let change = format!("mark {} as {}", papers(num_paper), progress);
+            return Ok(dry_run(&change, paper_list));
+        }
+
         // Ask for confirmation.
-        let num_paper = paper_list.0.len();
-        if num_paper > 1 {
-            confirm(format!("Mark {} papers as read?", num_paper), false)?;
+        if num_paper > 1 && !yes {
+            confirm(format!("Mark {} papers as {}?", num_paper, progress), false)?;
         }
 
-        for ind in paper_list.0 {
-            state.papers[ind].progress = ReadingProgress::Read;
+        state.snapshot("mark", &indices);
+        let who = config.general.identity();
+        for ind in indices {
+            state.papers[ind].set_progress_at(progress, when, who.as_deref());
         }
 
         Ok(CommandOutput::Message(format!(
-            "Marked {} {} as finished.\n",
+            "Marked {} {} as {}.\n",
             num_paper,
             if num_paper != 1 { "papers" } else { "paper" },
+            if progress == ReadingProgress::Read {
+                "finished".to_owned()
+            } else {
+                progress.to_string()
+            },
         )))
     }
 }
@@ -49,10 +162,13 @@ pub mod unmark {
     use super::*;
 
     pub fn execute(
-        input: CommandInput,
+        mut input: CommandInput,
         state: &mut State,
         config: &Config,
     ) -> Result<CommandOutput, Fallacy> {
+        let yes = take_yes(&mut input.args) || config.general.assume_yes;
+        let preview = take_dry_run(&mut input.args);
+
         let paper_list = match input.papers {
             // Papers are given through pipe.
             Some(list) => list,
@@ -66,14 +182,23 @@ pub mod unmark {
             }
         };
 
+        let indices = state.resolve(&paper_list)?;
+
+        let num_paper = indices.len();
+        if preview {
+            let change = format!("mark {} as unread", papers(num_paper));
+            return Ok(dry_run(&change, paper_list));
+        }
+
         // Ask for confirmation.
-        let num_paper = paper_list.0.len();
-        if num_paper > 1 {
+        if num_paper > 1 && !yes {
             confirm(format!("Mark {} papers as unread?", num_paper), false)?;
         }
 
-        for ind in paper_list.0 {
-            state.papers[ind].progress = ReadingProgress::Unread;
+        state.snapshot("unmark", &indices);
+        let who = config.general.identity();
+        for ind in indices {
+            state.papers[ind].set_progress(ReadingProgress::Unread, who.as_deref());
         }
 
         Ok(CommandOutput::Message(format!(
@@ -89,10 +214,14 @@ pub mod current {
     use super::*;
 
     pub fn execute(
-        input: CommandInput,
+        mut input: CommandInput,
         state: &mut State,
         config: &Config,
     ) -> Result<CommandOutput, Fallacy> {
+        let yes = take_yes(&mut input.args) || config.general.assume_yes;
+        let preview = take_dry_run(&mut input.args);
+        let progress = ReadingProgress::InProgress(take_partial(&mut input.args)?);
+
         let paper_list = match input.papers {
             // Papers are given through pipe.
             Some(list) => list,
@@ -106,17 +235,26 @@ pub mod current {
             }
         };
 
+        let indices = state.resolve(&paper_list)?;
+
+        let num_paper = indices.len();
+        if preview {
+            let change = format!("mark {} as {}", papers(num_paper), progress);
+            return Ok(dry_run(&change, paper_list));
+        }
+
         // Ask for confirmation.
-        let num_paper = paper_list.0.len();
-        if num_paper > 1 {
+        if num_paper > 1 && !yes {
             confirm(
                 format!("Mark {} papers as currently reading?", num_paper),
                 false,
             )?;
         }
 
-        for ind in paper_list.0 {
-            state.papers[ind].progress = ReadingProgress::InProgress;
+        state.snapshot("current", &indices);
+        let who = config.general.identity();
+        for ind in indices {
+            state.papers[ind].set_progress(progress, who.as_deref());
         }
 
         Ok(CommandOutput::Message(format!(
@@ -126,3 +264,210 @@ pub mod current {
         )))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        state: &mut State,
+    ) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, &Config::default())
+    }
+
+    fn state() -> State {
+        let paper = |title: &str, venue: &str| Paper {
+            title: title.to_owned(),
+            venue: venue.to_owned(),
+            ..Default::default()
+        };
+        State::from_papers(vec![paper("Reason", "OSDI"), paper("Shadowtutor", "SOSP")])
+    }
+
+    fn progress(state: &State) -> Vec<ReadingProgress> {
+        state.papers.iter().map(|p| p.progress).collect()
+    }
+
+    #[test]
+    fn mark_respects_working_filter() {
+        use ReadingProgress::*;
+        let mut state = state();
+        run(crate::cmd::cd::execute, &["cd", "at", "OSDI"], &mut state).unwrap();
+        run(mark::execute, &["mark"], &mut state).unwrap();
+        assert_eq!(progress(&state), vec![Read, Unread]);
+
+        // Filters given to `mark` are AND'ed with the working filter.
+        run(current::execute, &["current", "Shadowtutor"], &mut state).unwrap();
+        assert_eq!(progress(&state), vec![Read, Unread]);
+
+        // Leaving the working filter selects every paper again.
+        run(crate::cmd::cd::execute, &["cd", "/"], &mut state).unwrap();
+        run(current::execute, &["current", "Shadowtutor"], &mut state).unwrap();
+        assert_eq!(progress(&state), vec![Read, InProgress(None)]);
+
+        // Dry runs change nothing, even without -y.
+        let changes = state.journal.len();
+        for execute in [mark::execute, unmark::execute, current::execute] {
+            assert!(matches!(
+                run(execute, &["mark", "dry-run"], &mut state),
+                Ok(CommandOutput::Papers(list)) if list.0.len() == 2
+            ));
+        }
+        assert_eq!(progress(&state), vec![Read, InProgress(None)]);
+        assert_eq!(state.journal.len(), changes);
+    }
+
+    #[test]
+    fn partial_progress() {
+        use ReadingProgress::*;
+        let mut state = state();
+        run(mark::execute, &["mark", "45%", "Reason"], &mut state).unwrap();
+        run(
+            current::execute,
+            &["current", "page", "12/30", "Shadowtutor"],
+            &mut state,
+        )
+        .unwrap();
+        assert_eq!(
+            progress(&state),
+            vec![
+                InProgress(Some(PartialProgress::Percent(45))),
+                InProgress(Some(PartialProgress::Pages(12, Some(30)))),
+            ]
+        );
+        assert_eq!(
+            state.papers[1].field_as_string("state"),
+            "current (40%, p. 12/30)"
+        );
+
+        // Marking as read clears partial progress.
+        run(mark::execute, &["mark", "Reason"], &mut state).unwrap();
+        run(mark::execute, &["mark", "100%", "Shadowtutor"], &mut state).unwrap();
+        assert_eq!(progress(&state), vec![Read, Read]);
+        run(unmark::execute, &["unmark", "Shadowtutor"], &mut state).unwrap();
+        assert!(matches!(
+            run(mark::execute, &["mark", "page", "30/30", "Shadowtutor"], &mut state),
+            Ok(CommandOutput::Message(message)) if message == "Marked 1 paper as finished.\n"
+        ));
+        assert_eq!(progress(&state), vec![Read, Read]);
+
+        for bad in ["150%", "x%"] {
+            assert!(matches!(
+                run(mark::execute, &["mark", bad], &mut state),
+                Err(Fallacy::InvalidCommand(_))
+            ));
+        }
+        assert!(run(current::execute, &["current", "page", "31/30"], &mut state).is_err());
+    }
+
+    #[test]
+    fn read_on_date() {
+        let wed = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        let day = |arg: &str| parse_day(arg, wed).map(|day| day.ok());
+        let date = |y, m, d| Some(NaiveDate::from_ymd_opt(y, m, d));
+        assert_eq!(day("2024-03-10"), date(2024, 3, 10));
+        assert_eq!(day("today"), date(2024, 3, 13));
+        assert_eq!(day("yesterday"), date(2024, 3, 12));
+        assert_eq!(day("-3d"), date(2024, 3, 10));
+        assert_eq!(day("Reason"), None);
+        assert_eq!(day("2024"), None);
+        assert!(matches!(
+            parse_day("2024-02-30", wed),
+            Some(Err(Fallacy::MarkInvalidDate(_)))
+        ));
+        assert!(matches!(
+            parse_day("-xd", wed),
+            Some(Err(Fallacy::MarkInvalidDate(_)))
+        ));
+        assert!(matches!(
+            parse_day("2024-03-14", wed),
+            Some(Err(Fallacy::MarkFutureDate(_)))
+        ));
+
+        let mut state = state();
+        for huge in ["-200000000000000d", "--200000000000000d"] {
+            assert!(matches!(
+                run(mark::execute, &["mark", huge], &mut state),
+                Err(Fallacy::MarkInvalidDate(_))
+            ));
+        }
+        run(
+            mark::execute,
+            &["mark", "read", "2024-03-10", "Reason"],
+            &mut state,
+        )
+        .unwrap();
+        run(mark::execute, &["mark", "-2d", "Shadowtutor"], &mut state).unwrap();
+        let read_on = |paper: &crate::paper::Paper| paper.read_at.unwrap().naive_local().date();
+        assert_eq!(read_on(&state.papers[0]), NaiveDate::from_ymd(2024, 3, 10));
+        assert_eq!(
+            state.papers[0].history[0].when,
+            state.papers[0].read_at.unwrap()
+        );
+        assert_eq!(
+            read_on(&state.papers[1]),
+            today().checked_sub_signed(Duration::days(2)).unwrap()
+        );
+
+        // Without a date, papers are read right now.
+        run(mark::execute, &["mark", "Reason"], &mut state).unwrap();
+        assert_eq!(read_on(&state.papers[0]), today());
+        assert!(matches!(
+            run(mark::execute, &["mark", "2999-01-01", "Reason"], &mut state),
+            Err(Fallacy::MarkFutureDate(_))
+        ));
+    }
+
+    #[test]
+    fn yes_skips_confirmation() {
+        use ReadingProgress::*;
+        let mut state = state();
+        run(mark::execute, &["mark", "-y", "45%"], &mut state).unwrap();
+        assert_eq!(
+            progress(&state),
+            vec![InProgress(Some(PartialProgress::Percent(45))); 2]
+        );
+        run(unmark::execute, &["unmark", "yes"], &mut state).unwrap();
+        assert_eq!(progress(&state), vec![Unread, Unread]);
+
+        let mut config = Config::default();
+        config.general.assume_yes = true;
+        let input = CommandInput {
+            args: vec!["current".to_owned()],
+            papers: None,
+        };
+        current::execute(input, &mut state, &config).unwrap();
+        assert_eq!(progress(&state), vec![InProgress(None), InProgress(None)]);
+    }
+
+    #[test]
+    fn cd_stack() {
+        let mut state = state();
+        let pwd = |state: &mut State| match run(crate::cmd::pwd::execute, &["pwd"], state) {
+            Ok(CommandOutput::Message(message)) => message,
+            _ => panic!(),
+        };
+        run(
+            crate::cmd::cd::execute,
+            &["cd", "at", "OSDI|SOSP"],
+            &mut state,
+        )
+        .unwrap();
+        run(crate::cmd::cd::execute, &["cd", "Reason"], &mut state).unwrap();
+        assert_eq!(
+            pwd(&mut state),
+            "1  venue matches 'OSDI|SOSP'\n2  title matches 'Reason'\n"
+        );
+        run(crate::cmd::cd::execute, &["cd", ".."], &mut state).unwrap();
+        assert_eq!(pwd(&mut state), "1  venue matches 'OSDI|SOSP'\n");
+        run(crate::cmd::cd::execute, &["cd", "/"], &mut state).unwrap();
+        assert_eq!(pwd(&mut state), "No filters are active.\n");
+    }
+}
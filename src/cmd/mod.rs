@@ -1,23 +1,44 @@
 use crate::config::Config;
 use crate::error::Fallacy;
-use crate::paper::PaperList;
+use crate::paper::{Paper, PaperList};
 use crate::state::State;
 
+pub mod alias;
+mod archive;
 mod cd;
+mod check;
+mod cite;
 mod curl;
 mod ed;
 mod exit;
+mod export;
+mod grep;
+mod head;
+pub mod history;
+mod import;
+mod link;
 mod ls;
 mod man;
 mod mark;
+mod mv;
 mod open;
+mod pick;
 pub mod prelude;
 mod printf;
 mod pwd;
+mod random;
+mod rate;
+mod recent;
+mod refresh;
 mod rm;
 mod set;
 mod sort;
+mod stat;
+mod sync;
+mod tag;
 mod touch;
+mod undo;
+mod uniq;
 mod vimwiki;
 mod wc;
 
@@ -33,6 +54,14 @@ pub struct CommandInput {
 pub enum CommandOutput {
     None,
     Papers(PaperList),
+    /// Part of a longer paper list, with the length of the whole list.
+    Excerpt(PaperList, usize),
+    /// Papers in named groups. Piped on as one list, in group order.
+    Groups(Vec<(String, PaperList)>),
+    /// Papers shown in a table with the given columns. Piped on as they are.
+    Table(PaperList, Vec<String>),
+    /// Papers shown with every field. Piped on as they are.
+    Details(PaperList),
     Message(String),
 }
 
@@ -42,40 +71,143 @@ impl CommandInput {
             CommandOutput::None => None,
             CommandOutput::Message(_) => None,
             CommandOutput::Papers(p) => Some(p),
+            CommandOutput::Excerpt(p, _) => Some(p),
+            CommandOutput::Table(p, _) => Some(p),
+            CommandOutput::Details(p) => Some(p),
+            CommandOutput::Groups(groups) => {
+                // Papers in several groups are passed on once.
+                let mut ids = Vec::new();
+                for id in groups.into_iter().flat_map(|(_, p)| p.0) {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+                Some(PaperList(ids))
+            }
         };
         Self { args, papers }
     }
 }
 
+/// The footer line of a paper list cut short with `head` or `tail`.
+pub fn excerpt_footer(shown: usize, total: usize) -> String {
+    format!(
+        "Showing {} of {} {}.\n",
+        shown,
+        total,
+        if total != 1 { "papers" } else { "paper" }
+    )
+}
+
+/// Papers a command skipped, grouped by why.
+#[derive(Default)]
+pub struct Skipped(Vec<(String, Vec<String>)>);
+
+impl Skipped {
+    pub fn add(&mut self, reason: &str, paper: &Paper) {
+        let name = paper.mention();
+        match self.0.iter_mut().find(|(r, _)| r == reason) {
+            Some((_, names)) if names.contains(&name) => {}
+            Some((_, names)) => names.push(name),
+            None => self.0.push((reason.to_owned(), vec![name])),
+        }
+    }
+
+    /// Report skipped papers after the rest were handled, like 'Opened 4'.
+    /// Fails if every paper was skipped.
+    pub fn report(self, done: String, num_done: usize) -> Result<(), Fallacy> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let skipped = Fallacy::PapersSkipped(done, self.0);
+        if num_done == 0 {
+            return Err(skipped);
+        }
+        println!("{}", skipped);
+        Ok(())
+    }
+}
+
+/// Say what a command would have done to the papers, and pass them on
+/// without changing anything.
+pub fn dry_run(change: &str, papers: PaperList) -> CommandOutput {
+    println!("Dry run \u{2014} nothing changed. Would {}:", change);
+    CommandOutput::Papers(papers)
+}
+
 impl CommandOutput {
     pub fn into_string(self, state: &State, config: &Config) -> String {
         match self {
             CommandOutput::None => "".to_string(),
             CommandOutput::Message(s) => s,
             CommandOutput::Papers(p) => p.into_string(state, config),
+            CommandOutput::Excerpt(p, total) => {
+                let shown = p.0.len();
+                p.into_string(state, config) + &excerpt_footer(shown, total)
+            }
+            CommandOutput::Table(p, columns) => p.into_table(state, config, &columns),
+            CommandOutput::Details(p) => p.into_details(state),
+            CommandOutput::Groups(groups) => {
+                let mut output = String::new();
+                for (name, p) in groups {
+                    let count = p.0.len();
+                    output += &format!(
+                        "{} ({} {})\n",
+                        name,
+                        count,
+                        if count != 1 { "papers" } else { "paper" }
+                    );
+                    output += &p.into_table(state, config, &config.output.table_columns);
+                }
+                output
+            }
         }
     }
 }
 
 pub fn to_executor(command: String) -> Result<ExecuteFn, Fallacy> {
     match command.as_ref() {
+        "alias" => Ok(alias::execute),
+        "archive" => Ok(archive::archive::execute),
+        "unarchive" => Ok(archive::unarchive::execute),
         "cd" => Ok(cd::execute),
+        "check" => Ok(check::execute),
+        "cite" => Ok(cite::execute),
         "curl" => Ok(curl::execute),
         "exit" => Ok(exit::execute),
+        "export" => Ok(export::execute),
+        "grep" => Ok(grep::execute),
+        "head" => Ok(head::head::execute),
+        "history" => Ok(history::execute),
+        "import" => Ok(import::execute),
+        "link" => Ok(link::execute),
         "ls" => Ok(ls::execute),
         "man" => Ok(man::execute),
+        "mv" => Ok(mv::execute),
         "open" => Ok(open::execute),
+        "pick" => Ok(pick::execute),
         "printf" => Ok(printf::execute),
         "pwd" => Ok(pwd::execute),
+        "random" => Ok(random::execute),
+        "rate" => Ok(rate::execute),
+        "recent" => Ok(recent::execute),
+        "refresh" => Ok(refresh::execute),
         "ed" => Ok(ed::execute),
         "rm" => Ok(rm::execute),
         "set" => Ok(set::execute),
         "sort" => Ok(sort::execute),
+        "stat" => Ok(stat::execute),
+        "sync" => Ok(sync::execute),
+        "tag" => Ok(tag::execute),
         "touch" => Ok(touch::execute),
+        "undo" => Ok(undo::execute),
+        "uniq" => Ok(uniq::execute),
         "vimwiki" => Ok(vimwiki::execute),
         "mark" => Ok(mark::mark::execute),
         "unmark" => Ok(mark::unmark::execute),
         "current" => Ok(mark::current::execute),
+        "tail" => Ok(head::tail::execute),
+        "shuf" => Ok(head::shuf::execute),
         "wc" => Ok(wc::execute),
         _ => Err(Fallacy::UnknownCommand(command.to_owned())),
     }
@@ -85,8 +217,10 @@ pub fn to_executor(command: String) -> Result<ExecuteFn, Fallacy> {
 ///
 /// Reason implements its own command line parser.
 /// By default arguments are delimited with whitespace, but they can be chunked
-/// by grouping them in single quotes.
-/// Literal single quotes can be entered by escaping them with a backslash.
+/// by grouping them in single or double quotes. Inside one kind of quotes, the
+/// other kind is merely a character.
+/// Literal quotes can be entered by escaping them with a backslash. Other
+/// backslashes are kept as is, so that regexes like `\d` need no escaping.
 ///
 /// Multiple commands can be piped with the pipe(`|`) character. Pipes should
 /// only come between two commands.
@@ -95,7 +229,8 @@ pub fn parse_command(command: &str) -> Result<Vec<Vec<String>>, Fallacy> {
     let mut current_cmd: Vec<String> = Vec::new();
     let mut parsed_cmds: Vec<Vec<String>> = Vec::new(); // final result
 
-    let mut inside_quotes = false; // whether we're inside single quotes
+    let mut quote: Option<char> = None; // the quote we're inside, if any
+    let mut quoted = false; // whether the current piece had quotes, like ""
     let mut command_iter = command.chars().peekable();
 
     // A helper closure that consumes all whitespaces from an char peekable iterator.
@@ -121,14 +256,21 @@ pub fn parse_command(command: &str) -> Result<Vec<Vec<String>>, Fallacy> {
 
     // Parse commands.
     while let Some(c) = command_iter.next() {
-        // Escaped single quote
-        if c == '\\' && command_iter.peek() == Some(&'\'') {
-            command_iter.next();
-            current_piece.push('\'');
+        let inside_quotes = quote.is_some();
+        // Escaped quote
+        if c == '\\' && matches!(command_iter.peek(), Some('\'') | Some('"')) {
+            current_piece.push(command_iter.next().unwrap());
         }
-        // Unescaped single quote
-        else if c == '\'' {
-            inside_quotes = !inside_quotes;
+        // Unescaped quote
+        else if c == '\'' || c == '"' {
+            match quote {
+                None => {
+                    quote = Some(c);
+                    quoted = true;
+                }
+                Some(q) if q == c => quote = None,
+                Some(_) => current_piece.push(c),
+            }
         }
         // Pipe
         // If we're inside quotes, this is merely a character part of a regex.
@@ -138,9 +280,10 @@ pub fn parse_command(command: &str) -> Result<Vec<Vec<String>>, Fallacy> {
                 current_piece.push(c);
             } else {
                 // Wrap up the previous command and start a new one.
-                if !current_piece.is_empty() {
+                if !current_piece.is_empty() || quoted {
                     current_cmd.push(String::new());
                     std::mem::swap(current_cmd.last_mut().unwrap(), &mut current_piece);
+                    quoted = false;
                 }
                 // Pipe encountered when `current_cmd` is empty: double pipes!
                 if current_cmd.is_empty() {
@@ -161,9 +304,10 @@ pub fn parse_command(command: &str) -> Result<Vec<Vec<String>>, Fallacy> {
                 consume_whitespace(&mut command_iter);
 
                 // Wrap up the previous piece and start a new one.
-                if !current_piece.is_empty() {
+                if !current_piece.is_empty() || quoted {
                     current_cmd.push(String::new());
                     std::mem::swap(current_cmd.last_mut().unwrap(), &mut current_piece);
+                    quoted = false;
                 }
             }
         }
@@ -172,8 +316,11 @@ pub fn parse_command(command: &str) -> Result<Vec<Vec<String>>, Fallacy> {
             current_piece.push(c);
         }
     }
-    // No need to push empty pieces.
-    if !current_piece.is_empty() {
+    if let Some(q) = quote {
+        return Err(Fallacy::UnterminatedQuote(q));
+    }
+    // No need to push empty pieces, unless they are quoted like "".
+    if !current_piece.is_empty() || quoted {
         current_cmd.push(current_piece);
     }
     // Command ended with a pipe.
@@ -242,10 +389,26 @@ mod test {
         r"  ls  ' shadow| tutor\'' | 'printf ' 	 this\' paper  ",
         Ok(vec![vec!["ls", " shadow| tutor'"], vec!["printf ", "this'", "paper"]])
     );
+    parse_test!(quote_double:
+        r#"ls by "John Doe" at 'ICML'"#,
+        Ok(vec![vec!["ls", "by", "John Doe", "at", "ICML"]])
+    );
+    parse_test!(quote_nested:
+        r#"ls "it's all" 'say "hi"' "\"esc\" \d+""#,
+        Ok(vec![vec!["ls", "it's all", r#"say "hi""#, r#""esc" \d+"#]])
+    );
+    parse_test!(quote_bare_title:
+        r#"ls "attention is all" | open"#,
+        Ok(vec![vec!["ls", "attention is all"], vec!["open"]])
+    );
     parse_test!(empty:
         "",
         Ok(vec![vec![]])
     );
+    parse_test!(quote_empty:
+        r#"ls "" at ''|wc"#,
+        Ok(vec![vec!["ls", "", "at", ""], vec!["wc"]])
+    );
 
     // Wrong commands
     parse_test!(double_pipe:
@@ -260,6 +423,14 @@ mod test {
         "ls shadowtutor | ",
         Err(Fallacy::InvalidCommand("Command cannot end with a dangling pipe.".to_owned()))
     );
+    parse_test!(unterminated_single:
+        "ls 'shadow tutor",
+        Err(Fallacy::UnterminatedQuote('\''))
+    );
+    parse_test!(unterminated_double:
+        r#"ls by "John Doe | rm"#,
+        Err(Fallacy::UnterminatedQuote('"'))
+    );
     parse_test!(starts_with_pipe1:
         "|ls shadowtutor",
         Err(Fallacy::InvalidCommand("Command cannot start with a pipe.".to_owned()))
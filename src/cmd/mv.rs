@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+
+use crate::cmd::prelude::*;
+use crate::config::NoteFormat;
+use crate::filter::FilterExpr;
+use crate::paper::Paper;
+use crate::utils::{as_filename, make_unique_path};
+
+pub static MAN: &str = include_str!("../../man/mv.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // `mv files old new` also renames the wiki and files.
+    let files = matches!(
+        input.args.get(1).map(String::as_str),
+        Some("files") | Some("--files")
+    );
+    if files {
+        input.args.remove(1);
+    }
+    if input.args.len() != 3 {
+        return Err(Fallacy::InvalidCommand(
+            "`mv` expects the old and new nicknames, like `mv resnt resnet`.".to_owned(),
+        ));
+    }
+    let old = input.args[1].trim_start_matches('@');
+    let new = input.args[2].trim_start_matches('@');
+    Paper::parse_nickname(new)?;
+
+    // Find the paper by its nickname or one of its aliases.
+    let ind = match state
+        .papers
+        .iter()
+        .position(|paper| paper.nicknames().any(|name| name == old))
+    {
+        Some(ind) => ind,
+        None => {
+            let nicknames: Vec<&str> = state.papers.iter().flat_map(Paper::nicknames).collect();
+            return Err(Fallacy::MvUnknownNickname(
+                old.to_owned(),
+                FilterExpr::similar_nicknames(old, &nicknames),
+            ));
+        }
+    };
+    let mut paper = state.papers[ind].clone();
+    if paper.nickname.as_deref() == Some(old) {
+        paper.nickname = Some(new.to_owned());
+        // An alias that becomes the nickname is no longer an alias.
+        paper.aliases.retain(|alias| alias != new);
+    } else {
+        for alias in paper.aliases.iter_mut().filter(|alias| *alias == old) {
+            *alias = new.to_owned();
+        }
+    }
+    state.check_unique(&paper, &[ind])?;
+
+    // Renamed files cannot be put back by `undo`, so only the nickname
+    // alone can be undone.
+    let mut renamed = Vec::new();
+    if files {
+        renamed = rename_files(&mut paper, new, config)?;
+    } else {
+        state.snapshot("mv", &[ind]);
+    }
+    state.papers[ind] = paper;
+
+    // Saved filters refer to papers by nickname.
+    let (old_ref, new_ref) = (format!("@{}", old), format!("@{}", new));
+    for args in state.saved_filters.values_mut() {
+        for arg in args.iter_mut().filter(|arg| **arg == old_ref) {
+            *arg = new_ref.clone();
+        }
+    }
+
+    let mut message = format!("Renamed @{} to @{}.\n", old, new);
+    for (from, to) in renamed {
+        message += &format!("Moved '{}' to '{}'.\n", from.display(), to.display());
+    }
+    Ok(CommandOutput::Message(message))
+}
+
+/// Rename the wiki and files of the paper after the new nickname, and
+/// return what was moved where. Missing files are left alone.
+fn rename_files(
+    paper: &mut Paper,
+    new: &str,
+    config: &Config,
+) -> Result<Vec<(PathBuf, PathBuf)>, Fallacy> {
+    let stem = as_filename(new);
+    let mut renamed = Vec::new();
+
+    // Markdown notes are named after the citation key instead.
+    if config.storage.note_format == NoteFormat::Vimwiki {
+        if let Some(wikipath) = paper.wikipath.clone() {
+            let from = config.storage.wiki_dir.join(&wikipath);
+            if let Some(to) = move_file(&from, &stem, &mut renamed)? {
+                paper.wikipath = Some(wikipath.with_file_name(to));
+            }
+        }
+    }
+
+    let numbered = paper.files.len() > 1;
+    for (num, file) in paper.files.iter_mut().enumerate() {
+        let from = config.storage.file_dir.join(&*file);
+        let stem = if numbered {
+            format!("{}-{}", stem, num + 1)
+        } else {
+            stem.clone()
+        };
+        if let Some(to) = move_file(&from, &stem, &mut renamed)? {
+            *file = file.with_file_name(to);
+        }
+    }
+    Ok(renamed)
+}
+
+/// Move the file to a new name in the same directory, keeping its
+/// extension, and return the new file name.
+fn move_file(
+    from: &Path,
+    stem: &str,
+    renamed: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Option<String>, Fallacy> {
+    if !from.exists() {
+        println!("Skipped '{}', which does not exist.", from.display());
+        return Ok(None);
+    }
+    let ext = match from.extension() {
+        Some(ext) => format!(".{}", ext.to_string_lossy()),
+        None => String::new(),
+    };
+    let name = format!("{}{}", stem, ext);
+    if from.file_name().is_some_and(|old| old == name.as_str()) {
+        return Ok(None);
+    }
+    let dir = from.parent().unwrap_or_else(|| Path::new("."));
+    let to = make_unique_path(dir, stem, &ext);
+    if let Err(e) = std::fs::rename(from, &to) {
+        return Err(Fallacy::MvRenameFailed(from.to_owned(), e));
+    }
+    // `to` always has a file name made from `stem`.
+    let name = to.file_name().unwrap().to_string_lossy().into_owned();
+    renamed.push((from.to_owned(), to));
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mv(args: &[&str], state: &mut State, config: &Config) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, config)
+    }
+
+    fn paper(title: &str, nickname: &str) -> Paper {
+        Paper {
+            title: title.to_owned(),
+            nickname: Some(nickname.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rename_nickname() {
+        let config = Config::default();
+        let mut resnet = paper("Deep Residual Learning", "resnt");
+        resnet.aliases = vec!["resnet".to_owned(), "rn".to_owned()];
+        let mut state = State::from_papers(vec![resnet, paper("Densely Connected", "densenet")]);
+        state.saved_filters.insert(
+            "cnns".to_owned(),
+            vec!["@resnt".to_owned(), "@densenet".to_owned()],
+        );
+
+        mv(&["mv", "@resnt", "resnet"], &mut state, &config).unwrap();
+        assert_eq!(state.papers[0].nickname.as_deref(), Some("resnet"));
+        assert_eq!(state.papers[0].aliases, vec!["rn"]);
+        assert_eq!(state.saved_filters["cnns"], vec!["@resnet", "@densenet"]);
+        mv(&["mv", "rn", "r50"], &mut state, &config).unwrap();
+        assert_eq!(state.papers[0].aliases, vec!["r50"]);
+
+        // Only the nickname is undone.
+        state.undo().unwrap();
+        assert_eq!(state.papers[0].aliases, vec!["rn"]);
+
+        assert!(matches!(
+            mv(&["mv", "resnet", "densenet"], &mut state, &config),
+            Err(Fallacy::PaperDuplicateNickname(..))
+        ));
+        assert!(matches!(
+            mv(&["mv", "rn", "densenet"], &mut state, &config),
+            Err(Fallacy::PaperDuplicateAlias(..))
+        ));
+        assert!(matches!(
+            mv(&["mv", "resnet", "res net"], &mut state, &config),
+            Err(Fallacy::PaperInvalidNickname(_))
+        ));
+        match mv(&["mv", "densnet", "dn"], &mut state, &config) {
+            Err(Fallacy::MvUnknownNickname(name, close)) => {
+                assert_eq!(name, "densnet");
+                assert!(close.starts_with("@densenet"), "{}", close);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn rename_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().join("files");
+        config.storage.wiki_dir = dir.path().join("wiki");
+        std::fs::create_dir_all(config.storage.file_dir.join("2016")).unwrap();
+        std::fs::create_dir_all(&config.storage.wiki_dir).unwrap();
+        std::fs::write(config.storage.file_dir.join("2016/resnt.pdf"), "").unwrap();
+        std::fs::write(config.storage.wiki_dir.join("resnt.wiki"), "").unwrap();
+
+        let mut resnet = paper("Deep Residual Learning", "resnt");
+        resnet.files = vec!["2016/resnt.pdf".into()];
+        resnet.wikipath = Some("resnt.wiki".into());
+        let mut state = State::from_papers(vec![resnet]);
+
+        mv(&["mv", "files", "resnt", "resnet"], &mut state, &config).unwrap();
+        assert_eq!(
+            state.papers[0].files,
+            vec![PathBuf::from("2016/resnet.pdf")]
+        );
+        assert_eq!(state.papers[0].wikipath, Some(PathBuf::from("resnet.wiki")));
+        assert!(config.storage.file_dir.join("2016/resnet.pdf").exists());
+        assert!(config.storage.wiki_dir.join("resnet.wiki").exists());
+        assert!(!config.storage.wiki_dir.join("resnt.wiki").exists());
+    }
+}
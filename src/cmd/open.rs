@@ -1,109 +1,501 @@
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
 
 use crate::cmd::prelude::*;
-use crate::paper::PaperList;
-use crate::utils::confirm;
+use crate::cmd::Skipped;
+use crate::utils::spawn::{
+    browser_command, report_spawn_error, spawn, viewer_command, vimwiki_command,
+};
+use crate::utils::{confirm, take_yes};
 
 pub static MAN: &str = include_str!("../../man/open.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+
+    // `open web` opens paper URLs instead of files, and `open both` opens
+    // files along with wikis.
+    let web = input.args.get(1).map(String::as_str) == Some("web");
+    let both = input.args.get(1).map(String::as_str) == Some("both");
+    if (web || both) && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
+    // `open N` opens only the N-th file attached to each paper.
+    let index = match input.args.get(1).map(|arg| arg.parse::<usize>()) {
+        Some(Ok(0)) => {
+            return Err(Fallacy::InvalidCommand(
+                "File indices start from 1.".to_owned(),
+            ))
+        }
+        Some(Ok(index)) if !web && !both => {
+            if input.papers.is_none() {
+                input.args.remove(1);
+            }
+            Some(index - 1)
+        }
+        _ => None,
+    };
+
     // Build paper list from input.
-    let selected = match input.papers {
+    let paper_list = match input.papers {
         // Papers are given through pipe.
-        Some(list) => list.0,
+        Some(list) => list,
         // Papers are specified as filter.
         None => {
             match crate::cmd::ls::execute(input, state, config)? {
-                CommandOutput::Papers(paper_list) => paper_list.0,
+                CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!(),
             }
         }
     };
+    let selected = state.resolve(&paper_list)?;
+
+    if web {
+        return open_web(selected, state, config, yes);
+    }
+    if both {
+        return open_both(selected, state, config, yes);
+    }
 
-    // Build a vector of file paths.
-    let num_papers = selected.len();
-    let selected: Vec<_> = selected
-        .into_iter()
-        .filter(|&i| state.papers[i].filepath.is_some())
-        .collect();
+    // Build a vector of file paths. Papers without files, or whose files
+    // are missing, are skipped.
+    let mut skipped = Skipped::default();
     let mut files = Vec::new();
     for &ind in selected.iter() {
-        if let Some(path) = state.papers[ind].filepath(config) {
-            files.push((ind, path));
+        let paper = &state.papers[ind];
+        let mut paths = paper.files(config);
+        let paths = match index {
+            Some(index) if index < paths.len() => vec![paths.swap_remove(index)],
+            Some(index) => {
+                skipped.add(&format!("no file #{}", index + 1), paper);
+                continue;
+            }
+            None if paths.is_empty() => {
+                skipped.add("no files", paper);
+                continue;
+            }
+            None => paths,
+        };
+        for path in paths {
+            if path.exists() {
+                files.push((ind, path));
+            } else {
+                skipped.add("missing file", paper);
+            }
         }
     }
 
-    // Some reports.
-    let num_open = files.len();
-    if num_papers - num_open > 0 {
-        println!(
-            "{} {} selected. Skipping {} without file paths.",
-            num_papers,
-            if num_papers > 1 { "papers" } else { "paper" },
-            num_papers - num_open,
-        );
-    }
+    // Every file needs a viewer for its extension.
+    check_viewers(files.iter().map(|(_, file)| file.as_path()), config)?;
 
     // Ask for confirmation.
-    if num_open > 1 {
-        confirm(format!("Open {} papers?", num_open), true)?;
+    let num_open = files.len();
+    if num_open > 1 && !yes {
+        confirm(format!("Open {} files?", num_open), true)?;
     }
 
-    // Open papers.
-    if config.output.viewer_batch {
-        let (selected, files): (Vec<usize>, Vec<PathBuf>) = files.into_iter().unzip();
-        if spawn(build_viewer_command(files.as_ref(), config)) {
-            Ok(CommandOutput::Papers(PaperList(selected)))
-        } else {
-            Ok(CommandOutput::None)
+    // Open papers. Batched files are opened once per viewer.
+    let mut runs: Vec<(&Vec<String>, Vec<_>)> = Vec::new();
+    for (ind, file) in files {
+        let viewer = config.output.viewer_for(&file).unwrap();
+        match runs
+            .iter_mut()
+            .find(|(v, _)| config.output.viewer_batch && *v == viewer)
+        {
+            Some((_, batch)) => batch.push((ind, file)),
+            None => runs.push((viewer, vec![(ind, file)])),
         }
-    } else {
-        let mut selected = Vec::new();
-        for (i, file) in files.into_iter() {
-            if spawn(build_viewer_command(&[file], config)) {
-                selected.push(i);
+    }
+    let mut opened = Vec::new();
+    for (viewer, batch) in runs {
+        let paths: Vec<&Path> = batch.iter().map(|(_, file)| file.as_path()).collect();
+        if let Some(seconds) = view(viewer_command(viewer, &paths), config) {
+            let mut inds: Vec<usize> = batch.iter().map(|&(ind, _)| ind).collect();
+            inds.dedup();
+            for ind in inds {
+                state.papers[ind].reading_seconds += seconds;
+                if !opened.contains(&ind) {
+                    opened.push(ind);
+                }
             }
         }
-        Ok(CommandOutput::Papers(PaperList(selected)))
     }
+    skipped.report(format!("Opened {}", opened.len()), opened.len())?;
+    Ok(CommandOutput::Papers(state.paper_list(opened)))
 }
 
-fn spawn(mut command: Command) -> bool {
-    match command.spawn() {
-        Ok(_) => true,
+/// Fail unless every file has a viewer for its extension.
+fn check_viewers<'a>(
+    files: impl Iterator<Item = &'a Path>,
+    config: &Config,
+) -> Result<(), Fallacy> {
+    let mut missing: Vec<String> = Vec::new();
+    for file in files {
+        if config.output.viewer_for(file).is_none() {
+            let kind = match file.extension() {
+                Some(ext) => format!("'.{}' files", ext.to_string_lossy().to_lowercase()),
+                None => "files without an extension".to_owned(),
+            };
+            if !missing.contains(&kind) {
+                missing.push(kind);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return Err(Fallacy::OpenNoViewer(missing.join(" and ")));
+    }
+    Ok(())
+}
+
+/// Run the viewer and return how many seconds it was open. When reading time
+/// is not tracked, the viewer runs in the background and zero is returned.
+fn view(mut command: Command, config: &Config) -> Option<u64> {
+    if !config.output.track_reading_time {
+        return if spawn(command, false) { Some(0) } else { None };
+    }
+    let start = Instant::now();
+    match command.status() {
+        Ok(_) => Some(start.elapsed().as_secs()),
         Err(e) => {
-            if matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                println!("Invalid viewer command: '{:?}'", e);
-            } else {
-                println!("Failed to spawn subprocess: '{:?}'", e);
+            report_spawn_error(e);
+            None
+        }
+    }
+}
+
+/// Open the URLs of the selected papers in the browser, one at a time.
+fn open_web(
+    selected: Vec<usize>,
+    state: &State,
+    config: &Config,
+    yes: bool,
+) -> Result<CommandOutput, Fallacy> {
+    let mut skipped = Skipped::default();
+    let mut urls = Vec::new();
+    for ind in selected {
+        let paper = &state.papers[ind];
+        match paper.url.as_ref() {
+            Some(url) => urls.push((ind, url.clone())),
+            None => skipped.add("no URL", paper),
+        }
+    }
+
+    // Ask for confirmation.
+    if urls.len() > 1 && !yes {
+        confirm(format!("Open {} URLs?", urls.len()), true)?;
+    }
+
+    let mut opened = Vec::new();
+    for (ind, url) in urls {
+        if spawn(browser_command(&url, config), false) {
+            opened.push(ind);
+        }
+    }
+    skipped.report(format!("Opened {}", opened.len()), opened.len())?;
+    Ok(CommandOutput::Papers(state.paper_list(opened)))
+}
+
+/// Open the files of the selected papers next to their wikis. Viewers are
+/// started per paper in the background, while wikis follow `editor_batch`.
+fn open_both(
+    selected: Vec<usize>,
+    state: &mut State,
+    config: &Config,
+    yes: bool,
+) -> Result<CommandOutput, Fallacy> {
+    let mut skipped = Skipped::default();
+    let mut papers = Vec::new();
+    for ind in selected {
+        let files: Vec<_> = state.papers[ind]
+            .files(config)
+            .into_iter()
+            .filter(|file| file.exists())
+            .collect();
+        let wiki = state.papers[ind].wikipath(config, false)?;
+        let paper = &state.papers[ind];
+        match (files.is_empty(), &wiki) {
+            (true, None) => {
+                skipped.add("no files or wiki", paper);
+                continue;
             }
-            false
+            (true, Some(_)) => println!("{} has no files, opening its wiki only.", paper.mention()),
+            (false, None) => println!("{} has no wiki, opening its files only.", paper.mention()),
+            (false, Some(_)) => {}
         }
+        papers.push((ind, files, wiki));
     }
+    check_viewers(
+        papers
+            .iter()
+            .flat_map(|(_, files, _)| files.iter().map(PathBuf::as_path)),
+        config,
+    )?;
+
+    // Ask for confirmation.
+    if papers.len() > 1 && !yes {
+        confirm(
+            format!("Open {} papers with their wikis?", papers.len()),
+            true,
+        )?;
+    }
+
+    // Open files first, so that a blocking editor does not hold them back.
+    let mut opened = Vec::new();
+    let mut wikis = Vec::new();
+    for (ind, files, wiki) in papers {
+        let mut runs: Vec<(&Vec<String>, Vec<&Path>)> = Vec::new();
+        for file in files.iter() {
+            let viewer = config.output.viewer_for(file).unwrap();
+            match runs.iter_mut().find(|(v, _)| *v == viewer) {
+                Some((_, batch)) => batch.push(file),
+                None => runs.push((viewer, vec![file])),
+            }
+        }
+        let mut ok = false;
+        for (viewer, batch) in runs {
+            ok |= spawn(viewer_command(viewer, &batch), false);
+        }
+        if let Some(wiki) = wiki {
+            wikis.push(wiki);
+            ok = true;
+        }
+        if ok {
+            opened.push(ind);
+        }
+    }
+    if config.output.editor_batch {
+        if !wikis.is_empty() {
+            spawn(vimwiki_command(&wikis, config)?, true);
+        }
+    } else {
+        for wiki in wikis {
+            spawn(vimwiki_command(&[wiki], config)?, false);
+        }
+    }
+    skipped.report(format!("Opened {}", opened.len()), opened.len())?;
+    Ok(CommandOutput::Papers(state.paper_list(opened)))
 }
 
-fn build_viewer_command(files: &[PathBuf], config: &Config) -> Command {
-    let mut ret = Command::new(&config.output.viewer_command[0]);
-    let mut curly = false;
-    for command in &config.output.viewer_command[1..] {
-        if command == "{}" {
-            ret.args(files);
-            curly = true;
-        } else {
-            ret.arg(command);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn reading_time() {
+        let mut state = State::from_papers(vec![Paper {
+            title: "Reason".to_owned(),
+            files: vec!["reason.pdf".into()],
+            ..Default::default()
+        }]);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("reason.pdf"), "").unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config.output.viewers.insert(
+            "pdf".to_owned(),
+            ["sh", "-c", "sleep 1", "{}"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        let mut open = |config: &Config| {
+            let input = CommandInput {
+                args: vec!["open".to_owned()],
+                papers: None,
+            };
+            execute(input, &mut state, config).unwrap();
+        };
+
+        // Viewers in the background are not timed.
+        open(&config);
+        config.output.track_reading_time = true;
+        open(&config);
+        assert!(matches!(state.papers[0].reading_seconds, 1..=2));
+        assert!(state.papers[0].details(&state).contains("reading time"));
+
+        let input = CommandInput {
+            args: vec!["wc".to_owned(), "time".to_owned()],
+            papers: None,
+        };
+        match crate::cmd::wc::execute(input, &mut state, &config) {
+            Ok(CommandOutput::Message(message)) => {
+                assert!(message.starts_with("1 papers, read for"))
+            }
+            _ => panic!(),
         }
+        assert_eq!(crate::utils::humanize_seconds(3900), "1h 05m");
     }
-    if !curly {
-        ret.args(files);
+
+    #[test]
+    fn viewers_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("log");
+        for name in ["a.pdf", "a.HTML", "a.ps", "b.pdf"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config.output.viewers.clear();
+        config.output.viewer_batch = true;
+        config.output.track_reading_time = true;
+        let viewer = |name: &str| {
+            let script = format!("echo {} \"$@\" >> {}", name, log.display());
+            vec!["sh".to_owned(), "-c".to_owned(), script, name.to_owned()]
+        };
+        config
+            .output
+            .viewers
+            .insert("pdf".to_owned(), viewer("pdf"));
+        config
+            .output
+            .viewers
+            .insert("html".to_owned(), viewer("web"));
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "A".to_owned(),
+                files: vec!["a.pdf".into(), "a.HTML".into(), "a.ps".into()],
+                ..Default::default()
+            },
+            Paper {
+                title: "B".to_owned(),
+                files: vec!["b.pdf".into()],
+                ..Default::default()
+            },
+        ]);
+        let mut open = |config: &Config| {
+            let input = CommandInput {
+                args: vec!["open".to_owned(), "-y".to_owned()],
+                papers: None,
+            };
+            execute(input, &mut state, config)
+        };
+
+        // Nothing is opened unless every file has a viewer.
+        match open(&config) {
+            Err(Fallacy::OpenNoViewer(kinds)) => assert_eq!(kinds, "'.ps' files"),
+            _ => panic!(),
+        }
+        assert!(!log.exists());
+
+        // Batched files are grouped by viewer.
+        config
+            .output
+            .viewers
+            .insert("default".to_owned(), viewer("ps"));
+        open(&config).unwrap();
+        let log = std::fs::read_to_string(&log).unwrap();
+        let mut runs: Vec<_> = log.lines().map(|line| line.split(' ').count()).collect();
+        runs.sort_unstable();
+        assert_eq!(runs, vec![2, 2, 3]);
+        assert!(log.contains("web ") && log.contains("a.HTML"));
+    }
+
+    #[test]
+    fn skip_papers() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.pdf"), "").unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config
+            .output
+            .viewers
+            .insert("default".to_owned(), vec!["true".to_owned()]);
+        let paper = |nickname: &str, files: &[&str]| Paper {
+            title: nickname.to_uppercase(),
+            nickname: Some(nickname.to_owned()),
+            files: files.iter().map(PathBuf::from).collect(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("a", &["a.pdf"]),
+            paper("b", &["gone.pdf"]),
+            paper("c", &[]),
+        ]);
+        let mut open = |papers: Vec<u64>| {
+            let input = CommandInput {
+                args: vec!["open".to_owned(), "-y".to_owned()],
+                papers: Some(crate::paper::PaperList(papers)),
+            };
+            execute(input, &mut state, &config)
+        };
+
+        // The rest are opened.
+        assert!(matches!(
+            open(vec![1, 2, 3]),
+            Ok(CommandOutput::Papers(list)) if list.0 == vec![1]
+        ));
+
+        // Nothing could be opened.
+        match open(vec![2, 3]) {
+            Err(e @ Fallacy::PapersSkipped(..)) => assert_eq!(
+                e.to_string(),
+                "Opened 0, skipped 2 (missing file: @b; no files: @c)."
+            ),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn files_and_wikis() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("log");
+        for name in ["a.pdf", "a.wiki", "b.wiki"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let logger = |name: &str| {
+            let script = format!("echo {} \"$@\" >> {}", name, log.display());
+            vec!["sh".to_owned(), "-c".to_owned(), script, name.to_owned()]
+        };
+        config
+            .output
+            .viewers
+            .insert("default".to_owned(), logger("view"));
+        config.output.editor_command = logger("edit");
+        config.output.editor_batch = true;
+        let paper = |nickname: &str, files: &[&str], wiki: Option<&str>| Paper {
+            title: nickname.to_uppercase(),
+            nickname: Some(nickname.to_owned()),
+            files: files.iter().map(PathBuf::from).collect(),
+            wikipath: wiki.map(PathBuf::from),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("a", &["a.pdf"], Some("a.wiki")),
+            paper("b", &[], Some("b.wiki")),
+            paper("c", &[], None),
+        ]);
+        let input = CommandInput {
+            args: vec!["open".to_owned(), "-y".to_owned(), "both".to_owned()],
+            papers: None,
+        };
+
+        // Papers with either one are opened.
+        assert!(matches!(
+            execute(input, &mut state, &config),
+            Ok(CommandOutput::Papers(list)) if list.0 == vec![1, 2]
+        ));
+
+        // The viewer runs in the background, so wait for it to log.
+        for _ in 0..50 {
+            if std::fs::read_to_string(&log).unwrap().contains("view") {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let log = std::fs::read_to_string(&log).unwrap();
+        let edit = log.lines().find(|line| line.starts_with("edit")).unwrap();
+        assert!(edit.contains("a.wiki") && edit.contains("b.wiki"));
+        assert!(log.contains("a.pdf"));
     }
-    ret.stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null());
-    ret
 }
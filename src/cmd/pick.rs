@@ -0,0 +1,105 @@
+use crate::cmd::prelude::*;
+use crate::paper::PaperList;
+
+pub static MAN: &str = include_str!("../../man/pick.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    if input.args.len() < 2 {
+        return Err(Fallacy::InvalidCommand(
+            "`pick` expects row numbers like `pick 1 3 5`.".to_owned(),
+        ));
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Rows of plain `ls`.
+        None => {
+            let input = CommandInput {
+                args: vec!["ls".to_owned()],
+                papers: None,
+            };
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    // Row numbers are 1-based, as shown in the '#' column.
+    let num_rows = paper_list.0.len();
+    let mut picked = Vec::new();
+    for arg in input.args[1..].iter() {
+        let (start, end) = match arg.split_once('-') {
+            Some((start, end)) => (parse_row(arg, start)?, parse_row(arg, end)?),
+            None => (parse_row(arg, arg)?, parse_row(arg, arg)?),
+        };
+        if start > end {
+            return Err(Fallacy::PickInvalidRow(arg.clone()));
+        }
+        for row in start..=end {
+            if row == 0 || row > num_rows {
+                return Err(Fallacy::PickOutOfRange(row, num_rows));
+            }
+            picked.push(paper_list.0[row - 1]);
+        }
+    }
+
+    Ok(CommandOutput::Papers(PaperList(picked)))
+}
+
+fn parse_row(arg: &str, row: &str) -> Result<usize, Fallacy> {
+    row.parse()
+        .map_err(|_| Fallacy::PickInvalidRow(arg.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn pick_rows() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A"), paper("B"), paper("C"), paper("D")]);
+        let mut run = |args: &[&str], papers: Option<Vec<u64>>| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: papers.map(PaperList),
+            };
+            match execute(input, &mut state, &Config::default()) {
+                Ok(CommandOutput::Papers(PaperList(ids))) => Ok(ids),
+                Ok(_) => panic!(),
+                Err(e) => Err(e),
+            }
+        };
+
+        assert_eq!(run(&["pick", "4", "1"], None).unwrap(), vec![4, 1]);
+        assert_eq!(
+            run(&["pick", "2-3"], Some(vec![4, 3, 2, 1])).unwrap(),
+            vec![3, 2]
+        );
+        assert!(matches!(
+            run(&["pick", "3"], Some(vec![4, 3])),
+            Err(Fallacy::PickOutOfRange(3, 2))
+        ));
+        assert!(matches!(
+            run(&["pick", "0"], None),
+            Err(Fallacy::PickOutOfRange(0, 4))
+        ));
+        assert!(matches!(
+            run(&["pick", "first"], None),
+            Err(Fallacy::PickInvalidRow(_))
+        ));
+        assert!(run(&["pick", "3-2"], None).is_err());
+        assert!(run(&["pick"], None).is_err());
+    }
+}
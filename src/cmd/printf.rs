@@ -1,5 +1,3 @@
-use std::path::Path;
-use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use mdbook::book::{Link, MDBook, SectionNumber, Summary, SummaryItem};
@@ -7,27 +5,64 @@ use mdbook::config::Config as MDBookConfig;
 
 use crate::cmd::prelude::*;
 use crate::paper::PaperList;
+use crate::template;
+use crate::utils::spawn::{browser_command, spawn};
 
 pub static MAN: &str = include_str!("../../man/printf.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    // `printf bibtex` prints BibTeX entries instead of building the book.
+    let bibtex = input.args.get(1).map(String::as_str) == Some("bibtex");
+    if bibtex && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
+    // A template like "{title} ({year})", or a named one like @compact,
+    // prints a line per paper instead of building the book.
+    let template = match input.args.get(1).filter(|_| !bibtex) {
+        Some(arg) if arg.starts_with('@') => {
+            let name = &arg[1..];
+            match config.output.templates.get(name) {
+                Some(template) => Some(template::parse(template)?),
+                None => return Err(Fallacy::PrintfUnknownTemplate(name.to_owned())),
+            }
+        }
+        Some(arg) if arg.contains(&['{', '}'][..]) => Some(template::parse(arg)?),
+        _ => None,
+    };
+    if template.is_some() && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
     // Build paper list from input.
-    let selected = match input.papers {
+    let paper_list = match input.papers {
         // Papers are given through pipe.
-        Some(list) => list.0,
+        Some(list) => list,
         // Papers are specified as filter.
         None => {
             match crate::cmd::ls::execute(input, state, config)? {
-                CommandOutput::Papers(paper_list) => paper_list.0,
+                CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!("ls did not return CommandOutput::Papers."),
             }
         }
     };
+    let selected = state.resolve(&paper_list)?;
+
+    if bibtex {
+        return Ok(CommandOutput::Message(bibtex_entries(&selected, state)));
+    }
+    if let Some(template) = template {
+        let lines: String = selected
+            .iter()
+            .map(|&ind| template::expand(&template, &state.papers[ind]) + "\n")
+            .collect();
+        return Ok(CommandOutput::Message(lines));
+    }
 
     // Build book config.
     let config_str = "
@@ -56,7 +91,7 @@ pub fn execute(
             number: Some(SectionNumber(vec![sec as u32 + 1])),
             nested_items: Vec::new(),
         }));
-        formatted.push(idx);
+        formatted.push(p.id);
     }
 
     // Build book.
@@ -67,43 +102,88 @@ pub fn execute(
     let mut path = config.storage.note_dir.clone();
     path.push("book");
     path.push("index.html");
-    if spawn(build_browser_command(&path, config)) {
+    if spawn(browser_command(&path, config), false) {
         Ok(CommandOutput::Papers(PaperList(formatted)))
     } else {
         Ok(CommandOutput::None)
     }
 }
 
-fn spawn(mut command: Command) -> bool {
-    match command.spawn() {
-        Ok(_) => true,
-        Err(e) => {
-            if matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                println!("Invalid browser command: '{:?}'", e);
-            } else {
-                println!("Failed to spawn subprocess: '{:?}'", e);
-            }
-            false
-        }
-    }
+/// BibTeX entries of the given papers, separated by blank lines and ending
+/// with a newline.
+pub fn bibtex_entries(selected: &[usize], state: &State) -> String {
+    let entries: Vec<String> = selected
+        .iter()
+        .map(|&ind| format!("{}\n", state.papers[ind].to_bibtex_entry()))
+        .collect();
+    entries.join("\n")
 }
 
-fn build_browser_command(file: &Path, config: &Config) -> Command {
-    let mut ret = Command::new(&config.output.browser_command[0]);
-    let mut curly = false;
-    for command in &config.output.browser_command[1..] {
-        if command == "{}" {
-            ret.arg(file);
-            curly = true;
-        } else {
-            ret.arg(command);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn templates() {
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "Reason".to_owned(),
+                nickname: Some("reason".to_owned()),
+                authors: vec!["Jaehyeon Park".to_owned(), "Jaehoon Kim".to_owned()],
+                venue: "arXiv".to_owned(),
+                year: "2022".to_owned(),
+                ..Default::default()
+            },
+            Paper {
+                title: "Other".to_owned(),
+                ..Default::default()
+            },
+        ]);
+        let mut config = Config::default();
+        config
+            .output
+            .templates
+            .insert("compact".to_owned(), "{title} by {first_author}".to_owned());
+        let mut printf = |template: &str| {
+            let input = CommandInput {
+                args: vec!["printf".to_owned(), template.to_owned()],
+                papers: None,
+            };
+            execute(input, &mut state, &config)
+        };
+
+        match printf("{nickname}: {title} ({venue} {year}) [{status}] {{x}}") {
+            Ok(CommandOutput::Message(lines)) => assert_eq!(
+                lines,
+                "reason: Reason (arXiv 2022) [unread] {x}\n: Other ( ) [unread] {x}\n"
+            ),
+            _ => panic!(),
         }
+        match printf("@compact") {
+            Ok(CommandOutput::Message(lines)) => {
+                assert_eq!(lines.lines().next(), Some("Reason by Jaehyeon Park"))
+            }
+            _ => panic!(),
+        }
+        match printf("bibtex") {
+            Ok(CommandOutput::Message(entries)) => {
+                assert_eq!(entries.matches("}\n\n@").count(), 1);
+                assert!(entries.ends_with("}\n"));
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(
+            printf("{title} {venu}"),
+            Err(Fallacy::PrintfUnknownPlaceholder(name)) if name == "venu"
+        ));
+        assert!(matches!(
+            printf("{title"),
+            Err(Fallacy::PrintfInvalidTemplate(_))
+        ));
+        assert!(matches!(
+            printf("@long"),
+            Err(Fallacy::PrintfUnknownTemplate(name)) if name == "long"
+        ));
     }
-    if !curly {
-        ret.arg(file);
-    }
-    ret.stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null());
-    ret
 }
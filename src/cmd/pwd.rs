@@ -7,5 +7,15 @@ pub fn execute(
     state: &mut State,
     _config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    Ok(CommandOutput::Message(state.filters.current().to_string()))
+    let stack = state.filters.stack();
+    if stack.is_empty() {
+        return Ok(CommandOutput::Message(state.filters.current().to_string()));
+    }
+    let width = stack.len().to_string().len();
+    let lines: String = stack
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| format!("{:>width$}  {}", index + 1, filter))
+        .collect();
+    Ok(CommandOutput::Message(lines))
 }
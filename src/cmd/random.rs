@@ -0,0 +1,120 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::cmd::prelude::*;
+use crate::paper::{PaperList, ReadingProgress};
+
+pub static MAN: &str = include_str!("../../man/random.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Unread papers are picked unless told otherwise.
+    let status = match input.args.get(1).map(String::as_str) {
+        Some("unread") => Some(ReadingProgress::Unread),
+        Some("current") | Some("reading") => Some(ReadingProgress::InProgress(None)),
+        Some("any") => None,
+        _ => Some(ReadingProgress::Unread),
+    };
+    if matches!(
+        input.args.get(1).map(String::as_str),
+        Some("unread") | Some("current") | Some("reading") | Some("any")
+    ) {
+        input.args.remove(1);
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let candidates: Vec<usize> = state
+        .resolve(&paper_list)?
+        .into_iter()
+        .filter(|&ind| match status {
+            Some(ref status) => state.papers[ind].progress.same_kind(status),
+            None => true,
+        })
+        .collect();
+    if candidates.is_empty() {
+        let which = match status {
+            Some(status) => format!("{} papers", status.kind()),
+            None => "papers".to_owned(),
+        };
+        return Ok(CommandOutput::Message(format!(
+            "No {} to pick from.\n",
+            which
+        )));
+    }
+
+    let pick = pick(candidates.len(), RandomState::new().build_hasher().finish());
+    Ok(CommandOutput::Details(PaperList(vec![
+        state.papers[candidates[pick]].id,
+    ])))
+}
+
+/// An index below `len` from a random seed.
+fn pick(len: usize, seed: u64) -> usize {
+    (seed % len as u64) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn pick_by_status() {
+        let paper = |title: &str, progress: ReadingProgress| Paper {
+            title: title.to_owned(),
+            progress,
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("A", ReadingProgress::Read),
+            paper("B", ReadingProgress::Unread),
+            paper("C", ReadingProgress::InProgress(None)),
+            paper("D", ReadingProgress::Read),
+        ]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+        let mut random = |args: &[&str], papers: Option<Vec<u64>>| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: papers.map(PaperList),
+            };
+            match execute(input, &mut state, &Config::default()) {
+                Ok(CommandOutput::Details(PaperList(picked))) => Ok(picked),
+                Ok(CommandOutput::Message(message)) => Err(message),
+                _ => panic!(),
+            }
+        };
+
+        assert_eq!(random(&["random"], None), Ok(vec![ids[1]]));
+        assert_eq!(random(&["random", "current"], None), Ok(vec![ids[2]]));
+        assert_eq!(random(&["random", "[AB]"], None), Ok(vec![ids[1]]));
+        assert_eq!(random(&["random", "any", "D"], None), Ok(vec![ids[3]]));
+        let picked = random(&["random", "any"], Some(vec![ids[0], ids[3]])).unwrap();
+        assert!(picked == vec![ids[0]] || picked == vec![ids[3]]);
+
+        // Nothing to pick from is not an error.
+        assert_eq!(
+            random(&["random"], Some(vec![ids[0], ids[3]])),
+            Err("No unread papers to pick from.\n".to_owned())
+        );
+        assert_eq!(
+            random(&["random", "any", "E"], None),
+            Err("No papers to pick from.\n".to_owned())
+        );
+        assert_eq!(pick(3, 7), 1);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cmd::prelude::*;
+use crate::utils::{confirm, take_yes};
+
+pub static MAN: &str = include_str!("../../man/rate.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+
+    // Parse the rating.
+    let rating = match input.args.get(1).map(|arg| arg.parse::<u8>()) {
+        Some(Ok(rating)) if (1..=5).contains(&rating) => rating,
+        Some(_) => {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Rating should be an integer from 1 to 5, got '{}'.",
+                input.args[1]
+            )))
+        }
+        None => {
+            return Err(Fallacy::InvalidCommand(
+                "`rate` expects a rating from 1 to 5.".to_owned(),
+            ))
+        }
+    };
+    input.args.remove(1);
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let indices = state.resolve(&paper_list)?;
+
+    // Ask for confirmation.
+    let num_paper = indices.len();
+    if num_paper > 1 && !yes {
+        confirm(format!("Rate {} papers {}/5?", num_paper, rating), false)?;
+    }
+
+    state.snapshot("rate", &indices);
+    for ind in indices {
+        state.papers[ind].rating = Some(rating);
+    }
+
+    Ok(CommandOutput::Papers(paper_list))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, PaperList};
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        papers: Vec<u64>,
+        state: &mut State,
+    ) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: Some(PaperList(papers)),
+        };
+        execute(input, state, &Config::default())
+    }
+
+    #[test]
+    fn rate_and_sort() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A"), paper("B"), paper("C")]);
+        for bad in ["0", "6", "five"] {
+            assert!(matches!(
+                run(execute, &["rate", bad], vec![1], &mut state),
+                Err(Fallacy::InvalidCommand(_))
+            ));
+        }
+        run(execute, &["rate", "3"], vec![1], &mut state).unwrap();
+        run(execute, &["rate", "5"], vec![3], &mut state).unwrap();
+        let ratings: Vec<_> = state.papers.iter().map(|p| p.rating).collect();
+        assert_eq!(ratings, vec![Some(3), None, Some(5)]);
+
+        match run(
+            crate::cmd::sort::execute,
+            &["sort", "by", "rating"],
+            vec![1, 2, 3],
+            &mut state,
+        ) {
+            Ok(CommandOutput::Papers(PaperList(sorted))) => assert_eq!(sorted, vec![3, 1, 2]),
+            _ => panic!(),
+        }
+
+        // Papers without a priority come last.
+        state.papers[1].priority = Some(2);
+        state.papers[2].priority = Some(1);
+        match run(
+            crate::cmd::sort::execute,
+            &["sort", "by", "priority"],
+            vec![1, 2, 3],
+            &mut state,
+        ) {
+            Ok(CommandOutput::Papers(PaperList(sorted))) => assert_eq!(sorted, vec![3, 2, 1]),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Local};
+
+use crate::cmd::prelude::*;
+use crate::paper::{Paper, ReadingProgress};
+
+pub static MAN: &str = include_str!("../../man/recent.md");
+
+/// How many papers `recent` shows when no count is given.
+const DEFAULT_COUNT: usize = 10;
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Take the mode and the count.
+    let read = match input.args.get(1).map(String::as_str) {
+        Some("added") => false,
+        Some("read") => true,
+        _ => {
+            return Err(Fallacy::InvalidCommand(
+                "`recent` needs 'added' or 'read', like `recent read 5`.".to_owned(),
+            ))
+        }
+    };
+    input.args.remove(1);
+    let count = match input.args.get(1) {
+        Some(count) if count.chars().all(|c| c.is_ascii_digit()) => {
+            let count = count
+                .parse()
+                .map_err(|_| Fallacy::HeadInvalidCount(count.clone()))?;
+            input.args.remove(1);
+            count
+        }
+        _ => DEFAULT_COUNT,
+    };
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    // Only read papers were read recently. Papers without a date come last.
+    let mut indices: Vec<usize> = state
+        .resolve(&paper_list)?
+        .into_iter()
+        .filter(|&ind| !read || state.papers[ind].progress == ReadingProgress::Read)
+        .collect();
+    indices.sort_by_key(|&ind| {
+        let paper = &state.papers[ind];
+        let when = if read { read_at(paper) } else { paper.added };
+        (when.is_none(), std::cmp::Reverse(when))
+    });
+    indices.truncate(count);
+
+    // Show when each paper was added or read.
+    let column = if read { "read_at" } else { "added" };
+    let mut columns = config.output.table_columns.clone();
+    if !columns.iter().any(|col| col == column) {
+        columns.push(column.to_owned());
+    }
+    Ok(CommandOutput::Table(state.paper_list(indices), columns))
+}
+
+/// When the paper was last marked as read, from its reading history if
+/// the date itself is missing.
+fn read_at(paper: &Paper) -> Option<DateTime<Local>> {
+    paper.read_at.or_else(|| {
+        paper
+            .history
+            .iter()
+            .filter(|change| change.progress == ReadingProgress::Read)
+            .map(|change| change.when)
+            .max()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{PaperList, StatusChange};
+    use crate::utils::parse_timestamp;
+
+    #[test]
+    fn added_and_read() {
+        let at = |date: &str| parse_timestamp(&format!("{}T09:00:00+00:00", date));
+        let paper = |title: &str, added: &str| Paper {
+            title: title.to_owned(),
+            added: at(added),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("A", "2024-01-01"),
+            Paper {
+                progress: ReadingProgress::Read,
+                read_at: at("2024-03-01"),
+                ..paper("B", "2024-02-01")
+            },
+            Paper {
+                progress: ReadingProgress::Read,
+                ..paper("C", "not a date")
+            },
+            Paper {
+                progress: ReadingProgress::Read,
+                history: vec![StatusChange {
+                    progress: ReadingProgress::Read,
+                    when: at("2024-04-01").unwrap(),
+                    who: None,
+                }],
+                ..paper("D", "2023-12-01")
+            },
+        ]);
+        let titles = |args: &[&str], state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, state, &Config::default()) {
+                Ok(CommandOutput::Table(PaperList(ids), columns)) => {
+                    let titles: Vec<_> = state
+                        .resolve(&PaperList(ids))
+                        .unwrap()
+                        .into_iter()
+                        .map(|ind| state.papers[ind].title.clone())
+                        .collect();
+                    (titles, columns.last().cloned().unwrap())
+                }
+                _ => panic!(),
+            }
+        };
+
+        assert_eq!(
+            titles(&["recent", "added"], &mut state),
+            (
+                vec!["B".into(), "A".into(), "D".into(), "C".into()],
+                "added".into()
+            )
+        );
+        assert_eq!(
+            titles(&["recent", "read", "2"], &mut state),
+            (vec!["D".into(), "B".into()], "read_at".into())
+        );
+        assert_eq!(
+            titles(&["recent", "added", "1", "[CD]"], &mut state).0,
+            ["D"]
+        );
+        assert!(matches!(
+            execute(
+                CommandInput {
+                    args: vec!["recent".into()],
+                    papers: None
+                },
+                &mut state,
+                &Config::default()
+            ),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+    }
+}
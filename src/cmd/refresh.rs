@@ -0,0 +1,57 @@
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/refresh.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // Citation counts are the only thing to refresh for now.
+    match input.args.get(1).map(String::as_str) {
+        Some("citations") => {
+            input.args.remove(1);
+        }
+        Some(field) => return Err(Fallacy::RefreshUnknownField(field.to_owned())),
+        None => return Err(Fallacy::RefreshUnknownField("".to_owned())),
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let indices = state.resolve(&paper_list)?;
+    state.snapshot("refresh", &indices);
+
+    // A paper that fails does not stop the rest.
+    let mut failed = 0;
+    for (i, &ind) in indices.iter().enumerate() {
+        let paper = &state.papers[ind];
+        println!("[{}/{}] {}", i + 1, indices.len(), paper.title);
+        match crate::cmd::curl::fetch_citation_count(paper, config) {
+            Ok(count) => state.papers[ind].citation_count = Some(count),
+            Err(e) => {
+                failed += 1;
+                println!("Failed to refresh '{}': {}", paper.title, e);
+            }
+        }
+    }
+    if failed > 0 {
+        println!(
+            "Refreshed {} of {} papers.",
+            indices.len() - failed,
+            indices.len()
+        );
+    }
+
+    Ok(CommandOutput::Papers(paper_list))
+}
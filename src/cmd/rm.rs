@@ -1,14 +1,39 @@
+use crate::cmd::dry_run;
 use crate::cmd::prelude::*;
-use crate::utils::confirm;
+use crate::utils::{confirm, take_yes};
 
 pub static MAN: &str = include_str!("../../man/rm.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    let mut paper_list = match input.papers {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+
+    // Manage the trash.
+    if input.papers.is_none() {
+        match input.args.get(1).map(String::as_str) {
+            Some("--restore") => return restore(state),
+            Some("--empty-trash") => return empty_trash(state, config, yes),
+            _ => {}
+        }
+    }
+
+    // Take 'files', 'nofiles', and 'dry-run' right after the command.
+    let mut delete_files = config.storage.rm_files;
+    let mut preview = false;
+    while let Some(arg) = input.args.get(1) {
+        match arg.as_str() {
+            "files" => delete_files = true,
+            "nofiles" => delete_files = false,
+            "dry-run" | "--dry-run" => preview = true,
+            _ => break,
+        }
+        input.args.remove(1);
+    }
+
+    let paper_list = match input.papers {
         // Papers are given through pipe.
         Some(list) => list,
         // Papers are specified as filter.
@@ -21,36 +46,125 @@ pub fn execute(
         }
     };
 
-    // Ask for confirmation.
-    let num_paper = paper_list.0.len();
-    if num_paper > 1 {
+    let mut indices = state.resolve(&paper_list)?;
+    indices.sort_unstable();
+    indices.dedup();
+
+    // Files attached to the papers and their wiki pages.
+    let num_paper = indices.len();
+    let mut files = Vec::new();
+    if delete_files {
+        for &ind in indices.iter() {
+            files.extend(state.papers[ind].files(config));
+            files.extend(state.papers[ind].wikipath(config, false)?);
+        }
+    }
+
+    if preview {
+        let mut change = String::new();
+        if !files.is_empty() {
+            let files: Vec<_> = files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            change = format!("delete {} and ", files.join(", "));
+        }
+        change.push_str(&format!(
+            "move {} {} to the trash",
+            num_paper,
+            if num_paper != 1 { "papers" } else { "paper" },
+        ));
+        return Ok(dry_run(&change, paper_list));
+    }
+
+    // Ask for confirmation. Deleting files cannot be undone.
+    if (num_paper > 1 || !files.is_empty()) && !yes {
+        let prompt = if files.is_empty() {
+            format!("Move {} papers to the trash?", num_paper)
+        } else {
+            format!(
+                "Move {} {} to the trash and delete {} {}?",
+                num_paper,
+                if num_paper != 1 { "papers" } else { "paper" },
+                files.len(),
+                if files.len() != 1 { "files" } else { "file" },
+            )
+        };
+        confirm(prompt, false)?;
+    }
+
+    // Remember removed papers and papers linked to them for `undo`.
+    let ids: Vec<u64> = indices.iter().map(|&ind| state.papers[ind].id).collect();
+    let linked: Vec<usize> = (0..state.papers.len())
+        .filter(|&ind| {
+            indices.contains(&ind) || state.papers[ind].related.iter().any(|id| ids.contains(id))
+        })
+        .collect();
+    state.snapshot("rm", &linked);
+
+    // Papers are trashed even if some files cannot be deleted. Otherwise,
+    // files and notes are kept until the trash is emptied.
+    for file in files {
+        match std::fs::remove_file(&file) {
+            Ok(()) => println!("Deleted {}", file.display()),
+            Err(e) => println!("Failed to delete {}: {}", file.display(), e),
+        }
+    }
+    state.trash(&indices);
+
+    Ok(CommandOutput::Message(format!(
+        "Moved {} {} to the trash. `rm --restore` brings {} back.\n",
+        num_paper,
+        if num_paper != 1 { "papers" } else { "paper" },
+        if num_paper != 1 { "them" } else { "it" },
+    )))
+}
+
+/// Bring back the papers that were trashed together most recently.
+fn restore(state: &mut State) -> Result<CommandOutput, Fallacy> {
+    let restored = state.restore_trash()?;
+    if restored.is_empty() {
+        return Ok(CommandOutput::Message("The trash is empty.\n".to_owned()));
+    }
+    Ok(CommandOutput::Papers(state.paper_list(restored)))
+}
+
+/// Forget trashed papers for good, deleting their files and notes.
+fn empty_trash(state: &mut State, config: &Config, yes: bool) -> Result<CommandOutput, Fallacy> {
+    let num_paper = state.trash.len();
+    if num_paper == 0 {
+        return Ok(CommandOutput::Message("The trash is empty.\n".to_owned()));
+    }
+    if !yes {
         confirm(
-            format!("Remove {} papers, including files and notes?", num_paper),
+            format!(
+                "Delete {} trashed {} for good, including files and notes?",
+                num_paper,
+                if num_paper != 1 { "papers" } else { "paper" },
+            ),
             false,
         )?;
     }
 
     // Remove files and notes.
     let mut errors = Vec::new();
-    for &ind in paper_list.0.iter() {
-        if let Some(filepath) = state.papers[ind].filepath(config) {
+    for mut trashed in state.empty_trash() {
+        // Files may have been deleted by `rm files` already.
+        for filepath in trashed.paper.files(config) {
+            if !filepath.exists() {
+                continue;
+            }
             if let Err(e) = std::fs::remove_file(&filepath) {
                 errors.push(e);
             }
         }
-        if let Some(notepath) = state.papers[ind].notepath(config, false)? {
+        if let Some(notepath) = trashed.paper.notepath(config, false)? {
             if let Err(e) = std::fs::remove_file(&notepath) {
                 errors.push(e);
             }
         }
     }
 
-    // Remove papers.
-    paper_list.0.reverse();
-    for ind in paper_list.0 {
-        state.papers.remove(ind);
-    }
-
     // Print errors.
     if !errors.is_empty() {
         println!("Errors occured while deleting files and notes:");
@@ -60,8 +174,173 @@ pub fn execute(
     }
 
     Ok(CommandOutput::Message(format!(
-        "Removed {} {} from the paperbase.\n",
+        "Deleted {} {} from the trash.\n",
         num_paper,
         if num_paper != 1 { "papers" } else { "paper" },
     )))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, PaperList};
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        papers: Option<Vec<u64>>,
+        state: &mut State,
+    ) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: papers.map(PaperList),
+        };
+        execute(input, state, &Config::default())
+    }
+
+    #[test]
+    fn rm_then_use_pipe() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A"), paper("B"), paper("C")]);
+        let before = match run(crate::cmd::ls::execute, &["ls"], None, &mut state) {
+            Ok(CommandOutput::Papers(PaperList(ids))) => ids,
+            _ => panic!(),
+        };
+        run(execute, &["rm", "B"], None, &mut state).unwrap();
+
+        // Papers after the removed one are still found through old lists.
+        run(
+            crate::cmd::rate::execute,
+            &["rate", "4"],
+            Some(vec![before[2]]),
+            &mut state,
+        )
+        .unwrap();
+        let ratings: Vec<_> = state
+            .papers
+            .iter()
+            .map(|p| (p.title.as_str(), p.rating))
+            .collect();
+        assert_eq!(ratings, vec![("A", None), ("C", Some(4))]);
+
+        // The removed paper is reported instead of panicking.
+        assert!(matches!(
+            run(crate::cmd::rate::execute, &["rate", "4"], Some(vec![before[1]]), &mut state),
+            Err(Fallacy::PaperNotFound(id)) if id == before[1]
+        ));
+        assert!(matches!(
+            run(execute, &["rm"], Some(vec![before[1]]), &mut state),
+            Err(Fallacy::PaperNotFound(_))
+        ));
+        assert_eq!(state.papers.len(), 2);
+
+        // New papers never reuse the removed ID.
+        let ind = state.add_paper(paper("D"));
+        assert!(!before.contains(&state.papers[ind].id));
+    }
+
+    #[test]
+    fn trash_and_restore() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A"), paper("B"), paper("C")]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+        state.papers[0].related = vec![ids[1]];
+        state.papers[1].related = vec![ids[0]];
+
+        run(execute, &["rm", "-y", "[BC]"], None, &mut state).unwrap();
+        assert_eq!(state.papers.len(), 1);
+        assert!(state.papers[0].related.is_empty());
+        assert_eq!(state.trash.len(), 2);
+
+        // Trashed papers are only listed with 'trashed'.
+        let listed = |args: &[&str], state: &mut State| match run(
+            crate::cmd::ls::execute,
+            args,
+            None,
+            state,
+        ) {
+            Ok(CommandOutput::Message(table)) => table,
+            Ok(CommandOutput::Papers(PaperList(ids))) => format!("{:?}", ids),
+            _ => panic!(),
+        };
+        assert_eq!(listed(&["ls", "B"], &mut state), "[]");
+        let table = listed(&["ls", "trashed", "B"], &mut state);
+        assert!(table.contains(" B ") && !table.contains(" C "));
+
+        // The most recent removal is restored, along with links.
+        state.trash[1].removed_at = state.trash[1].removed_at - chrono::Duration::seconds(1);
+        run(execute, &["rm", "--restore"], None, &mut state).unwrap();
+        let titles: Vec<_> = state.papers.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+        assert_eq!(state.papers[1].id, ids[1]);
+        assert_eq!(state.papers[0].related, vec![ids[1]]);
+        assert_eq!(state.trash.len(), 1);
+
+        run(execute, &["rm", "-y", "--empty-trash"], None, &mut state).unwrap();
+        assert!(state.trash.is_empty());
+        assert!(matches!(
+            run(execute, &["rm", "--restore"], None, &mut state),
+            Ok(CommandOutput::Message(message)) if message == "The trash is empty.\n"
+        ));
+    }
+
+    #[test]
+    fn delete_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config.storage.wiki_dir = dir.path().to_owned();
+        for name in ["a.pdf", "a-slides.pdf", "a.wiki", "b.pdf"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "A".to_owned(),
+                files: vec!["a.pdf".into(), "a-slides.pdf".into(), "gone.pdf".into()],
+                wikipath: Some("a.wiki".into()),
+                ..Default::default()
+            },
+            Paper {
+                title: "B".to_owned(),
+                files: vec!["b.pdf".into()],
+                ..Default::default()
+            },
+        ]);
+        let rm = |args: &[&str], state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, state, &config) {
+                Ok(CommandOutput::Message(message)) => message,
+                _ => panic!(),
+            }
+        };
+
+        // Nothing changes on a dry run.
+        let id = state.papers[0].id;
+        assert!(matches!(
+            run(execute, &["rm", "files", "dry-run", "A"], None, &mut state),
+            Ok(CommandOutput::Papers(PaperList(ids))) if ids == vec![id]
+        ));
+        assert!(dir.path().join("a.pdf").exists());
+        assert_eq!(state.papers.len(), 2);
+
+        // A missing file does not keep the paper from being trashed.
+        rm(&["rm", "-y", "files", "A"], &mut state);
+        assert_eq!(state.papers.len(), 1);
+        for name in ["a.pdf", "a-slides.pdf", "a.wiki"] {
+            assert!(!dir.path().join(name).exists());
+        }
+
+        // Files are kept by default.
+        rm(&["rm", "B"], &mut state);
+        assert!(dir.path().join("b.pdf").exists());
+    }
+}
@@ -1,21 +1,275 @@
+use crate::cmd::dry_run;
 use crate::cmd::prelude::*;
+use crate::filter::FilterExpr;
+use crate::paper::Paper;
+use crate::utils::{confirm, take_dry_run, take_yes};
 
 pub static MAN: &str = include_str!("../../man/set.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
-    _config: &Config,
+    config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    // Manage saved filters.
+    if input.papers.is_none() && input.args.get(1).map(String::as_str) == Some("filter") {
+        return set_filter(&input.args[2..], state, config);
+    }
+
     // We need papers from pipe.
     if input.papers.is_none() {
         return Err(Fallacy::SetNoPapers);
     }
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+    let preview = take_dry_run(&mut input.args);
 
-    // Apply changes.
-    for &ind in input.papers.as_ref().unwrap().0.iter() {
-        state.papers[ind].apply_from_args(&input.args[1..])?;
+    // Apply changes to copies first, so that nothing changes on error.
+    let selected = state.resolve(input.papers.as_ref().unwrap())?;
+    let sets_nickname = input.args[1..]
+        .iter()
+        .any(|arg| arg == "as" || arg.starts_with("nickname="));
+    if sets_nickname && selected.len() > 1 {
+        return Err(Fallacy::SetNicknameOfMany(selected.len()));
+    }
+    let mut changed = Vec::with_capacity(selected.len());
+    for &ind in selected.iter() {
+        let mut paper = state.papers[ind].clone();
+        paper.apply_from_args(&input.args[1..])?;
+        state.check_unique(&paper, &selected)?;
+        changed.push(paper);
+    }
+    // DOIs, aliases, and citation keys should also be unique among the
+    // changed papers.
+    if changed.len() > 1 {
+        if input.args[1..].iter().any(|arg| arg == "doi") {
+            return Err(Fallacy::PaperDuplicateDoi(
+                changed[0].doi.clone().unwrap_or_default(),
+                changed[0].title.clone(),
+            ));
+        }
+        if let Some(pos) = input.args.iter().position(|arg| arg == "alias") {
+            return Err(Fallacy::PaperDuplicateAlias(
+                input.args.get(pos + 1).cloned().unwrap_or_default(),
+                changed[0].title.clone(),
+            ));
+        }
+        if input.args[1..].iter().any(|arg| arg == "key") {
+            return Err(Fallacy::PaperDuplicateCitekey(
+                changed[0].citekey.clone().unwrap_or_default(),
+                changed[0].title.clone(),
+            ));
+        }
+    }
+    if preview {
+        let change = format!(
+            "set {} of {} {}",
+            input.args[1..].join(" "),
+            selected.len(),
+            if selected.len() != 1 {
+                "papers"
+            } else {
+                "paper"
+            },
+        );
+        return Ok(dry_run(&change, input.papers.unwrap()));
+    }
+    if selected.len() > 1 && !yes {
+        confirm(
+            format!(
+                "Set {} of {} papers?",
+                input.args[1..].join(" "),
+                selected.len()
+            ),
+            true,
+        )?;
+    }
+
+    // Count how many papers changed in each field.
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for (&ind, paper) in selected.iter().zip(changed.iter()) {
+        for field in changed_fields(&state.papers[ind], paper) {
+            match counts.iter_mut().find(|(name, _)| *name == field) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((field, 1)),
+            }
+        }
+    }
+
+    state.snapshot("set", &selected);
+    for (&ind, paper) in selected.iter().zip(changed) {
+        state.papers[ind] = paper;
+    }
+    if counts.is_empty() {
+        println!("Nothing changed.");
+    } else {
+        let counts: Vec<_> = counts
+            .into_iter()
+            .map(|(field, count)| {
+                format!(
+                    "{} of {} paper{}",
+                    field,
+                    count,
+                    if count == 1 { "" } else { "s" }
+                )
+            })
+            .collect();
+        println!("Changed {}.", counts.join(", "));
     }
 
     Ok(CommandOutput::Papers(input.papers.unwrap()))
 }
+
+/// Names of the fields that differ between the two papers.
+fn changed_fields(before: &Paper, after: &Paper) -> Vec<&'static str> {
+    let fields = [
+        ("title", before.title != after.title),
+        ("nickname", before.nickname != after.nickname),
+        ("aliases", before.aliases != after.aliases),
+        ("authors", before.authors != after.authors),
+        ("venue", before.venue != after.venue),
+        ("year", before.year != after.year),
+        ("files", before.files != after.files),
+        ("tags", before.labels != after.labels),
+        ("doi", before.doi != after.doi),
+        ("key", before.citekey != after.citekey),
+        ("url", before.url != after.url),
+        ("abstract", before.abstract_text != after.abstract_text),
+        ("priority", before.priority != after.priority),
+        ("due", before.due != after.due),
+        ("extra fields", before.extra != after.extra),
+    ];
+    fields
+        .iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// List, save, or delete named filters.
+fn set_filter(
+    args: &[String],
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // List saved filters.
+    if args.is_empty() {
+        if state.saved_filters.is_empty() {
+            return Ok(CommandOutput::Message("No saved filters.\n".to_owned()));
+        }
+        let mut message = String::new();
+        for (name, filter) in state.saved_filters.iter() {
+            message.push_str(&format!("@{}: {}\n", name, filter.join(" ")));
+        }
+        return Ok(CommandOutput::Message(message));
+    }
+
+    let name = args[0].trim_start_matches('@').to_owned();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(Fallacy::InvalidCommand(format!(
+            "Invalid filter name: '{}'.",
+            args[0]
+        )));
+    }
+
+    // Delete a saved filter.
+    if args.len() == 1 {
+        return match state.saved_filters.remove(&name) {
+            Some(_) => Ok(CommandOutput::Message(format!(
+                "Deleted saved filter '@{}'.\n",
+                name
+            ))),
+            None => Err(Fallacy::FilterUnknownSaved(name)),
+        };
+    }
+
+    // Save a filter, making sure it actually builds.
+    let mut saved = state.saved_filters.clone();
+    saved.insert(name.clone(), args[1..].to_vec());
+    let filter = FilterExpr::from_args(&args[1..], &config.filter, &saved)?;
+    state.saved_filters = saved;
+
+    Ok(CommandOutput::Message(format!(
+        "Saved '@{}': {}",
+        name, filter
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::PaperList;
+
+    #[test]
+    fn set_fields() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            authors: vec!["Jaehoon Kim".to_owned()],
+            venue: "OSDI".to_owned(),
+            year: "2021".to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("Reason"), paper("Other")]);
+        let config = Config::default();
+        let mut set = |args: &[&str], papers: Vec<u64>| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: Some(PaperList(papers)),
+            };
+            execute(input, &mut state, &config)
+        };
+
+        set(&["set", "-y", "year=2022", "tags=os, shell"], vec![1, 2]).unwrap();
+        set(
+            &["set", "nickname=reason", "authors=Kim, J.; Park"],
+            vec![1],
+        )
+        .unwrap();
+
+        // Nicknames are unique.
+        assert!(matches!(
+            set(&["set", "nickname=reason"], vec![2]),
+            Err(Fallacy::PaperDuplicateNickname(..))
+        ));
+        assert!(matches!(
+            set(&["set", "-y", "nickname=both"], vec![1, 2]),
+            Err(Fallacy::SetNicknameOfMany(2))
+        ));
+        assert!(matches!(
+            set(&["set", "-y", "as", "both"], vec![1, 2]),
+            Err(Fallacy::SetNicknameOfMany(2))
+        ));
+
+        assert!(state.papers.iter().all(|p| p.year == "2022"));
+        assert!(state.papers.iter().all(|p| p.labels.contains("shell")));
+        assert_eq!(state.papers[0].nickname.as_deref(), Some("reason"));
+        assert_eq!(state.papers[0].authors, vec!["Kim, J.", "Park"]);
+        assert_eq!(
+            changed_fields(&state.papers[0], &state.papers[1]),
+            vec!["title", "nickname", "authors"]
+        );
+    }
+
+    #[test]
+    fn due_dates() {
+        let mut state = State::from_papers(vec![Paper::default(), Paper::default()]);
+        let config = Config::default();
+        let set = |args: &[&str], papers: Vec<u64>, state: &mut State| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: Some(PaperList(papers)),
+            };
+            execute(input, state, &config)
+        };
+
+        set(&["set", "-y", "due", "2024-06-01"], vec![1, 2], &mut state).unwrap();
+        let due = chrono::NaiveDate::from_ymd_opt(2024, 6, 1);
+        assert!(state.papers.iter().all(|p| p.due == due));
+        set(&["set", "due", "none"], vec![2], &mut state).unwrap();
+        assert_eq!(state.papers[1].due, None);
+        assert!(matches!(
+            set(&["set", "due", "June"], vec![1], &mut state),
+            Err(Fallacy::PaperInvalidDue(_))
+        ));
+        assert_eq!(state.papers[0].due, due);
+    }
+}
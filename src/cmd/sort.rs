@@ -2,49 +2,472 @@
 // use std::path::PathBuf;
 // use std::process::Command;
 
-use std::collections::BTreeSet;
+use std::cmp::Ordering;
 
 use crate::cmd::prelude::*;
-use crate::paper::{PaperList, ReadingProgress};
+use crate::paper::{Paper, ReadingProgress};
 
 pub static MAN: &str = include_str!("../../man/sort.md");
 
+/// Names of sort keys, for error messages.
+const SORT_KEYS: &str =
+    "title, year, author, venue, added, status, rating, priority, due, and citations";
+
+/// Reading statuses `sort by` and `sort only` accept.
+const STATUSES: [&str; 4] = ["unread", "current", "reading", "read"];
+
+/// What to sort papers by. Shared by `sort` and `ls ... sort`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Title,
+    Year,
+    /// The surname of the first author.
+    Author,
+    Venue,
+    Added,
+    /// Unread, then currently reading, then read.
+    Status,
+    Rating,
+    Priority,
+    /// The due date, earliest first.
+    Due,
+    /// How many times the paper was cited.
+    Citations,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = Fallacy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(Self::Title),
+            "year" => Ok(Self::Year),
+            "author" | "first_author" => Ok(Self::Author),
+            "venue" => Ok(Self::Venue),
+            "added" => Ok(Self::Added),
+            "status" | "state" => Ok(Self::Status),
+            "rating" => Ok(Self::Rating),
+            "priority" => Ok(Self::Priority),
+            "due" => Ok(Self::Due),
+            "citations" => Ok(Self::Citations),
+            _ => Err(Fallacy::InvalidCommand(format!(
+                "Unknown sort key: '{}'. Expected one of {}.",
+                s, SORT_KEYS
+            ))),
+        }
+    }
+}
+
+impl SortKey {
+    /// Ratings and citation counts are sorted highest first unless asked
+    /// otherwise. Everything else, including priorities, goes in ascending
+    /// order.
+    pub fn default_descending(self) -> bool {
+        matches!(self, Self::Rating | Self::Citations)
+    }
+
+    /// Whether the paper has something to sort by.
+    fn has_value(self, paper: &Paper) -> bool {
+        match self {
+            Self::Title | Self::Status => true,
+            Self::Year => !paper.year.is_empty(),
+            Self::Author => paper.first_surname().is_some(),
+            Self::Venue => !paper.venue.is_empty(),
+            Self::Added => paper.added.is_some(),
+            Self::Rating => paper.rating.is_some(),
+            Self::Priority => paper.priority.is_some(),
+            Self::Due => paper.due.is_some(),
+            Self::Citations => paper.citation_count.is_some(),
+        }
+    }
+
+    /// Compare two papers that both have something to sort by.
+    fn compare(self, a: &Paper, b: &Paper) -> Ordering {
+        let status = |paper: &Paper| match paper.progress {
+            ReadingProgress::Unread => 0,
+            ReadingProgress::InProgress(_) => 1,
+            ReadingProgress::Read => 2,
+        };
+        let surname = |paper: &Paper| paper.first_surname().map(str::to_lowercase);
+        match self {
+            Self::Title => a.title.cmp(&b.title),
+            Self::Year => a.year.cmp(&b.year),
+            Self::Author => surname(a).cmp(&surname(b)),
+            Self::Venue => a.venue.cmp(&b.venue),
+            Self::Added => a.added.cmp(&b.added),
+            Self::Status => status(a).cmp(&status(b)),
+            Self::Rating => a.rating.cmp(&b.rating),
+            Self::Priority => a.priority.cmp(&b.priority),
+            Self::Due => a.due.cmp(&b.due),
+            Self::Citations => a.citation_count.cmp(&b.citation_count),
+        }
+    }
+}
+
+/// Stably sort paper indices by a chain of keys, each with its direction.
+/// Later keys break ties of earlier ones. Papers without anything to sort by,
+/// like unrated papers for ratings, come last in either direction.
+pub fn sort_papers(indices: &mut [usize], papers: &[Paper], keys: &[(SortKey, bool)]) {
+    indices.sort_by(|&a, &b| {
+        let (a, b) = (&papers[a], &papers[b]);
+        keys.iter()
+            .map(
+                |&(key, descending)| match (key.has_value(a), key.has_value(b)) {
+                    (true, false) => Ordering::Less,
+                    (false, true) => Ordering::Greater,
+                    (false, false) => Ordering::Equal,
+                    (true, true) if descending => key.compare(b, a),
+                    (true, true) => key.compare(a, b),
+                },
+            )
+            .find(|&ordering| ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// How `sort` orders papers.
+enum Order {
+    /// Sort by the keys, each descending if true.
+    By(Vec<(SortKey, bool)>),
+    /// Keep only papers with the reading status, in reverse if true.
+    Only(ReadingProgress, bool),
+}
+
+/// Parse a sort key. Statuses are the same as `status`.
+fn parse_key(name: &str) -> Result<SortKey, Fallacy> {
+    if STATUSES.contains(&name) {
+        return Ok(SortKey::Status);
+    }
+    name.parse().map_err(|_| {
+        Fallacy::InvalidCommand(format!(
+            "Unknown sort key: '{}'. Expected one of {}, or a reading \
+             status (unread, current, or read).",
+            name, SORT_KEYS
+        ))
+    })
+}
+
+/// Parse the keys of 'by year desc then title' or 'by year,title', starting
+/// right after 'by'. Returns the keys and how many arguments they took.
+fn parse_keys(args: &[String]) -> Result<(Vec<(SortKey, bool)>, usize), Fallacy> {
+    // Commas separate keys like 'then' does, with or without spaces around.
+    let mut tokens: Vec<(&str, usize)> = Vec::new();
+    for (ind, arg) in args.iter().enumerate() {
+        for (i, piece) in arg.split(',').enumerate() {
+            if i > 0 {
+                tokens.push((",", ind));
+            }
+            if !piece.is_empty() {
+                tokens.push((piece, ind));
+            }
+        }
+    }
+
+    let mut keys = Vec::new();
+    let mut pos = 0;
+    loop {
+        let name = tokens.get(pos).map(|t| t.0).unwrap_or_default();
+        let key = parse_key(name)?;
+        pos += 1;
+        // 'rev' flips the default direction of the key.
+        let descending = match tokens.get(pos).map(|t| t.0) {
+            Some("asc") => false,
+            Some("desc") => true,
+            Some("rev") => !key.default_descending(),
+            _ => key.default_descending(),
+        };
+        if pos < tokens.len() && ["asc", "desc", "rev"].contains(&tokens[pos].0) {
+            pos += 1;
+        }
+        keys.push((key, descending));
+        match tokens.get(pos).map(|t| t.0) {
+            Some("then") | Some(",") => pos += 1,
+            _ => break,
+        }
+    }
+
+    // The keys should end where an argument does.
+    let last = tokens[pos - 1].1;
+    if let Some(&(rest, ind)) = tokens.get(pos) {
+        if ind == last {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Unexpected '{}' after sort key '{}'.",
+                rest, args[last]
+            )));
+        }
+    }
+    Ok((keys, last + 1))
+}
+
+/// Take the order like 'by year desc then title' off the front of the
+/// arguments, right after the command. The rest is a filter.
+fn take_order(args: &mut Vec<String>) -> Result<Order, Fallacy> {
+    let order = match args.get(1).map(String::as_str) {
+        // Sort by keys like year, then title.
+        Some("by") => {
+            let (keys, len) = parse_keys(&args[2..])?;
+            args.drain(1..2 + len);
+            return Ok(Order::By(keys));
+        }
+        // Keep only papers with the reading status.
+        Some("only") => {
+            let name = args.get(2).map(String::as_str).unwrap_or_default();
+            if !STATUSES.contains(&name) {
+                return Err(Fallacy::InvalidCommand(format!(
+                    "Unknown reading status: '{}'. Expected unread, current, or read.",
+                    name
+                )));
+            }
+            let status = name.parse().unwrap();
+            args.drain(1..3);
+            Order::Only(status, false)
+        }
+        // Sort by title.
+        _ => Order::By(vec![(SortKey::Title, false)]),
+    };
+
+    // A following 'rev' or 'desc' reverses the order.
+    if !matches!(args.get(1).map(String::as_str), Some("rev") | Some("desc")) {
+        return Ok(order);
+    }
+    args.remove(1);
+    Ok(match order {
+        Order::By(keys) => Order::By(keys.into_iter().map(|(k, d)| (k, !d)).collect()),
+        Order::Only(status, reverse) => Order::Only(status, !reverse),
+    })
+}
+
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
-    _config: &Config,
+    config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
-    if input.papers.is_none() {
-        return Err(Fallacy::SetNoPapers);
-    }
+    let order = take_order(&mut input.args)?;
 
-    let papers = input.papers.unwrap().0; // list of paper index
-    let mut sorted = Vec::new();
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+    let mut papers = state.resolve(&paper_list)?; // list of paper index
 
-    if input.args.len() == 1 {
-        // Sort by title
-        let mut keep = BTreeSet::new();
-        for id in papers {
-            keep.insert((&state.papers[id].title, id));
+    match order {
+        // Papers without a value for the key come last.
+        Order::By(keys) => sort_papers(&mut papers, &state.papers, &keys),
+        Order::Only(status, reverse) => {
+            papers.retain(|&ind| state.papers[ind].progress.same_kind(&status));
+            if reverse {
+                papers.reverse();
+            }
         }
+    }
+
+    Ok(CommandOutput::Papers(state.paper_list(papers)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::PaperList;
 
-        for (_, id) in keep {
-            sorted.push(id);
+    fn sort(args: &[&str], state: &mut State) -> Vec<u64> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: Some(PaperList(state.papers.iter().map(|p| p.id).collect())),
+        };
+        match execute(input, state, &Config::default()) {
+            Ok(CommandOutput::Papers(PaperList(ids))) => ids,
+            _ => panic!(),
         }
-    } else if input.args.len() == 3 {
-        match input.args[1].as_str() {
-            "by" => {
-                let status = input.args[2].parse::<ReadingProgress>().unwrap();
-                // Sort by reading status
-                for id in papers {
-                    if state.papers[id].progress == status {
-                        sorted.push(id);
-                    }
-                }
+    }
+
+    #[test]
+    fn reverse() {
+        let paper = |title: &str, rating, priority, progress| Paper {
+            title: title.to_owned(),
+            rating,
+            priority,
+            progress,
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("B", Some(3), None, ReadingProgress::Read),
+            paper("C", None, Some(1), ReadingProgress::Unread),
+            paper("A", Some(5), Some(2), ReadingProgress::Read),
+        ]);
+        assert_eq!(sort(&["sort"], &mut state), vec![3, 1, 2]);
+        assert_eq!(sort(&["sort", "rev"], &mut state), vec![2, 1, 3]);
+        // Papers without a rating or priority stay last.
+        assert_eq!(sort(&["sort", "by", "rating"], &mut state), vec![3, 1, 2]);
+        assert_eq!(
+            sort(&["sort", "by", "rating", "rev"], &mut state),
+            vec![1, 3, 2]
+        );
+        assert_eq!(sort(&["sort", "by", "priority"], &mut state), vec![2, 3, 1]);
+        assert_eq!(
+            sort(&["sort", "by", "priority", "desc"], &mut state),
+            vec![3, 2, 1]
+        );
+        // The most cited papers come first.
+        state.papers[0].citation_count = Some(40);
+        state.papers[2].citation_count = Some(900);
+        assert_eq!(
+            sort(&["sort", "by", "citations"], &mut state),
+            vec![3, 1, 2]
+        );
+        state.papers[0].citation_count = None;
+        state.papers[2].citation_count = None;
+        // Statuses sort papers stably: unread, current, then read.
+        assert_eq!(sort(&["sort", "by", "read"], &mut state), vec![2, 1, 3]);
+        assert_eq!(
+            sort(&["sort", "by", "status", "rev"], &mut state),
+            vec![1, 3, 2]
+        );
+        assert_eq!(sort(&["sort", "only", "read"], &mut state), vec![1, 3]);
+        assert_eq!(
+            sort(&["sort", "only", "read", "rev"], &mut state),
+            vec![3, 1]
+        );
+    }
+
+    #[test]
+    fn keys() {
+        use chrono::TimeZone;
+
+        let paper = |title: &str, year: &str, author: &str, venue: &str, day: Option<u32>| Paper {
+            title: title.to_owned(),
+            year: year.to_owned(),
+            authors: vec![author.to_owned()],
+            venue: venue.to_owned(),
+            added: day.map(|day| chrono::Local.ymd(2022, 3, day).and_hms(0, 0, 0)),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Zeus", "2023", "Jae-Won Chung", "NSDI", Some(2)),
+            paper("Perseus", "2024", "Lee, Jiwon", "SOSP", None),
+            paper("Oobleck", "2023", "Insu Jang", "", Some(1)),
+        ]);
+        assert_eq!(sort(&["sort", "by", "year"], &mut state), vec![1, 3, 2]);
+        assert_eq!(
+            sort(&["sort", "by", "year", "desc"], &mut state),
+            vec![2, 1, 3]
+        );
+        assert_eq!(sort(&["sort", "by", "author"], &mut state), vec![1, 3, 2]);
+        assert_eq!(
+            sort(&["sort", "by", "author", "desc"], &mut state),
+            vec![2, 3, 1]
+        );
+        // Papers without a venue or date come last in either direction.
+        assert_eq!(sort(&["sort", "by", "venue"], &mut state), vec![1, 2, 3]);
+        assert_eq!(
+            sort(&["sort", "by", "venue", "desc"], &mut state),
+            vec![2, 1, 3]
+        );
+        assert_eq!(sort(&["sort", "by", "added"], &mut state), vec![3, 1, 2]);
+        assert_eq!(
+            sort(&["sort", "by", "added", "desc"], &mut state),
+            vec![1, 3, 2]
+        );
+
+        let input = CommandInput {
+            args: vec!["sort".to_owned(), "by".to_owned(), "length".to_owned()],
+            papers: Some(PaperList(vec![1])),
+        };
+        assert!(matches!(
+            execute(input, &mut state, &Config::default()),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+    }
+
+    #[test]
+    fn chained_keys() {
+        let paper = |title: &str, year: &str, rating| Paper {
+            title: title.to_owned(),
+            year: year.to_owned(),
+            rating,
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Zeus", "2023", Some(4)),
+            paper("Perseus", "2024", Some(4)),
+            paper("Oobleck", "2023", None),
+            paper("Reason", "2022", Some(5)),
+        ]);
+        assert_eq!(
+            sort(&["sort", "by", "year", "then", "title"], &mut state),
+            vec![4, 3, 1, 2]
+        );
+        assert_eq!(
+            sort(
+                &["sort", "by", "year", "desc", "then", "title", "asc"],
+                &mut state
+            ),
+            vec![2, 3, 1, 4]
+        );
+        assert_eq!(
+            sort(&["sort", "by", "year,title", "desc"], &mut state),
+            vec![4, 1, 3, 2]
+        );
+        assert_eq!(
+            sort(&["sort", "by", "rating", ",", "title"], &mut state),
+            vec![4, 2, 1, 3]
+        );
+        assert_eq!(
+            sort(&["sort", "by", "rating,", "title", "desc"], &mut state),
+            vec![4, 1, 2, 3]
+        );
+
+        let mut fails = |args: &[&str]| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: Some(PaperList(vec![1])),
+            };
+            match execute(input, &mut state, &Config::default()) {
+                Err(Fallacy::InvalidCommand(msg)) => msg,
+                _ => panic!(),
             }
-            _ => return Ok(CommandOutput::None),
-        }
+        };
+        assert!(fails(&["sort", "by", "year", "then", "length"]).contains(SORT_KEYS));
+        assert!(fails(&["sort", "by", "year", "then"]).contains(SORT_KEYS));
+        fails(&["sort", "by", "year,title,"]);
     }
 
-    Ok(CommandOutput::Papers(PaperList(sorted)))
+    #[test]
+    fn without_pipe() {
+        let paper = |title: &str, venue: &str, year: &str| Paper {
+            title: title.to_owned(),
+            venue: venue.to_owned(),
+            year: year.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("Zeus", "NSDI", "2023"),
+            paper("Reason", "OSDI", "2022"),
+            paper("Oobleck", "OSDI", "2023"),
+        ]);
+        let mut run = |args: &[&str]| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, &mut state, &Config::default()) {
+                Ok(CommandOutput::Papers(PaperList(ids))) => ids,
+                _ => panic!(),
+            }
+        };
+        assert_eq!(run(&["sort"]), vec![3, 2, 1]);
+        assert_eq!(run(&["sort", "by", "year", "at", "OSDI"]), vec![2, 3]);
+        assert_eq!(
+            run(&["sort", "by", "year", "desc", "at", "OSDI"]),
+            vec![3, 2]
+        );
+        assert_eq!(run(&["sort", "rev", "in", "2023"]), vec![1, 3]);
+    }
 }
@@ -0,0 +1,86 @@
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/stat.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => match crate::cmd::ls::execute(input, state, config)? {
+            CommandOutput::Papers(paper_list) => paper_list,
+            // `ls` always returns CommandOutput::Papers.
+            _ => panic!(),
+        },
+    };
+
+    let blocks: Vec<_> = state
+        .resolve(&paper_list)?
+        .into_iter()
+        .map(|ind| state.papers[ind].stat(state, config))
+        .collect();
+    Ok(CommandOutput::Message(blocks.join("\n")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, PaperList};
+
+    #[test]
+    fn full_record() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("reason.pdf"), "").unwrap();
+        std::fs::write(dir.path().join("reason.wiki"), "= Reason =\nA shell.\n").unwrap();
+        let mut config = Config::default();
+        config.storage.file_dir = dir.path().to_owned();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let mut state = State::from_papers(vec![
+            Paper {
+                title: "Reason".to_owned(),
+                nickname: Some("reason".to_owned()),
+                authors: vec!["Jae-Won Chung".to_owned(), "Jaehoon Kim".to_owned()],
+                doi: Some("10.1145/1234567.8901234".to_owned()),
+                rating: Some(4),
+                files: vec!["reason.pdf".into(), "slides.pdf".into()],
+                wikipath: Some("reason.wiki".into()),
+                ..Default::default()
+            },
+            Paper {
+                title: "Zeus".to_owned(),
+                wikipath: Some("zeus.wiki".into()),
+                ..Default::default()
+            },
+        ]);
+        let (first, second) = (state.papers[0].id, state.papers[1].id);
+        state.papers[0].related.push(second);
+        state.papers[1].related.push(first);
+
+        let input = CommandInput {
+            args: vec!["stat".to_owned()],
+            papers: Some(PaperList(vec![first, second])),
+        };
+        let blocks = match execute(input, &mut state, &config) {
+            Ok(CommandOutput::Message(blocks)) => blocks,
+            _ => panic!(),
+        };
+        for text in [
+            "Jaehoon Kim",
+            "10.1145/1234567.8901234",
+            "4/5",
+            "(5 words)",
+            "(missing)",
+            "@reason",
+        ] {
+            assert!(blocks.contains(text), "{} not in\n{}", text, blocks);
+        }
+        let pdf = dir.path().join("reason.pdf");
+        let slides = dir.path().join("slides.pdf");
+        assert!(blocks.contains(&format!("✓ {}", pdf.display())));
+        assert!(blocks.contains(&format!("✗ {}", slides.display())));
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::Local;
+
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/sync.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let status = match input.args.get(1).map(String::as_str) {
+        None => false,
+        Some("status") => true,
+        Some(arg) => {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Unknown argument to `sync`: '{}'. Expected nothing or 'status'.",
+                arg
+            )))
+        }
+    };
+
+    // The paperbase is the directory of the metadata file.
+    let state_path = &config.storage.paper_metadata;
+    let dir = match state_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    };
+    if !is_git_repo(&dir) {
+        return Err(Fallacy::SyncNotGitRepo(dir));
+    }
+
+    // Only the paperbase is synced, even if it is part of a larger repository.
+    if status {
+        let changes = git(&dir, &["status", "--short", "--", "."])?;
+        if changes.is_empty() {
+            return Ok(CommandOutput::Message("Nothing to sync.\n".to_owned()));
+        }
+        return Ok(CommandOutput::Message(changes));
+    }
+
+    // Commit what is in memory, not what was last saved.
+    state.store(state_path)?;
+    if git(&dir, &["status", "--porcelain", "--", "."])?.is_empty() {
+        println!("Nothing to commit.");
+    } else {
+        git(&dir, &["add", "-A", "--", "."])?;
+        let message = format!("reason sync {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+        stream(&dir, &["commit", "-m", &message, "--", "."])?;
+    }
+
+    // Pulled papers replace the ones in memory. The default filter stays.
+    let head = git(&dir, &["rev-parse", "HEAD"])?;
+    stream(&dir, &["pull", "--rebase"])?;
+    if git(&dir, &["rev-parse", "HEAD"])? != head {
+        let filters = state.filters.clone();
+        *state = State::load(state_path)?;
+        state.filters = filters;
+    }
+    stream(&dir, &["push"])?;
+
+    Ok(CommandOutput::Message("Synced the paperbase.\n".to_owned()))
+}
+
+/// Whether the directory is inside the work tree of a git repository.
+fn is_git_repo(dir: &Path) -> bool {
+    match Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+    {
+        Ok(output) => output.status.success() && output.stdout.starts_with(b"true"),
+        Err(_) => false,
+    }
+}
+
+/// Run git in the directory and return what it printed.
+fn git(dir: &Path, args: &[&str]) -> Result<String, Fallacy> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Fallacy::SyncGitFailed(
+            args.join(" "),
+            stderr.trim().to_owned(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run git in the directory with its output shown as it comes, keeping
+/// what it printed to stderr for the error.
+fn stream(dir: &Path, args: &[&str]) -> Result<(), Fallacy> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stderr = String::new();
+    if let Some(pipe) = child.stderr.take() {
+        for line in BufReader::new(pipe).lines() {
+            let line = line?;
+            eprintln!("{}", line);
+            stderr.push_str(&line);
+            stderr.push('\n');
+        }
+    }
+    if !child.wait()?.success() {
+        return Err(Fallacy::SyncGitFailed(
+            args.join(" "),
+            stderr.trim().to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    fn sync(args: &[&str], state: &mut State, config: &Config) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, config)
+    }
+
+    #[test]
+    fn commit_pull_and_push() {
+        let dir = tempfile::tempdir().unwrap();
+        let (remote, local, other) = (
+            dir.path().join("remote"),
+            dir.path().join("local"),
+            dir.path().join("other"),
+        );
+        std::fs::create_dir(&remote).unwrap();
+        run(&remote, &["init", "--bare", "-b", "main"]);
+        // The remote starts with an empty commit so that there is
+        // something to pull.
+        for clone in [&other, &local] {
+            run(
+                dir.path(),
+                &["clone", remote.to_str().unwrap(), clone.to_str().unwrap()],
+            );
+            run(clone, &["config", "user.name", "Reason"]);
+            run(clone, &["config", "user.email", "reason@example.com"]);
+            if clone == &other {
+                run(clone, &["checkout", "-b", "main"]);
+                run(clone, &["commit", "--allow-empty", "-m", "Start"]);
+                run(clone, &["push", "origin", "main"]);
+            }
+        }
+
+        let mut config = Config::default();
+        // The paperbase is a directory of a larger repository.
+        std::fs::create_dir(local.join("papers")).unwrap();
+        config.storage.paper_metadata = local.join("papers/metadata.yaml");
+        std::fs::write(local.join("unrelated.txt"), "not a paper").unwrap();
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("Reason")]);
+
+        // Papers in memory are committed and pushed.
+        match sync(&["sync", "status"], &mut state, &config) {
+            Ok(CommandOutput::Message(message)) => assert_eq!(message, "Nothing to sync.\n"),
+            _ => panic!(),
+        }
+        sync(&["sync"], &mut state, &config).unwrap();
+        run(&other, &["pull", "origin", "main"]);
+        let pushed = std::fs::read_to_string(other.join("papers/metadata.yaml")).unwrap();
+        assert!(pushed.contains("Reason"));
+        assert!(!other.join("unrelated.txt").exists());
+
+        // Papers pushed from elsewhere are pulled and loaded.
+        let elsewhere = State::from_papers(vec![paper("Reason"), paper("Zeus")]);
+        elsewhere
+            .store(&other.join("papers/metadata.yaml"))
+            .unwrap();
+        run(&other, &["commit", "-am", "Add Zeus"]);
+        run(&other, &["push", "origin", "main"]);
+        sync(&["sync"], &mut state, &config).unwrap();
+        assert_eq!(state.papers.len(), 2);
+
+        // Uncommitted changes show up in the status.
+        std::fs::write(local.join("papers/notes.wiki"), "notes").unwrap();
+        match sync(&["sync", "status"], &mut state, &config) {
+            Ok(CommandOutput::Message(message)) => assert_eq!(message, "?? notes.wiki\n"),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn not_a_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.paper_metadata = dir.path().join("metadata.yaml");
+        let mut state = State::from_papers(Vec::new());
+        assert!(matches!(
+            sync(&["sync"], &mut state, &config),
+            Err(Fallacy::SyncNotGitRepo(_))
+        ));
+        assert!(!config.storage.paper_metadata.exists());
+    }
+}
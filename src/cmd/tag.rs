@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use crate::cmd::dry_run;
+use crate::cmd::prelude::*;
+use crate::paper::Paper;
+use crate::utils::{confirm, take_dry_run, take_yes};
+
+pub static MAN: &str = include_str!("../../man/tag.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+    let preview = take_dry_run(&mut input.args);
+
+    // Take '+label' and '-label' arguments right after the command.
+    let mut add = HashSet::new();
+    let mut remove = HashSet::new();
+    let num_changes = input.args[1..]
+        .iter()
+        .take_while(|arg| arg.len() > 1 && (arg.starts_with('+') || arg.starts_with('-')))
+        .count();
+    for arg in input.args.drain(1..1 + num_changes) {
+        let (sign, label) = arg.split_at(1);
+        if sign == "+" {
+            Paper::parse_label(label)?;
+            remove.remove(label);
+            add.insert(label.to_owned());
+        } else {
+            add.remove(label);
+            remove.insert(label.to_owned());
+        }
+    }
+    if add.is_empty() && remove.is_empty() {
+        return Err(Fallacy::InvalidCommand(
+            "`tag` expects labels to add (+label) or remove (-label).".to_owned(),
+        ));
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+
+    let indices = state.resolve(&paper_list)?;
+
+    let num_paper = indices.len();
+    if preview {
+        let mut changes: Vec<_> = add.iter().map(|label| format!("+{}", label)).collect();
+        changes.extend(remove.iter().map(|label| format!("-{}", label)));
+        changes.sort_unstable_by(|a, b| a[1..].cmp(&b[1..]));
+        let change = format!(
+            "tag {} {} with {}",
+            num_paper,
+            if num_paper != 1 { "papers" } else { "paper" },
+            changes.join(" "),
+        );
+        return Ok(dry_run(&change, paper_list));
+    }
+
+    // Ask for confirmation.
+    if num_paper > 1 && !yes {
+        confirm(format!("Change labels of {} papers?", num_paper), false)?;
+    }
+
+    state.snapshot("tag", &indices);
+
+    // Count papers whose labels actually change.
+    let mut num_changed = 0;
+    for ind in indices {
+        let labels = &mut state.papers[ind].labels;
+        let mut changed = false;
+        for label in add.iter() {
+            changed |= labels.insert(label.clone());
+        }
+        for label in remove.iter() {
+            changed |= labels.remove(label);
+        }
+        if changed {
+            num_changed += 1;
+        }
+    }
+
+    Ok(CommandOutput::Message(format!(
+        "Changed labels of {} {}.\n",
+        num_changed,
+        if num_changed != 1 { "papers" } else { "paper" },
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::PaperList;
+
+    #[test]
+    fn add_and_remove() {
+        let paper = |title: &str, labels: &[&str]| Paper {
+            title: title.to_owned(),
+            labels: labels.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A", &["systems"]), paper("B", &["todo"])]);
+        let mut run = |args: &[&str], papers: Option<Vec<u64>>| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: papers.map(PaperList),
+            };
+            execute(input, &mut state, &Config::default())
+        };
+
+        // Adding a label a paper already has is not a change.
+        match run(&["tag", "+systems", "-todo"], Some(vec![1])) {
+            Ok(CommandOutput::Message(message)) => {
+                assert_eq!(message, "Changed labels of 0 papers.\n")
+            }
+            _ => panic!(),
+        }
+        match run(&["tag", "+systems", "-todo", "B"], None) {
+            Ok(CommandOutput::Message(message)) => {
+                assert_eq!(message, "Changed labels of 1 paper.\n")
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(
+            run(&["tag", "B"], None),
+            Err(Fallacy::InvalidCommand(_))
+        ));
+
+        // A dry run passes the papers on without changing them.
+        assert!(matches!(
+            run(&["tag", "dry-run", "+nlp", "B"], None),
+            Ok(CommandOutput::Papers(PaperList(ids))) if ids == vec![2]
+        ));
+        assert_eq!(state.papers[1].labels, ["systems".to_owned()].into());
+    }
+}
@@ -1,5 +1,10 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
 use crate::cmd::prelude::*;
-use crate::paper::{Paper, PaperList};
+use crate::paper::Paper;
+use crate::pdfinfo::{self, Guess};
+use crate::utils::{as_filename, ask_for, ask_for_line, confirm, expand_tilde, make_unique_path};
 
 pub static MAN: &str = include_str!("../../man/touch.md");
 
@@ -9,19 +14,192 @@ pub fn execute(
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
     // Parse input to paper metadata.
-    let paper = Paper::from_args(input.args)?;
+    let (paper, used_defaults) = match input.args.get(1).map(String::as_str) {
+        Some("--bibtex") => (
+            Paper::from_bibtex(&read_bibtex(input.args.get(2))?)?,
+            Vec::new(),
+        ),
+        Some("--from") => return touch_from(&input.args[2..], state, config),
+        _ => Paper::from_args_with_defaults(input.args, &config.defaults)?,
+    };
+
+    paper.validate()?;
 
-    // Verify file path.
-    if let Some(filepath) = paper.filepath(config) {
+    // Verify file paths.
+    for filepath in paper.files(config) {
         if !filepath.exists() {
             return Err(Fallacy::PathDoesNotExist(filepath));
         }
     }
 
+    // DOIs identify papers.
+    state.check_unique(&paper, &[])?;
+
+    if !used_defaults.is_empty() {
+        let used: Vec<_> = used_defaults
+            .iter()
+            .map(|field| format!("{} '{}'", field, paper.field_as_string(field)))
+            .collect();
+        println!("Used defaults for {}. See `man config`.", used.join(", "));
+    }
+
     // Add paper to state.
-    state.papers.push(paper);
+    let ind = state.add_paper(paper);
+
+    Ok(CommandOutput::Papers(state.paper_list([ind])))
+}
+
+/// Read a BibTeX entry from the given file, or from stdin if no file or
+/// '-' is given.
+fn read_bibtex(path: Option<&String>) -> Result<String, Fallacy> {
+    match path.map(String::as_str) {
+        Some("-") | None => {
+            println!("Paste a BibTeX entry and press Ctrl-D.");
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if !path.exists() {
+                return Err(Fallacy::PathDoesNotExist(path));
+            }
+            Ok(std::fs::read_to_string(path)?)
+        }
+    }
+}
 
-    Ok(CommandOutput::Papers(PaperList(vec![
-        state.papers.len() - 1,
-    ])))
+/// Add a paper for a local file, which is copied into the file directory.
+/// The title, authors, and year are guessed from PDFs and confirmed by the
+/// user. Anything else is asked for.
+fn touch_from(
+    args: &[String],
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let mut path = None;
+    let mut move_file = false;
+    let mut yes = config.general.assume_yes;
+    for arg in args {
+        match arg.as_str() {
+            "--move" => move_file = true,
+            "-y" => yes = true,
+            _ if path.is_none() => path = Some(expand_tilde(Path::new(arg))?),
+            _ => {
+                return Err(Fallacy::InvalidCommand(format!(
+                    "Unexpected argument to `touch --from`: '{}'",
+                    arg
+                )))
+            }
+        }
+    }
+    let path = match path {
+        Some(path) if path.is_file() => path,
+        Some(path) => return Err(Fallacy::PathDoesNotExist(path)),
+        None => return Err(Fallacy::PaperMissingFields("files(@)".to_owned())),
+    };
+
+    let guess = if pdfinfo::is_pdf(&path) {
+        pdfinfo::guess(&path)
+    } else {
+        println!("Not a PDF. Attaching it without guessing metadata.");
+        Guess::default()
+    };
+
+    // Take usable guesses as they are if confirmed. Otherwise, offer them as
+    // defaults in the prompt.
+    let confirmed = match guess.title {
+        Some(ref title) => {
+            println!("Guessed from the PDF:");
+            println!("  title:   {}", title);
+            println!("  authors: {}", guess.authors.join(", "));
+            println!("  year:    {}", guess.year.clone().unwrap_or_default());
+            // Scripts without a terminal should pass -y rather than
+            // fall through to prompts.
+            yes || match confirm("Use these fields?".to_owned(), true) {
+                Ok(()) => true,
+                Err(e @ Fallacy::ConfirmWithoutTerminal(_)) => return Err(e),
+                Err(_) => false,
+            }
+        }
+        None => {
+            if pdfinfo::is_pdf(&path) {
+                println!("Could not guess metadata from the PDF.");
+            }
+            false
+        }
+    };
+    let ask = |prompt: &str, guess: Option<String>, check: fn(&str) -> Result<(), Fallacy>| {
+        if let Some(value) = guess
+            .as_ref()
+            .filter(|value| confirmed && check(value).is_ok())
+        {
+            return Ok(value.clone());
+        }
+        ask_until_valid(prompt, guess, check)
+    };
+    let title = ask("Title", guess.title.clone(), |_| Ok(()))?;
+    if title.is_empty() {
+        return Err(Fallacy::PaperMissingFields("title".to_owned()));
+    }
+    let authors = ask(
+        "Comma-separated authors",
+        Some(guess.authors.join(", ")).filter(|authors| !authors.is_empty()),
+        |authors| Paper::parse_authors(authors).map(|_| ()),
+    )?;
+    let venue = ask_for("Venue", None)?;
+    let year = ask("Year", guess.year.clone(), |year| {
+        Paper::parse_year(year).map(|_| ())
+    })?;
+
+    let mut paper = Paper {
+        title,
+        authors: Paper::parse_authors(&authors)?,
+        venue,
+        year,
+        added: Some(chrono::Local::now()),
+        ..Default::default()
+    };
+    state.check_unique(&paper, &[])?;
+
+    // Files already in the file directory stay where they are.
+    let file_dir = &config.storage.file_dir;
+    let filepath = if path.starts_with(file_dir) {
+        path
+    } else {
+        let ext = path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let filepath = make_unique_path(file_dir, &as_filename(&paper.title), &ext);
+        println!("Saving to {:?}.", filepath);
+        std::fs::copy(&path, &filepath)?;
+        if move_file {
+            std::fs::remove_file(&path)?;
+        }
+        filepath
+    };
+    paper.files.push(filepath);
+
+    let ind = state.add_paper(paper);
+    Ok(CommandOutput::Papers(state.paper_list([ind])))
+}
+
+/// Ask for a field until the answer passes `check`, showing why it did not.
+/// Fails if the input ends first.
+fn ask_until_valid(
+    prompt: &str,
+    default: Option<String>,
+    check: fn(&str) -> Result<(), Fallacy>,
+) -> Result<String, Fallacy> {
+    loop {
+        let answer = match ask_for_line(prompt, default.clone())? {
+            Some(answer) => answer,
+            None => return Err(Fallacy::InputEnded(prompt.to_owned())),
+        };
+        match check(&answer) {
+            Ok(()) => return Ok(answer),
+            Err(e) => println!("{}", e),
+        }
+    }
 }
@@ -0,0 +1,135 @@
+use crate::cmd::prelude::*;
+
+pub static MAN: &str = include_str!("../../man/undo.md");
+
+pub fn execute(
+    input: CommandInput,
+    state: &mut State,
+    _config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    // How many changes to undo.
+    let count = match input.args.get(1).map(|arg| arg.parse::<usize>()) {
+        None => 1,
+        Some(Ok(count)) if count > 0 => count,
+        Some(_) => {
+            return Err(Fallacy::InvalidCommand(format!(
+                "`undo` expects a number of changes like 2, got '{}'.",
+                input.args[1]
+            )))
+        }
+    };
+
+    if state.journal.is_empty() {
+        return Ok(CommandOutput::Message(
+            "Nothing to undo. See `man undo` for the changes that can be undone.\n".to_owned(),
+        ));
+    }
+
+    let mut message = String::new();
+    for _ in 0..count {
+        let snapshot = match state.undo() {
+            Some(snapshot) => snapshot,
+            None => break,
+        };
+        let num_paper = snapshot.papers.len();
+        message.push_str(&format!(
+            "Undid `{}`. Restored {} {}:\n",
+            snapshot.command,
+            num_paper,
+            if num_paper != 1 { "papers" } else { "paper" },
+        ));
+        for (_, paper) in snapshot.papers.iter() {
+            message.push_str(&format!("  {}\n", paper.title));
+        }
+    }
+    let left = state.journal.len();
+    if left > 0 {
+        message.push_str(&format!(
+            "{} more {} can be undone.\n",
+            left,
+            if left != 1 { "changes" } else { "change" },
+        ));
+    }
+    Ok(CommandOutput::Message(message))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::{Paper, ReadingProgress};
+
+    fn run(
+        execute: crate::cmd::ExecuteFn,
+        args: &[&str],
+        state: &mut State,
+    ) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, &Config::default())
+    }
+
+    fn message(output: Result<CommandOutput, Fallacy>) -> String {
+        match output {
+            Ok(CommandOutput::Message(message)) => message,
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn undo_mutations() {
+        let paper = |title: &str| Paper {
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![paper("A"), paper("B"), paper("C")]);
+        let (a, b) = (state.papers[0].id, state.papers[1].id);
+        state.papers[0].related.push(b);
+        state.papers[1].related.push(a);
+        assert!(message(run(execute, &["undo"], &mut state)).starts_with("Nothing to undo."));
+
+        run(crate::cmd::mark::mark::execute, &["mark", "A"], &mut state).unwrap();
+        run(crate::cmd::tag::execute, &["tag", "+nlp", "B"], &mut state).unwrap();
+        run(crate::cmd::rm::execute, &["rm", "B"], &mut state).unwrap();
+        assert_eq!(state.papers.len(), 2);
+        assert!(state.papers[0].related.is_empty());
+
+        // Removed papers come back where they were, with their links.
+        let restored = message(run(execute, &["undo"], &mut state));
+        assert!(restored.starts_with("Undid `rm`. Restored 2 papers:\n"));
+        let titles: Vec<_> = state.papers.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "C"]);
+        assert_eq!(state.papers[0].related, vec![b]);
+        assert!(state.papers[1].labels.contains("nlp"));
+        assert!(state.trash.is_empty());
+
+        // Several levels at once.
+        run(execute, &["undo", "2"], &mut state).unwrap();
+        assert!(state.papers[1].labels.is_empty());
+        assert_eq!(state.papers[0].progress, ReadingProgress::Unread);
+        assert!(state.journal.is_empty());
+        assert!(run(execute, &["undo", "x"], &mut state).is_err());
+    }
+
+    #[test]
+    fn journal_depth() {
+        let mut state = State::from_papers(vec![Paper::default()]);
+        state.journal = crate::state::Journal::new(2);
+        for rating in ["1", "2", "3"] {
+            run(crate::cmd::rate::execute, &["rate", rating], &mut state).unwrap();
+        }
+        assert_eq!(state.journal.len(), 2);
+        run(execute, &["undo", "5"], &mut state).unwrap();
+        assert_eq!(state.papers[0].rating, Some(1));
+
+        // Snapshots survive across sessions.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("undo.yaml");
+        run(crate::cmd::rate::execute, &["rate", "4"], &mut state).unwrap();
+        state.journal.store(&path).unwrap();
+        state.journal = crate::state::Journal::load(&path, 2).unwrap();
+        run(execute, &["undo"], &mut state).unwrap();
+        assert_eq!(state.papers[0].rating, Some(1));
+    }
+}
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+
+use crate::cmd::prelude::*;
+use crate::paper::{Paper, PaperList, ReadingProgress};
+use crate::utils::{confirm, take_yes};
+
+pub static MAN: &str = include_str!("../../man/uniq.md");
+
+pub fn execute(
+    mut input: CommandInput,
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+    let preview = matches!(
+        input.args.get(1).map(String::as_str),
+        Some("-n") | Some("dry-run") | Some("--dry-run")
+    );
+    if preview {
+        input.args.remove(1);
+    }
+
+    let paper_list = match input.papers {
+        // Papers are given through pipe.
+        Some(list) => list,
+        // Papers are specified as filter.
+        None => {
+            match crate::cmd::ls::execute(input, state, config)? {
+                CommandOutput::Papers(paper_list) => paper_list,
+                // `ls` always returns CommandOutput::Papers.
+                _ => panic!(),
+            }
+        }
+    };
+    let mut indices = state.resolve(&paper_list)?;
+    indices.sort_unstable();
+    indices.dedup();
+
+    let clusters = clusters(&state.papers, &indices);
+    if clusters.is_empty() {
+        return Ok(CommandOutput::Message("No duplicates found.\n".to_owned()));
+    }
+
+    // Only report clusters on a dry run.
+    let num_cluster = clusters.len();
+    let plural = if num_cluster != 1 {
+        "clusters"
+    } else {
+        "cluster"
+    };
+    if preview {
+        let mut message: String = clusters
+            .iter()
+            .enumerate()
+            .map(|(num, cluster)| describe(num + 1, cluster, &state.papers))
+            .collect();
+        message.push_str(&format!(
+            "Found {} {} of likely duplicates.\n",
+            num_cluster, plural
+        ));
+        return Ok(CommandOutput::Message(message));
+    }
+
+    // Ask about every cluster before changing anything, so that a single
+    // `undo` reverts all merges.
+    let mut accepted = Vec::new();
+    for (num, cluster) in clusters.into_iter().enumerate() {
+        print!("{}", describe(num + 1, &cluster, &state.papers));
+        if !yes {
+            match confirm("Merge them?".to_owned(), false) {
+                Ok(()) => {}
+                Err(Fallacy::FailedUserInteraction(_)) => {
+                    println!("Skipped.");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        accepted.push(cluster);
+    }
+    if accepted.is_empty() {
+        return Ok(CommandOutput::Message("Merged nothing.\n".to_owned()));
+    }
+
+    // Remember merged papers and papers linked to duplicates for `undo`.
+    let duplicates: Vec<u64> = accepted
+        .iter()
+        .flat_map(|cluster| cluster[1..].iter().map(|&ind| state.papers[ind].id))
+        .collect();
+    let changed: Vec<usize> = (0..state.papers.len())
+        .filter(|&ind| {
+            accepted.iter().any(|cluster| cluster.contains(&ind))
+                || state.papers[ind]
+                    .related
+                    .iter()
+                    .any(|id| duplicates.contains(id))
+        })
+        .collect();
+    state.snapshot("uniq", &changed);
+
+    let mut kept = Vec::with_capacity(accepted.len());
+    let mut trashed = Vec::new();
+    for cluster in accepted.iter() {
+        merge(state, cluster);
+        kept.push(state.papers[cluster[0]].id);
+        trashed.extend_from_slice(&cluster[1..]);
+    }
+    state.trash(&trashed);
+    state.resolve_citekeys();
+
+    let num_merged = kept.len();
+    println!(
+        "Merged {} of {} {}. `undo` splits them again.",
+        num_merged, num_cluster, plural
+    );
+    Ok(CommandOutput::Papers(PaperList(kept)))
+}
+
+/// Group the papers at the indices that are likely the same paper, as
+/// they share a title, DOI, or arXiv ID. In each cluster, the paper added
+/// first comes first, and the paper added first among clusters comes first.
+fn clusters(papers: &[Paper], indices: &[usize]) -> Vec<Vec<usize>> {
+    // Union-find over positions in `indices`.
+    let mut parent: Vec<usize> = (0..indices.len()).collect();
+    fn root(parent: &mut [usize], mut pos: usize) -> usize {
+        while parent[pos] != pos {
+            parent[pos] = parent[parent[pos]];
+            pos = parent[pos];
+        }
+        pos
+    }
+
+    let keys: [fn(&Paper) -> Option<String>; 3] = [normalized_title, doi, arxiv_id];
+    for key in keys.iter() {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (pos, &ind) in indices.iter().enumerate() {
+            let value = match key(&papers[ind]) {
+                Some(value) => value,
+                None => continue,
+            };
+            match seen.get(&value) {
+                Some(&first) => {
+                    let (a, b) = (root(&mut parent, first), root(&mut parent, pos));
+                    parent[a.max(b)] = a.min(b);
+                }
+                None => {
+                    seen.insert(value, pos);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (pos, &ind) in indices.iter().enumerate() {
+        groups.entry(root(&mut parent, pos)).or_default().push(ind);
+    }
+    let added = |ind: &usize| (papers[*ind].added.is_none(), papers[*ind].added, *ind);
+    let mut clusters: Vec<_> = groups
+        .into_values()
+        .map(|mut cluster| {
+            cluster.sort_by_key(added);
+            cluster
+        })
+        .filter(|cluster| cluster.len() > 1)
+        .collect();
+    clusters.sort_by_key(|cluster| added(&cluster[0]));
+    clusters
+}
+
+/// The title in lowercase with only its words, so that case, punctuation,
+/// and spacing do not matter.
+fn normalized_title(paper: &Paper) -> Option<String> {
+    let title = paper
+        .title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(title).filter(|title| !title.is_empty())
+}
+
+fn doi(paper: &Paper) -> Option<String> {
+    paper.doi.as_ref().map(|doi| doi.to_lowercase())
+}
+
+/// The arXiv ID of the paper without its version, from its eprint, URL,
+/// or arXiv DOI.
+fn arxiv_id(paper: &Paper) -> Option<String> {
+    let id = paper.extra.get("eprint").cloned().or_else(|| {
+        let url = paper.url.as_ref()?.to_lowercase();
+        let (_, id) = url
+            .split_once("arxiv.org/abs/")
+            .or_else(|| url.split_once("arxiv.org/pdf/"))?;
+        Some(id.trim_end_matches('/').trim_end_matches(".pdf").to_owned())
+    });
+    let id = id.or_else(|| {
+        let doi = paper.doi.as_ref()?.to_lowercase();
+        doi.strip_prefix("10.48550/arxiv.").map(str::to_owned)
+    })?;
+    let id = id.trim().to_lowercase();
+    let id = match id.rfind('v') {
+        Some(v) if v > 0 && v + 1 < id.len() && id[v + 1..].chars().all(|c| c.is_ascii_digit()) => {
+            &id[..v]
+        }
+        _ => &id[..],
+    };
+    Some(id.to_owned()).filter(|id| !id.is_empty())
+}
+
+/// A numbered cluster with why its papers look the same, and a line for
+/// each paper.
+fn describe(num: usize, cluster: &[usize], papers: &[Paper]) -> String {
+    let shared = |key: fn(&Paper) -> Option<String>| {
+        let values: Vec<_> = cluster
+            .iter()
+            .filter_map(|&ind| key(&papers[ind]))
+            .collect();
+        values
+            .iter()
+            .enumerate()
+            .any(|(i, value)| values[i + 1..].contains(value))
+    };
+    let reasons: Vec<_> = [
+        ("title", shared(normalized_title)),
+        ("DOI", shared(doi)),
+        ("arXiv ID", shared(arxiv_id)),
+    ]
+    .iter()
+    .filter(|(_, shared)| *shared)
+    .map(|(reason, _)| *reason)
+    .collect();
+
+    let mut ret = format!("Cluster {} (same {}):\n", num, reasons.join(", "));
+    for &ind in cluster {
+        let paper = &papers[ind];
+        let added = match paper.added {
+            Some(added) => added.format("%Y-%m-%d").to_string(),
+            None => "unknown".to_owned(),
+        };
+        ret.push_str(&format!(
+            "  {} ({} {}, added {}, {}, {} {})\n",
+            paper.mention(),
+            paper.venue,
+            paper.year,
+            added,
+            paper.progress,
+            paper.files.len(),
+            if paper.files.len() != 1 {
+                "files"
+            } else {
+                "file"
+            },
+        ));
+    }
+    ret
+}
+
+/// How far reading progress goes, for keeping the most read status.
+fn rank(progress: &ReadingProgress) -> u8 {
+    match progress {
+        ReadingProgress::Unread => 0,
+        ReadingProgress::InProgress(_) => 1,
+        ReadingProgress::Read => 2,
+    }
+}
+
+/// Merge the rest of the cluster into the paper at its first index. The
+/// merged paper keeps the union of tags, files, and links, the earliest
+/// added date, and the most read status. Fields it lacks are taken from
+/// the duplicates, whose nicknames become its aliases. Links to the
+/// duplicates now point to it. The duplicates are left to be trashed
+/// without files or notes, so that emptying the trash keeps them.
+fn merge(state: &mut State, cluster: &[usize]) {
+    let keep = cluster[0];
+    let keep_id = state.papers[keep].id;
+    let dup_ids: Vec<u64> = cluster[1..]
+        .iter()
+        .map(|&ind| state.papers[ind].id)
+        .collect();
+
+    for &ind in cluster[1..].iter() {
+        let dup = state.papers[ind].clone();
+        let paper = &mut state.papers[keep];
+
+        paper.labels.extend(dup.labels.iter().cloned());
+        for file in dup.files.iter() {
+            if !paper.files.contains(file) {
+                paper.files.push(file.clone());
+            }
+        }
+        for id in dup.related.iter() {
+            if !paper.related.contains(id) {
+                paper.related.push(*id);
+            }
+        }
+        for name in dup.nicknames() {
+            if paper.nickname.is_none() {
+                paper.nickname = Some(name.to_owned());
+            } else if !paper.nicknames().any(|n| n == name) {
+                paper.aliases.push(name.to_owned());
+            }
+        }
+
+        if let Some(added) = dup.added {
+            if paper.added.is_none_or(|kept| added < kept) {
+                paper.added = Some(added);
+            }
+        }
+        if rank(&dup.progress) > rank(&paper.progress) {
+            paper.progress = dup.progress;
+        }
+        if let Some(read_at) = dup.read_at {
+            if paper.read_at.is_none_or(|kept| read_at < kept) {
+                paper.read_at = Some(read_at);
+            }
+        }
+        for change in dup.history.iter() {
+            if !paper.history.contains(change) {
+                paper.history.push(change.clone());
+            }
+        }
+        paper.history.sort_by_key(|change| change.when);
+        paper.reading_seconds += dup.reading_seconds;
+
+        // Fill in what the merged paper lacks.
+        paper.doi = paper.doi.take().or(dup.doi);
+        paper.citekey = paper.citekey.take().or(dup.citekey);
+        paper.url = paper.url.take().or(dup.url);
+        paper.abstract_text = paper.abstract_text.take().or(dup.abstract_text);
+        paper.rating = paper.rating.or(dup.rating);
+        paper.priority = paper.priority.or(dup.priority);
+        paper.citation_count = paper.citation_count.max(dup.citation_count);
+        for (name, value) in dup.extra {
+            paper.extra.entry(name).or_insert(value);
+        }
+        match (&paper.notepath, dup.notepath) {
+            (None, notepath) => paper.notepath = notepath,
+            (Some(_), Some(notepath)) => {
+                println!("Kept the notes of the duplicate at {}.", notepath.display())
+            }
+            (Some(_), None) => {}
+        }
+        match (&paper.wikipath, dup.wikipath) {
+            (None, wikipath) => paper.wikipath = wikipath,
+            (Some(_), Some(wikipath)) => {
+                println!("Kept the wiki of the duplicate at {}.", wikipath.display())
+            }
+            (Some(_), None) => {}
+        }
+
+        let dup = &mut state.papers[ind];
+        dup.files.clear();
+        dup.notepath = None;
+        dup.wikipath = None;
+    }
+
+    // Point links to the duplicates at the merged paper.
+    for paper in state.papers.iter_mut() {
+        if !paper.related.iter().any(|id| dup_ids.contains(id)) {
+            continue;
+        }
+        for id in paper.related.iter_mut() {
+            if dup_ids.contains(id) {
+                *id = keep_id;
+            }
+        }
+        let mut seen = Vec::with_capacity(paper.related.len());
+        paper.related.retain(|id| {
+            let new = !seen.contains(id);
+            seen.push(*id);
+            new
+        });
+    }
+    let paper = &mut state.papers[keep];
+    paper
+        .related
+        .retain(|id| *id != keep_id && !dup_ids.contains(id));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    use chrono::{Duration, Local};
+
+    fn uniq(args: &[&str], state: &mut State) -> Result<CommandOutput, Fallacy> {
+        let input = CommandInput {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            papers: None,
+        };
+        execute(input, state, &Config::default())
+    }
+
+    #[test]
+    fn find_and_merge() {
+        let now = Local::now();
+        let paper = |title: &str, days: i64| Paper {
+            title: title.to_owned(),
+            added: Some(now - Duration::days(days)),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            Paper {
+                nickname: Some("zeus".to_owned()),
+                labels: vec!["energy".to_owned()].into_iter().collect(),
+                files: vec!["zeus.pdf".into()],
+                ..paper("Zeus: Understanding GPU Energy", 1)
+            },
+            Paper {
+                labels: vec!["gpu".to_owned()].into_iter().collect(),
+                files: vec!["zeus-arxiv.pdf".into()],
+                progress: ReadingProgress::Read,
+                url: Some("https://arxiv.org/abs/2208.06102v2".to_owned()),
+                ..paper("zeus -- understanding GPU energy", 5)
+            },
+            Paper {
+                nickname: Some("zeus-nsdi".to_owned()),
+                doi: Some("10.48550/arXiv.2208.06102".to_owned()),
+                ..paper("Zeus (NSDI)", 3)
+            },
+            paper("Reason", 2),
+            paper("Other", 4),
+        ]);
+        let ids: Vec<_> = state.papers.iter().map(|p| p.id).collect();
+        state.papers[3].related = vec![ids[2]];
+        state.papers[2].related = vec![ids[3]];
+
+        // Clusters are only reported on a dry run.
+        match uniq(&["uniq", "-n"], &mut state) {
+            Ok(CommandOutput::Message(message)) => {
+                assert!(message.starts_with("Cluster 1 (same title, arXiv ID):\n"));
+                assert!(message.ends_with("Found 1 cluster of likely duplicates.\n"));
+            }
+            _ => panic!(),
+        }
+        assert_eq!(state.papers.len(), 5);
+
+        // The paper added first is kept with everything merged into it.
+        match uniq(&["uniq", "-y"], &mut state) {
+            Ok(CommandOutput::Papers(PaperList(kept))) => assert_eq!(kept, vec![ids[1]]),
+            _ => panic!(),
+        }
+        let titles: Vec<_> = state.papers.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["zeus -- understanding GPU energy", "Reason", "Other"]
+        );
+        let merged = &state.papers[0];
+        assert_eq!(merged.nickname.as_deref(), Some("zeus-nsdi"));
+        assert_eq!(merged.aliases, vec!["zeus".to_owned()]);
+        assert_eq!(merged.labels.len(), 2);
+        assert_eq!(merged.files.len(), 2);
+        assert_eq!(merged.progress, ReadingProgress::Read);
+        assert_eq!(merged.added, Some(now - Duration::days(5)));
+        assert_eq!(merged.related, vec![ids[3]]);
+        assert_eq!(state.papers[1].related, vec![ids[1]]);
+        assert!(state.trash.iter().all(|t| t.paper.files.is_empty()));
+        assert!(matches!(
+            uniq(&["uniq"], &mut state),
+            Ok(CommandOutput::Message(message)) if message == "No duplicates found.\n"
+        ));
+
+        // Undo brings the duplicates back as they were.
+        state.undo().unwrap();
+        assert_eq!(state.papers.len(), 5);
+        assert!(state.trash.is_empty());
+        assert_eq!(state.papers[0].files, vec![PathBuf::from("zeus.pdf")]);
+        assert_eq!(state.papers[3].related, vec![ids[2]]);
+    }
+
+    #[test]
+    fn identifiers() {
+        let paper = |doi: Option<&str>, url: Option<&str>| Paper {
+            doi: doi.map(str::to_owned),
+            url: url.map(str::to_owned),
+            ..Default::default()
+        };
+        assert_eq!(
+            arxiv_id(&paper(None, Some("https://arxiv.org/pdf/2208.06102v1.pdf"))),
+            Some("2208.06102".to_owned())
+        );
+        assert_eq!(
+            arxiv_id(&paper(Some("10.48550/arXiv.2208.06102"), None)),
+            Some("2208.06102".to_owned())
+        );
+        assert_eq!(arxiv_id(&paper(Some("10.1145/1234"), None)), None);
+        assert_eq!(
+            normalized_title(&Paper {
+                title: "  A   Title: With-Punctuation! ".to_owned(),
+                ..Default::default()
+            }),
+            Some("a title with punctuation".to_owned())
+        );
+        assert_eq!(normalized_title(&Paper::default()), None);
+    }
+}
@@ -1,89 +1,253 @@
 //! Provides an integration with VimWiki by creating a new wiki index in the
 //! default paperbase path and opening all wikis with vimwiki filetypes.
-use std::path::PathBuf;
-use std::process::Command;
-
+use crate::cmd::ls::{group_papers, GroupKey};
 use crate::cmd::prelude::*;
-use crate::utils::confirm;
+use crate::cmd::Skipped;
+use crate::config::NoteFormat;
+use crate::paper::ReadingProgress;
+use crate::utils::spawn::{spawn, vimwiki_command};
+use crate::utils::{confirm, take_yes};
 
 pub static MAN: &str = include_str!("../../man/vimwiki.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    let yes = take_yes(&mut input.args) || config.general.assume_yes;
+
+    // `vimwiki index` writes an index page of all wikis.
+    if input.papers.is_none() && input.args.get(1).map(String::as_str) == Some("index") {
+        return write_index(&input.args[2..], state, config);
+    }
+
     // Build paper list from input.
-    let selected = match input.papers {
+    let paper_list = match input.papers {
         // Papers are given through pipe.
-        Some(list) => list.0,
+        Some(list) => list,
         // Papers are specified as filter.
         None => {
             match crate::cmd::ls::execute(input, state, config)? {
-                CommandOutput::Papers(paper_list) => paper_list.0,
+                CommandOutput::Papers(paper_list) => paper_list,
                 // `ls` always returns CommandOutput::Papers.
                 _ => panic!("internal ls invocation returned wrong output variant"),
             }
         }
     };
 
-    // Build a vector of wiki paths.
-    let num_papers = selected.len();
+    let selected = state.resolve(&paper_list)?;
+
+    // Build a vector of wiki paths, skipping papers whose wiki cannot be
+    // made.
+    let mut skipped = Skipped::default();
     let mut wikis = Vec::new();
     for i in selected {
-        wikis.push(state.papers[i].wikipath(config, true)?.unwrap());
+        match state.papers[i].wikipath(config, true) {
+            Ok(Some(wiki)) => wikis.push(wiki),
+            Ok(None) => skipped.add("no wiki path", &state.papers[i]),
+            Err(e) => {
+                println!("{}", e);
+                skipped.add("cannot create wiki", &state.papers[i]);
+            }
+        }
+    }
+    let num_papers = wikis.len();
+    if num_papers == 0 {
+        skipped.report("Opened 0".to_owned(), 0)?;
+        return Ok(CommandOutput::None);
     }
 
     // Ask for confirmation.
-    if num_papers > 1 {
-        confirm(format!("Open wikis for {} paper?", num_papers), true)?;
+    if num_papers > 1 && !yes {
+        confirm(format!("Open wikis for {} papers?", num_papers), true)?;
     }
 
     // Open wikis.
     if config.output.editor_batch {
-        spawn(build_editor_command(wikis.as_ref(), config), true);
+        spawn(vimwiki_command(wikis.as_ref(), config)?, true);
     } else {
         for wiki in wikis {
-            spawn(build_editor_command(&[wiki], config), false);
+            spawn(vimwiki_command(&[wiki], config)?, false);
         }
     }
+    skipped.report(format!("Opened {}", num_papers), num_papers)?;
 
     Ok(CommandOutput::None)
 }
 
-fn spawn(mut command: Command, block: bool) {
-    match command.spawn() {
-        Ok(mut handle) => {
-            if !block {
-                return;
-            }
-            if let Err(e) = handle.wait() {
-                println!("Failed to wait subprocess: {}", e);
-            }
+/// Lines around the part of the index that is kept when it is rewritten,
+/// as VimWiki and markdown comments.
+const USER_BEGIN: [&str; 2] = [
+    "%% BEGIN USER CONTENT (kept by `vimwiki index`)",
+    "<!-- BEGIN USER CONTENT (kept by `vimwiki index`) -->",
+];
+const USER_END: [&str; 2] = ["%% END USER CONTENT", "<!-- END USER CONTENT -->"];
+
+/// Write `index.wiki` (or `index.md` for markdown notes) in the wiki
+/// directory, linking to the wiki of every paper, grouped by year or tag. Content between the user content markers
+/// is kept, and the rest is written anew.
+fn write_index(
+    args: &[String],
+    state: &mut State,
+    config: &Config,
+) -> Result<CommandOutput, Fallacy> {
+    let key = match args.first().map(String::as_str) {
+        None | Some("year") => GroupKey::Year,
+        Some("tag") => GroupKey::Tag,
+        Some(key) => {
+            return Err(Fallacy::InvalidCommand(format!(
+                "Cannot group the index by '{}'. Expected 'year' or 'tag'.",
+                key
+            )))
+        }
+    };
+
+    let markdown = config.storage.note_format == NoteFormat::Markdown;
+    let syntax = markdown as usize;
+    let path = config
+        .storage
+        .wiki_dir
+        .join(if markdown { "index.md" } else { "index.wiki" });
+    let user = match std::fs::read_to_string(&path) {
+        Ok(text) => user_content(&text, USER_BEGIN[syntax], USER_END[syntax]),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    // Only papers with a wiki are linked.
+    let mut with_wiki = Vec::new();
+    for ind in 0..state.papers.len() {
+        if let Some(wiki) = state.papers[ind].wikipath(config, false)? {
+            with_wiki.push((ind, wiki));
         }
-        Err(e) => {
-            if matches!(e.kind(), std::io::ErrorKind::NotFound) {
-                println!("Invalid editor command: '{:?}'", e);
+    }
+    let indices: Vec<usize> = with_wiki.iter().map(|(ind, _)| *ind).collect();
+
+    let mut text = String::from(if markdown {
+        "# Papers\n\n"
+    } else {
+        "= Papers =\n\n"
+    });
+    text.push_str(USER_BEGIN[syntax]);
+    text.push('\n');
+    text.push_str(user.as_deref().unwrap_or("\n"));
+    text.push_str(USER_END[syntax]);
+    text.push('\n');
+    let mut links = 0;
+    for (group, members) in group_papers(&indices, &state.papers, key, false) {
+        if markdown {
+            text.push_str(&format!("\n## {}\n", group));
+        } else {
+            text.push_str(&format!("\n== {} ==\n", group));
+        }
+        for ind in members {
+            let paper = &state.papers[ind];
+            let wiki = &with_wiki.iter().find(|(i, _)| *i == ind).unwrap().1;
+            let target = wiki.strip_prefix(&config.storage.wiki_dir).unwrap_or(wiki);
+            let marker = match (paper.progress, markdown) {
+                (ReadingProgress::Unread, _) => ' ',
+                (ReadingProgress::InProgress(_), false) => '.',
+                (ReadingProgress::InProgress(_), true) => '/',
+                (ReadingProgress::Read, false) => 'X',
+                (ReadingProgress::Read, true) => 'x',
+            };
+            let link = if markdown {
+                format!(
+                    "[{}]({})",
+                    paper.title.replace('[', "\\[").replace(']', "\\]"),
+                    target.display().to_string().replace(' ', "%20")
+                )
             } else {
-                println!("Failed to spawn subprocess: '{:?}'", e);
-            }
+                format!(
+                    "[[{}|{}]]",
+                    target.with_extension("").display(),
+                    paper.title.replace('|', "/")
+                )
+            };
+            text.push_str(&format!("- [{}] {}\n", marker, link));
+            links += 1;
         }
     }
+    std::fs::write(&path, text)?;
+
+    Ok(CommandOutput::Message(format!(
+        "Wrote {} links to '{}'.\n",
+        links,
+        path.display()
+    )))
 }
 
-fn build_editor_command(wikis: &[PathBuf], config: &Config) -> Command {
-    let command = &config.output.editor_command;
-    let mut ret = Command::new(&command[0]);
-    ret.args(&command[1..]).args(wikis);
-
-    let vimwiki_opts = vec![
-        String::from("-c"),
-        String::from(format!(
-            "\"call add(g:vimwiki_list, {{'path': \'{}\'}})\"",
-            &config.storage.wiki_dir.to_str().unwrap()
-        )),
-    ];
-
-    ret.args(&vimwiki_opts[0..]).args(wikis);
-    ret
+/// The lines between the user content markers, if both are there.
+fn user_content(text: &str, begin: &str, end: &str) -> Option<String> {
+    let start = text.find(begin)? + begin.len();
+    let end = start + text[start..].find(end)?;
+    let user = text[start..end].trim_start_matches('\n');
+    Some(if user.is_empty() { "\n" } else { user }.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::paper::Paper;
+
+    #[test]
+    fn index() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.wiki", "b.wiki"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let mut config = Config::default();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let paper = |title: &str, year: &str, wiki: Option<&str>, progress| Paper {
+            title: title.to_owned(),
+            year: year.to_owned(),
+            wikipath: wiki.map(std::path::PathBuf::from),
+            progress,
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("A", "2022", Some("a.wiki"), ReadingProgress::Read),
+            paper("B", "2021", Some("b.wiki"), ReadingProgress::Unread),
+            paper("C", "2021", None, ReadingProgress::Unread),
+        ]);
+        let mut index = |args: &[&str]| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, &mut state, &config) {
+                Ok(CommandOutput::Message(message)) => message,
+                _ => panic!(),
+            }
+        };
+
+        assert!(index(&["vimwiki", "index"]).starts_with("Wrote 2 links"));
+        let path = dir.path().join("index.wiki");
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.ends_with("== 2021 ==\n- [ ] [[b|B]]\n\n== 2022 ==\n- [X] [[a|A]]\n"));
+
+        // User content is kept, and rewriting changes nothing else.
+        let text = text.replacen("\n\n%% END", "\nMy reading list.\n%% END", 1);
+        std::fs::write(&path, &text).unwrap();
+        index(&["vimwiki", "index"]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+        index(&["vimwiki", "index", "tag"]);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("My reading list.") && text.contains("== (none) =="));
+
+        // Markdown notes get a markdown index.
+        config.storage.note_format = NoteFormat::Markdown;
+        std::fs::write(dir.path().join("c key.md"), "").unwrap();
+        state.papers[2].wikipath = Some("c key.md".into());
+        state.papers[2].progress = ReadingProgress::InProgress(None);
+        let input = CommandInput {
+            args: vec!["vimwiki".to_owned(), "index".to_owned()],
+            papers: None,
+        };
+        execute(input, &mut state, &config).unwrap();
+        let text = std::fs::read_to_string(dir.path().join("index.md")).unwrap();
+        assert!(text.starts_with("# Papers\n\n<!-- BEGIN USER CONTENT"));
+        assert!(text.contains("## 2021\n- [ ] [B](b.wiki)\n- [/] [C](c%20key.md)\n"));
+    }
 }
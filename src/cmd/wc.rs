@@ -1,12 +1,79 @@
+use chrono::{Duration, Local};
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
+
+use crate::cmd::ls::GroupKey;
 use crate::cmd::prelude::*;
+use crate::paper::{Paper, ReadingProgress};
+use crate::utils::humanize_seconds;
 
 pub static MAN: &str = include_str!("../../man/wc.md");
 
 pub fn execute(
-    input: CommandInput,
+    mut input: CommandInput,
     state: &mut State,
     config: &Config,
 ) -> Result<CommandOutput, Fallacy> {
+    // `wc time` also sums up reading time.
+    let time = input.args.get(1).map(String::as_str) == Some("time");
+    if time && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
+    // `wc velocity [window]` reports how fast papers are read.
+    let velocity = match input.args.get(1).map(String::as_str) {
+        Some("velocity") => {
+            let window = match input.args.get(2).and_then(|arg| parse_window(arg)) {
+                Some(days) => {
+                    let days = days?;
+                    if input.papers.is_none() {
+                        input.args.remove(2);
+                    }
+                    days
+                }
+                None => DEFAULT_WINDOW_DAYS,
+            };
+            if input.papers.is_none() {
+                input.args.remove(1);
+            }
+            Some(window)
+        }
+        _ => None,
+    };
+
+    // `wc notes` counts lines, words, and characters of wikis.
+    let notes = input.args.get(1).map(String::as_str) == Some("notes");
+    if notes && input.papers.is_none() {
+        input.args.remove(1);
+    }
+
+    // `wc by KEY` counts papers in each group.
+    let by = match (input.args.get(1).map(String::as_str), input.args.get(2)) {
+        (Some("by"), Some(key)) => match GroupKey::parse(key) {
+            Some(key) => Some(key),
+            None => {
+                return Err(Fallacy::InvalidCommand(format!(
+                    "Cannot count papers by '{}'. Expected 'year', 'venue', 'tag', or 'status'.",
+                    key
+                )))
+            }
+        },
+        (Some("by"), None) => {
+            return Err(Fallacy::InvalidCommand(
+                "`wc by` expects 'year', 'venue', 'tag', or 'status'.".to_owned(),
+            ))
+        }
+        _ => None,
+    };
+    if by.is_some() && input.papers.is_none() {
+        input.args.drain(1..3);
+    }
+
+    // Archived papers hidden by the filter are counted separately.
+    let filter_args = match input.papers {
+        Some(_) => None,
+        None => Some(input.args[1..].to_vec()),
+    };
+
     let paper_list = match input.papers {
         // Papers are given through pipe.
         Some(list) => list,
@@ -20,8 +87,381 @@ pub fn execute(
         }
     };
 
+    if notes {
+        let indices = state.resolve(&paper_list)?;
+        return Ok(CommandOutput::Message(count_notes(
+            &indices, state, config,
+        )?));
+    }
+    if let Some(days) = velocity {
+        let indices = state.resolve(&paper_list)?;
+        return Ok(CommandOutput::Message(report_velocity(
+            &indices, state, days,
+        )?));
+    }
+    if let Some(key) = by {
+        let indices = state.resolve(&paper_list)?;
+        return Ok(CommandOutput::Message(breakdown(&indices, state, key)));
+    }
+
+    if !time {
+        let indices = state.resolve(&paper_list)?;
+        let hidden = match filter_args {
+            Some(args) => crate::cmd::ls::hidden_archived(&args, state, config)?,
+            None => 0,
+        };
+        return Ok(CommandOutput::Message(count_papers(
+            &indices, state, hidden,
+        )));
+    }
+
+    let indices = state.resolve(&paper_list)?;
+    let seconds: u64 = indices
+        .iter()
+        .map(|&ind| state.papers[ind].reading_seconds)
+        .sum();
     Ok(CommandOutput::Message(format!(
-        "{} papers.\n",
-        paper_list.0.len()
+        "{} papers, read for {}.\n",
+        indices.len(),
+        humanize_seconds(seconds)
     )))
 }
+
+/// How many papers there are, and how many of them or besides them are
+/// archived, like '12 papers, plus 3 archived.'
+fn count_papers(indices: &[usize], state: &State, hidden: usize) -> String {
+    let archived = indices
+        .iter()
+        .filter(|&&ind| state.papers[ind].archived)
+        .count();
+    let mut message = format!("{} papers", indices.len());
+    if archived > 0 {
+        message.push_str(&format!(", {} of them archived", archived));
+    }
+    if hidden > 0 {
+        message.push_str(&format!(", plus {} archived", hidden));
+    }
+    message + ".\n"
+}
+
+/// A table of how many papers are in each group, largest first. Papers
+/// without a group are counted as '(none)'.
+fn breakdown(indices: &[usize], state: &State, key: GroupKey) -> String {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for &ind in indices {
+        let mut names: Vec<_> = key
+            .groups_of(&state.papers[ind])
+            .into_iter()
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            names.push("(none)".to_owned());
+        }
+        for name in names {
+            match counts.iter_mut().find(|(group, _)| *group == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+    }
+    // Ties are broken by name, and '(none)' comes last among them.
+    counts.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| (a.0 == "(none)").cmp(&(b.0 == "(none)")))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(["group", "count", "percent"].map(|s| {
+        Cell::new(s)
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }));
+    for (name, count) in counts {
+        let percent = count as f64 * 100.0 / indices.len() as f64;
+        table.add_row(vec![
+            Cell::new(name),
+            Cell::new(count).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.1}%", percent)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    format!("{}\n{} papers.\n", table, indices.len())
+}
+
+/// Lines, words, and characters of the wiki of each paper, like `wc` of
+/// the shell, with a total in the end. Papers without a wiki count as zero,
+/// and wikis that cannot be read as text are left out with a warning.
+fn count_notes(indices: &[usize], state: &mut State, config: &Config) -> Result<String, Fallacy> {
+    let mut rows = Vec::new();
+    let mut total = [0; 3];
+    for &ind in indices {
+        let paper = &mut state.papers[ind];
+        let mut name = paper.mention();
+        let counts = match paper.wikipath(config, false)? {
+            Some(wiki) => match std::fs::read_to_string(&wiki) {
+                Ok(text) => [
+                    text.lines().count(),
+                    text.split_whitespace().count(),
+                    text.chars().count(),
+                ],
+                Err(e) => {
+                    println!("Skipped the wiki of {}: {}", name, e);
+                    continue;
+                }
+            },
+            None => {
+                name.push_str(" (no wiki)");
+                [0; 3]
+            }
+        };
+        for (sum, count) in total.iter_mut().zip(counts) {
+            *sum += count;
+        }
+        rows.push((counts, name));
+    }
+    rows.push((total, "total".to_owned()));
+
+    let width = total[2].to_string().len();
+    Ok(rows
+        .iter()
+        .map(|([lines, words, chars], name)| {
+            format!(
+                "{:>w$} {:>w$} {:>w$} {}\n",
+                lines,
+                words,
+                chars,
+                name,
+                w = width
+            )
+        })
+        .collect())
+}
+
+/// The window of `wc velocity` when none is given: 12 weeks.
+const DEFAULT_WINDOW_DAYS: i64 = 84;
+
+/// The longest window of `wc velocity`, well beyond any reading history.
+const MAX_WINDOW_DAYS: i64 = 100_000;
+
+/// Parse a window like '12w', '6m', or '30d' into days. A bare number is in
+/// weeks. Returns `None` if the argument does not look like a window at all,
+/// so that it can be part of the filter.
+fn parse_window(window: &str) -> Option<Result<i64, Fallacy>> {
+    let (count, unit) = match window.char_indices().last()? {
+        (i, 'd') => (&window[..i], 1),
+        (i, 'w') => (&window[..i], 7),
+        (i, 'm') => (&window[..i], 30),
+        _ => (window, 7),
+    };
+    if count.is_empty() || !count.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let days = count
+        .parse::<i64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .filter(|&days| days > 0 && days <= MAX_WINDOW_DAYS);
+    Some(days.ok_or_else(|| Fallacy::WcInvalidWindow(window.to_owned(), MAX_WINDOW_DAYS)))
+}
+
+/// When the paper was read, from `read_at` or else from its history.
+fn read_date(paper: &Paper) -> Option<chrono::DateTime<Local>> {
+    paper.read_at.or_else(|| {
+        paper
+            .history
+            .iter()
+            .rev()
+            .find(|change| change.progress == ReadingProgress::Read)
+            .map(|change| change.when)
+    })
+}
+
+/// Papers read per week and month over the last `days` days, and how long
+/// the rest would take at that pace. Read papers without a read date are
+/// left out and counted.
+fn report_velocity(indices: &[usize], state: &State, days: i64) -> Result<String, Fallacy> {
+    let since = Local::now()
+        .checked_sub_signed(Duration::days(days))
+        .ok_or_else(|| Fallacy::WcInvalidWindow(format!("{}d", days), MAX_WINDOW_DAYS))?;
+    let mut read = 0;
+    let mut backlog = 0;
+    let mut skipped = 0;
+    for &ind in indices {
+        let paper = &state.papers[ind];
+        if paper.progress != ReadingProgress::Read {
+            backlog += 1;
+            continue;
+        }
+        match read_date(paper) {
+            Some(date) if date >= since => read += 1,
+            Some(_) => {}
+            None => skipped += 1,
+        }
+    }
+
+    let per_week = read as f64 * 7.0 / days as f64;
+    let per_month = read as f64 * 30.0 / days as f64;
+    let cleared = if backlog == 0 {
+        "already".to_owned()
+    } else if read == 0 {
+        "never at this pace".to_owned()
+    } else {
+        format!("in {:.0} weeks", (backlog as f64 / per_week).ceil())
+    };
+    let mut rows = vec![
+        (
+            "window".to_owned(),
+            format!("{} days, since {}", days, since.format("%Y-%m-%d")),
+        ),
+        ("read".to_owned(), read.to_string()),
+        ("per week".to_owned(), format!("{:.1}", per_week)),
+        ("per month".to_owned(), format!("{:.1}", per_month)),
+        ("backlog".to_owned(), backlog.to_string()),
+        ("cleared".to_owned(), cleared),
+    ];
+    if skipped > 0 {
+        rows.push((
+            "skipped".to_owned(),
+            format!("{} read papers without a read date", skipped),
+        ));
+    }
+
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    Ok(rows
+        .iter()
+        .map(|(name, value)| format!("{:width$}  {}\n", name, value, width = width))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_by_group() {
+        let paper = |venue: &str, labels: &[&str]| Paper {
+            title: venue.to_owned(),
+            venue: venue.to_owned(),
+            labels: labels.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("NeurIPS", &["ml"]),
+            paper("", &["ml", "systems"]),
+            paper("NeurIPS", &[]),
+            paper("OSDI", &["systems"]),
+        ]);
+        let config = Config::default();
+        let mut wc = |args: &[&str]| {
+            let input = CommandInput {
+                args: args.iter().map(|s| s.to_string()).collect(),
+                papers: None,
+            };
+            match execute(input, &mut state, &config) {
+                Ok(CommandOutput::Message(message)) => message,
+                _ => panic!(),
+            }
+        };
+
+        let venues = wc(&["wc", "by", "venue"]);
+        // '(none)' comes last among groups of the same size.
+        let rows: Vec<_> = ["NeurIPS", "OSDI", "(none)"]
+            .iter()
+            .map(|name| venues.find(name).unwrap())
+            .collect();
+        assert!(rows[0] < rows[1] && rows[1] < rows[2]);
+        assert!(venues.contains("50.0%") && venues.contains("25.0%"));
+
+        // Papers with several tags count in each.
+        let tags = wc(&["wc", "by", "tag"]);
+        assert!(tags.contains("(none)") && tags.ends_with("4 papers.\n"));
+
+        // Filters still apply.
+        let filtered = wc(&["wc", "by", "status", "NeurIPS"]);
+        assert!(filtered.contains("unread") && filtered.contains("100.0%"));
+    }
+
+    #[test]
+    fn velocity() {
+        let paper = |progress: ReadingProgress, days_ago: Option<i64>| Paper {
+            title: "Reason".to_owned(),
+            progress,
+            read_at: days_ago.map(|days| Local::now() - Duration::days(days)),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper(ReadingProgress::Read, Some(1)),
+            paper(ReadingProgress::Read, Some(10)),
+            paper(ReadingProgress::Read, Some(100)),
+            paper(ReadingProgress::Read, None),
+            paper(ReadingProgress::Unread, None),
+            paper(ReadingProgress::InProgress(None), None),
+        ]);
+        let input = CommandInput {
+            args: vec!["wc".to_owned(), "velocity".to_owned(), "4w".to_owned()],
+            papers: None,
+        };
+        let report = match execute(input, &mut state, &Config::default()) {
+            Ok(CommandOutput::Message(message)) => message,
+            _ => panic!(),
+        };
+        let rows: Vec<_> = report.lines().collect();
+        assert!(rows[0].starts_with("window     28 days"));
+        assert_eq!(rows[1], "read       2");
+        assert_eq!(rows[2], "per week   0.5");
+        assert_eq!(rows[4], "backlog    2");
+        assert_eq!(rows[5], "cleared    in 4 weeks");
+        assert_eq!(rows[6], "skipped    1 read papers without a read date");
+
+        let window = |arg: &str| parse_window(arg).map(Result::ok);
+        assert_eq!(window("6m"), Some(Some(180)));
+        assert_eq!(window("8"), Some(Some(56)));
+        assert_eq!(window("NeurIPS"), None);
+        assert_eq!(window("2x"), None);
+
+        // Windows out of range are errors rather than filters.
+        for bad in ["0", "0d", "200000000000000d", "99999999999999999999w"] {
+            let input = CommandInput {
+                args: vec!["wc".to_owned(), "velocity".to_owned(), bad.to_owned()],
+                papers: None,
+            };
+            assert!(matches!(
+                execute(input, &mut state, &Config::default()),
+                Err(Fallacy::WcInvalidWindow(window, _)) if window == bad
+            ));
+        }
+    }
+
+    #[test]
+    fn count_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.wiki"), "# A\n\nnotes on a\n").unwrap();
+        std::fs::write(dir.path().join("b.wiki"), [0xff, 0xfe]).unwrap();
+        let mut config = Config::default();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let paper = |nickname: &str, wiki: Option<&str>| Paper {
+            title: nickname.to_uppercase(),
+            nickname: Some(nickname.to_owned()),
+            wikipath: wiki.map(std::path::PathBuf::from),
+            ..Default::default()
+        };
+        let mut state = State::from_papers(vec![
+            paper("a", Some("a.wiki")),
+            paper("b", Some("b.wiki")),
+            paper("c", None),
+        ]);
+        let input = CommandInput {
+            args: vec!["wc".to_owned(), "notes".to_owned()],
+            papers: None,
+        };
+        let report = match execute(input, &mut state, &config) {
+            Ok(CommandOutput::Message(message)) => message,
+            _ => panic!(),
+        };
+        assert_eq!(
+            report,
+            " 3  5 16 @a\n 0  0  0 @c (no wiki)\n 3  5 16 total\n"
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+use chrono::Datelike;
+use comfy_table::Color;
 use serde::{Deserialize, Serialize};
 
 use crate::error::Fallacy;
@@ -7,11 +10,119 @@ use crate::utils::{expand_tilde, expand_tilde_str};
 
 pub static MAN: &str = include_str!("../man/config.md");
 
+/// Paper attributes that can be shown as table columns.
+pub const TABLE_COLUMNS: [&str; 19] = [
+    "title",
+    "nickname",
+    "authors",
+    "first author",
+    "first_author",
+    "venue",
+    "year",
+    "state",
+    "tags",
+    "labels",
+    "added",
+    "read_at",
+    "doi",
+    "key",
+    "url",
+    "rating",
+    "priority",
+    "due",
+    "citations",
+];
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct Config {
+    #[serde(default)]
+    pub general: GeneralConfig,
     pub storage: StorageConfig,
     pub filter: FilterConfig,
     pub output: OutputConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub defaults: DefaultsConfig,
+    /// Command aliases, like `backlog = 'ls unread'`. See `man alias`.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GeneralConfig {
+    /// Skip confirmation prompts as if `-y` were given.
+    #[serde(default)]
+    pub assume_yes: bool,
+    /// Who changes reading progress, recorded in the history of papers.
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Whether to say how many papers are overdue when reason starts.
+    #[serde(default)]
+    pub remind_overdue: bool,
+}
+
+impl GeneralConfig {
+    /// The configured identity, or `$USER@hostname` if not configured.
+    /// `None` if not even the user is known.
+    pub fn identity(&self) -> Option<String> {
+        if let Some(identity) = self.identity.as_ref() {
+            return Some(identity.clone());
+        }
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .filter(|user| !user.is_empty())?;
+        match hostname() {
+            Some(host) => Some(format!("{}@{}", user, host)),
+            None => Some(user),
+        }
+    }
+}
+
+/// The name of this machine, from `$HOSTNAME` or the `hostname` command.
+fn hostname() -> Option<String> {
+    let host = match std::env::var("HOSTNAME") {
+        Ok(host) => host,
+        Err(_) => {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            String::from_utf8(output.stdout).ok()?
+        }
+    };
+    let host = host.trim();
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_owned())
+    }
+}
+
+/// Field values `touch` uses when they are not given.
+#[derive(Serialize, Deserialize, Default)]
+pub struct DefaultsConfig {
+    #[serde(default)]
+    pub venue: Option<String>,
+    /// A year, or 'current' for the year at the time of `touch`.
+    #[serde(default)]
+    pub year: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// A contact address sent along with requests to APIs like Crossref.
+    #[serde(default)]
+    pub mailto: Option<String>,
+    /// How long a request may take before giving up on it.
+    #[serde(default = "NetworkConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// How many more times to try requests that failed transiently.
+    #[serde(default = "NetworkConfig::default_retries")]
+    pub retries: u32,
+    /// The proxy to send requests through, like 'http://proxy:3128'.
+    #[serde(default)]
+    pub proxy: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -22,21 +133,120 @@ pub struct StorageConfig {
     pub file_dir: PathBuf,
     pub note_dir: PathBuf,
     pub wiki_dir: PathBuf, /* Path to VimWiki index */
+    #[serde(default = "StorageConfig::default_undo_depth")]
+    pub undo_depth: usize,
+    /// Where to keep undo snapshots across sessions, if anywhere.
+    #[serde(default)]
+    pub undo_journal: Option<PathBuf>,
+    /// Whether `curl` downloads PDFs of arXiv papers unless told otherwise.
+    #[serde(default = "StorageConfig::default_download_pdf")]
+    pub download_pdf: bool,
+    /// Whether `rm` deletes attached files and wiki pages unless told not to.
+    #[serde(default)]
+    pub rm_files: bool,
+    /// A template for new wiki pages, with `printf` placeholders.
+    #[serde(default)]
+    pub note_template: Option<PathBuf>,
+    /// Whether wiki pages are for VimWiki or plain markdown, like Obsidian.
+    #[serde(default)]
+    pub note_format: NoteFormat,
+}
+
+/// The kind of wiki pages `vimwiki` creates and opens.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteFormat {
+    /// '.wiki' files named after the nickname or title, opened with VimWiki.
+    #[default]
+    Vimwiki,
+    /// '<citekey>.md' files with YAML front matter, opened with any editor.
+    Markdown,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct FilterConfig {
     pub case_insensitive_regex: bool,
+    #[serde(default)]
+    pub literal: bool,
+    #[serde(default)]
+    pub fuzzy: bool,
+    #[serde(default = "FilterConfig::default_fuzzy_threshold")]
+    pub fuzzy_threshold: f64,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct OutputConfig {
     pub table_columns: Vec<String>,
+    /// The default viewer of older configs, before `viewers`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub viewer_command: Vec<String>,
     pub viewer_batch: bool,
     pub editor_command: Vec<String>,
     pub editor_batch: bool,
     pub browser_command: Vec<String>,
+    #[serde(default)]
+    pub track_reading_time: bool,
+    #[serde(default = "OutputConfig::default_color")]
+    pub color: bool,
+    /// Pager for `man`. Empty means `$PAGER`, or else `less -R`.
+    #[serde(default)]
+    pub pager_command: Vec<String>,
+    /// Authors `cite` lists before cutting to "et al.".
+    #[serde(default = "OutputConfig::default_cite_max_authors")]
+    pub cite_max_authors: usize,
+    // TOML tables come after plain values, or the config cannot be saved.
+    /// Viewer commands by file extension. 'default' is for the rest.
+    #[serde(default)]
+    pub viewers: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub progress_colors: ProgressColors,
+    /// Named `printf` templates, used as `printf @name`.
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+}
+
+/// Colors of table rows by reading progress. See `color_of` for the names.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProgressColors {
+    pub unread: String,
+    pub current: String,
+    pub read: String,
+}
+
+impl Default for ProgressColors {
+    fn default() -> Self {
+        Self {
+            unread: "default".to_owned(),
+            current: "yellow".to_owned(),
+            read: "green".to_owned(),
+        }
+    }
+}
+
+/// Parse a color name like 'green' or 'dark_blue'. 'default' is the
+/// terminal's own color, and gives `Some(None)`.
+pub fn color_of(name: &str) -> Option<Option<Color>> {
+    let color = match name {
+        "default" => return Some(None),
+        "black" => Color::Black,
+        "grey" => Color::Grey,
+        "dark_grey" => Color::DarkGrey,
+        "red" => Color::Red,
+        "dark_red" => Color::DarkRed,
+        "green" => Color::Green,
+        "dark_green" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "dark_yellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "dark_blue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "dark_magenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "dark_cyan" => Color::DarkCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(Some(color))
 }
 
 impl Config {
@@ -44,8 +254,75 @@ impl Config {
         self.storage.validate()?;
         self.filter.validate()?;
         self.output.validate()?;
+        self.network.validate()?;
+        self.defaults.validate()?;
+        for (name, value) in self.aliases.iter() {
+            if let Err(e) = crate::cmd::alias::check(name, value, &self.aliases) {
+                return Err(Fallacy::ConfigAuditError(e.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DefaultsConfig {
+    fn validate(&mut self) -> Result<(), Fallacy> {
+        if let Some(year) = self.year.as_ref() {
+            if year != "current" && year.parse::<u32>().is_err() {
+                return Err(Fallacy::ConfigAuditError(format!(
+                    "Default year {} is neither a year nor 'current'.",
+                    year
+                )));
+            }
+        }
         Ok(())
     }
+
+    /// The default year, with 'current' resolved to this year.
+    pub fn year(&self) -> Option<String> {
+        match self.year.as_deref() {
+            Some("current") => Some(chrono::Local::now().year().to_string()),
+            year => year.map(str::to_owned),
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn validate(&mut self) -> Result<(), Fallacy> {
+        if self.timeout_secs == 0 {
+            return Err(Fallacy::ConfigAuditError(
+                "Network timeout should be at least a second.".to_owned(),
+            ));
+        }
+        if let Some(proxy) = self.proxy.as_ref() {
+            if url::Url::parse(proxy).is_err() {
+                return Err(Fallacy::ConfigAuditError(format!(
+                    "Proxy {} is not a URL.",
+                    proxy
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The proxy to use. Falls back to the `HTTPS_PROXY` environment
+    /// variable if none is configured.
+    pub fn proxy(&self) -> Option<String> {
+        self.proxy.clone().or_else(|| {
+            ["HTTPS_PROXY", "https_proxy"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok())
+                .filter(|proxy| !proxy.is_empty())
+        })
+    }
+
+    fn default_timeout_secs() -> u64 {
+        30
+    }
+
+    fn default_retries() -> u32 {
+        2
+    }
 }
 
 impl StorageConfig {
@@ -58,20 +335,63 @@ impl StorageConfig {
         std::fs::create_dir_all(&self.note_dir)?;
         self.wiki_dir = expand_tilde(&self.wiki_dir)?;
         std::fs::create_dir_all(&self.wiki_dir)?;
+        if let Some(path) = self.undo_journal.as_mut() {
+            *path = expand_tilde(path)?;
+        }
+        if let Some(path) = self.note_template.as_mut() {
+            *path = expand_tilde(path)?;
+        }
         Ok(())
     }
+
+    fn default_undo_depth() -> usize {
+        crate::state::DEFAULT_UNDO_DEPTH
+    }
+
+    fn default_download_pdf() -> bool {
+        true
+    }
 }
 
 impl FilterConfig {
     fn validate(&mut self) -> Result<(), Fallacy> {
+        if !(0.0..=1.0).contains(&self.fuzzy_threshold) {
+            return Err(Fallacy::ConfigAuditError(format!(
+                "Fuzzy threshold {} is not between 0 and 1.",
+                self.fuzzy_threshold
+            )));
+        }
         Ok(())
     }
+
+    fn default_fuzzy_threshold() -> f64 {
+        0.8
+    }
 }
 
 impl OutputConfig {
-    fn validate(&mut self) -> Result<(), Fallacy> {
-        let allowed_columns = vec!["title", "authors", "first author", "venue", "year", "state"];
+    fn default_color() -> bool {
+        true
+    }
+
+    fn default_cite_max_authors() -> usize {
+        3
+    }
+
+    /// The viewer command for the file, chosen by its extension. Falls back
+    /// to the 'default' viewer, if any.
+    pub fn viewer_for(&self, file: &Path) -> Option<&Vec<String>> {
+        file.extension()
+            .and_then(|ext| self.viewers.get(&ext.to_string_lossy().to_lowercase()))
+            .or_else(|| self.viewers.get("default"))
+    }
+
+    /// Whether to color output. Setting `NO_COLOR` turns colors off.
+    pub fn use_color(&self) -> bool {
+        self.color && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
 
+    fn validate(&mut self) -> Result<(), Fallacy> {
         // Convert everything to lowercase.
         for field in &mut self.table_columns {
             *field = field.to_lowercase();
@@ -79,7 +399,7 @@ impl OutputConfig {
 
         // Check table columns.
         for col in self.table_columns.iter() {
-            if !allowed_columns.contains(&&col[..]) {
+            if !TABLE_COLUMNS.contains(&&col[..]) {
                 return Err(Fallacy::ConfigAuditError(format!(
                     "Table column name {} is not supported.",
                     col
@@ -87,15 +407,37 @@ impl OutputConfig {
             }
         }
 
-        // Check viewer command and expand tilde.
-        if self.viewer_command.is_empty() {
-            return Err(Fallacy::ConfigAuditError(
-                "Viewer command cannot be empty.".to_owned(),
-            ));
+        // Check row colors.
+        let colors = &self.progress_colors;
+        for name in [&colors.unread, &colors.current, &colors.read] {
+            if color_of(name).is_none() {
+                return Err(Fallacy::ConfigAuditError(format!(
+                    "Color name {} is not supported.",
+                    name
+                )));
+            }
         }
-        for path in self.viewer_command.iter_mut() {
-            *path = expand_tilde_str(path)?;
+
+        // Check viewer commands and expand tilde. Extensions are matched
+        // case-insensitively, with or without the dot.
+        if !self.viewer_command.is_empty() && !self.viewers.contains_key("default") {
+            let command = std::mem::take(&mut self.viewer_command);
+            self.viewers.insert("default".to_owned(), command);
+        }
+        let mut viewers = BTreeMap::new();
+        for (ext, mut command) in std::mem::take(&mut self.viewers) {
+            if command.is_empty() {
+                return Err(Fallacy::ConfigAuditError(format!(
+                    "Viewer command for {} cannot be empty.",
+                    ext
+                )));
+            }
+            for path in command.iter_mut() {
+                *path = expand_tilde_str(path)?;
+            }
+            viewers.insert(ext.trim_start_matches('.').to_lowercase(), command);
         }
+        self.viewers = viewers;
 
         // Check editor command and expand tilde.
         if self.editor_command.is_empty() {
@@ -116,6 +458,9 @@ impl OutputConfig {
         for path in self.browser_command.iter_mut() {
             *path = expand_tilde_str(path)?;
         }
+        for path in self.pager_command.iter_mut() {
+            *path = expand_tilde_str(path)?;
+        }
 
         Ok(())
     }
@@ -171,6 +516,23 @@ impl Default for StorageConfig {
             file_dir: file_base_dir,
             note_dir,
             wiki_dir,
+            undo_depth: Self::default_undo_depth(),
+            undo_journal: None,
+            download_pdf: Self::default_download_pdf(),
+            rm_files: false,
+            note_template: None,
+            note_format: NoteFormat::default(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            mailto: None,
+            timeout_secs: Self::default_timeout_secs(),
+            retries: Self::default_retries(),
+            proxy: None,
         }
     }
 }
@@ -178,7 +540,10 @@ impl Default for StorageConfig {
 impl Default for FilterConfig {
     fn default() -> Self {
         Self {
-            case_insensitive_regex: false,
+            case_insensitive_regex: true,
+            literal: false,
+            fuzzy: false,
+            fuzzy_threshold: Self::default_fuzzy_threshold(),
         }
     }
 }
@@ -187,19 +552,43 @@ impl Default for OutputConfig {
     fn default() -> Self {
         let table_columns = vec!["title", "first author", "venue", "year"];
         let table_columns = table_columns.into_iter().map(|s| s.to_string()).collect();
-        let viewer_command = vec![String::from("zathura")];
+        let viewers = BTreeMap::from([("default".to_owned(), vec![String::from("zathura")])]);
         let viewer_batch = false;
         let editor_command = vec![String::from("vim"), String::from("-p")];
         let editor_batch = true;
         let browser_command = vec![String::from("google-chrome-stable")];
+        let track_reading_time = false;
+        let color = OutputConfig::default_color();
+        let progress_colors = ProgressColors::default();
 
         Self {
             table_columns,
-            viewer_command,
+            viewers,
+            viewer_command: Vec::new(),
             viewer_batch,
             editor_command,
             editor_batch,
             browser_command,
+            track_reading_time,
+            color,
+            progress_colors,
+            templates: BTreeMap::new(),
+            pager_command: Vec::new(),
+            cite_max_authors: OutputConfig::default_cite_max_authors(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn store_and_load_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        confy::store_path(&path, Config::default()).unwrap();
+        let config: Config = confy::load_path(&path).unwrap();
+        assert_eq!(config.output.viewers, Config::default().output.viewers);
+    }
+}
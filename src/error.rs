@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use pdf::error::PdfError;
 use thiserror::Error;
 
+use crate::filter::FilterDiagnostic;
+
 /// A use of invalid or faulty reason.
 #[derive(Error, Debug)]
 pub enum Fallacy {
@@ -30,18 +32,86 @@ pub enum Fallacy {
     UnknownCommand(String),
     #[error("Invalid command: {0}")]
     InvalidCommand(String),
+    #[error("Unterminated quote: {0}")]
+    UnterminatedQuote(char),
     #[error("I/O error: '{0}'")]
     IOError(#[from] std::io::Error),
     #[error("{0}")]
     FailedUserInteraction(String),
+    #[error("{0} Cannot confirm without a terminal. Pass -y to answer yes.")]
+    ConfirmWithoutTerminal(String),
+    #[error("Input ended while asking for '{0}'.")]
+    InputEnded(String),
+    /// What was done, like 'Opened 4', and the papers skipped for each reason.
+    #[error("{}", skipped_message(.0, .1))]
+    PapersSkipped(String, Vec<(String, Vec<String>)>),
+    #[error("Failed to read the note template '{0}': '{1}'")]
+    NoteTemplateReadFailed(PathBuf, std::io::Error),
     // filter
-    #[error("Failed to build filter from regex:\n{0}")]
-    FilterBuildFailed(regex::Error),
+    #[error("{0}")]
+    FilterParseFailed(FilterDiagnostic),
+    #[error("{1}\nFailed to build {0} filter from regex:\n{2}")]
+    FilterRegexInvalid(&'static str, FilterDiagnostic, regex::Error),
+    #[error("Invalid year range: '{0}'. Expected a form like '2018-2022' or '2020-'.")]
+    FilterYearRangeInvalid(String),
+    #[error("Invalid date: '{0}'. Expected a form like '2024', '2024-01', '2024-01-31', 'last-week', or a range 'A..B'.")]
+    FilterDateInvalid(String),
+    #[error("Filter groups joined with 'or' cannot be empty.")]
+    FilterEmptyGroup,
+    #[error("{0}")]
+    FilterUnbalancedParens(FilterDiagnostic),
+    #[error("Unknown saved filter: '@{0}'. See `man set`.")]
+    FilterUnknownSaved(String),
+    #[error("No saved filter or paper nicknamed '@{0}'.{}", if .1.is_empty() { String::new() } else { format!(" Did you mean {}?", .1) })]
+    FilterUnknownNickname(String, String),
+    #[error("Saved filter '@{0}' refers to itself.")]
+    FilterSavedCycle(String),
     // paper
     #[error("Duplicate paper field keyword specified: '{0}'")]
     PaperDuplicateField(String),
     #[error("Required paper fields not given: {0}")]
     PaperMissingFields(String),
+    #[error("Invalid DOI: '{0}'. Expected a form like '10.1145/3492321.3519584'.")]
+    PaperInvalidDoi(String),
+    #[error("DOI '{0}' already belongs to '{1}'.")]
+    PaperDuplicateDoi(String, String),
+    #[error(
+        "Invalid citation key: '{0}'. Keys cannot have whitespace or any of {{}}(),\"#%'=\\~."
+    )]
+    PaperInvalidCitekey(String),
+    #[error("Citation key '{0}' already belongs to '{1}'.")]
+    PaperDuplicateCitekey(String, String),
+    #[error("Nickname '{0}' already belongs to '{1}'. Aliases must be unique.")]
+    PaperDuplicateAlias(String, String),
+    #[error("Nickname '{0}' already belongs to '{1}'. Nicknames must be unique.")]
+    PaperDuplicateNickname(String, String),
+    #[error("'{0}' is a built-in paper field. See `man paper` for how to set it.")]
+    PaperReservedField(String),
+    #[error("Invalid priority: '{0}'. Expected a positive integer like 1, or 'none'.")]
+    PaperInvalidPriority(String),
+    #[error("Invalid due date: '{0}'. Expected a date like 2024-06-01, or 'none'.")]
+    PaperInvalidDue(String),
+    #[error("Invalid year: '{0}'. Expected a year between 1800 and {1}.")]
+    PaperInvalidYear(String, i32),
+    #[error("A paper needs at least one author.")]
+    PaperNoAuthors,
+    #[error("Author names cannot be blank, but got '{0}'.")]
+    PaperBlankAuthor(String),
+    #[error("Invalid nickname: '{0}'. Nicknames cannot be empty or have whitespace.")]
+    PaperInvalidNickname(String),
+    #[error("Invalid label: '{0}'. Labels cannot be empty or have commas.")]
+    PaperInvalidLabel(String),
+    #[error("Paper #{0} is no longer in the paperbase.")]
+    PaperNotFound(u64),
+    // bibtex
+    #[error("Malformed BibTeX entry: {0}.")]
+    BibtexInvalidEntry(String),
+    #[error("Malformed BibTeX field '{0}': {1}.")]
+    BibtexInvalidField(String, String),
+    #[error("BibTeX entry '{0}' has no '{1}' field.")]
+    BibtexMissingField(String, String),
+    #[error("Expected a single BibTeX entry, but found {0}. Use `import` for whole files.")]
+    BibtexNotSingleEntry(usize),
     // path
     #[error("Specified file path does not exist: '{0}'")]
     PathDoesNotExist(PathBuf),
@@ -52,11 +122,18 @@ pub enum Fallacy {
     // exit
     #[error("Exit reason")]
     ExitReason,
+    // ls command
+    #[error("Unknown output format: '{0}'. Expected 'table', 'long', 'json', or 'csv'.")]
+    LsUnknownFormat(String),
+    #[error("Unknown table column: '{0}'. See `man config` for the columns.")]
+    LsUnknownColumn(String),
+    #[error("Failed to serialize papers: '{0}'")]
+    LsSerializeFailed(#[from] serde_json::Error),
     // man command
-    #[error("`man` accepts exactly one argument.")]
+    #[error("`man` accepts at most one argument.")]
     ManInvalidArgument,
-    #[error("Unknown subject: '{0}'")]
-    ManUnknownSubject(String),
+    #[error("Unknown subject: '{0}'.{}", if .1.is_empty() { " Run `man` to list subjects.".to_owned() } else { format!(" Did you mean '{}'?", .1) })]
+    ManUnknownSubject(String, String),
     // curl command
     #[error("`curl` accepts exactly one argument as source.")]
     CurlNoSource,
@@ -66,16 +143,132 @@ pub enum Fallacy {
     CurlURLParseError(#[from] url::ParseError),
     #[error("Failed to fetch from url: '{0}'")]
     CurlGetFailed(#[from] reqwest::Error),
+    #[error("Request to '{0}' failed with HTTP status {1}.")]
+    CurlHttpStatus(String, u16),
+    #[error("arXiv has no paper '{0}'.")]
+    CurlArxivNotFound(String),
+    #[error("Semantic Scholar has no paper '{0}'.")]
+    CurlS2NotFound(String),
+    #[error("Unknown option to `curl`: '{0}'. Expected 'pdf' or 'nopdf'.")]
+    CurlUnknownOption(String),
+    #[error("Timed out fetching '{0}'.")]
+    CurlTimedOut(String),
+    #[error("Gave up on '{0}' after {1} attempts. The last error was: {2}")]
+    CurlGaveUp(String, u32, Box<Fallacy>),
+    #[error("Download of '{0}' was cut off: {1}")]
+    CurlDownloadInterrupted(String, String),
+    #[error("Invalid proxy: '{0}'")]
+    CurlInvalidProxy(String),
+    #[error("Too many requests to '{0}'. Try again {}.", match .1 { Some(secs) => format!("in {} seconds", secs), None => "later".to_owned() })]
+    CurlRateLimited(String, Option<u64>),
+    #[error("Unexpected response from '{0}': {1}")]
+    CurlInvalidResponse(String, String),
     #[error("Failed to parse title. {0}")]
     CurlCannotFindTitle(String),
     #[error("Failed to parse author list. {0}")]
     CurlCannotFindAuthor(String),
     #[error("Failed to parse information from PDF File. {0}")]
     CurlPdfParsingError(#[from] PdfError),
+    // import command
+    #[error("`import` needs a file to import from.")]
+    ImportNoFile,
+    #[error("Failed to read '{0}': '{1}'")]
+    ImportReadFailed(PathBuf, std::io::Error),
+    // export command
+    #[error("Unknown export format: '{0}'. Expected 'bibtex' or 'html'.")]
+    ExportUnknownFormat(String),
+    #[error("`export` needs a format and a file to export to, like `export bibtex library.bib`.")]
+    ExportNoFile,
+    #[error("'{0}' already exists. Give `force` after the file to overwrite it.")]
+    ExportFileExists(PathBuf),
+    #[error("Failed to write '{0}': '{1}'")]
+    ExportWriteFailed(PathBuf, std::io::Error),
+    // grep command
+    #[error("`grep` needs a pattern to search notes for, like `grep consensus`.")]
+    GrepNoPattern,
+    // alias command
+    #[error("'{0}' is a command, so it cannot be an alias.")]
+    AliasShadowsCommand(String),
+    #[error("Alias '{0}' expands into itself.")]
+    AliasRecursive(String),
+    #[error("Alias '{0}' should start with a command or another alias, not '{1}'.")]
+    AliasNotCommand(String, String),
+    #[error("No alias named '{0}'. See them with `alias`.")]
+    AliasUnknown(String),
+    #[error("Failed to save aliases to the config: '{0}'")]
+    AliasStoreFailed(confy::ConfyError),
+    // history command
+    #[error("No command '!{0}' in history. See the numbers with `history`.")]
+    HistoryNoEntry(String),
+    // ed command
+    #[error("Metadata was not changed. {0}")]
+    EdInvalidMetadata(String),
+    // refresh command
+    #[error("Unknown thing to refresh: '{0}'. Expected 'citations'.")]
+    RefreshUnknownField(String),
+    #[error("The paper has no DOI, arXiv ID, or Semantic Scholar URL to look it up with.")]
+    RefreshNoIdentifier,
+    // open command
+    #[error("No viewer is configured for {0}. Add one to 'output.viewers', as in `man config`.")]
+    OpenNoViewer(String),
     // printf command
     #[error("Failed to build book: '{0}'")]
     PrintfBuildError(#[from] mdbook::errors::Error),
+    #[error("Unknown placeholder '{{{0}}}' in template. See `man printf` for placeholders.")]
+    PrintfUnknownPlaceholder(String),
+    #[error("Invalid template: {0}. Write '{{{{' and '}}}}' for literal braces.")]
+    PrintfInvalidTemplate(String),
+    #[error("No template named '{0}'. Add one to 'output.templates', as in `man config`.")]
+    PrintfUnknownTemplate(String),
     // set command
     #[error("No papers given through pipe.")]
     SetNoPapers,
+    #[error("Nicknames are unique, so one cannot be set on {0} papers at once.")]
+    SetNicknameOfMany(usize),
+    // mark command
+    #[error("Invalid date: '{0}'. Expected a form like '2024-03-10', 'yesterday', or '-3d'.")]
+    MarkInvalidDate(String),
+    #[error("Cannot mark papers as read on '{0}', which is in the future.")]
+    MarkFutureDate(String),
+    // archive and unarchive commands
+    #[error("`{0}` needs papers given through pipe, like `ls at OSDI | {0}`.")]
+    ArchiveNoPapers(&'static str),
+    // head, tail, and shuf commands
+    #[error("`{0}` needs papers given through pipe, like `ls unread | {0}`.")]
+    HeadNoPapers(&'static str),
+    #[error("Invalid count: '{0}'. Expected a number like 3.")]
+    HeadInvalidCount(String),
+    // pick command
+    #[error("Invalid row: '{0}'. Expected a number like 3 or a range like 2-4.")]
+    PickInvalidRow(String),
+    #[error("Row {0} is out of range. Rows are numbered from 1 to {1}.")]
+    PickOutOfRange(usize, usize),
+    // mv command
+    #[error("No paper nicknamed '@{0}'.{}", if .1.is_empty() { String::new() } else { format!(" Did you mean {}?", .1) })]
+    MvUnknownNickname(String, String),
+    #[error("Failed to rename '{0}': '{1}'")]
+    MvRenameFailed(PathBuf, std::io::Error),
+    // sync command
+    #[error("'{0}' is not in a git repository. Run `git init` there to sync it.")]
+    SyncNotGitRepo(PathBuf),
+    #[error("`git {0}` failed: {1}")]
+    SyncGitFailed(String, String),
+    // link command
+    #[error("`link` links exactly two papers, but {0} were given.")]
+    LinkNotTwoPapers(usize),
+    #[error("A paper cannot be related to itself.")]
+    LinkSelf,
+    // wc command
+    #[error("Invalid window: '{0}'. Expected a positive count like '30d', '12w', or '6m', up to {1} days.")]
+    WcInvalidWindow(String, i64),
+}
+
+/// Like "Opened 4, skipped 2 (no wiki path: @foo, @bar)."
+fn skipped_message(done: &str, skipped: &[(String, Vec<String>)]) -> String {
+    let count: usize = skipped.iter().map(|(_, names)| names.len()).sum();
+    let reasons: Vec<_> = skipped
+        .iter()
+        .map(|(reason, names)| format!("{}: {}", reason, names.join(", ")))
+        .collect();
+    format!("{}, skipped {} ({}).", done, count, reasons.join("; "))
 }
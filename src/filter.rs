@@ -1,12 +1,19 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
 use regex::{Regex, RegexBuilder};
 
+use crate::config::{Config, FilterConfig};
 use crate::error::Fallacy;
-use crate::paper::Paper;
+use crate::paper::{Paper, ReadingProgress};
+use crate::utils::{fuzzy_score, today};
 
 pub static MAN: &str = include_str!("../man/filter.md");
 
+/// Filters saved by name with `set filter`, stored as filter arguments.
+pub type SavedFilters = BTreeMap<String, Vec<String>>;
+
 #[derive(Default, Debug, Clone)]
 pub struct PaperFilter {
     pub title: Vec<Regex>,
@@ -17,45 +24,568 @@ pub struct PaperFilter {
     pub year: Vec<Regex>,
     pub is_label: Vec<Regex>,
     pub not_label: Vec<Regex>,
+    pub doi: Vec<Regex>,
+    pub key: Vec<Regex>,
+    pub about: Vec<Regex>,
+    pub year_range: Vec<YearRange>,
+    /// Priority ranges, written like year ranges. `None` matches papers
+    /// without a priority.
+    pub priority: Vec<Option<YearRange>>,
+    pub status: Vec<ReadingProgress>,
+    pub added: Vec<DateRange>,
+    pub read_date: Vec<DateRange>,
+    /// Due date ranges. `None` matches papers without a due date.
+    pub due: Vec<Option<DateRange>>,
+    /// Only papers not read by their due date.
+    pub overdue: bool,
+    /// Only archived papers.
+    pub archived: bool,
+    /// Archived papers are not hidden.
+    pub all: bool,
+    /// Nicknames given exactly as '@name', which reach archived papers.
+    pub exact_nicknames: Vec<String>,
+    pub has_notes: Vec<bool>,
+    pub has_labels: Vec<bool>,
+    pub file: Vec<FileCondition>,
+    pub fuzzy: Vec<String>,
+}
+
+/// Points at the filter argument that could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterDiagnostic {
+    /// All filter arguments, to render the whole filter.
+    pub args: Vec<String>,
+    /// Index of the offending argument.
+    pub index: usize,
+    pub reason: String,
+}
+
+impl FilterDiagnostic {
+    pub fn new(args: &[String], index: usize, reason: String) -> Self {
+        Self {
+            args: args.to_vec(),
+            index,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for FilterDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} (argument {}):", self.reason, self.index + 1)?;
+        writeln!(f, "  {}", self.args.join(" "))?;
+        // Offset of the offending argument in the joined rendering.
+        let offset: usize = self.args[..self.index]
+            .iter()
+            .map(|arg| arg.chars().count() + 1)
+            .sum();
+        let width = self.args[self.index].chars().count().max(1);
+        write!(f, "  {}{}", " ".repeat(offset), "^".repeat(width))
+    }
+}
+
+/// Conditions on the paper file attached to a paper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileCondition {
+    /// Some attached file exists.
+    Present,
+    /// No file is attached, or none of the attached files exist.
+    Absent,
+    /// Some attached file does not exist.
+    Broken,
+}
+
+impl FileCondition {
+    fn holds(&self, paper: &Paper, config: &Config) -> bool {
+        let exists: Vec<_> = paper
+            .files(config)
+            .iter()
+            .map(|path| path.is_file())
+            .collect();
+        match self {
+            Self::Present => exists.contains(&true),
+            Self::Absent => !exists.contains(&true),
+            Self::Broken => exists.contains(&false),
+        }
+    }
+}
+
+impl fmt::Display for FileCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Present => write!(f, "has a file"),
+            Self::Absent => write!(f, "has no file"),
+            Self::Broken => write!(f, "has a broken file path"),
+        }
+    }
+}
+
+/// An inclusive range of publication years. Either end may be open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YearRange {
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+}
+
+impl YearRange {
+    /// Try to interpret the argument of `in` as a year range.
+    /// Returns `None` if the argument does not look like a range, in which
+    /// case it should be treated as a regex instead.
+    ///
+    /// Accepted forms are `2018-2022`, `2018..2022`, `2020-`, and `-2020`.
+    pub fn parse(arg: &str) -> Option<Result<Self, Fallacy>> {
+        let (start, end) = if let Some(split) = arg.split_once("..") {
+            split
+        } else if arg.contains(|c: char| "[](){}|^$*+?\\.".contains(c)) {
+            // A regex like '201[5-9]' uses hyphens, too.
+            return None;
+        } else {
+            arg.split_once('-')?
+        };
+
+        let parse = |s: &str| -> Result<Option<i32>, Fallacy> {
+            let s = s.trim();
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                s.parse()
+                    .map(Some)
+                    .map_err(|_| Fallacy::FilterYearRangeInvalid(arg.to_owned()))
+            }
+        };
+        let range = match (parse(start), parse(end)) {
+            (Ok(start), Ok(end)) => Self { start, end },
+            (Err(e), _) | (_, Err(e)) => return Some(Err(e)),
+        };
+
+        match range {
+            Self {
+                start: None,
+                end: None,
+            } => Some(Err(Fallacy::FilterYearRangeInvalid(arg.to_owned()))),
+            Self {
+                start: Some(start),
+                end: Some(end),
+            } if end < start => Some(Err(Fallacy::FilterYearRangeInvalid(arg.to_owned()))),
+            range => Some(Ok(range)),
+        }
+    }
+
+    /// Check if the given year string falls within this range.
+    /// Years that are not numbers never match.
+    pub fn contains(&self, year: &str) -> bool {
+        let year: i32 = match year.trim().parse() {
+            Ok(year) => year,
+            Err(_) => return false,
+        };
+        self.start.is_none_or(|start| start <= year) && self.end.is_none_or(|end| year <= end)
+    }
+}
+
+/// An inclusive range of calendar dates. Either end may be open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRange {
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl DateRange {
+    /// Parse a date expression relative to `today`.
+    ///
+    /// Accepted forms are `2024`, `2024-01`, `2024-01-31`, `today`,
+    /// `yesterday`, `this-week`, `last-week`, `this-month`, `last-month`,
+    /// `this-year`, `last-year`, and ranges `A..B` of those where either
+    /// end may be omitted.
+    pub fn parse(arg: &str, today: NaiveDate) -> Result<Self, Fallacy> {
+        let invalid = || Fallacy::FilterDateInvalid(arg.to_owned());
+
+        if let Some((start, end)) = arg.split_once("..") {
+            let start = if start.is_empty() {
+                None
+            } else {
+                Self::parse_single(start, today).ok_or_else(invalid)?.start
+            };
+            let end = if end.is_empty() {
+                None
+            } else {
+                Self::parse_single(end, today).ok_or_else(invalid)?.end
+            };
+            match (start, end) {
+                (None, None) => return Err(invalid()),
+                (Some(start), Some(end)) if end < start => return Err(invalid()),
+                _ => return Ok(Self { start, end }),
+            }
+        }
+
+        Self::parse_single(arg, today).ok_or_else(invalid)
+    }
+
+    fn parse_single(arg: &str, today: NaiveDate) -> Option<Self> {
+        let month = |year: i32, month: u32| -> Option<Self> {
+            let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+            let next = if month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)?
+            };
+            Some(Self {
+                start: Some(start),
+                end: Some(next.pred_opt()?),
+            })
+        };
+        let year = |year: i32| -> Option<Self> {
+            Some(Self {
+                start: Some(NaiveDate::from_ymd_opt(year, 1, 1)?),
+                end: Some(NaiveDate::from_ymd_opt(year, 12, 31)?),
+            })
+        };
+        let monday = today
+            .checked_sub_signed(Duration::days(today.weekday().num_days_from_monday() as i64))?;
+
+        match arg {
+            "today" => Some(Self {
+                start: Some(today),
+                end: Some(today),
+            }),
+            "yesterday" => {
+                let yesterday = today.pred_opt()?;
+                Some(Self {
+                    start: Some(yesterday),
+                    end: Some(yesterday),
+                })
+            }
+            "this-week" => Some(Self {
+                start: Some(monday),
+                end: Some(monday.checked_add_signed(Duration::days(6))?),
+            }),
+            "last-week" => Some(Self {
+                start: Some(monday.checked_sub_signed(Duration::days(7))?),
+                end: Some(monday.pred_opt()?),
+            }),
+            "this-month" => month(today.year(), today.month()),
+            "last-month" => {
+                if today.month() == 1 {
+                    month(today.year() - 1, 12)
+                } else {
+                    month(today.year(), today.month() - 1)
+                }
+            }
+            "this-year" => year(today.year()),
+            "last-year" => year(today.year() - 1),
+            _ => {
+                let pieces: Vec<_> = arg.split('-').collect();
+                if pieces
+                    .iter()
+                    .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+                {
+                    return None;
+                }
+                match pieces[..] {
+                    [y] if y.len() == 4 => year(y.parse().ok()?),
+                    [y, m] if y.len() == 4 => month(y.parse().ok()?, m.parse().ok()?),
+                    [y, m, d] if y.len() == 4 => {
+                        let date = NaiveDate::from_ymd_opt(
+                            y.parse().ok()?,
+                            m.parse().ok()?,
+                            d.parse().ok()?,
+                        )?;
+                        Some(Self {
+                            start: Some(date),
+                            end: Some(date),
+                        })
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Check if the given date falls within this range.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start.is_none_or(|start| start <= date) && self.end.is_none_or(|end| date <= end)
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start == end => write!(f, "on {}", start),
+            (Some(start), Some(end)) => write!(f, "between {} and {}", start, end),
+            (Some(start), None) => write!(f, "on or after {}", start),
+            (None, Some(end)) => write!(f, "on or before {}", end),
+            (None, None) => write!(f, "on any date"),
+        }
+    }
+}
+
+impl fmt::Display for YearRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => write!(f, "between {} and {}", start, end),
+            (Some(start), None) => write!(f, "{} or later", start),
+            (None, Some(end)) => write!(f, "{} or earlier", end),
+            (None, None) => write!(f, "any year"),
+        }
+    }
 }
 
 impl PaperFilter {
     /// Accepts filter arguments given to commands and builds an
     /// instance of `PaperFilter`. Remove the command (first argument)
     /// and pass the rest to this function.
-    pub fn from_args(args: &[String], case_insensitive: bool) -> Result<Self, Fallacy> {
+    ///
+    /// Terms prefixed with '!' are always matched case-sensitively, and
+    /// terms prefixed with '=' are always matched literally instead of as
+    /// regexes, regardless of `config`. Prefixes can be combined.
+    pub fn from_args(args: &[String], config: &FilterConfig) -> Result<Self, Fallacy> {
         let mut filter = Self::default();
         let mut arg_iter = args.iter();
+        // Index of the argument last taken from `arg_iter`.
+        let position = |iter: &std::slice::Iter<String>| args.len() - iter.as_slice().len() - 1;
+        let parse_error = |index: usize, reason: String| {
+            Fallacy::FilterParseFailed(FilterDiagnostic::new(args, index, reason))
+        };
         while let Some(arg) = arg_iter.next() {
-            let (mut place, item) = match arg.as_ref() {
-                "as" => (&mut filter.nickname, arg_iter.next()),
-                "by" => (&mut filter.author, arg_iter.next()),
-                "by1" => (&mut filter.first_author, arg_iter.next()),
-                "at" => (&mut filter.venue, arg_iter.next()),
-                "in" => (&mut filter.year, arg_iter.next()),
-                "is" => (&mut filter.is_label, arg_iter.next()),
-                "not" => (&mut filter.not_label, arg_iter.next()),
-                _ => (&mut filter.title, Some(arg)),
+            // Keywords that need a value.
+            if arg_iter.as_slice().is_empty()
+                && matches!(
+                    arg.as_ref(),
+                    "as" | "by"
+                        | "by1"
+                        | "at"
+                        | "in"
+                        | "is"
+                        | "tag"
+                        | "not"
+                        | "status"
+                        | "added"
+                        | "read-in"
+                        | "due"
+                        | "doi"
+                        | "key"
+                        | "about"
+                        | "priority"
+                )
+            {
+                return Err(parse_error(
+                    position(&arg_iter),
+                    format!("Keyword '{}' expects a value", arg),
+                ));
+            }
+            // Reading progress keywords.
+            let status = match arg.as_ref() {
+                "status" => match arg_iter.next() {
+                    Some(value) => match Self::parse_status(value) {
+                        Some(status) => Some(status),
+                        None => {
+                            return Err(parse_error(
+                                position(&arg_iter),
+                                format!("Unknown reading status '{}'", value),
+                            ))
+                        }
+                    },
+                    None => None,
+                },
+                keyword => Self::parse_status(keyword),
+            };
+            if let Some(status) = status {
+                filter.status.push(status);
+                continue;
+            }
+            // Presence of labels, notes, and files.
+            match arg.as_ref() {
+                "tagged" => {
+                    filter.has_labels.push(true);
+                    continue;
+                }
+                "untagged" => {
+                    filter.has_labels.push(false);
+                    continue;
+                }
+                "has-notes" => {
+                    filter.has_notes.push(true);
+                    continue;
+                }
+                "no-notes" => {
+                    filter.has_notes.push(false);
+                    continue;
+                }
+                "has-file" => {
+                    filter.file.push(FileCondition::Present);
+                    continue;
+                }
+                "no-file" => {
+                    filter.file.push(FileCondition::Absent);
+                    continue;
+                }
+                "broken-file" => {
+                    filter.file.push(FileCondition::Broken);
+                    continue;
+                }
+                "overdue" => {
+                    filter.overdue = true;
+                    continue;
+                }
+                "archived" => {
+                    filter.archived = true;
+                    continue;
+                }
+                "all" => {
+                    filter.all = true;
+                    continue;
+                }
+                _ => {}
+            }
+            // Dates when papers were added.
+            if arg == "added" || arg == "read-in" {
+                if let Some(date) = arg_iter.next() {
+                    let range = DateRange::parse(date, today())?;
+                    if arg == "added" {
+                        filter.added.push(range);
+                    } else {
+                        filter.read_date.push(range);
+                    }
+                    continue;
+                }
+            }
+            // Due dates are a date expression or 'none'.
+            if arg == "due" {
+                let value = arg_iter.next().unwrap();
+                if value == "none" {
+                    filter.due.push(None);
+                } else {
+                    filter.due.push(Some(DateRange::parse(value, today())?));
+                }
+                continue;
+            }
+            // Exact nicknames.
+            if let Some(name) = arg.strip_prefix('@').filter(|name| !name.is_empty()) {
+                let exact = format!("^{}$", regex::escape(name));
+                filter
+                    .nickname
+                    .push(Regex::new(&exact).expect("escaped regex"));
+                filter.exact_nicknames.push(name.to_owned());
+                continue;
+            }
+            // Priorities are a number, a range, or 'none'.
+            if arg == "priority" {
+                let value = arg_iter.next().unwrap();
+                if value == "none" {
+                    filter.priority.push(None);
+                    continue;
+                }
+                let range = match YearRange::parse(value) {
+                    Some(range) => range.ok(),
+                    None => value.parse().ok().map(|priority| YearRange {
+                        start: Some(priority),
+                        end: Some(priority),
+                    }),
+                };
+                match range {
+                    Some(range) => filter.priority.push(Some(range)),
+                    None => {
+                        return Err(parse_error(
+                            position(&arg_iter),
+                            format!("Invalid priority '{}'", value),
+                        ))
+                    }
+                }
+                continue;
+            }
+            // Years can also be specified as a range.
+            if arg == "in" {
+                if let Some(range) = arg_iter
+                    .as_slice()
+                    .first()
+                    .and_then(|a| YearRange::parse(a))
+                {
+                    filter.year_range.push(range?);
+                    arg_iter.next();
+                    continue;
+                }
+            }
+            let (place, field, item) = match arg.as_ref() {
+                "as" => (&mut filter.nickname, "nickname", arg_iter.next()),
+                "by" => (&mut filter.author, "author", arg_iter.next()),
+                "by1" => (&mut filter.first_author, "first author", arg_iter.next()),
+                "at" => (&mut filter.venue, "venue", arg_iter.next()),
+                "in" => (&mut filter.year, "year", arg_iter.next()),
+                "is" | "tag" => (&mut filter.is_label, "label", arg_iter.next()),
+                "not" => (&mut filter.not_label, "label", arg_iter.next()),
+                "doi" => (&mut filter.doi, "DOI", arg_iter.next()),
+                "key" => (&mut filter.key, "citation key", arg_iter.next()),
+                "about" => (&mut filter.about, "abstract", arg_iter.next()),
+                _ => (&mut filter.title, "title", None),
             };
             let item = match item {
                 Some(string) => string,
                 None => {
-                    // If no matching regex is found, instead match title.
-                    place = &mut filter.title;
+                    // Terms without a keyword match the title. Fuzzy title
+                    // terms are matched against the title, nickname, and
+                    // authors.
+                    match arg.strip_prefix('~') {
+                        Some(term) if !term.is_empty() => {
+                            filter.fuzzy.push(term.to_lowercase());
+                            continue;
+                        }
+                        None if config.fuzzy => {
+                            filter.fuzzy.push(arg.to_lowercase());
+                            continue;
+                        }
+                        _ => {}
+                    }
                     arg
                 }
             };
+            // A leading '!' forces case-sensitive matching and a leading '='
+            // forces literal matching for this term.
+            let mut item = item.as_str();
+            let mut case_insensitive = config.case_insensitive_regex;
+            let mut literal = config.literal;
+            loop {
+                match item.chars().next() {
+                    Some('!') if item.len() > 1 => case_insensitive = false,
+                    Some('=') if item.len() > 1 => literal = true,
+                    _ => break,
+                }
+                item = &item[1..];
+            }
+            let escaped;
+            if literal {
+                escaped = regex::escape(item);
+                item = &escaped;
+            }
             match RegexBuilder::new(item)
                 .case_insensitive(case_insensitive)
                 .build()
             {
                 Ok(regex) => place.push(regex),
-                Err(e) => return Err(Fallacy::FilterBuildFailed(e)),
+                Err(e) => {
+                    return Err(Fallacy::FilterRegexInvalid(
+                        field,
+                        FilterDiagnostic::new(
+                            args,
+                            position(&arg_iter),
+                            "Invalid regex".to_owned(),
+                        ),
+                        e,
+                    ))
+                }
             }
         }
         Ok(filter)
     }
 
+    /// Parse reading progress keywords used by filters.
+    fn parse_status(keyword: &str) -> Option<ReadingProgress> {
+        match keyword {
+            "unread" => Some(ReadingProgress::Unread),
+            "reading" | "current" => Some(ReadingProgress::InProgress(None)),
+            "read" => Some(ReadingProgress::Read),
+            _ => None,
+        }
+    }
+
     /// Merges multiple filters into one.
     pub fn merge(filters: &[Self]) -> Self {
         let mut merged = Self::default();
@@ -68,12 +598,31 @@ impl PaperFilter {
             merged.year.extend(filter.year.clone());
             merged.is_label.extend(filter.is_label.clone());
             merged.not_label.extend(filter.not_label.clone());
+            merged.doi.extend(filter.doi.clone());
+            merged.key.extend(filter.key.clone());
+            merged.about.extend(filter.about.clone());
+            merged.year_range.extend(filter.year_range.clone());
+            merged.priority.extend(filter.priority.clone());
+            merged.status.extend(filter.status.clone());
+            merged.added.extend(filter.added.clone());
+            merged.read_date.extend(filter.read_date.clone());
+            merged.due.extend(filter.due.clone());
+            merged.overdue |= filter.overdue;
+            merged.archived |= filter.archived;
+            merged.all |= filter.all;
+            merged
+                .exact_nicknames
+                .extend(filter.exact_nicknames.clone());
+            merged.has_notes.extend(filter.has_notes.clone());
+            merged.has_labels.extend(filter.has_labels.clone());
+            merged.file.extend(filter.file.clone());
+            merged.fuzzy.extend(filter.fuzzy.clone());
         }
         merged
     }
 
     /// Check if the filter matches the given paper.
-    pub fn matches(&self, paper: &Paper) -> bool {
+    pub fn matches(&self, paper: &Paper, config: &Config) -> bool {
         macro_rules! checker {
             // A field value should match all regexes in the filter.
             ($regex_field:ident) => {
@@ -118,17 +667,112 @@ impl PaperFilter {
         }
 
         checker!(title);
-        checker!(nickname, getter => paper.nickname.as_ref().unwrap_or(&"".to_string()));
+        // Nicknames match if the primary nickname or any alias matches.
+        if !self.nickname.iter().all(|regex| {
+            paper.nicknames().any(|name| regex.is_match(name))
+                || (paper.nickname.is_none() && regex.is_match(""))
+        }) {
+            return false;
+        }
         checker!(author, vector => authors);
-        checker!(first_author, getter => paper.authors.first().unwrap());
+        checker!(first_author, getter => paper.authors.first().map(String::as_str).unwrap_or(""));
         checker!(venue);
         checker!(year);
         checker!(is_label, vector => labels);
         checker!(not_label, vector =!> labels);
+        checker!(doi, getter => paper.doi.as_deref().unwrap_or(""));
+        checker!(key, getter => &paper.citekey());
+        checker!(about, getter => paper.abstract_text.as_deref().unwrap_or(""));
+
+        if !self
+            .year_range
+            .iter()
+            .all(|range| range.contains(&paper.year))
+        {
+            return false;
+        }
+        if !self
+            .priority
+            .iter()
+            .all(|range| match (range, paper.priority) {
+                (None, priority) => priority.is_none(),
+                (Some(range), Some(priority)) => range.contains(&priority.to_string()),
+                (Some(_), None) => false,
+            })
+        {
+            return false;
+        }
+        if !self
+            .status
+            .iter()
+            .all(|status| status.same_kind(&paper.progress))
+        {
+            return false;
+        }
+        if !Self::date_matches(&self.added, paper.added) {
+            return false;
+        }
+        if !self.read_date.is_empty() && paper.progress != ReadingProgress::Read {
+            return false;
+        }
+        if !Self::date_matches(&self.read_date, paper.read_at) {
+            return false;
+        }
+        if !self.due.iter().all(|range| match (range, paper.due) {
+            (None, due) => due.is_none(),
+            (Some(range), Some(due)) => range.contains(due),
+            (Some(_), None) => false,
+        }) {
+            return false;
+        }
+        if self.overdue && !paper.is_overdue(today()) {
+            return false;
+        }
+        if self.archived && !paper.archived {
+            return false;
+        }
+        let has_labels = !paper.labels.is_empty();
+        if !self.has_labels.iter().all(|&want| want == has_labels) {
+            return false;
+        }
+        if !self.has_notes.is_empty() {
+            let has_notes = paper.has_notes(config);
+            if !self.has_notes.iter().all(|&want| want == has_notes) {
+                return false;
+            }
+        }
+        if !self.file.iter().all(|cond| cond.holds(paper, config)) {
+            return false;
+        }
+        if !self.fuzzy.iter().all(|term| {
+            std::iter::once(paper.title.as_str())
+                .chain(paper.nicknames())
+                .chain(paper.authors.iter().map(String::as_str))
+                .any(|field| {
+                    fuzzy_score(term, &field.to_lowercase()) >= config.filter.fuzzy_threshold
+                })
+        }) {
+            return false;
+        }
 
         true
     }
 
+    /// Check if a timestamp falls within all the given date ranges.
+    /// Missing timestamps never match.
+    fn date_matches(ranges: &[DateRange], stamp: Option<DateTime<Local>>) -> bool {
+        if ranges.is_empty() {
+            return true;
+        }
+        match stamp {
+            Some(datetime) => {
+                let date = datetime.naive_local().date();
+                ranges.iter().all(|range| range.contains(date))
+            }
+            None => false,
+        }
+    }
+
     /// Check if this filter is empty.
     pub fn is_empty(&self) -> bool {
         macro_rules! checker {
@@ -147,13 +791,38 @@ impl PaperFilter {
         checker!(year);
         checker!(is_label);
         checker!(not_label);
+        checker!(doi);
+        checker!(key);
+        checker!(about);
+        checker!(year_range);
+        checker!(priority);
+        checker!(status);
+        checker!(added);
+        checker!(read_date);
+        checker!(due);
+        checker!(has_notes);
+        checker!(has_labels);
+        checker!(file);
+        checker!(fuzzy);
 
-        true
+        !self.overdue && !self.archived && !self.all
+    }
+
+    /// Check if archived papers are asked for with `archived` or `all`, or
+    /// if the paper is named by its exact nickname.
+    fn shows_archived(&self, paper: &Paper) -> bool {
+        self.archived
+            || self.all
+            || self
+                .exact_nicknames
+                .iter()
+                .any(|name| paper.nicknames().any(|nickname| nickname == name))
     }
 }
 
-impl fmt::Display for PaperFilter {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl PaperFilter {
+    /// Describe each active condition of this filter in words.
+    fn describe(&self) -> Vec<String> {
         let mut segments = Vec::new();
         let displayer = |ret: &mut Vec<String>, filter: &Vec<Regex>, name: &str, matches: bool| {
             let joined = filter
@@ -178,7 +847,79 @@ impl fmt::Display for PaperFilter {
         displayer(&mut segments, &self.year, "year", true);
         displayer(&mut segments, &self.is_label, "label", true);
         displayer(&mut segments, &self.not_label, "label", false);
+        displayer(&mut segments, &self.doi, "DOI", true);
+        displayer(&mut segments, &self.key, "citation key", true);
+        displayer(&mut segments, &self.about, "abstract", true);
+        for range in self.year_range.iter() {
+            segments.push(format!("year is {}", range));
+        }
+        for range in self.priority.iter() {
+            segments.push(match range {
+                None => "has no priority".to_owned(),
+                Some(YearRange {
+                    start: Some(start),
+                    end: Some(end),
+                }) if start == end => format!("priority is {}", start),
+                Some(range) => format!("priority is {}", range),
+            });
+        }
+        for status in self.status.iter() {
+            segments.push(format!("status is {}", status));
+        }
+        for range in self.added.iter() {
+            segments.push(format!("added {}", range));
+        }
+        for range in self.read_date.iter() {
+            segments.push(format!("read {}", range));
+        }
+        for range in self.due.iter() {
+            segments.push(match range {
+                None => "has no due date".to_owned(),
+                Some(range) => format!("due {}", range),
+            });
+        }
+        if self.overdue {
+            segments.push("is overdue".to_owned());
+        }
+        if self.archived {
+            segments.push("is archived".to_owned());
+        } else if self.all {
+            segments.push("archived or not".to_owned());
+        }
+        for &has_labels in self.has_labels.iter() {
+            segments.push(
+                if has_labels {
+                    "has labels"
+                } else {
+                    "has no labels"
+                }
+                .to_owned(),
+            );
+        }
+        for &has_notes in self.has_notes.iter() {
+            segments.push(
+                if has_notes {
+                    "has notes"
+                } else {
+                    "has no notes"
+                }
+                .to_owned(),
+            );
+        }
+        for cond in self.file.iter() {
+            segments.push(cond.to_string());
+        }
+        for term in self.fuzzy.iter() {
+            segments.push(format!("title, nickname, or author resembles '{}'", term));
+        }
+
+        segments
+    }
+}
 
+impl fmt::Display for PaperFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segments = self.describe();
         if segments.is_empty() {
             writeln!(f, "No filters are active.")
         } else {
@@ -186,3 +927,999 @@ impl fmt::Display for PaperFilter {
         }
     }
 }
+
+/// A filter expression tree with `PaperFilter`s as leaves.
+#[derive(Debug, Clone)]
+pub enum FilterExpr {
+    Leaf(Box<PaperFilter>),
+    /// Matches if all subexpressions match.
+    And(Vec<FilterExpr>),
+    /// Matches if any subexpression matches.
+    Or(Vec<FilterExpr>),
+    /// Matches if the subexpression does not match.
+    Not(Box<FilterExpr>),
+}
+
+/// Recursive descent parser over filter arguments.
+///
+/// expr := conj ('or' conj)*
+/// conj := ('(' expr ')' | 'not' '(' expr ')' | '@name' | term)+
+struct FilterParser<'a> {
+    args: &'a [String],
+    pos: usize,
+    config: &'a FilterConfig,
+    saved: &'a SavedFilters,
+    /// Names of saved filters being expanded, to detect cycles.
+    expanding: &'a mut Vec<String>,
+}
+
+impl FilterParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.args.get(self.pos).map(|arg| arg.as_str())
+    }
+
+    fn unbalanced(&self, index: usize, reason: &str) -> Fallacy {
+        Fallacy::FilterUnbalancedParens(FilterDiagnostic::new(self.args, index, reason.to_owned()))
+    }
+
+    fn parse(mut self) -> Result<FilterExpr, Fallacy> {
+        if self.args.is_empty() {
+            return Ok(FilterExpr::default());
+        }
+        let expr = self.parse_or()?;
+        // Only a stray closing parenthesis can stop the parser early.
+        if self.pos < self.args.len() {
+            return Err(self.unbalanced(self.pos, "Unmatched closing parenthesis"));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, Fallacy> {
+        let mut alternatives = vec![self.parse_and()?];
+        while self.peek() == Some("or") {
+            self.pos += 1;
+            alternatives.push(self.parse_and()?);
+        }
+        Ok(FilterExpr::or(alternatives))
+    }
+
+    /// Parse a parenthesized group. The opening parenthesis is next.
+    fn parse_group(&mut self) -> Result<FilterExpr, Fallacy> {
+        let open = self.pos;
+        self.pos += 1;
+        let expr = self.parse_or()?;
+        if self.peek() != Some(")") {
+            return Err(self.unbalanced(open, "Unclosed parenthesis"));
+        }
+        self.pos += 1;
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, Fallacy> {
+        let mut terms = Vec::new();
+        // Plain arguments are parsed together into one leaf, placed where
+        // the first of them appeared. Their original positions are kept
+        // for error reporting.
+        let mut rest = Vec::new();
+        let mut rest_index = Vec::new();
+        let mut leaf_at = None;
+        let mut nicknames = Vec::new();
+        while let Some(arg) = self.peek() {
+            match arg {
+                "or" | ")" => break,
+                "(" => terms.push(self.parse_group()?),
+                "not" if self.args.get(self.pos + 1).map(|a| a.as_str()) == Some("(") => {
+                    self.pos += 1;
+                    terms.push(FilterExpr::Not(Box::new(self.parse_group()?)));
+                }
+                _ => {
+                    let arg = &self.args[self.pos];
+                    match arg.strip_prefix('@').filter(|name| !name.is_empty()) {
+                        // Not a saved filter, so a nickname. Nicknames are OR'ed.
+                        Some(name) if !self.saved.contains_key(name) => {
+                            let filter =
+                                PaperFilter::from_args(std::slice::from_ref(arg), self.config)?;
+                            nicknames.push(FilterExpr::Leaf(Box::new(filter)));
+                        }
+                        Some(name) => {
+                            if self.expanding.iter().any(|n| n == name) {
+                                return Err(Fallacy::FilterSavedCycle(name.to_owned()));
+                            }
+                            self.expanding.push(name.to_owned());
+                            let expr = FilterParser {
+                                args: &self.saved[name],
+                                pos: 0,
+                                config: self.config,
+                                saved: self.saved,
+                                expanding: self.expanding,
+                            }
+                            .parse()?;
+                            self.expanding.pop();
+                            terms.push(expr);
+                        }
+                        None => {
+                            leaf_at.get_or_insert(terms.len());
+                            rest.push(arg.clone());
+                            rest_index.push(self.pos);
+                        }
+                    }
+                    self.pos += 1;
+                }
+            }
+        }
+        if terms.is_empty() && rest.is_empty() && nicknames.is_empty() {
+            return Err(Fallacy::FilterEmptyGroup);
+        }
+        if let Some(leaf_at) = leaf_at {
+            // Point diagnostics at the original arguments.
+            let rebase = |mut diag: FilterDiagnostic| {
+                diag.index = rest_index[diag.index];
+                diag.args = self.args.to_vec();
+                diag
+            };
+            let filter = PaperFilter::from_args(&rest, self.config).map_err(|e| match e {
+                Fallacy::FilterParseFailed(diag) => Fallacy::FilterParseFailed(rebase(diag)),
+                Fallacy::FilterRegexInvalid(field, diag, e) => {
+                    Fallacy::FilterRegexInvalid(field, rebase(diag), e)
+                }
+                e => e,
+            })?;
+            terms.insert(leaf_at, FilterExpr::Leaf(Box::new(filter)));
+        }
+        if !nicknames.is_empty() {
+            terms.push(FilterExpr::or(nicknames));
+        }
+        Ok(FilterExpr::and(terms))
+    }
+}
+
+impl FilterExpr {
+    /// Accepts filter arguments given to commands and builds an
+    /// instance of `FilterExpr`. Alternatives are separated by the `or`
+    /// keyword, and can be grouped with parentheses given as separate
+    /// arguments. `not ( ... )` negates a group. Arguments outside
+    /// parentheses are parsed with `PaperFilter::from_args`.
+    ///
+    /// Arguments of the form `@name` are replaced with the saved filter
+    /// of that name, AND'ed with the rest of the group. If there is no such
+    /// saved filter, `@name` matches the paper nicknamed exactly `name`, and
+    /// all such nicknames in a group are OR'ed.
+    pub fn from_args(
+        args: &[String],
+        config: &FilterConfig,
+        saved: &SavedFilters,
+    ) -> Result<Self, Fallacy> {
+        FilterParser {
+            args,
+            pos: 0,
+            config,
+            saved,
+            expanding: &mut Vec::new(),
+        }
+        .parse()
+    }
+
+    /// AND the given expressions together, flattening nested conjunctions,
+    /// merging adjacent leaves, and dropping filters that match everything.
+    pub fn and(exprs: Vec<Self>) -> Self {
+        let mut terms: Vec<Self> = Vec::new();
+        let flattened = exprs.into_iter().flat_map(|expr| match expr {
+            Self::And(inner) => inner,
+            expr => vec![expr],
+        });
+        for expr in flattened {
+            match (terms.last_mut(), expr) {
+                (_, Self::Leaf(filter)) if filter.is_empty() => {}
+                (Some(Self::Leaf(last)), Self::Leaf(filter)) => {
+                    **last = PaperFilter::merge(&[(**last).clone(), *filter]);
+                }
+                (_, expr) => terms.push(expr),
+            }
+        }
+        match terms.len() {
+            0 => Self::default(),
+            1 => terms.pop().unwrap(),
+            _ => Self::And(terms),
+        }
+    }
+
+    /// OR the given expressions together, flattening nested disjunctions.
+    pub fn or(exprs: Vec<Self>) -> Self {
+        let mut alternatives = Vec::new();
+        for expr in exprs {
+            match expr {
+                Self::Or(inner) => alternatives.extend(inner),
+                expr => alternatives.push(expr),
+            }
+        }
+        match alternatives.len() {
+            0 => Self::default(),
+            1 => alternatives.pop().unwrap(),
+            _ => Self::Or(alternatives),
+        }
+    }
+
+    /// Check that every `@name` in the given filter arguments refers to
+    /// either a saved filter or the nickname of some paper.
+    pub fn check_nicknames(
+        args: &[String],
+        saved: &SavedFilters,
+        papers: &[Paper],
+    ) -> Result<(), Fallacy> {
+        let nicknames: Vec<&str> = papers.iter().flat_map(Paper::nicknames).collect();
+        for arg in args {
+            let name = match arg.strip_prefix('@') {
+                Some(name) if !name.is_empty() && !saved.contains_key(name) => name,
+                _ => continue,
+            };
+            if nicknames.contains(&name) {
+                continue;
+            }
+            return Err(Fallacy::FilterUnknownNickname(
+                name.to_owned(),
+                Self::similar_nicknames(name, &nicknames),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Up to three nicknames that look like `name`, like '@foo, @fob'.
+    pub fn similar_nicknames(name: &str, nicknames: &[&str]) -> String {
+        let lower = name.to_lowercase();
+        let mut close: Vec<(f64, &str)> = nicknames
+            .iter()
+            .map(|nick| (fuzzy_score(&lower, &nick.to_lowercase()), *nick))
+            .filter(|(score, _)| *score >= 0.5)
+            .collect();
+        close.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(b.1)));
+        let close: Vec<String> = close
+            .iter()
+            .take(3)
+            .map(|(_, n)| format!("@{}", n))
+            .collect();
+        close.join(", ")
+    }
+
+    /// Merges multiple filter expressions into one by AND'ing them together.
+    pub fn merge(exprs: &[Self]) -> Self {
+        Self::and(exprs.to_vec())
+    }
+
+    /// Check if the expression matches the given paper.
+    pub fn matches(&self, paper: &Paper, config: &Config) -> bool {
+        match self {
+            Self::Leaf(filter) => filter.matches(paper, config),
+            Self::And(exprs) => exprs.iter().all(|expr| expr.matches(paper, config)),
+            Self::Or(exprs) => exprs.iter().any(|expr| expr.matches(paper, config)),
+            Self::Not(expr) => !expr.matches(paper, config),
+        }
+    }
+
+    /// Check if the expression lets the paper through even if it is
+    /// archived. Negated parts never do.
+    pub fn shows_archived(&self, paper: &Paper) -> bool {
+        match self {
+            Self::Leaf(filter) => filter.shows_archived(paper),
+            Self::And(exprs) | Self::Or(exprs) => {
+                exprs.iter().any(|expr| expr.shows_archived(paper))
+            }
+            Self::Not(_) => false,
+        }
+    }
+
+    /// Check if this expression matches everything.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Leaf(filter) => filter.is_empty(),
+            Self::And(exprs) => exprs.iter().all(|expr| expr.is_empty()),
+            Self::Or(exprs) => exprs.iter().any(|expr| expr.is_empty()),
+            Self::Not(_) => false,
+        }
+    }
+
+    /// Render the expression, parenthesizing it if it is compound and
+    /// `wrap` is set.
+    fn render(&self, wrap: bool) -> String {
+        let (text, compound) = match self {
+            Self::Leaf(filter) => {
+                let segments = filter.describe();
+                if segments.is_empty() {
+                    ("any paper".to_owned(), false)
+                } else {
+                    (segments.join(", "), segments.len() > 1)
+                }
+            }
+            Self::And(exprs) => {
+                let terms: Vec<_> = exprs
+                    .iter()
+                    .map(|expr| expr.render(matches!(expr, Self::Or(_))))
+                    .collect();
+                (terms.join(", "), true)
+            }
+            Self::Or(exprs) => {
+                let terms: Vec<_> = exprs.iter().map(|expr| expr.render(true)).collect();
+                (terms.join(" or "), true)
+            }
+            Self::Not(expr) => (format!("not ({})", expr.render(false)), false),
+        };
+        if wrap && compound {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+}
+
+impl Default for FilterExpr {
+    fn default() -> Self {
+        Self::Leaf(Box::default())
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "No filters are active.")
+        } else {
+            writeln!(f, "{}", self.render(false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::parse_timestamp;
+
+    fn paper(title: &str, authors: &[&str], venue: &str, year: &str) -> Paper {
+        Paper {
+            title: title.to_owned(),
+            authors: authors.iter().map(|s| s.to_string()).collect(),
+            venue: venue.to_owned(),
+            year: year.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn cfg() -> Config {
+        Config::default()
+    }
+
+    fn config(case_insensitive_regex: bool) -> FilterConfig {
+        FilterConfig {
+            case_insensitive_regex,
+            ..Default::default()
+        }
+    }
+
+    fn filter(args: &[&str]) -> PaperFilter {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        PaperFilter::from_args(&args, &config(false)).unwrap()
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let f = filter(&[]);
+        assert!(f.is_empty());
+        assert!(f.matches(&paper("Reason", &["Chung"], "OSDI", "2022"), &cfg()));
+        assert!(f.matches(&paper("", &[], "", ""), &cfg()));
+    }
+
+    #[test]
+    fn multiple_regexes_are_anded() {
+        let p = paper(
+            "Shadowtutor: Distributed Partial Distillation",
+            &["Chung"],
+            "ICPP",
+            "2020",
+        );
+        assert!(filter(&["Distributed", "Distillation"]).matches(&p, &cfg()));
+        assert!(!filter(&["Distributed", "Federated"]).matches(&p, &cfg()));
+    }
+
+    #[test]
+    fn authors_match_any_first_author_only_first() {
+        let p = paper(
+            "Nimble",
+            &["Woosuk Kwon", "Gyeong-In Yu"],
+            "NeurIPS",
+            "2020",
+        );
+        assert!(filter(&["by", "Yu"]).matches(&p, &cfg()));
+        assert!(filter(&["by", "Kwon", "by", "Yu"]).matches(&p, &cfg()));
+        assert!(filter(&["by1", "Kwon"]).matches(&p, &cfg()));
+        assert!(!filter(&["by1", "Yu"]).matches(&p, &cfg()));
+        assert!(!filter(&["by1", "Kwon"]).matches(&paper("Nimble", &[], "NeurIPS", "2020"), &cfg()));
+    }
+
+    #[test]
+    fn year_matches() {
+        let p2020 = paper("A", &["X"], "OSDI", "2020");
+        let p2021 = paper("B", &["X"], "OSDI", "2021");
+        let p2019 = paper("C", &["X"], "OSDI", "2019");
+        assert!(filter(&["in", "2021"]).matches(&p2021, &cfg()));
+        assert!(!filter(&["in", "2021"]).matches(&p2020, &cfg()));
+        let partial = filter(&["in", "20(20|21)"]);
+        assert!(partial.matches(&p2020, &cfg()));
+        assert!(partial.matches(&p2021, &cfg()));
+        assert!(!partial.matches(&p2019, &cfg()));
+    }
+
+    #[test]
+    fn year_ranges() {
+        let p2017 = paper("A", &["X"], "OSDI", "2017");
+        let p2020 = paper("B", &["X"], "OSDI", "2020");
+        let p2023 = paper("C", &["X"], "OSDI", "2023");
+        let closed = filter(&["in", "2018-2022"]);
+        assert!(!closed.matches(&p2017, &cfg()));
+        assert!(closed.matches(&p2020, &cfg()));
+        assert!(!closed.matches(&p2023, &cfg()));
+        assert_eq!(filter(&["in", "2018..2022"]).year_range, closed.year_range);
+        let open = filter(&["in", "2020-"]);
+        assert!(!open.matches(&p2017, &cfg()));
+        assert!(open.matches(&p2020, &cfg()));
+        assert!(open.matches(&p2023, &cfg()));
+        assert!(filter(&["in", "..2017"]).matches(&p2017, &cfg()));
+        assert!(!filter(&["in", "..2017"]).matches(&p2020, &cfg()));
+        // Hyphens inside regexes are not ranges.
+        assert!(filter(&["in", "201[5-9]"]).matches(&p2017, &cfg()));
+        assert_eq!(closed.to_string(), "year is between 2018 and 2022\n");
+    }
+
+    #[test]
+    fn invalid_year_ranges() {
+        for arg in ["2022-2018", "20x0-2022", "-", "2018..abc"] {
+            let args = vec!["in".to_owned(), arg.to_owned()];
+            assert!(matches!(
+                PaperFilter::from_args(&args, &config(false)),
+                Err(Fallacy::FilterYearRangeInvalid(s)) if s == arg
+            ));
+        }
+    }
+
+    #[test]
+    fn case_insensitive_by_default() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let p = paper(
+            "Transformers are RNNs",
+            &["Angelos Katharopoulos", "François Fleuret"],
+            "ICML",
+            "2020",
+        );
+        let insensitive = |a: &[&str]| PaperFilter::from_args(&args(a), &config(true)).unwrap();
+        assert!(insensitive(&["transformer"]).matches(&p, &cfg()));
+        assert!(insensitive(&["TRANSFORMERS ARE rnns"]).matches(&p, &cfg()));
+        assert!(insensitive(&["by", "FRANÇOIS"]).matches(&p, &cfg()));
+        assert!(insensitive(&["by", "françois fleuret"]).matches(&p, &cfg()));
+        assert!(!insensitive(&["by", "francois"]).matches(&p, &cfg()));
+
+        // Case-sensitive via config.
+        assert!(!filter(&["transformer"]).matches(&p, &cfg()));
+        assert!(filter(&["Transformer"]).matches(&p, &cfg()));
+
+        // Case-sensitive via the '!' prefix.
+        assert!(!insensitive(&["!transformer"]).matches(&p, &cfg()));
+        assert!(insensitive(&["!Transformer"]).matches(&p, &cfg()));
+        assert!(!insensitive(&["by", "!FRANÇOIS"]).matches(&p, &cfg()));
+    }
+
+    #[test]
+    fn reading_progress() {
+        let mut p = paper("Reason", &["Chung"], "NeurIPS", "2023");
+        assert!(filter(&["unread"]).matches(&p, &cfg()));
+        assert!(filter(&["unread", "at", "NeurIPS", "in", "2023"]).matches(&p, &cfg()));
+        assert!(!filter(&["reading"]).matches(&p, &cfg()));
+        p.progress = ReadingProgress::InProgress(Some(crate::paper::PartialProgress::Percent(10)));
+        assert!(filter(&["reading"]).matches(&p, &cfg()));
+        assert!(filter(&["status", "current"]).matches(&p, &cfg()));
+        p.progress = ReadingProgress::Read;
+        assert!(filter(&["read"]).matches(&p, &cfg()));
+        assert!(!filter(&["read", "at", "OSDI"]).matches(&p, &cfg()));
+        assert_eq!(
+            filter(&["unread", "at", "NeurIPS"]).to_string(),
+            "venue matches 'NeurIPS', status is unread\n"
+        );
+    }
+
+    #[test]
+    fn date_ranges() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // A Wednesday.
+        let today = date(2024, 3, 13);
+        let range = |arg: &str| DateRange::parse(arg, today).unwrap();
+        let date = |y, m, d| Some(date(y, m, d));
+        assert_eq!(range("2024").start, date(2024, 1, 1));
+        assert_eq!(range("2024").end, date(2024, 12, 31));
+        assert_eq!(range("2024-02").end, date(2024, 2, 29));
+        assert_eq!(range("2023-12").end, date(2023, 12, 31));
+        assert_eq!(range("2024-01-05").start, date(2024, 1, 5));
+        assert_eq!(range("yesterday").start, date(2024, 3, 12));
+        assert_eq!(range("this-week").start, date(2024, 3, 11));
+        assert_eq!(range("this-week").end, date(2024, 3, 17));
+        assert_eq!(range("last-week").start, date(2024, 3, 4));
+        assert_eq!(range("last-week").end, date(2024, 3, 10));
+        assert_eq!(range("last-month").start, date(2024, 2, 1));
+        assert_eq!(range("2023..2024-01").end, date(2024, 1, 31));
+        assert!(range("2023-06..").contains(date(2030, 1, 1).unwrap()));
+        assert!(!range("..2023-06").contains(date(2023, 7, 1).unwrap()));
+        for arg in ["last-decade", "2024-13", "24-01", "2024..2023", "abc"] {
+            assert!(DateRange::parse(arg, today).is_err(), "{}", arg);
+        }
+    }
+
+    #[test]
+    fn added_dates() {
+        let mut p = paper("Reason", &["Chung"], "OSDI", "2022");
+        assert!(!filter(&["added", "2024"]).matches(&p, &cfg()));
+        p.added = parse_timestamp("2024-01-15T10:00:00+00:00");
+        assert!(filter(&["added", "2024"]).matches(&p, &cfg()));
+        assert!(filter(&["added", "2024-01"]).matches(&p, &cfg()));
+        assert!(!filter(&["added", "2024-02"]).matches(&p, &cfg()));
+        assert!(filter(&["added", "2023-12..2024-01"]).matches(&p, &cfg()));
+    }
+
+    #[test]
+    fn read_dates() {
+        let mut p = paper("Reason", &["Chung"], "OSDI", "2022");
+        assert!(!filter(&["read-in", "2023"]).matches(&p, &cfg()));
+        // Unread papers never match, even with a stale timestamp.
+        p.read_at = parse_timestamp("2023-05-01T09:00:00+00:00");
+        assert!(!filter(&["read-in", "2023"]).matches(&p, &cfg()));
+        p.progress = ReadingProgress::Read;
+        assert!(filter(&["read-in", "2023"]).matches(&p, &cfg()));
+        assert!(filter(&["read-in", "2023-01..2023-06"]).matches(&p, &cfg()));
+        assert!(!filter(&["read-in", "2022"]).matches(&p, &cfg()));
+        assert_eq!(
+            filter(&["read-in", "2023-05-01"]).to_string(),
+            "read on 2023-05-01\n"
+        );
+    }
+
+    #[test]
+    fn due_dates() {
+        let mut due = paper("Reason", &["Chung"], "OSDI", "2022");
+        let whenever = paper("Other", &["Chung"], "OSDI", "2022");
+        let yesterday = today().pred_opt().unwrap();
+        due.due = Some(yesterday);
+        let overdue = filter(&["overdue"]);
+        assert!(overdue.matches(&due, &cfg()) && !overdue.matches(&whenever, &cfg()));
+        assert_eq!(overdue.to_string(), "is overdue\n");
+        assert!(filter(&["due", "yesterday"]).matches(&due, &cfg()));
+        assert!(!filter(&["due", "today"]).matches(&due, &cfg()));
+        let none = filter(&["due", "none"]);
+        assert!(none.matches(&whenever, &cfg()) && !none.matches(&due, &cfg()));
+
+        // Read papers are never overdue.
+        due.progress = ReadingProgress::Read;
+        assert!(!overdue.matches(&due, &cfg()));
+        due.progress = ReadingProgress::Unread;
+        due.due = Some(today());
+        assert!(!overdue.matches(&due, &cfg()));
+        assert!(filter(&["due", "this-week"]).matches(&due, &cfg()));
+    }
+
+    #[test]
+    fn or_groups() {
+        let set = |a: &[&str]| {
+            let args: Vec<String> = a.iter().map(|s| s.to_string()).collect();
+            FilterExpr::from_args(&args, &config(false), &SavedFilters::new())
+        };
+        let osdi = paper("A", &["X"], "OSDI", "2021");
+        let sosp = paper("B", &["X"], "SOSP", "2021");
+        let nsdi = paper("C", &["X"], "NSDI", "2021");
+        let either = set(&["at", "OSDI", "or", "at", "SOSP"]).unwrap();
+        assert!(either.matches(&osdi, &cfg()));
+        assert!(either.matches(&sosp, &cfg()));
+        assert!(!either.matches(&nsdi, &cfg()));
+        assert_eq!(
+            either.to_string(),
+            "venue matches 'OSDI' or venue matches 'SOSP'\n"
+        );
+        assert!(matches!(
+            set(&["or", "at", "OSDI"]),
+            Err(Fallacy::FilterEmptyGroup)
+        ));
+        assert!(matches!(
+            set(&["at", "OSDI", "or"]),
+            Err(Fallacy::FilterEmptyGroup)
+        ));
+
+        // (OSDI or SOSP) and title 'B'
+        let merged = FilterExpr::merge(&[either, set(&["B"]).unwrap()]);
+        assert_eq!(
+            merged.to_string(),
+            "(venue matches 'OSDI' or venue matches 'SOSP'), title matches 'B'\n"
+        );
+        assert!(!merged.matches(&osdi, &cfg()));
+        assert!(merged.matches(&sosp, &cfg()));
+        assert!(FilterExpr::merge(&[FilterExpr::default()]).is_empty());
+    }
+
+    #[test]
+    fn parenthesized_expressions() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let expr =
+            |a: &[&str]| FilterExpr::from_args(&args(a), &config(true), &SavedFilters::new());
+
+        let osdi = paper("A", &["Smith"], "OSDI", "2022");
+        let sosp = paper("B", &["Jones"], "SOSP", "2022");
+        let old = paper("C", &["Jones"], "OSDI", "2019");
+        let nsdi = paper("D", &["Jones"], "NSDI", "2022");
+
+        let e = expr(&[
+            "(", "at", "OSDI", "or", "at", "SOSP", ")", "in", "2022", "not", "(", "by", "Smith",
+            ")",
+        ])
+        .unwrap();
+        assert!(!e.matches(&osdi, &cfg()));
+        assert!(e.matches(&sosp, &cfg()));
+        assert!(!e.matches(&old, &cfg()));
+        assert!(!e.matches(&nsdi, &cfg()));
+        assert_eq!(
+            e.to_string(),
+            "(venue matches 'OSDI' or venue matches 'SOSP'), year matches '2022', not (author matches 'Smith')\n"
+        );
+
+        // Nested groups.
+        let e = expr(&[
+            "(", "(", "at", "OSDI", "in", "2019", ")", "or", "by", "Smith", ")", "or", "D",
+        ])
+        .unwrap();
+        assert!(e.matches(&osdi, &cfg()));
+        assert!(!e.matches(&sosp, &cfg()));
+        assert!(e.matches(&old, &cfg()));
+        assert!(e.matches(&nsdi, &cfg()));
+        assert_eq!(
+            e.to_string(),
+            "(venue matches 'OSDI', year matches '2019') or author matches 'Smith' or title matches 'D'\n"
+        );
+
+        // `not` without parentheses still excludes labels.
+        assert_eq!(
+            expr(&["not", "draft"]).unwrap().to_string(),
+            "label does not match 'draft'\n"
+        );
+
+        match expr(&["(", "at", "OSDI", "or", "(", "at", "SOSP", ")"]) {
+            Err(Fallacy::FilterUnbalancedParens(diag)) => assert_eq!(diag.index, 0),
+            other => panic!("unexpected {:?}", other),
+        }
+        match expr(&["at", "OSDI", ")", "in", "2022"]) {
+            Err(Fallacy::FilterUnbalancedParens(diag)) => assert_eq!(diag.index, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(expr(&["(", ")"]), Err(Fallacy::FilterEmptyGroup)));
+        // Positions inside groups refer to the whole filter.
+        match expr(&["(", "at", "OSDI", ")", "by"]) {
+            Err(Fallacy::FilterParseFailed(diag)) => assert_eq!(diag.index, 4),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn literal_terms() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let cpp = paper("C++ Concurrency (Part 2)", &["X"], "Book", "2019");
+        let what = paper("What? Really.", &["X"], "Blog", "2020");
+        let other = paper("CCC Concurrency Part 2", &["X"], "Book", "2019");
+
+        // As regexes, these either fail to build or match the wrong things.
+        assert!(
+            PaperFilter::from_args(&args(&["C++ Concurrency (Part 2"]), &config(false)).is_err()
+        );
+        assert!(filter(&["(Part 2)"]).matches(&other, &cfg()));
+
+        assert!(filter(&["=C++"]).matches(&cpp, &cfg()));
+        assert!(filter(&["=(Part 2)"]).matches(&cpp, &cfg()));
+        assert!(!filter(&["=(Part 2)"]).matches(&other, &cfg()));
+        assert!(filter(&["=What? Really."]).matches(&what, &cfg()));
+        assert!(!filter(&["=What. Really?"]).matches(&what, &cfg()));
+        assert!(!filter(&["=!c++"]).matches(&cpp, &cfg()));
+        assert!(!PaperFilter::from_args(&args(&["!=c++"]), &config(true))
+            .unwrap()
+            .matches(&cpp, &cfg()));
+
+        let literal = FilterConfig {
+            case_insensitive_regex: true,
+            literal: true,
+            ..Default::default()
+        };
+        let f = PaperFilter::from_args(&args(&["c++ concurrency (part 2)"]), &literal).unwrap();
+        assert!(f.matches(&cpp, &cfg()));
+        assert!(!f.matches(&other, &cfg()));
+    }
+
+    #[test]
+    fn saved_filters() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut saved = SavedFilters::new();
+        saved.insert(
+            "sysconfs".to_owned(),
+            args(&["at", "OSDI|SOSP|EuroSys", "in", "202."]),
+        );
+        saved.insert(
+            "either".to_owned(),
+            args(&["at", "OSDI", "or", "at", "NSDI"]),
+        );
+        saved.insert("loop".to_owned(), args(&["@loop"]));
+        let set = |a: &[&str]| FilterExpr::from_args(&args(a), &config(false), &saved);
+
+        let mut osdi = paper("A", &["X"], "OSDI", "2021");
+        let nsdi = paper("B", &["X"], "NSDI", "2021");
+        let old = paper("C", &["X"], "SOSP", "2019");
+        let sysconfs = set(&["@sysconfs"]).unwrap();
+        assert!(sysconfs.matches(&osdi, &cfg()));
+        assert!(!sysconfs.matches(&nsdi, &cfg()));
+        assert!(!sysconfs.matches(&old, &cfg()));
+        assert!(set(&["@sysconfs", "unread"])
+            .unwrap()
+            .matches(&osdi, &cfg()));
+        osdi.progress = ReadingProgress::Read;
+        assert!(!set(&["@sysconfs", "unread"])
+            .unwrap()
+            .matches(&osdi, &cfg()));
+
+        // Saved filters with groups are AND'ed with the rest as a whole.
+        let either = set(&["@either", "B"]).unwrap();
+        assert!(either.matches(&nsdi, &cfg()));
+        assert!(!either.matches(&osdi, &cfg()));
+
+        assert!(matches!(set(&["@loop"]), Err(Fallacy::FilterSavedCycle(n)) if n == "loop"));
+        assert!(set(&["@nope"]).is_ok());
+    }
+
+    #[test]
+    fn exact_nicknames() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut saved = SavedFilters::new();
+        saved.insert("bert".to_owned(), args(&["at", "NAACL"]));
+        let set = |a: &[&str]| FilterExpr::from_args(&args(a), &config(true), &saved).unwrap();
+
+        let mut resnet = paper("Deep Residual Learning", &["He"], "CVPR", "2016");
+        resnet.nickname = Some("resnet".to_owned());
+        let mut resnext = paper(
+            "Aggregated Residual Transformations",
+            &["Xie"],
+            "CVPR",
+            "2017",
+        );
+        resnext.nickname = Some("resnext".to_owned());
+        let mut vit = paper(
+            "An Image is Worth 16x16 Words",
+            &["Dosovitskiy"],
+            "ICLR",
+            "2021",
+        );
+        vit.nickname = Some("vit".to_owned());
+        resnet.aliases = vec!["resnet15".to_owned()];
+
+        // Nicknames are matched exactly and OR'ed.
+        let picked = set(&["@resnet", "@vit"]);
+        assert!(picked.matches(&resnet, &cfg()));
+        assert!(!picked.matches(&resnext, &cfg()));
+        assert!(picked.matches(&vit, &cfg()));
+        // Aliases count as nicknames.
+        assert!(set(&["@resnet15"]).matches(&resnet, &cfg()));
+        assert!(set(&["as", "net15"]).matches(&resnet, &cfg()));
+        assert!(!set(&["as", "net15"]).matches(&resnext, &cfg()));
+        // ... but AND'ed with the rest of the group.
+        let picked = set(&["@resnet", "@vit", "at", "CVPR"]);
+        assert!(picked.matches(&resnet, &cfg()));
+        assert!(!picked.matches(&vit, &cfg()));
+        // Saved filters take precedence.
+        let bert = paper("BERT", &["Devlin"], "NAACL", "2019");
+        assert!(set(&["@bert"]).matches(&bert, &cfg()));
+
+        let papers = vec![resnet, resnext, vit];
+        let check = |a: &[&str]| FilterExpr::check_nicknames(&args(a), &saved, &papers);
+        assert!(check(&["@resnet", "@vit", "@bert", "@resnet15"]).is_ok());
+        match check(&["@resnet", "@resnt"]) {
+            Err(Fallacy::FilterUnknownNickname(name, close)) => {
+                assert_eq!(name, "resnt");
+                assert_eq!(close, "@resnet, @resnet15, @resnext");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            check(&["@transformer"]),
+            Err(Fallacy::FilterUnknownNickname(_, close)) if close.is_empty()
+        ));
+    }
+
+    #[test]
+    fn priorities() {
+        let mut must = paper("A", &["X"], "OSDI", "2022");
+        must.priority = Some(1);
+        let mut maybe = paper("B", &["X"], "OSDI", "2022");
+        maybe.priority = Some(2);
+        let someday = paper("C", &["X"], "OSDI", "2022");
+
+        let first = filter(&["priority", "1"]);
+        assert!(first.matches(&must, &cfg()));
+        assert!(!first.matches(&maybe, &cfg()) && !first.matches(&someday, &cfg()));
+        let range = filter(&["priority", "1-2"]);
+        assert!(range.matches(&must, &cfg()) && range.matches(&maybe, &cfg()));
+        assert!(!range.matches(&someday, &cfg()));
+        let none = filter(&["priority", "none"]);
+        assert!(none.matches(&someday, &cfg()) && !none.matches(&must, &cfg()));
+        assert_eq!(first.to_string(), "priority is 1\n");
+        assert!(
+            PaperFilter::from_args(&["priority".to_owned(), "soon".to_owned()], &cfg().filter)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn abstracts() {
+        let mut p = paper("Reason", &["Chung"], "OSDI", "2022");
+        assert!(!filter(&["about", "shell"]).matches(&p, &cfg()));
+        p.abstract_text = Some("A shell for research papers.".to_owned());
+        assert!(filter(&["about", "shell"]).matches(&p, &cfg()));
+        assert!(!filter(&["about", "database"]).matches(&p, &cfg()));
+        assert_eq!(
+            filter(&["about", "shell"]).to_string(),
+            "abstract matches 'shell'\n"
+        );
+    }
+
+    #[test]
+    fn tagged_and_untagged() {
+        let mut tagged = paper("Reason", &["Chung"], "OSDI", "2022");
+        tagged.labels.insert("systems".to_owned());
+        let untagged = paper("Shadowtutor", &["Chung"], "ICPP", "2020");
+
+        assert!(filter(&["tagged"]).matches(&tagged, &cfg()));
+        assert!(!filter(&["tagged"]).matches(&untagged, &cfg()));
+        assert!(filter(&["untagged", "at", "ICPP"]).matches(&untagged, &cfg()));
+        assert!(!filter(&["untagged", "at", "ICPP"]).matches(&tagged, &cfg()));
+        assert_eq!(
+            filter(&["untagged", "by", "Chung"]).to_string(),
+            "author matches 'Chung', has no labels\n"
+        );
+    }
+
+    #[test]
+    fn notes_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = cfg();
+        config.storage.wiki_dir = dir.path().join("wiki");
+        config.storage.note_dir = dir.path().join("notes");
+
+        let mut p = paper("Reason", &["Chung"], "OSDI", "2022");
+        p.wikipath = Some("Reason.wiki".into());
+        // The wiki directory does not even exist.
+        assert!(!filter(&["has-notes"]).matches(&p, &config));
+        assert!(filter(&["no-notes"]).matches(&p, &config));
+
+        std::fs::create_dir_all(&config.storage.wiki_dir).unwrap();
+        assert!(filter(&["no-notes"]).matches(&p, &config));
+        std::fs::write(config.storage.wiki_dir.join("Reason.wiki"), "# Reason").unwrap();
+        assert!(filter(&["has-notes"]).matches(&p, &config));
+        assert!(!filter(&["no-notes"]).matches(&p, &config));
+        assert_eq!(filter(&["no-notes"]).to_string(), "has no notes\n");
+    }
+
+    #[test]
+    fn attached_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = cfg();
+        config.storage.file_dir = dir.path().to_path_buf();
+
+        let none = paper("A", &["X"], "OSDI", "2022");
+        let mut present = paper("B", &["X"], "OSDI", "2022");
+        present.files = vec!["B.pdf".into()];
+        std::fs::write(dir.path().join("B.pdf"), "%PDF").unwrap();
+        let mut broken = paper("C", &["X"], "OSDI", "2022");
+        broken.files = vec!["C.pdf".into()];
+
+        let has = filter(&["has-file"]);
+        let no = filter(&["no-file"]);
+        let bad = filter(&["broken-file"]);
+        assert!(!has.matches(&none, &config) && no.matches(&none, &config));
+        assert!(has.matches(&present, &config) && !no.matches(&present, &config));
+        assert!(!has.matches(&broken, &config) && no.matches(&broken, &config));
+        assert!(!bad.matches(&none, &config));
+        assert!(!bad.matches(&present, &config));
+        assert!(bad.matches(&broken, &config));
+        assert_eq!(bad.to_string(), "has a broken file path\n");
+    }
+
+    #[test]
+    fn fuzzy_terms() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut attention = paper(
+            "Attention Is All You Need",
+            &["Ashish Vaswani", "Noam Shazeer"],
+            "NeurIPS",
+            "2017",
+        );
+        attention.nickname = Some("Transformer".to_owned());
+        let bert = paper(
+            "BERT: Pre-training of Deep Bidirectional Transformers",
+            &["Jacob Devlin"],
+            "NAACL",
+            "2019",
+        );
+
+        let typo = filter(&["~atention is all"]);
+        assert!(typo.matches(&attention, &cfg()));
+        assert!(!typo.matches(&bert, &cfg()));
+        assert!(filter(&["~vaswany"]).matches(&attention, &cfg()));
+        assert!(filter(&["~transfromer"]).matches(&attention, &cfg()));
+        assert!(!filter(&["~transfromer", "at", "NAACL"]).matches(&attention, &cfg()));
+        assert!(!filter(&["~diffusion"]).matches(&attention, &cfg()));
+
+        // Fuzzy matching by default.
+        let fuzzy = FilterConfig {
+            fuzzy: true,
+            ..Default::default()
+        };
+        let f = PaperFilter::from_args(&args(&["atention", "at", "NeurIPS"]), &fuzzy).unwrap();
+        assert_eq!(f.fuzzy, vec!["atention"]);
+        assert_eq!(f.venue.len(), 1);
+        assert!(f.matches(&attention, &cfg()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let parse =
+            |a: &[&str]| FilterExpr::from_args(&args(a), &config(true), &SavedFilters::new());
+
+        // Empty argument lists match everything.
+        assert!(PaperFilter::from_args(&[], &config(true))
+            .unwrap()
+            .is_empty());
+        assert!(parse(&[]).unwrap().is_empty());
+
+        match parse(&["attention", "by"]) {
+            Err(Fallacy::FilterParseFailed(diag)) => {
+                assert_eq!(diag.index, 1);
+                assert_eq!(
+                    diag.to_string(),
+                    "Keyword 'by' expects a value (argument 2):\n  attention by\n            ^^"
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse(&["status", "skimmed", "by", "Doe"]) {
+            Err(Fallacy::FilterParseFailed(diag)) => assert_eq!(diag.index, 1),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Positions refer to the whole filter, across 'or' groups.
+        match parse(&["by", "Doe", "or", "at", "ICML", "as", "(bad"]) {
+            Err(Fallacy::FilterRegexInvalid(field, diag, _)) => {
+                assert_eq!(field, "nickname");
+                assert_eq!(diag.index, 6);
+                assert_eq!(diag.args.len(), 7);
+                assert!(diag.to_string().ends_with("\n                       ^^^^"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse(&["[unclosed", "by", "Doe"]) {
+            Err(Fallacy::FilterRegexInvalid(field, diag, _)) => {
+                assert_eq!(field, "title");
+                assert_eq!(diag.index, 0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn labels_match_any() {
+        let mut p = paper("Reason", &["Chung"], "OSDI", "2022");
+        p.labels.insert("ml".to_owned());
+        p.labels.insert("systems".to_owned());
+        assert!(filter(&["is", "ml", "is", "systems"]).matches(&p, &cfg()));
+        assert!(filter(&["tag", "sys"]).matches(&p, &cfg()));
+        assert!(!filter(&["is", "ml", "tag", "theory"]).matches(&p, &cfg()));
+        assert!(!filter(&["not", "ml"]).matches(&p, &cfg()));
+        let merged = PaperFilter::merge(&[filter(&["tag", "ml"]), filter(&["not", "theory"])]);
+        assert!(merged.matches(&p, &cfg()));
+        assert_eq!(
+            merged.to_string(),
+            "label matches 'ml', label does not match 'theory'\n"
+        );
+    }
+}
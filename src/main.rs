@@ -1,10 +1,13 @@
 mod app;
+mod bibtex;
 mod cmd;
 mod config;
 mod error;
 mod filter;
 mod paper;
+mod pdfinfo;
 mod state;
+mod template;
 mod utils;
 
 use crate::app::App;
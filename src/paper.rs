@@ -1,59 +1,119 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
 use std::path::PathBuf;
 
-use comfy_table::{Attribute, Cell, CellAlignment, Color, ContentArrangement, Table};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
+use crate::bibtex;
+use crate::config::{color_of, Config, DefaultsConfig, NoteFormat};
 use crate::error::Fallacy;
 use crate::state::State;
-use crate::utils::{as_filename, make_unique_path};
+use crate::template;
+use crate::utils::{as_filename, humanize_seconds, make_unique_path, parse_timestamp, today};
 
 pub static MAN: &str = include_str!("../man/paper.md");
 
-pub struct PaperList(pub Vec<usize>);
+/// A list of papers, by their IDs.
+pub struct PaperList(pub Vec<u64>);
 
 impl PaperList {
     pub fn into_string(self, state: &State, config: &Config) -> String {
+        self.into_table(state, config, &config.output.table_columns)
+    }
+
+    /// Show every field of each paper, one block per paper.
+    pub fn into_details(self, state: &State) -> String {
+        match state.resolve(&self) {
+            Ok(indices) => {
+                let blocks: Vec<_> = indices
+                    .into_iter()
+                    .map(|ind| state.papers[ind].details(state))
+                    .collect();
+                blocks.join("\n")
+            }
+            Err(e) => format!("{}\n", e),
+        }
+    }
+
+    /// Show papers in a table with the given columns. See `TABLE_COLUMNS`.
+    pub fn into_table(self, state: &State, config: &Config, columns: &[String]) -> String {
+        let indices = match state.resolve(&self) {
+            Ok(indices) => indices,
+            Err(e) => return format!("{}\n", e),
+        };
+
         let mut table = Table::new();
 
         // Content width is dynamically arranged.
         table.set_content_arrangement(ContentArrangement::Dynamic);
 
-        // Header line.
-        let header = config.output.table_columns.iter().map(|s| {
-            Cell::new(s)
-                .set_alignment(CellAlignment::Center)
-                .add_attribute(Attribute::Bold)
-        });
+        // Show ratings and priorities only when some paper has one.
+        let mut columns = columns.to_vec();
+        if !columns.iter().any(|col| col == "rating")
+            && indices
+                .iter()
+                .any(|&ind| state.papers[ind].rating.is_some())
+        {
+            columns.push("rating".to_owned());
+        }
+        if !columns.iter().any(|col| col == "priority")
+            && indices
+                .iter()
+                .any(|&ind| state.papers[ind].priority.is_some())
+        {
+            columns.push("priority".to_owned());
+        }
+        if !columns.iter().any(|col| col == "due")
+            && indices.iter().any(|&ind| state.papers[ind].due.is_some())
+        {
+            columns.push("due".to_owned());
+        }
+
+        // Header line. The first column numbers papers for `pick`.
+        let header = std::iter::once("#")
+            .chain(columns.iter().map(String::as_str))
+            .map(|s| {
+                Cell::new(s)
+                    .set_alignment(CellAlignment::Center)
+                    .add_attribute(Attribute::Bold)
+            });
         table.set_header(header);
 
         // One row per paper.
-        for ind in self.0 {
+        for (num, ind) in indices.into_iter().enumerate() {
             let p = &state.papers[ind];
-            let mut row = Vec::new();
-            for col in config.output.table_columns.iter() {
+            let mut row = vec![(num + 1).to_string()];
+            for col in columns.iter() {
                 row.push(p.field_as_string(col));
             }
 
-            let progress = &state.papers[ind].progress;
-
-            match progress {
-                ReadingProgress::Read => {
-                    table.add_row(row.iter().map(|s| Cell::new(s).fg(Color::Green)));
-                }
-                ReadingProgress::InProgress => {
-                    table.add_row(row.iter().map(|s| {
-                        Cell::new(s)
-                            .fg(Color::Yellow)
-                            .add_attribute(Attribute::Reverse)
-                    }));
+            // Color rows by reading progress.
+            if !config.output.use_color() {
+                table.add_row(row);
+                continue;
+            }
+            let colors = &config.output.progress_colors;
+            let (color, reverse) = match p.progress {
+                ReadingProgress::Read => (&colors.read, false),
+                ReadingProgress::InProgress(_) => (&colors.current, true),
+                ReadingProgress::Unread => (&colors.unread, false),
+            };
+            // Overdue dates are red regardless.
+            let overdue = p.is_overdue(today());
+            table.add_row(row.into_iter().enumerate().map(|(i, s)| {
+                let mut cell = Cell::new(s);
+                if overdue && i > 0 && columns[i - 1] == "due" {
+                    cell = cell.fg(comfy_table::Color::Red);
+                } else if let Some(Some(color)) = color_of(color) {
+                    cell = cell.fg(color);
                 }
-                ReadingProgress::Unread => {
-                    table.add_row(row);
+                if reverse {
+                    cell = cell.add_attribute(Attribute::Reverse);
                 }
-            };
+                cell
+            }));
         }
 
         table.to_string() + "\n"
@@ -64,29 +124,237 @@ impl PaperList {
 ///
 /// When a note is created, the progress is updated as `InProgress` and `mark`
 /// command can be used to mark a paper as Read. Default is Unread.
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(from = "StoredProgress", into = "StoredProgress")]
 pub enum ReadingProgress {
     #[default]
+    Unread,
+    /// Currently reading, optionally with how far along.
+    InProgress(Option<PartialProgress>),
+    Read,
+}
+
+/// How far along a paper currently being read is.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum PartialProgress {
+    Percent(u8),
+    /// The current page, out of the total number of pages if known.
+    Pages(u32, Option<u32>),
+}
+
+impl PartialProgress {
+    /// Whether the paper has been read to the end, like '100%' or 'page 30/30'.
+    pub fn is_complete(&self) -> bool {
+        match *self {
+            Self::Percent(percent) => percent == 100,
+            Self::Pages(page, total) => Some(page) == total,
+        }
+    }
+}
+
+/// On-disk form of `ReadingProgress`. Progress without a partial value is
+/// stored as a plain variant name, as it used to be.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredProgress {
+    Plain(PlainProgress),
+    Partial {
+        #[serde(rename = "InProgress")]
+        in_progress: PartialProgress,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum PlainProgress {
     Unread,
     InProgress,
     Read,
 }
 
+impl From<StoredProgress> for ReadingProgress {
+    fn from(stored: StoredProgress) -> Self {
+        match stored {
+            StoredProgress::Plain(PlainProgress::Unread) => Self::Unread,
+            StoredProgress::Plain(PlainProgress::InProgress) => Self::InProgress(None),
+            StoredProgress::Plain(PlainProgress::Read) => Self::Read,
+            StoredProgress::Partial { in_progress } => Self::InProgress(Some(in_progress)),
+        }
+    }
+}
+
+impl From<ReadingProgress> for StoredProgress {
+    fn from(progress: ReadingProgress) -> Self {
+        match progress {
+            ReadingProgress::Unread => Self::Plain(PlainProgress::Unread),
+            ReadingProgress::InProgress(None) => Self::Plain(PlainProgress::InProgress),
+            ReadingProgress::InProgress(Some(in_progress)) => Self::Partial { in_progress },
+            ReadingProgress::Read => Self::Plain(PlainProgress::Read),
+        }
+    }
+}
+
+/// A change of reading progress.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(from = "StoredStatusChange", into = "StoredStatusChange")]
+pub struct StatusChange {
+    pub progress: ReadingProgress,
+    pub when: DateTime<Local>,
+    /// Who made the change, like 'jw@laptop'. See `general.identity`.
+    pub who: Option<String>,
+}
+
+/// On-disk form of `StatusChange`. Changes without provenance are stored as
+/// a pair, as they used to be.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredStatusChange {
+    WithWho(ReadingProgress, DateTime<Local>, String),
+    Plain(ReadingProgress, DateTime<Local>),
+}
+
+impl From<StoredStatusChange> for StatusChange {
+    fn from(stored: StoredStatusChange) -> Self {
+        match stored {
+            StoredStatusChange::WithWho(progress, when, who) => Self {
+                progress,
+                when,
+                who: Some(who),
+            },
+            StoredStatusChange::Plain(progress, when) => Self {
+                progress,
+                when,
+                who: None,
+            },
+        }
+    }
+}
+
+impl From<StatusChange> for StoredStatusChange {
+    fn from(change: StatusChange) -> Self {
+        match change.who {
+            Some(who) => Self::WithWho(change.progress, change.when, who),
+            None => Self::Plain(change.progress, change.when),
+        }
+    }
+}
+
+/// On-disk form of `Paper::files`, which used to be a single optional path.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredFiles {
+    Many(Vec<PathBuf>),
+    One(Option<PathBuf>),
+}
+
+fn deserialize_files<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match StoredFiles::deserialize(deserializer)? {
+        StoredFiles::Many(files) => files,
+        StoredFiles::One(file) => file.into_iter().collect(),
+    })
+}
+
+/// Timestamps are stored in RFC 3339, but older metadata has them in
+/// whatever format `Display` produced. Those are parsed if possible and
+/// dropped otherwise.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?
+        .as_deref()
+        .and_then(parse_timestamp))
+}
+
+impl ReadingProgress {
+    /// The kind of progress without partial progress, like 'current'.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Unread => "unread",
+            Self::InProgress(_) => "current",
+            Self::Read => "read",
+        }
+    }
+
+    /// Whether both are the same kind of progress, ignoring partial progress.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
 impl std::str::FromStr for ReadingProgress {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "unread" => Self::Unread,
-            "current" => Self::InProgress,
+            "current" | "reading" => Self::InProgress(None),
             "read" => Self::Read,
-            _ => Self::Unread,
+            partial => match partial.parse::<PartialProgress>() {
+                Ok(partial) if partial.is_complete() => Self::Read,
+                Ok(partial) => Self::InProgress(Some(partial)),
+                Err(_) => Self::Unread,
+            },
         })
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+impl std::str::FromStr for PartialProgress {
+    type Err = ();
+
+    /// Parse '45%', 'page 12', or 'page 12/30'.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(percent) = s.strip_suffix('%') {
+            return match percent.trim().parse::<u8>() {
+                Ok(percent) if percent <= 100 => Ok(Self::Percent(percent)),
+                _ => Err(()),
+            };
+        }
+        let pages = s.strip_prefix("page").ok_or(())?.trim();
+        let (page, total) = match pages.split_once('/') {
+            Some((page, total)) => (page, Some(total.trim().parse().map_err(|_| ())?)),
+            None => (pages, None),
+        };
+        let page = page.trim().parse().map_err(|_| ())?;
+        match total {
+            Some(total) if page > total || total == 0 => Err(()),
+            _ => Ok(Self::Pages(page, total)),
+        }
+    }
+}
+
+impl std::fmt::Display for PartialProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Percent(percent) => write!(f, "{}%", percent),
+            Self::Pages(page, Some(total)) => {
+                write!(f, "{}%, p. {}/{}", page * 100 / total, page, total)
+            }
+            Self::Pages(page, None) => write!(f, "p. {}", page),
+        }
+    }
+}
+
+impl std::fmt::Display for ReadingProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unread => write!(f, "unread"),
+            Self::InProgress(None) => write!(f, "current"),
+            Self::InProgress(Some(partial)) => write!(f, "current ({})", partial),
+            Self::Read => write!(f, "read"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Paper {
+    /// Identifies the paper across commands, even when other papers are
+    /// removed. Assigned by `State`, and 0 if not assigned yet.
+    #[serde(default)]
+    pub id: u64,
+
     /// The title of the paper, in full. This field is queryable.
     /// Keyword: None. An argument without a keyword is considered a title.
     pub title: String,
@@ -96,6 +364,11 @@ pub struct Paper {
     /// Keyword: 'as'
     pub nickname: Option<String>,
 
+    /// Other nicknames of the paper. Unique across the paperbase.
+    /// Keyword: 'as' after the first, 'alias'
+    #[serde(default)]
+    pub aliases: Vec<String>,
+
     /// A list of authors, in order. This field is queryable.
     /// Keyword: 'by', 'by1' (first author)
     pub authors: Vec<String>,
@@ -108,14 +381,38 @@ pub struct Paper {
     /// Keyword: 'in'
     pub year: String,
 
-    /// The path to the PDF file of the paper. The user may choose not to specify one.
+    /// Paths to files attached to the paper, like the PDF and slides.
+    /// Older metadata stores a single `filepath`, which is read as one file.
     /// Keyword: '@'
-    pub filepath: Option<PathBuf>,
+    #[serde(alias = "filepath", default, deserialize_with = "deserialize_files")]
+    pub files: Vec<PathBuf>,
 
     /// Labels assigned to this paper.
     /// Keyword: 'is', 'not'
     pub labels: HashSet<String>,
 
+    /// The DOI of the paper, without any URL prefix. Unique across the paperbase.
+    /// This field is queryable.
+    /// Keyword: 'doi'
+    pub doi: Option<String>,
+
+    /// A citation key overriding the generated one. Unique across the paperbase.
+    /// Keyword: 'key'
+    pub citekey: Option<String>,
+
+    /// The generated citation key, made unique against the rest of the
+    /// paperbase by `State::resolve_citekeys`.
+    #[serde(skip)]
+    pub resolved_citekey: Option<String>,
+
+    /// Where the paper was found on the web, opened with `open web`.
+    /// Keyword: 'url'
+    pub url: Option<String>,
+
+    /// The abstract of the paper. This field is queryable.
+    /// Keyword: 'abstract', 'about' (filter)
+    pub abstract_text: Option<String>,
+
     /// The path to the markdown note of the paper. File names are created with the
     /// title of the paper. If collisions are detected, an integer will be appended
     /// to the file name.
@@ -124,28 +421,157 @@ pub struct Paper {
     /// The path to VimWiki note of the paper.
     pub wikipath: Option<PathBuf>,
 
+    /// How good the paper was, from 1 to 5. Set with `rate`.
+    pub rating: Option<u8>,
+
+    /// How soon the paper should be read. 1 is the most urgent.
+    /// Keyword: 'priority'
+    pub priority: Option<u8>,
+
+    /// When the paper should be read by, like for a reading group.
+    /// Keyword: 'due'
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+
+    /// How many papers cite this one, according to Semantic Scholar.
+    /// Keyword: None. Set with `refresh citations`.
+    #[serde(default)]
+    pub citation_count: Option<u32>,
+
     /// Track reading progress.
     #[serde(default = "ReadingProgress::default")]
     pub progress: ReadingProgress,
+
+    /// When the paper was added to the paperbase.
+    /// Keyword: 'added' (filter only)
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub added: Option<DateTime<Local>>,
+
+    /// When the paper was marked as read.
+    /// Keyword: 'read-in' (filter only)
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub read_at: Option<DateTime<Local>>,
+
+    /// Every change of reading progress, oldest first.
+    #[serde(default)]
+    pub history: Vec<StatusChange>,
+
+    /// How long the paper was open in the viewer, in seconds.
+    /// Only counted when `output.track_reading_time` is set.
+    #[serde(default)]
+    pub reading_seconds: u64,
+
+    /// IDs of related papers, like follow-ups. Links go both ways.
+    /// Keyword: None. Set with `link`.
+    #[serde(default)]
+    pub related: Vec<u64>,
+
+    /// Archived papers are hidden from `ls` unless asked for.
+    /// Keyword: 'archived', 'all' (filter only). Set with `archive`.
+    #[serde(default)]
+    pub archived: bool,
+
+    /// Metadata reason does not model, like the publisher or pages.
+    /// Keyword: None. Given as 'name=value'.
+    #[serde(default)]
+    pub extra: BTreeMap<String, String>,
 }
 
+/// The template of new wikis, unless `storage.note_template` is set.
+const DEFAULT_NOTE_TEMPLATE: &str = "\
+# {title}
+
+- {authors}
+- {venue} {year}
+- Tags: {tags}
+
+## Summary
+
+## Strengths
+
+## Weaknesses
+
+## Questions
+";
+
+/// Field names that cannot be used for extra metadata.
+const RESERVED_FIELDS: [&str; 29] = [
+    "id",
+    "title",
+    "nickname",
+    "aliases",
+    "authors",
+    "venue",
+    "year",
+    "files",
+    "filepath",
+    "labels",
+    "tags",
+    "doi",
+    "citekey",
+    "key",
+    "url",
+    "abstract",
+    "notepath",
+    "wikipath",
+    "rating",
+    "priority",
+    "due",
+    "citation_count",
+    "progress",
+    "added",
+    "read_at",
+    "history",
+    "related",
+    "reading_seconds",
+    "archived",
+];
+
 impl Paper {
-    /// Accepts arguments given to commands and builds an instance
-    /// of `Paper`.
+    /// `from_args_with_defaults` without any defaults.
+    #[cfg(test)]
     pub fn from_args(args: Vec<String>) -> Result<Self, Fallacy> {
+        Self::from_args_with_defaults(args, &DefaultsConfig::default()).map(|(paper, _)| paper)
+    }
+
+    /// Accepts arguments given to commands and builds an instance
+    /// of `Paper`. Venue, year, and labels that are not given are taken
+    /// from `defaults`, and the names of those fields are returned too.
+    pub fn from_args_with_defaults(
+        args: Vec<String>,
+        defaults: &DefaultsConfig,
+    ) -> Result<(Self, Vec<&'static str>), Fallacy> {
         // Collect a mapping of keyword -> Option<argument>.
         let mut map = HashMap::new();
+        let mut files = Vec::new();
+        let mut nicknames = Vec::new();
+        let mut extra = BTreeMap::new();
         let mut arg_iter = args.into_iter();
         arg_iter.next(); // Skip the command.
         while let Some(arg) = arg_iter.next() {
             match arg.as_ref() {
-                "as" | "by" | "at" | "in" | "@" | "is" => {
+                // A paper may have several files attached.
+                "@" => match arg_iter.next() {
+                    Some(file) => files.push(PathBuf::from(file)),
+                    None => return Err(Fallacy::PaperMissingFields("files(@)".to_owned())),
+                },
+                // Nicknames after the first are aliases.
+                "as" | "alias" => match arg_iter.next() {
+                    Some(name) => nicknames.push(name),
+                    None => return Err(Fallacy::PaperMissingFields("nickname(as)".to_owned())),
+                },
+                "by" | "at" | "in" | "is" | "doi" | "key" | "url" | "abstract" | "priority"
+                | "due" => {
                     if map.contains_key(arg.as_str()) {
                         return Err(Fallacy::PaperDuplicateField(arg));
                     }
                     map.insert(arg, arg_iter.next());
                 }
                 _ => {
+                    if let Some((name, value)) = Self::parse_extra(&arg)? {
+                        extra.insert(name, value);
+                        continue;
+                    }
                     if map.contains_key("_") {
                         return Err(Fallacy::PaperDuplicateField("title".to_owned()));
                     }
@@ -156,22 +582,36 @@ impl Paper {
 
         // Collect a mapping of field -> argument, along with missing required fields.
         let mut missing = Vec::new();
+        let mut used_defaults = Vec::new();
         let mut fields: HashMap<&str, String> = HashMap::new();
         for (keyword, field_name, required) in [
             ("by", "authors", true),
             ("at", "venue", true),
             ("in", "year", true),
             ("_", "title", true),
-            ("as", "nickname", false),
-            ("@", "filepath", false),
             ("is", "labels", false),
+            ("doi", "doi", false),
+            ("key", "citekey", false),
+            ("priority", "priority", false),
+            ("due", "due", false),
+            ("url", "url", false),
+            ("abstract", "abstract", false),
         ] {
             match map.remove(keyword) {
                 Some(Some(string)) => {
                     fields.insert(field_name, string);
                 }
                 _ => {
-                    if required {
+                    let default = match field_name {
+                        "venue" => defaults.venue.clone(),
+                        "year" => defaults.year(),
+                        "labels" if !defaults.tags.is_empty() => Some(defaults.tags.join(",")),
+                        _ => None,
+                    };
+                    if let Some(value) = default {
+                        fields.insert(field_name, value);
+                        used_defaults.push(field_name);
+                    } else if required {
                         missing.push(format!("{}({})", field_name, keyword));
                     }
                 }
@@ -185,45 +625,277 @@ impl Paper {
 
         // Make paper fields.
         let title = fields.remove("title").unwrap();
-        let nickname = fields.remove("nickname");
-        let authors = fields
-            .remove("authors")
-            .unwrap()
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect();
+        let mut nicknames = nicknames.into_iter();
+        let nickname = nicknames.next();
+        let mut aliases = Vec::new();
+        for alias in nicknames {
+            if Some(&alias) != nickname.as_ref() && !aliases.contains(&alias) {
+                aliases.push(alias);
+            }
+        }
+        let authors = Self::parse_authors(&fields.remove("authors").unwrap())?;
         let venue = fields.remove("venue").unwrap();
         let year = fields.remove("year").unwrap();
         let labels = fields
             .remove("labels")
             .map(|l| l.split(',').map(|s| s.trim().to_string()).collect())
             .unwrap_or_default();
-        let filepath = fields.remove("filepath").map(PathBuf::from);
+        let doi = fields
+            .remove("doi")
+            .map(|doi| Self::parse_doi(&doi))
+            .transpose()?;
+        let citekey = fields
+            .remove("citekey")
+            .map(|key| Self::parse_citekey(&key))
+            .transpose()?;
+        let priority = match fields.remove("priority") {
+            Some(priority) => Self::parse_priority(&priority)?,
+            None => None,
+        };
+        let due = match fields.remove("due") {
+            Some(due) => Self::parse_due(&due)?,
+            None => None,
+        };
+        let url = fields.remove("url");
+        let abstract_text = fields.remove("abstract");
         let notepath = None;
         let wikipath = None;
         let progress = ReadingProgress::Unread;
+        let added = Some(Local::now());
 
-        Ok(Paper {
+        let paper = Paper {
+            id: 0,
             title,
             nickname,
+            aliases,
             authors,
             venue,
             year,
             labels,
-            filepath,
+            doi,
+            citekey,
+            resolved_citekey: None,
+            url,
+            abstract_text,
+            files,
             notepath,
             wikipath,
             progress,
+            rating: None,
+            priority,
+            due,
+            citation_count: None,
+            added,
+            read_at: None,
+            history: Vec::new(),
+            reading_seconds: 0,
+            related: Vec::new(),
+            archived: false,
+            extra: extra
+                .into_iter()
+                .filter(|(_, value)| !value.is_empty())
+                .collect(),
+        };
+        Ok((paper, used_defaults))
+    }
+
+    /// Build a paper from a single BibTeX entry.
+    pub fn from_bibtex(input: &str) -> Result<Self, Fallacy> {
+        let mut entries = bibtex::parse(input);
+        if entries.len() != 1 {
+            return Err(Fallacy::BibtexNotSingleEntry(entries.len()));
+        }
+        Self::from_bibtex_entry(&entries.remove(0)?)
+    }
+
+    /// Build a paper from a parsed BibTeX entry. The venue is the booktitle
+    /// or journal, and fields reason does not model are kept in `extra`.
+    pub fn from_bibtex_entry(entry: &bibtex::Entry) -> Result<Self, Fallacy> {
+        let missing =
+            |field: &str| Fallacy::BibtexMissingField(entry.key.clone(), field.to_owned());
+        let text = |field: &str| entry.get(field).map(bibtex::to_text);
+
+        let title = text("title")
+            .filter(|title| !title.is_empty())
+            .ok_or_else(|| missing("title"))?;
+        let authors: Vec<String> = bibtex::split_words(entry.get("author").unwrap_or(""), "and")
+            .into_iter()
+            .filter(|author| author != "others")
+            .map(|author| {
+                // 'Last, First' and 'Last, Jr., First' become 'First Last'.
+                let parts: Vec<_> = bibtex::split_words(&author.replace(',', " , "), ",");
+                let author = match parts.as_slice() {
+                    [last, first] => format!("{} {}", first, last),
+                    [last, jr, first] => format!("{} {} {}", first, last, jr),
+                    _ => author,
+                };
+                bibtex::to_text(&author)
+            })
+            .collect();
+        if authors.is_empty() {
+            return Err(missing("author"));
+        }
+        let venue = text("booktitle")
+            .or_else(|| text("journal"))
+            .or_else(|| text("howpublished"))
+            .or_else(|| {
+                entry
+                    .get("archiveprefix")
+                    .or_else(|| entry.get("eprint").map(|_| "arXiv"))
+                    .map(bibtex::to_text)
+            })
+            .or_else(|| text("publisher"))
+            .ok_or_else(|| missing("booktitle"))?;
+        let year = text("year")
+            .or_else(|| text("date").map(|date| date.chars().take(4).collect()))
+            .ok_or_else(|| missing("year"))?;
+        if year.parse::<u32>().is_err() {
+            return Err(Fallacy::BibtexInvalidField(
+                "year".to_owned(),
+                format!("'{}' is not a year", year),
+            ));
+        }
+        let doi = text("doi").map(|doi| Self::parse_doi(&doi)).transpose()?;
+        let citekey = Some(Self::parse_citekey(&entry.key)?);
+
+        // Everything else is extra metadata.
+        let mut extra = BTreeMap::new();
+        for (name, value) in entry.fields.iter() {
+            let modeled = [
+                "title",
+                "author",
+                "booktitle",
+                "journal",
+                "year",
+                "doi",
+                "url",
+                "abstract",
+            ];
+            if modeled.contains(&name.as_str()) || RESERVED_FIELDS.contains(&name.as_str()) {
+                continue;
+            }
+            if let Ok(Some((name, value))) = Self::parse_extra(&format!("{}={}", name, value)) {
+                extra.insert(name, bibtex::to_text(&value));
+            }
+        }
+
+        Ok(Paper {
+            title,
+            authors,
+            venue,
+            year,
+            doi,
+            citekey,
+            url: text("url"),
+            abstract_text: text("abstract"),
+            added: Some(Local::now()),
+            extra,
+            ..Default::default()
         })
     }
 
+    /// Build a BibTeX entry for the paper. The entry type is guessed from
+    /// the venue: arXiv papers are `@misc` with an eprint, journals are
+    /// `@article`, and the rest are `@inproceedings`.
+    pub fn to_bibtex_entry(&self) -> bibtex::Entry {
+        let venue = self.venue.to_lowercase();
+        let arxiv = venue.contains("arxiv") || venue == "corr";
+        let kind = if arxiv {
+            "misc"
+        } else if ["journal", "transactions", "letters", "magazine", "review"]
+            .iter()
+            .any(|word| venue.contains(word))
+        {
+            "article"
+        } else if venue.is_empty() {
+            "misc"
+        } else {
+            "inproceedings"
+        };
+
+        let mut fields = vec![
+            ("title".to_owned(), self.title.clone()),
+            ("author".to_owned(), self.authors.join(" and ")),
+        ];
+        let eprint = self.extra.get("eprint").cloned().or_else(|| {
+            let url = self.url.as_ref()?;
+            let (_, id) = url.split_once("arxiv.org/abs/")?;
+            Some(id.trim_end_matches('/').to_owned())
+        });
+        match kind {
+            "misc" => match eprint.as_ref().filter(|_| arxiv) {
+                Some(eprint) => {
+                    fields.push(("eprint".to_owned(), eprint.clone()));
+                    fields.push(("archiveprefix".to_owned(), "arXiv".to_owned()));
+                }
+                None if !venue.is_empty() => {
+                    fields.push(("howpublished".to_owned(), self.venue.clone()))
+                }
+                None => {}
+            },
+            "article" => fields.push(("journal".to_owned(), self.venue.clone())),
+            _ => fields.push(("booktitle".to_owned(), self.venue.clone())),
+        }
+        fields.push(("year".to_owned(), self.year.clone()));
+        let optional = [
+            ("doi", self.doi.as_ref()),
+            ("url", self.url.as_ref()),
+            ("abstract", self.abstract_text.as_ref()),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                fields.push((name.to_owned(), value.clone()));
+            }
+        }
+        for (name, value) in self.extra.iter() {
+            if !["eprint", "archiveprefix"].contains(&name.as_str()) {
+                fields.push((name.clone(), value.clone()));
+            }
+        }
+
+        // DOIs and URLs are not text, so they are not escaped.
+        for (name, value) in fields.iter_mut() {
+            if !["doi", "url"].contains(&name.as_str()) {
+                *value = bibtex::escape(value);
+            }
+        }
+        bibtex::Entry {
+            kind: kind.to_owned(),
+            key: self.citekey(),
+            fields,
+        }
+    }
+
     pub fn apply_from_args(&mut self, args: &[String]) -> Result<(), Fallacy> {
         // Collect a mapping of keyword -> Option<argument>.
         let mut map = HashMap::new();
         let mut arg_iter = args.iter().cloned();
         while let Some(arg) = arg_iter.next() {
             match arg.as_ref() {
-                "as" | "by" | "at" | "in" | "is" | "not" => {
+                // Files are attached with '@' and detached with '-@'.
+                "@" | "-@" if arg_iter.len() > 0 => {
+                    let file = PathBuf::from(arg_iter.next().unwrap());
+                    if arg == "-@" {
+                        self.files.retain(|f| f != &file);
+                    } else if !self.files.contains(&file) {
+                        self.files.push(file);
+                    }
+                }
+                // Likewise for aliases.
+                "alias" | "-alias" if arg_iter.len() > 0 => {
+                    let alias = arg_iter.next().unwrap();
+                    if arg == "-alias" {
+                        self.aliases.retain(|a| a != &alias);
+                    } else {
+                        Self::parse_nickname(&alias)?;
+                        if self.nickname.as_ref() != Some(&alias) && !self.aliases.contains(&alias)
+                        {
+                            self.aliases.push(alias);
+                        }
+                    }
+                }
+                "as" | "by" | "at" | "in" | "is" | "not" | "doi" | "key" | "url" | "abstract"
+                | "priority" | "due" => {
                     if map.contains_key(arg.as_str()) {
                         return Err(Fallacy::PaperDuplicateField(arg));
                     }
@@ -234,6 +906,24 @@ impl Paper {
                     }
                 }
                 _ => {
+                    // Some fields can also be given as 'field=value'.
+                    if let Some((keyword, value)) = Self::parse_field(&arg) {
+                        if map.contains_key(keyword) {
+                            let field = arg.split_once('=').unwrap().0;
+                            return Err(Fallacy::PaperDuplicateField(field.to_owned()));
+                        }
+                        map.insert(keyword.to_owned(), value.to_owned());
+                        continue;
+                    }
+                    // An empty value removes the extra field.
+                    if let Some((name, value)) = Self::parse_extra(&arg)? {
+                        if value.is_empty() {
+                            self.extra.remove(&name);
+                        } else {
+                            self.extra.insert(name, value);
+                        }
+                        continue;
+                    }
                     if map.contains_key("_") {
                         return Err(Fallacy::PaperDuplicateField("title".to_owned()));
                     }
@@ -247,39 +937,512 @@ impl Paper {
             self.title = title;
         }
         if let Some(nickname) = map.remove("as") {
+            Self::parse_nickname(&nickname)?;
+            self.aliases.retain(|alias| alias != &nickname);
             self.nickname = Some(nickname);
         }
         if let Some(authors) = map.remove("by") {
-            self.authors = authors.split(',').map(|s| s.trim().to_string()).collect();
+            self.authors = Self::parse_authors(&authors)?;
+        }
+        if let Some(authors) = map.remove("authors") {
+            let authors: Vec<_> = authors.split(';').map(|s| s.trim().to_string()).collect();
+            Self::check_authors(&authors)?;
+            self.authors = authors;
         }
         if let Some(venue) = map.remove("at") {
             self.venue = venue;
         }
         if let Some(year) = map.remove("in") {
+            Self::parse_year(&year)?;
             self.year = year;
         }
         if let Some(labels) = map.remove("is") {
             for label in labels.split(',') {
+                Self::parse_label(label)?;
                 self.labels.insert(label.trim().to_string());
             }
         }
+        if let Some(labels) = map.remove("tags") {
+            let mut tags = HashSet::new();
+            for label in labels.split(',').filter(|label| !label.trim().is_empty()) {
+                Self::parse_label(label)?;
+                tags.insert(label.trim().to_string());
+            }
+            self.labels = tags;
+        }
         if let Some(labels) = map.remove("not") {
             for label in labels.split(',') {
-                self.labels.remove(&label.trim().to_string());
+                self.labels.remove(label.trim());
+            }
+        }
+        if let Some(doi) = map.remove("doi") {
+            self.doi = Some(Self::parse_doi(&doi)?);
+        }
+        if let Some(key) = map.remove("key") {
+            self.citekey = Some(Self::parse_citekey(&key)?);
+        }
+        if let Some(priority) = map.remove("priority") {
+            self.priority = Self::parse_priority(&priority)?;
+        }
+        if let Some(due) = map.remove("due") {
+            self.due = Self::parse_due(&due)?;
+        }
+        if let Some(url) = map.remove("url") {
+            self.url = Some(url);
+        }
+        if let Some(abstract_text) = map.remove("abstract") {
+            self.abstract_text = Some(abstract_text);
+        }
+
+        Ok(())
+    }
+
+    /// Change reading progress and record the change in `history`, along
+    /// with who made it if known.
+    pub fn set_progress(&mut self, progress: ReadingProgress, who: Option<&str>) {
+        self.set_progress_at(progress, Local::now(), who);
+    }
+
+    /// Change reading progress as if it happened at `when`, like a paper
+    /// read a few days ago.
+    pub fn set_progress_at(
+        &mut self,
+        progress: ReadingProgress,
+        when: DateTime<Local>,
+        who: Option<&str>,
+    ) {
+        self.progress = progress;
+        self.read_at = match progress {
+            ReadingProgress::Read => Some(when),
+            _ => None,
+        };
+        self.history.push(StatusChange {
+            progress,
+            when,
+            who: who.map(str::to_owned),
+        });
+    }
+
+    /// Papers stored before reading progress history was kept only have
+    /// their current progress. Make that the only entry of the history,
+    /// dated when the paper was read or added if known.
+    pub fn synthesize_history(&mut self) {
+        if !self.history.is_empty() || self.progress == ReadingProgress::Unread {
+            return;
+        }
+        let when = self.read_at.or(self.added).unwrap_or_else(Local::now);
+        self.history.push(StatusChange {
+            progress: self.progress,
+            when,
+            who: None,
+        });
+    }
+
+    /// Show every field of the paper as a two-column table, with long values
+    /// like the abstract wrapped to the terminal width. `state` is used to
+    /// name related papers.
+    pub fn details(&self, state: &State) -> String {
+        let mut rows = self.detail_rows(&self.files);
+        rows.extend(self.related_row(state));
+        rows.extend(self.history_row());
+        render_rows(rows)
+    }
+
+    /// Show every field of the paper for `stat`, with whether each file
+    /// exists, how long the wiki is, and related papers.
+    pub fn stat(&self, state: &State, config: &Config) -> String {
+        let mut rows = self.detail_rows(&[]);
+        if !self.files.is_empty() {
+            let files: Vec<_> = self
+                .files(config)
+                .iter()
+                .map(|file| {
+                    let mark = if file.exists() { '✓' } else { '✗' };
+                    format!("{} {}", mark, file.display())
+                })
+                .collect();
+            rows.push(("files", files.join("\n")));
+        }
+        if let Some(notepath) = self.notepath.as_ref() {
+            let notepath = config.storage.note_dir.join(notepath);
+            rows.push(("notes", notepath.display().to_string()));
+        }
+        if let Some(wikipath) = self.wikipath.as_ref() {
+            let wikipath = config.storage.wiki_dir.join(wikipath);
+            let length = match std::fs::read_to_string(&wikipath) {
+                Ok(text) => format!("{} words", text.split_whitespace().count()),
+                Err(_) => "missing".to_owned(),
+            };
+            rows.push(("wiki", format!("{} ({})", wikipath.display(), length)));
+        }
+        rows.extend(self.related_row(state));
+        rows.extend(self.history_row());
+        render_rows(rows)
+    }
+
+    /// Show every field of the paper with files and notes as absolute paths.
+    /// Long values like the author list and abstract wrap to the terminal.
+    pub fn detailed_display(&self, config: &Config) -> String {
+        let mut rows = self.detail_rows(&self.files(config));
+        if let Some(notepath) = self.notepath.as_ref() {
+            let notepath = config.storage.note_dir.join(notepath);
+            rows.push(("notes", notepath.display().to_string()));
+        }
+        rows.extend(self.history_row());
+        render_rows(rows)
+    }
+
+    /// Rows of the detailed views, except related papers and history.
+    fn detail_rows(&self, files: &[PathBuf]) -> Vec<(&str, String)> {
+        let mut rows = vec![("title", self.title.clone())];
+        if let Some(nickname) = self.nickname.as_ref() {
+            rows.push(("nickname", nickname.clone()));
+        }
+        if !self.aliases.is_empty() {
+            rows.push(("aliases", self.aliases.join(", ")));
+        }
+        rows.push(("key", self.citekey()));
+        rows.push(("authors", self.authors.join(", ")));
+        rows.push(("venue", self.venue.clone()));
+        rows.push(("year", self.year.clone()));
+        if !self.labels.is_empty() {
+            rows.push(("labels", self.field_as_string("labels")));
+        }
+        rows.push(("progress", self.progress.to_string()));
+        if self.archived {
+            rows.push(("archived", "yes".to_owned()));
+        }
+        if let Some(rating) = self.rating {
+            rows.push(("rating", format!("{}/5", rating)));
+        }
+        if let Some(priority) = self.priority {
+            rows.push(("priority", priority.to_string()));
+        }
+        if let Some(due) = self.due {
+            rows.push(("due", due.to_string()));
+        }
+        if let Some(citations) = self.citation_count {
+            rows.push(("citations", citations.to_string()));
+        }
+        if self.reading_seconds > 0 {
+            rows.push(("reading time", humanize_seconds(self.reading_seconds)));
+        }
+        if !files.is_empty() {
+            let files: Vec<_> = files.iter().map(|f| f.display().to_string()).collect();
+            rows.push(("files", files.join("\n")));
+        }
+        let format = |when: &Option<DateTime<Local>>| {
+            when.map(|when| when.format("%Y-%m-%d %H:%M").to_string())
+        };
+        let optional = [
+            ("doi", &self.doi),
+            ("url", &self.url),
+            ("added", &format(&self.added)),
+            ("read_at", &format(&self.read_at)),
+            ("abstract", &self.abstract_text),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                rows.push((name, value.clone()));
             }
         }
+        for (name, value) in self.extra.iter() {
+            rows.push((name, value.clone()));
+        }
+        rows
+    }
+
+    fn related_row(&self, state: &State) -> Option<(&'static str, String)> {
+        if self.related.is_empty() {
+            return None;
+        }
+        let related: Vec<_> = state
+            .papers
+            .iter()
+            .filter(|paper| self.related.contains(&paper.id))
+            .map(Paper::mention)
+            .collect();
+        Some(("related", related.join("\n")))
+    }
+
+    fn history_row(&self) -> Option<(&'static str, String)> {
+        if self.history.is_empty() {
+            return None;
+        }
+        let history: Vec<_> = self
+            .history
+            .iter()
+            .map(|change| {
+                let when = change.when.format("%Y-%m-%d %H:%M");
+                match change.who.as_ref() {
+                    Some(who) => format!("{} {} ({})", when, change.progress, who),
+                    None => format!("{} {}", when, change.progress),
+                }
+            })
+            .collect();
+        Some(("history", history.join("\n")))
+    }
+
+    /// Validate a DOI, stripping a leading `doi:` or doi.org URL.
+    pub fn parse_doi(doi: &str) -> Result<String, Fallacy> {
+        let mut stripped = doi.trim();
+        for prefix in [
+            "https://doi.org/",
+            "http://doi.org/",
+            "https://dx.doi.org/",
+            "http://dx.doi.org/",
+            "doi:",
+        ] {
+            if stripped.len() >= prefix.len()
+                && stripped[..prefix.len()].eq_ignore_ascii_case(prefix)
+            {
+                stripped = &stripped[prefix.len()..];
+                break;
+            }
+        }
+        let shape = regex::Regex::new(r"^10\.\d{4,9}/\S+$").unwrap();
+        if shape.is_match(stripped) {
+            Ok(stripped.to_owned())
+        } else {
+            Err(Fallacy::PaperInvalidDoi(doi.to_owned()))
+        }
+    }
+
+    /// Parse 'field=value' for fields that `set` takes this way, into the
+    /// keyword of the field and the value. Authors are separated by ';', and
+    /// tags replace all labels.
+    fn parse_field(arg: &str) -> Option<(&'static str, &str)> {
+        let (name, value) = arg.split_once('=')?;
+        let keyword = match name {
+            "title" => "_",
+            "nickname" => "as",
+            "venue" => "at",
+            "year" => "in",
+            "authors" => "authors",
+            "tags" => "tags",
+            _ => return None,
+        };
+        Some((keyword, value.trim()))
+    }
 
+    /// Split an argument like 'publisher=ACM' into an extra field name and
+    /// its value. Returns `None` if the argument is not of that form.
+    fn parse_extra(arg: &str) -> Result<Option<(String, String)>, Fallacy> {
+        let (name, value) = match arg.split_once('=') {
+            Some(pair) => pair,
+            None => return Ok(None),
+        };
+        let mut chars = name.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_lowercase())
+            || !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        {
+            return Ok(None);
+        }
+        if RESERVED_FIELDS.contains(&name) {
+            return Err(Fallacy::PaperReservedField(name.to_owned()));
+        }
+        Ok(Some((name.to_owned(), value.trim().to_owned())))
+    }
+
+    /// How messages refer to the paper: '@nickname', or its title.
+    pub fn mention(&self) -> String {
+        match self.nickname.as_ref() {
+            Some(nickname) => format!("@{}", nickname),
+            None => self.title.clone(),
+        }
+    }
+
+    /// The nickname of the paper followed by its aliases.
+    pub fn nicknames(&self) -> impl Iterator<Item = &str> {
+        self.nickname
+            .iter()
+            .chain(self.aliases.iter())
+            .map(String::as_str)
+    }
+
+    /// Parse a priority, which is a positive integer. 'none' clears it.
+    pub fn parse_priority(priority: &str) -> Result<Option<u8>, Fallacy> {
+        match priority.trim() {
+            "none" => Ok(None),
+            p => match p.parse::<u8>() {
+                Ok(p) if p > 0 => Ok(Some(p)),
+                _ => Err(Fallacy::PaperInvalidPriority(priority.to_owned())),
+            },
+        }
+    }
+
+    /// Parse a due date like '2024-06-01'. 'none' clears it.
+    pub fn parse_due(due: &str) -> Result<Option<NaiveDate>, Fallacy> {
+        match due.trim() {
+            "none" => Ok(None),
+            d => match NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+                Ok(date) => Ok(Some(date)),
+                Err(_) => Err(Fallacy::PaperInvalidDue(due.to_owned())),
+            },
+        }
+    }
+
+    /// Whether the paper is still not read after its due date.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.progress != ReadingProgress::Read && self.due.is_some_and(|due| due < today)
+    }
+
+    /// Validate a citation key, which BibTeX does not allow to have
+    /// whitespace or some punctuation.
+    pub fn parse_citekey(key: &str) -> Result<String, Fallacy> {
+        let key = key.trim();
+        if key.is_empty()
+            || key
+                .chars()
+                .any(|c| c.is_whitespace() || "{}(),\"#%'=\\~".contains(c))
+        {
+            Err(Fallacy::PaperInvalidCitekey(key.to_owned()))
+        } else {
+            Ok(key.to_owned())
+        }
+    }
+
+    /// Check that fields given by the user make sense: the year is between
+    /// 1800 and next year, every author has a name, nicknames have no
+    /// whitespace, and labels have no commas.
+    pub fn validate(&self) -> Result<(), Fallacy> {
+        Self::parse_year(&self.year)?;
+        Self::check_authors(&self.authors)?;
+        for name in self.nickname.iter().chain(self.aliases.iter()) {
+            Self::parse_nickname(name)?;
+        }
+        for label in self.labels.iter() {
+            Self::parse_label(label)?;
+        }
         Ok(())
     }
 
+    /// Validate a year, which should be no later than next year.
+    pub fn parse_year(year: &str) -> Result<i32, Fallacy> {
+        let latest = Local::now().year() + 1;
+        match year.trim().parse::<i32>() {
+            Ok(y) if (1800..=latest).contains(&y) => Ok(y),
+            _ => Err(Fallacy::PaperInvalidYear(year.to_owned(), latest)),
+        }
+    }
+
+    /// Split a comma-separated list of authors, each of which should have
+    /// a name.
+    pub fn parse_authors(authors: &str) -> Result<Vec<String>, Fallacy> {
+        let authors: Vec<_> = authors.split(',').map(|s| s.trim().to_string()).collect();
+        Self::check_authors(&authors)?;
+        Ok(authors)
+    }
+
+    fn check_authors(authors: &[String]) -> Result<(), Fallacy> {
+        if authors.iter().all(|author| author.is_empty()) {
+            return Err(Fallacy::PaperNoAuthors);
+        }
+        if authors.iter().any(|author| author.trim().is_empty()) {
+            return Err(Fallacy::PaperBlankAuthor(authors.join(", ")));
+        }
+        Ok(())
+    }
+
+    /// Validate a nickname, which is typed as a single word.
+    pub fn parse_nickname(name: &str) -> Result<(), Fallacy> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(Fallacy::PaperInvalidNickname(name.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Validate a label, which is given in comma-separated lists.
+    pub fn parse_label(label: &str) -> Result<(), Fallacy> {
+        if label.trim().is_empty() || label.contains(',') {
+            return Err(Fallacy::PaperInvalidLabel(label.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// The surname of the first author, if any.
+    pub fn first_surname(&self) -> Option<&str> {
+        // Authors are either 'First Last' or 'Last, First'.
+        self.authors
+            .first()
+            .and_then(|author| match author.split_once(',') {
+                Some((last, _)) => Some(last.trim()),
+                None => author.split_whitespace().last(),
+            })
+    }
+
+    /// Generate a citation key from the surname of the first author, the
+    /// year, and the first word of the title, like `smith2021efficient`.
+    /// Articles at the start of the title are skipped.
+    pub fn base_citekey(&self) -> String {
+        let simplify = |s: &str| -> String {
+            s.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let surname = self
+            .first_surname()
+            .map(simplify)
+            .filter(|surname| !surname.is_empty())
+            .unwrap_or_else(|| "anonymous".to_owned());
+        let year: String = self.year.chars().filter(char::is_ascii_digit).collect();
+        let word = self
+            .title
+            .split(|c: char| c.is_whitespace() || c == '-' || c == ':')
+            .map(simplify)
+            .find(|word| !word.is_empty() && !["a", "an", "the"].contains(&word.as_str()))
+            .unwrap_or_default();
+        format!("{}{}{}", surname, year, word)
+    }
+
+    /// The citation key of the paper. This is the stored key if there is
+    /// one, and the generated key otherwise.
+    pub fn citekey(&self) -> String {
+        self.citekey
+            .clone()
+            .or_else(|| self.resolved_citekey.clone())
+            .unwrap_or_else(|| self.base_citekey())
+    }
+
     pub fn field_as_string(&self, field: &str) -> String {
         match field {
             "title" => self.title.clone(),
             "nickname" => self.nickname.clone().unwrap_or_default(),
             "authors" => self.authors.join(", "),
-            "first author" => self.authors.first().unwrap().clone(),
+            "first author" | "first_author" => self.authors.first().cloned().unwrap_or_default(),
             "venue" => self.venue.clone(),
             "year" => self.year.clone(),
+            "state" => self.progress.to_string(),
+            "tags" | "labels" => {
+                let mut labels: Vec<_> = self.labels.iter().map(String::as_str).collect();
+                labels.sort_unstable();
+                labels.join(", ")
+            }
+            "added" => self
+                .added
+                .map(|added| added.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            "read_at" => self
+                .read_at
+                .map(|read_at| read_at.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            "doi" => self.doi.clone().unwrap_or_default(),
+            "key" => self.citekey(),
+            "url" => self.url.clone().unwrap_or_default(),
+            "rating" => self
+                .rating
+                .map(|rating| format!("{}/5", rating))
+                .unwrap_or_default(),
+            "priority" => self
+                .priority
+                .map(|priority| priority.to_string())
+                .unwrap_or_default(),
+            "citations" => self
+                .citation_count
+                .map(|citations| citations.to_string())
+                .unwrap_or_default(),
+            "due" => self.due.map(|due| due.to_string()).unwrap_or_default(),
             _ => "".to_string(),
         }
     }
@@ -365,14 +1528,18 @@ impl Paper {
             if !create {
                 return Ok(None);
             } else {
-                // Generate filename with nickname, if possible.
-                let file = match self.nickname.clone() {
-                    Some(string) => as_filename(&string),
-                    None => as_filename(&self.title),
+                // Markdown notes are named after the citation key, and
+                // VimWiki ones after the nickname, if possible.
+                let (file, ext) = match config.storage.note_format {
+                    NoteFormat::Markdown => (as_filename(&self.citekey()), ".md"),
+                    NoteFormat::Vimwiki => match self.nickname.clone() {
+                        Some(string) => (as_filename(&string), ".wiki"),
+                        None => (as_filename(&self.title), ".wiki"),
+                    },
                 };
 
                 // Find a filename that doesn't exist.
-                wiki = make_unique_path(&config.storage.wiki_dir, &file, ".wiki");
+                wiki = make_unique_path(&config.storage.wiki_dir, &file, ext);
                 // `note` will never termiante with '..', so `unwrap` will not panic.
                 self.wikipath
                     .replace(PathBuf::from(wiki.file_name().unwrap()));
@@ -382,41 +1549,546 @@ impl Paper {
         if !create {
             Ok(None)
         } else {
-            // Create/truncate the note file and fill with default content, that
-            // is, populate the index.wiki with this entry as a linkable wiki.
-            match std::fs::File::create(&wiki) {
-                Ok(mut file) => {
-                    if let Err(e) = write!(
-                        file,
-                        "# {}\n\n- {}\n- {} {}\n\n",
-                        self.title,
-                        self.authors.join(", "),
-                        self.venue,
-                        self.year
-                    ) {
-                        return Err(e.into());
-                    }
-                    /* TODO (vimwiki:auto_toc)
-                    if let Err(e) = write!(
-                        /* write to index.wiki */
-                    ) {
-                        return Err(e.into());
-                    }
-                    */
-                }
-                Err(e) => return Err(e.into()),
-            };
+            // Create the wiki and fill it from the template. Existing files
+            // are never overwritten.
+            let content = self.wiki_content(config)?;
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&wiki)?;
+            file.write_all(content.as_bytes())?;
+            /* TODO (vimwiki:auto_toc)
+            Populate the index.wiki with this entry as a linkable wiki.
+            */
             Ok(Some(wiki))
         }
     }
 
-    /// Return the absolute path to the paper file.
-    /// Returns `None` if the paper does not have a filepath.
-    pub fn filepath(&self, config: &Config) -> Option<PathBuf> {
-        self.filepath.as_ref().map(|filepath| {
-            let mut base = config.storage.file_dir.clone();
-            base.push(filepath);
-            base
-        })
+    /// The content of a new wiki, from `storage.note_template` or the
+    /// default template.
+    fn wiki_content(&self, config: &Config) -> Result<String, Fallacy> {
+        let text = match config.storage.note_template.as_ref() {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| Fallacy::NoteTemplateReadFailed(path.clone(), e))?,
+            None => DEFAULT_NOTE_TEMPLATE.to_owned(),
+        };
+        let body = template::expand(&template::parse(&text)?, self);
+        if config.storage.note_format == NoteFormat::Vimwiki {
+            return Ok(body);
+        }
+
+        // Markdown notes start with YAML front matter.
+        let mut labels: Vec<_> = self.labels.iter().cloned().collect();
+        labels.sort_unstable();
+        let mut front = serde_yaml::Mapping::new();
+        let mut put = |key: &str, value: serde_yaml::Value| {
+            front.insert(key.into(), value);
+        };
+        put("title", self.title.clone().into());
+        put("authors", self.authors.clone().into());
+        put("tags", labels.into());
+        put("status", self.progress.kind().into());
+        let front = serde_yaml::to_string(&front)
+            .map_err(|e| Fallacy::InvalidCommand(format!("Cannot write front matter: {}", e)))?;
+        Ok(format!("{}---\n\n{}", front, body))
+    }
+
+    /// Whether a markdown or VimWiki note of this paper exists on disk.
+    pub fn has_notes(&self, config: &Config) -> bool {
+        let exists = |dir: &PathBuf, path: &Option<PathBuf>| {
+            path.as_ref().is_some_and(|path| dir.join(path).is_file())
+        };
+        exists(&config.storage.wiki_dir, &self.wikipath)
+            || exists(&config.storage.note_dir, &self.notepath)
+    }
+
+    /// Return the absolute paths to the files attached to the paper.
+    pub fn files(&self, config: &Config) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .map(|file| {
+                let mut base = config.storage.file_dir.clone();
+                base.push(file);
+                base
+            })
+            .collect()
+    }
+}
+
+/// Render name-value rows as a two-column table that fits the terminal.
+fn render_rows(rows: Vec<(&str, String)>) -> String {
+    let mut table = Table::new();
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    for (name, value) in rows {
+        table.add_row(vec![
+            Cell::new(name).add_attribute(Attribute::Bold),
+            Cell::new(value),
+        ]);
+    }
+    table.to_string() + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn doi_shapes() {
+        for doi in [
+            "10.1145/3492321.3519584",
+            "doi:10.1145/3492321.3519584",
+            "https://doi.org/10.1145/3492321.3519584",
+            "HTTP://DX.DOI.ORG/10.1145/3492321.3519584",
+        ] {
+            assert_eq!(Paper::parse_doi(doi).unwrap(), "10.1145/3492321.3519584");
+        }
+        for doi in ["", "10.1145", "10.12/abc", "11.1145/abc", "10.1145/a b"] {
+            assert!(matches!(
+                Paper::parse_doi(doi),
+                Err(Fallacy::PaperInvalidDoi(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn defaults_from_config() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let defaults = DefaultsConfig {
+            venue: Some("arXiv".to_owned()),
+            year: Some("current".to_owned()),
+            tags: vec!["inbox".to_owned()],
+        };
+        let (paper, used) =
+            Paper::from_args_with_defaults(args(&["touch", "Reason", "by", "Chung"]), &defaults)
+                .unwrap();
+        assert_eq!(paper.venue, "arXiv");
+        assert_eq!(paper.year, Local::now().format("%Y").to_string());
+        assert!(paper.labels.contains("inbox"));
+        assert_eq!(used, vec!["venue", "year", "labels"]);
+
+        // Given fields win.
+        let (paper, used) = Paper::from_args_with_defaults(
+            args(&["touch", "Reason", "by", "Chung", "at", "OSDI", "is", "os"]),
+            &defaults,
+        )
+        .unwrap();
+        assert_eq!(paper.venue, "OSDI");
+        assert_eq!(paper.labels.len(), 1);
+        assert!(paper.labels.contains("os"));
+        assert_eq!(used, vec!["year"]);
+
+        // Without defaults, fields are still required.
+        assert!(matches!(
+            Paper::from_args(args(&["touch", "Reason", "by", "Chung"])),
+            Err(Fallacy::PaperMissingFields(_))
+        ));
+    }
+
+    #[test]
+    fn validation() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let touch = |a: &[&str]| {
+            let mut full = args(&["touch", "Reason", "by", "Chung", "at", "OSDI"]);
+            full.extend(args(a));
+            Paper::from_args(full).and_then(|paper| paper.validate().map(|_| paper))
+        };
+        let next_year = (Local::now().year() + 1).to_string();
+        assert!(touch(&["in", "1800"]).is_ok());
+        assert!(touch(&["in", &next_year]).is_ok());
+        for year in ["20233", "1799", "", "2022a"] {
+            assert!(matches!(
+                touch(&["in", year]),
+                Err(Fallacy::PaperInvalidYear(y, _)) if y == year
+            ));
+        }
+        let mut paper = touch(&["in", "2022", "as", "reason"]).unwrap();
+        assert!(matches!(
+            paper.apply_from_args(&args(&["by", " "])),
+            Err(Fallacy::PaperNoAuthors)
+        ));
+        assert!(matches!(
+            paper.apply_from_args(&args(&["by", "Chung,,Jeong"])),
+            Err(Fallacy::PaperBlankAuthor(_))
+        ));
+        assert!(matches!(
+            paper.apply_from_args(&args(&["alias", "a reason"])),
+            Err(Fallacy::PaperInvalidNickname(_))
+        ));
+        assert!(matches!(
+            touch(&["in", "2022", "as", "re ason"]),
+            Err(Fallacy::PaperInvalidNickname(_))
+        ));
+        assert!(matches!(
+            touch(&["in", "2022", "is", "os,"]),
+            Err(Fallacy::PaperInvalidLabel(_))
+        ));
+        assert!(Paper::parse_label("a,b").is_err());
+    }
+
+    #[test]
+    fn doi_from_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let paper = Paper::from_args(args(&[
+            "touch",
+            "Reason",
+            "by",
+            "Chung",
+            "at",
+            "OSDI",
+            "in",
+            "2022",
+            "doi",
+            "doi:10.1/x",
+        ]));
+        assert!(matches!(paper, Err(Fallacy::PaperInvalidDoi(_))));
+        let mut paper = Paper::from_args(args(&[
+            "touch",
+            "Reason",
+            "by",
+            "Chung",
+            "at",
+            "OSDI",
+            "in",
+            "2022",
+            "doi",
+            "doi:10.1234/x",
+        ]))
+        .unwrap();
+        assert_eq!(paper.doi.as_deref(), Some("10.1234/x"));
+        paper.apply_from_args(&args(&["doi", "10.5678/y"])).unwrap();
+        assert_eq!(paper.doi.as_deref(), Some("10.5678/y"));
+    }
+
+    #[test]
+    fn details_show_set_fields() {
+        let mut paper = Paper {
+            title: "Reason".to_owned(),
+            authors: vec!["Chung".to_owned()],
+            ..Default::default()
+        };
+        assert!(!paper.details(&State::default()).contains("abstract"));
+        paper.abstract_text = Some("A shell for research papers.".to_owned());
+        let details = paper.details(&State::default());
+        assert!(details.contains("abstract"));
+        assert!(details.contains("A shell for research papers."));
+        assert!(!details.contains("doi"));
+
+        // A single paper is still listed in a table, without its abstract.
+        let state = State::from_papers(vec![paper]);
+        let table = state
+            .paper_list(vec![0])
+            .into_string(&state, &Config::default());
+        assert!(table.contains('#') && !table.contains("A shell for research papers."));
+    }
+
+    #[test]
+    fn attached_files() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut paper = Paper::from_args(args(&[
+            "touch", "Reason", "by", "Chung", "at", "OSDI", "in", "2022", "@", "a.pdf", "@",
+            "b.pdf",
+        ]))
+        .unwrap();
+        assert_eq!(paper.files, vec![PathBuf::from("a.pdf"), "b.pdf".into()]);
+        paper
+            .apply_from_args(&args(&["-@", "a.pdf", "@", "c.pdf"]))
+            .unwrap();
+        assert_eq!(paper.files, vec![PathBuf::from("b.pdf"), "c.pdf".into()]);
+
+        // A single file path from older metadata becomes the only file.
+        let yaml = "title: Reason\nauthors: []\nvenue: OSDI\nyear: '2022'\nlabels: []\n";
+        for (file, files) in [("~", vec![]), ("a.pdf", vec![PathBuf::from("a.pdf")])] {
+            let old = format!("{}filepath: {}\n", yaml, file);
+            assert_eq!(serde_yaml::from_str::<Paper>(&old).unwrap().files, files);
+        }
+        let new = format!("{}files: [a.pdf, b.pdf]\n", yaml);
+        assert_eq!(serde_yaml::from_str::<Paper>(&new).unwrap().files.len(), 2);
+    }
+
+    #[test]
+    fn aliases() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut paper = Paper::from_args(args(&[
+            "touch", "ResNet", "by", "He", "at", "CVPR", "in", "2016", "as", "resnet", "as",
+            "resnet15",
+        ]))
+        .unwrap();
+        assert_eq!(paper.nickname.as_deref(), Some("resnet"));
+        assert_eq!(paper.aliases, vec!["resnet15"]);
+        paper
+            .apply_from_args(&args(&["alias", "rn", "-alias", "resnet15"]))
+            .unwrap();
+        assert_eq!(paper.nicknames().collect::<Vec<_>>(), vec!["resnet", "rn"]);
+        // Promoting an alias to the nickname drops it from the aliases.
+        paper.apply_from_args(&args(&["as", "rn"])).unwrap();
+        assert_eq!(paper.nicknames().collect::<Vec<_>>(), vec!["rn"]);
+
+        let mut state = State {
+            papers: vec![paper],
+            ..Default::default()
+        };
+        let mut other = state.papers[0].clone();
+        other.aliases.clear();
+        assert!(matches!(
+            state.check_unique(&other, &[]),
+            Err(Fallacy::PaperDuplicateNickname(..))
+        ));
+        other.nickname = None;
+        other.aliases = vec!["rn".to_owned()];
+        assert!(matches!(
+            state.check_unique(&other, &[]),
+            Err(Fallacy::PaperDuplicateAlias(..))
+        ));
+        state.papers[0].aliases.clear();
+        state.papers[0].nickname = None;
+        assert!(state.check_unique(&other, &[]).is_ok());
+    }
+
+    #[test]
+    fn extra_fields() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut paper = Paper::from_args(args(&[
+            "touch",
+            "E=mc2",
+            "by",
+            "Einstein",
+            "at",
+            "Annalen",
+            "in",
+            "1905",
+            "publisher=Wiley",
+            "pages=639-641",
+        ]))
+        .unwrap();
+        assert_eq!(paper.title, "E=mc2");
+        assert_eq!(paper.extra["pages"], "639-641");
+        paper
+            .apply_from_args(&args(&["volume=18", "pages="]))
+            .unwrap();
+        assert_eq!(
+            paper.extra.keys().collect::<Vec<_>>(),
+            vec!["publisher", "volume"]
+        );
+        assert!(paper.details(&State::default()).contains("Wiley"));
+        assert!(matches!(
+            paper.apply_from_args(&args(&["rating=5"])),
+            Err(Fallacy::PaperReservedField(_))
+        ));
+
+        // Some fields can be set as 'field=value' too.
+        paper
+            .apply_from_args(&args(&[
+                "year=1906",
+                "authors=Einstein, Albert; Grossmann",
+                "tags=physics, relativity",
+            ]))
+            .unwrap();
+        assert_eq!(paper.year, "1906");
+        assert_eq!(paper.authors, vec!["Einstein, Albert", "Grossmann"]);
+        assert_eq!(paper.labels.len(), 2);
+        assert!(matches!(
+            paper.apply_from_args(&args(&["year=next"])),
+            Err(Fallacy::PaperInvalidYear(..))
+        ));
+        assert!(matches!(
+            paper.apply_from_args(&args(&["in", "1907", "year=1908"])),
+            Err(Fallacy::PaperDuplicateField(_))
+        ));
+
+        let yaml = serde_yaml::to_string(&paper).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<Paper>(&yaml).unwrap().extra,
+            paper.extra
+        );
+    }
+
+    #[test]
+    fn bibtex_entries() {
+        let paper = Paper::from_bibtex(
+            r#"@article{goedel1931,
+              author = {G{\"o}del, Kurt and others},
+              title = "{\"U}ber formal unentscheidbare S{\"a}tze",
+              journal = {Monatshefte f{\"u}r Mathematik},
+              year = {1931},
+              volume = 38,
+              doi = {10.1007/BF01700692},
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(paper.title, "Über formal unentscheidbare Sätze");
+        assert_eq!(paper.authors, vec!["Kurt Gödel"]);
+        assert_eq!(paper.venue, "Monatshefte für Mathematik");
+        assert_eq!(paper.citekey.as_deref(), Some("goedel1931"));
+        assert_eq!(paper.extra["volume"], "38");
+
+        let arxiv = Paper::from_bibtex(
+            "@misc{vaswani2017, title={Attention Is All You Need}, author={Ashish Vaswani and Noam Shazeer}, year={2017}, eprint={1706.03762}}",
+        )
+        .unwrap();
+        assert_eq!(arxiv.venue, "arXiv");
+        assert_eq!(arxiv.authors.len(), 2);
+
+        for (bad, field) in [
+            (
+                "@inproceedings{x, title={T}, year={2020}, booktitle={B}}",
+                "author",
+            ),
+            (
+                "@inproceedings{x, title={T}, author={A}, booktitle={B}}",
+                "year",
+            ),
+            (
+                "@article{x, title={T}, author={A}, year={2020}}",
+                "booktitle",
+            ),
+        ] {
+            assert!(matches!(
+                Paper::from_bibtex(bad),
+                Err(Fallacy::BibtexMissingField(_, f)) if f == field
+            ));
+        }
+        assert!(matches!(
+            Paper::from_bibtex("@article{x, title=\"T, author={A}}"),
+            Err(Fallacy::BibtexInvalidField(f, _)) if f == "title"
+        ));
+        assert!(matches!(
+            Paper::from_bibtex(""),
+            Err(Fallacy::BibtexNotSingleEntry(0))
+        ));
+    }
+
+    #[test]
+    fn progress_serialization() {
+        use PartialProgress::*;
+        use ReadingProgress::*;
+        for progress in [
+            Unread,
+            InProgress(None),
+            InProgress(Some(Percent(45))),
+            InProgress(Some(Pages(12, Some(30)))),
+            InProgress(Some(Pages(3, None))),
+            Read,
+        ] {
+            let yaml = serde_yaml::to_string(&progress).unwrap();
+            assert_eq!(
+                serde_yaml::from_str::<ReadingProgress>(&yaml).unwrap(),
+                progress
+            );
+        }
+        // Progress without a partial value is stored as before.
+        assert_eq!(
+            serde_yaml::from_str::<ReadingProgress>("InProgress").unwrap(),
+            InProgress(None)
+        );
+        assert!(serde_yaml::to_string(&InProgress(None))
+            .unwrap()
+            .contains("InProgress"));
+
+        assert_eq!("45%".parse(), Ok(InProgress(Some(Percent(45)))));
+        assert_eq!("page 7".parse(), Ok(InProgress(Some(Pages(7, None)))));
+        assert_eq!("100%".parse(), Ok(Read));
+        assert_eq!("page 30/30".parse(), Ok(Read));
+        assert!("page 7/0".parse::<PartialProgress>().is_err());
+    }
+
+    #[test]
+    fn progress_history() {
+        let mut paper = Paper {
+            progress: ReadingProgress::Read,
+            read_at: parse_timestamp("2022-03-04T05:06:07+09:00"),
+            ..Default::default()
+        };
+        paper.synthesize_history();
+        assert_eq!(paper.history.len(), 1);
+        assert_eq!(paper.history[0].progress, ReadingProgress::Read);
+        assert_eq!(
+            paper.history[0].when,
+            DateTime::parse_from_rfc3339("2022-03-04T05:06:07+09:00").unwrap()
+        );
+        // Only once.
+        paper.synthesize_history();
+        assert_eq!(paper.history.len(), 1);
+
+        paper.set_progress(ReadingProgress::InProgress(None), None);
+        assert!(paper.read_at.is_none());
+        paper.set_progress(ReadingProgress::Read, Some("jw@laptop"));
+        assert!(paper.read_at.is_some());
+        let progress: Vec<_> = paper.history.iter().map(|c| c.progress).collect();
+        use ReadingProgress::*;
+        assert_eq!(progress, vec![Read, InProgress(None), Read]);
+        let details = paper.details(&State::default());
+        assert!(details.contains("history"));
+        assert!(details.contains("read (jw@laptop)"));
+
+        // Changes without provenance are stored as they used to be.
+        let stored = serde_yaml::to_string(&paper.history).unwrap();
+        let history: Vec<StatusChange> = serde_yaml::from_str(&stored).unwrap();
+        assert_eq!(history, paper.history);
+        let old: Vec<StatusChange> =
+            serde_yaml::from_str("- - Read\n  - \"2022-03-04T05:06:07+09:00\"\n").unwrap();
+        assert_eq!(old[0].progress, Read);
+        assert!(old[0].who.is_none());
+
+        // Unread papers without history have nothing to remember.
+        let mut paper = Paper::default();
+        paper.synthesize_history();
+        assert!(paper.history.is_empty());
+    }
+
+    #[test]
+    fn wiki_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.wiki_dir = dir.path().to_owned();
+        let mut paper = Paper {
+            title: "Reason".to_owned(),
+            nickname: Some("reason".to_owned()),
+            authors: vec!["Jaehoon Kim".to_owned(), "Jaehyeon Park".to_owned()],
+            venue: "OSDI".to_owned(),
+            year: "2022".to_owned(),
+            labels: HashSet::from(["shell".to_owned()]),
+            ..Default::default()
+        };
+
+        // The default template.
+        let wiki = paper.clone().wikipath(&config, true).unwrap().unwrap();
+        let text = std::fs::read_to_string(&wiki).unwrap();
+        assert!(text.starts_with("# Reason\n\n- Jaehoon Kim, Jaehyeon Park\n- OSDI 2022\n"));
+        assert!(text.contains("- Tags: shell\n") && text.contains("## Questions"));
+
+        // A template from the config.
+        let template = dir.path().join("template.txt");
+        std::fs::write(&template, "{{{nickname}}} {first_author}\n").unwrap();
+        config.storage.note_template = Some(template.clone());
+        let wiki = paper.wikipath(&config, true).unwrap().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&wiki).unwrap(),
+            "{reason} Jaehoon Kim\n"
+        );
+
+        // Existing wikis are kept.
+        std::fs::write(&wiki, "my notes").unwrap();
+        paper.wikipath(&config, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&wiki).unwrap(), "my notes");
+
+        // Markdown notes are named after the citation key, with front matter.
+        config.storage.note_format = NoteFormat::Markdown;
+        let mut markdown = paper.clone();
+        markdown.wikipath = None;
+        let wiki = markdown.wikipath(&config, true).unwrap().unwrap();
+        assert_eq!(wiki.file_name().unwrap(), "kim2022reason.md");
+        let text = std::fs::read_to_string(&wiki).unwrap();
+        assert!(
+            text.starts_with("---\ntitle: Reason\nauthors:\n"),
+            "{}",
+            text
+        );
+        assert!(text.contains("\nstatus: unread\n---\n\n{reason} Jaehoon Kim\n"));
+
+        std::fs::write(&template, "{summary}").unwrap();
+        assert!(matches!(
+            paper.clone().wiki_content(&config),
+            Err(Fallacy::PrintfUnknownPlaceholder(_))
+        ));
     }
 }
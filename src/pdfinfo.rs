@@ -0,0 +1,374 @@
+//! Guess the title, authors, and year of a paper from its PDF file.
+//! Metadata comes from the XMP packet and the information dictionary,
+//! and the title falls back to the first line of text on the first page.
+
+use std::path::Path;
+
+use pdf::content::Operation;
+use pdf::primitive::{PdfString, Primitive};
+use regex::Regex;
+
+use crate::utils::unescape_xml;
+
+/// Metadata guessed from a PDF. Anything that looked unusable is left out.
+#[derive(Debug, Default, PartialEq)]
+pub struct Guess {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+}
+
+/// Whether the file looks like a PDF, judging by its header.
+pub fn is_pdf(path: &Path) -> bool {
+    use std::io::Read;
+    let mut header = [0; 5];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && &header == b"%PDF-"
+}
+
+/// Guess metadata from the PDF at `path`. Never fails; a PDF that cannot be
+/// read just gives nothing.
+pub fn guess(path: &Path) -> Guess {
+    let data = std::fs::read(path).unwrap_or_default();
+    let xmp = xmp_packet(&data);
+    let file = pdf::file::File::open(path).ok();
+    let info = |field: &str| -> Option<String> {
+        match file.as_ref()?.trailer.info_dict.as_ref()?.get(field)? {
+            Primitive::String(s) => decode(s),
+            _ => None,
+        }
+    };
+
+    let title = xmp
+        .as_deref()
+        .and_then(|xmp| xmp_items(xmp, "title").into_iter().next())
+        .filter(|title| usable_title(title))
+        .or_else(|| info("Title").filter(|title| usable_title(title)))
+        .or_else(|| file.as_ref().and_then(first_line));
+
+    let mut authors = xmp
+        .as_deref()
+        .map(|xmp| xmp_items(xmp, "creator"))
+        .unwrap_or_default();
+    if authors.len() == 1 {
+        authors = split_authors(&authors[0]);
+    }
+    if authors.is_empty() {
+        authors = info("Author")
+            .map(|a| split_authors(&a))
+            .unwrap_or_default();
+    }
+
+    // Dates look like '2019-10-27T...' in XMP and 'D:20191027...' in the
+    // information dictionary.
+    let year = xmp
+        .as_deref()
+        .and_then(|xmp| xmp_value(xmp, "xmp:CreateDate"))
+        .or_else(|| info("CreationDate").map(|date| date.trim_start_matches("D:").to_owned()))
+        .map(|date| date.chars().take(4).collect::<String>())
+        .filter(|year| year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()));
+
+    Guess {
+        title,
+        authors,
+        year,
+    }
+}
+
+/// The XMP metadata packet in the file, if it is stored uncompressed, which
+/// is what the XMP specification recommends.
+fn xmp_packet(data: &[u8]) -> Option<String> {
+    let find = |needle: &[u8], from: usize| {
+        data[from..]
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .map(|pos| pos + from)
+    };
+    let start = find(b"<x:xmpmeta", 0)?;
+    let end = find(b"</x:xmpmeta>", start)?;
+    Some(String::from_utf8_lossy(&data[start..end]).into_owned())
+}
+
+/// The items of a Dublin Core property in XMP, like the list of creators.
+fn xmp_items(xmp: &str, property: &str) -> Vec<String> {
+    let property = Regex::new(&format!(r"(?s)<dc:{0}>(.*?)</dc:{0}>", property)).unwrap();
+    let item = Regex::new(r"(?s)<rdf:li[^>]*>(.*?)</rdf:li>").unwrap();
+    property
+        .captures(xmp)
+        .map(|captures| {
+            item.captures_iter(&captures[1])
+                .map(|item| clean(&unescape_xml(&item[1])))
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A simple XMP property, given either as an element or as an attribute.
+fn xmp_value(xmp: &str, property: &str) -> Option<String> {
+    let element = Regex::new(&format!(r"<{0}>([^<]*)</{0}>", property)).unwrap();
+    let attribute = Regex::new(&format!(r#"{}="([^"]*)""#, property)).unwrap();
+    element
+        .captures(xmp)
+        .or_else(|| attribute.captures(xmp))
+        .map(|captures| captures[1].trim().to_owned())
+}
+
+/// Split an author list like 'A. Author; B. Author' or 'A, B and C'.
+fn split_authors(authors: &str) -> Vec<String> {
+    let separators: &[&str] = if authors.contains(';') {
+        &[";"]
+    } else {
+        &[",", " and ", "&"]
+    };
+    let mut names = vec![authors.to_owned()];
+    for separator in separators {
+        names = names
+            .iter()
+            .flat_map(|name| name.split(separator))
+            .map(str::to_owned)
+            .collect();
+    }
+    names
+        .iter()
+        .map(|name| clean(name))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Whether a title from metadata is worth suggesting. Tools often fill in
+/// the name of the source file, or nothing meaningful.
+fn usable_title(title: &str) -> bool {
+    let lower = title.to_lowercase();
+    let junk_prefixes = ["microsoft word - ", "untitled", "arxiv:"];
+    let junk_suffixes = [".pdf", ".dvi", ".tex", ".doc", ".docx", ".ps"];
+    title.split_whitespace().count() >= 2
+        && title.chars().filter(|c| c.is_alphabetic()).count() >= 4
+        && !junk_prefixes.iter().any(|prefix| lower.starts_with(prefix))
+        && !junk_suffixes.iter().any(|suffix| lower.ends_with(suffix))
+}
+
+/// The first line of text on the first page, if its text can be read
+/// without knowing about fonts.
+fn first_line<B: pdf::backend::Backend>(file: &pdf::file::File<B>) -> Option<String> {
+    let page = file.get_page(0).ok()?;
+    let operations = &page.contents.as_ref()?.operations;
+    let line = text_lines(operations)
+        .into_iter()
+        .find(|line| !line.to_lowercase().starts_with("arxiv:"))?;
+    Some(line).filter(|line| usable_title(line))
+}
+
+/// Lines of text shown by content stream operations, until a string that
+/// cannot be decoded.
+fn text_lines(operations: &[Operation]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut end_line = |line: &mut String| {
+        let text = clean(line);
+        if !text.is_empty() {
+            lines.push(text);
+        }
+        line.clear();
+    };
+    'ops: for op in operations {
+        let operand = |i: usize| op.operands.get(i);
+        match op.operator.as_str() {
+            "Tj" | "'" | "\"" => {
+                if op.operator != "Tj" {
+                    end_line(&mut line);
+                }
+                match op.operands.last() {
+                    Some(Primitive::String(s)) => match decode_text(s) {
+                        Some(text) => line.push_str(&text),
+                        None => break 'ops,
+                    },
+                    _ => continue,
+                }
+            }
+            "TJ" => {
+                let parts = match operand(0) {
+                    Some(Primitive::Array(parts)) => parts,
+                    _ => continue,
+                };
+                for part in parts {
+                    match part {
+                        Primitive::String(s) => match decode_text(s) {
+                            Some(text) => line.push_str(&text),
+                            None => break 'ops,
+                        },
+                        // Large negative kerning is a space between words.
+                        Primitive::Integer(n) if *n < -200 => line.push(' '),
+                        Primitive::Number(n) if *n < -200.0 => line.push(' '),
+                        _ => {}
+                    }
+                }
+            }
+            // Moving to the side keeps the line, and moving down ends it.
+            "Td" | "TD" => match operand(1) {
+                Some(Primitive::Integer(0)) => line.push(' '),
+                Some(Primitive::Number(y)) if *y == 0.0 => line.push(' '),
+                _ => end_line(&mut line),
+            },
+            "T*" | "ET" => end_line(&mut line),
+            _ => {}
+        }
+    }
+    end_line(&mut line);
+    lines
+}
+
+/// Decode a string shown on the page. Only plain single-byte encodings are
+/// understood, with TeX's ligatures.
+fn decode_text(s: &PdfString) -> Option<String> {
+    let mut text = String::new();
+    for &b in s.as_bytes() {
+        match b {
+            0x0b => text.push_str("ff"),
+            0x0c => text.push_str("fi"),
+            0x0d => text.push_str("fl"),
+            0x0e => text.push_str("ffi"),
+            0x0f => text.push_str("ffl"),
+            0x20..=0x7e => text.push(b as char),
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// Decode a string in the information dictionary, which is UTF-16 with a
+/// byte order mark, or close enough to Latin-1.
+fn decode(s: &PdfString) -> Option<String> {
+    let text = match s.as_str() {
+        Ok(text) => text.into_owned(),
+        Err(_) => s.as_bytes().iter().map(|&b| b as char).collect(),
+    };
+    Some(clean(&text)).filter(|text| !text.is_empty())
+}
+
+/// Collapse whitespace.
+fn clean(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Write a single page PDF with the given information dictionary entries
+    /// and content stream.
+    fn write_pdf(path: &Path, info: &str, content: &str) {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_owned(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len() + 1,
+                content
+            ),
+            format!("<< {} >>", info),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .bytes(),
+        );
+        std::fs::write(path, pdf).unwrap();
+    }
+
+    #[test]
+    fn info_and_first_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paper.pdf");
+
+        // The information dictionary has a usable title and authors.
+        write_pdf(
+            &path,
+            "/Title (Reason: A Shell for Papers) /Author (Jae-Won Chung; Chaehyun Jeong) \
+             /CreationDate (D:20220315120000Z)",
+            "BT /F1 12 Tf (Something Else) Tj ET",
+        );
+        assert!(is_pdf(&path));
+        assert_eq!(
+            guess(&path),
+            Guess {
+                title: Some("Reason: A Shell for Papers".to_owned()),
+                authors: vec!["Jae-Won Chung".to_owned(), "Chaehyun Jeong".to_owned()],
+                year: Some("2022".to_owned()),
+            }
+        );
+
+        // A junk title falls back to the first line of text.
+        write_pdf(
+            &path,
+            "/Title (main.dvi)",
+            "BT /F1 7 Tf (arXiv:2203.01234v1 [cs.OS]) Tj ET \
+             BT /F1 17 Tf [(E) 30 (\\016cient) -333 (Shells)] TJ 0 -20 Td (for Papers) Tj ET",
+        );
+        let guessed = guess(&path);
+        assert_eq!(guessed.title.as_deref(), Some("Efficient Shells"));
+        assert!(guessed.authors.is_empty());
+        assert_eq!(guessed.year, None);
+
+        std::fs::write(&path, "not a pdf").unwrap();
+        assert!(!is_pdf(&path));
+        assert_eq!(guess(&path), Guess::default());
+    }
+
+    #[test]
+    fn xmp_metadata() {
+        let xmp = r#"<?xpacket begin=""?><x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF><rdf:Description xmp:CreateDate="2019-10-27T10:00:00Z">
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">PipeDream: Generalized
+  Pipeline Parallelism &amp; More</rdf:li></rdf:Alt></dc:title>
+<dc:creator><rdf:Seq><rdf:li>Deepak Narayanan</rdf:li><rdf:li>Aaron Harlap</rdf:li></rdf:Seq></dc:creator>
+</rdf:Description></rdf:RDF></x:xmpmeta><?xpacket end="w"?>"#;
+        let packet = xmp_packet(format!("%PDF-1.5 junk {} junk", xmp).as_bytes()).unwrap();
+        assert_eq!(
+            xmp_items(&packet, "title"),
+            vec!["PipeDream: Generalized Pipeline Parallelism & More"]
+        );
+        assert_eq!(
+            xmp_items(&packet, "creator"),
+            vec!["Deepak Narayanan", "Aaron Harlap"]
+        );
+        assert_eq!(
+            xmp_value(&packet, "xmp:CreateDate").as_deref(),
+            Some("2019-10-27T10:00:00Z")
+        );
+        assert_eq!(xmp_packet(b"%PDF-1.5 no metadata"), None);
+    }
+
+    #[test]
+    fn authors_and_titles() {
+        assert_eq!(
+            split_authors("Ann Lee, Bo Kim and Cy Park"),
+            vec!["Ann Lee", "Bo Kim", "Cy Park"]
+        );
+        assert_eq!(
+            split_authors("Lee, Ann; Kim, Bo"),
+            vec!["Lee, Ann", "Kim, Bo"]
+        );
+        assert!(usable_title("Attention Is All You Need"));
+        assert!(!usable_title("Microsoft Word - final.docx"));
+        assert!(!usable_title("paper.pdf"));
+        assert!(!usable_title("Untitled"));
+    }
+}
@@ -1,17 +1,19 @@
+use crate::config::FilterConfig;
 use crate::error::Fallacy;
-use crate::filter::PaperFilter;
+use crate::filter::{FilterExpr, SavedFilters};
+use crate::paper::Paper;
 
 pub enum FilterInst {
     /// cd something
     /// Add a new filter joined with AND.
-    Add(PaperFilter),
+    Add(FilterExpr),
     /// cd .
     /// Changes nothing, but `cd -` takes this into account.
     Here,
     /// cd ..
     /// Remove the most recent non-empty filter.
     Parent,
-    /// cd
+    /// cd, cd /
     /// Clear the filter history vector.
     Reset,
     /// cd -
@@ -23,11 +25,16 @@ impl FilterInst {
     /// Accepts filter arguments given to commands and builds an
     /// instance of `FilterInst`. Remove the command (first argument)
     /// and pass the rest to this function.
+    ///
+    /// `papers` is used to check that nicknames referred to with `@name` exist.
     pub fn from_args(
         args: &[String],
         reset_if_empty: bool,
-        case_insensitive: bool,
+        config: &FilterConfig,
+        saved: &SavedFilters,
+        papers: &[Paper],
     ) -> Result<Self, Fallacy> {
+        FilterExpr::check_nicknames(args, saved, papers)?;
         // No arguments given.
         if args.is_empty() {
             if reset_if_empty {
@@ -41,33 +48,43 @@ impl FilterInst {
             match args[0].as_ref() {
                 "." => Ok(Self::Here),
                 ".." => Ok(Self::Parent),
+                "/" => Ok(Self::Reset),
                 "-" => Ok(Self::Prev),
-                _ => Ok(Self::Add(PaperFilter::from_args(args, case_insensitive)?)),
+                _ => Ok(Self::Add(FilterExpr::from_args(args, config, saved)?)),
             }
         }
         // A normal filter.
         else {
-            Ok(Self::Add(PaperFilter::from_args(args, case_insensitive)?))
+            Ok(Self::Add(FilterExpr::from_args(args, config, saved)?))
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct FilterState {
-    history: Vec<PaperFilter>,
+    history: Vec<FilterExpr>,
     current: usize,
     previous: usize,
 }
 
 impl FilterState {
     /// Generate the current filter based on history.
-    pub fn current(&self) -> PaperFilter {
-        PaperFilter::merge(&self.history[..self.current + 1])
+    pub fn current(&self) -> FilterExpr {
+        FilterExpr::merge(&self.history[..self.current + 1])
+    }
+
+    /// The filters added with `cd` that make up the current filter,
+    /// from the outermost. Empty levels like `cd .` are left out.
+    pub fn stack(&self) -> Vec<&FilterExpr> {
+        self.history[1..self.current + 1]
+            .iter()
+            .filter(|filter| !filter.is_empty())
+            .collect()
     }
 
     /// Record the given filter instruction in history and generate
     /// the current filter based on the updated history.
-    pub fn record(&mut self, inst: FilterInst) -> PaperFilter {
+    pub fn record(&mut self, inst: FilterInst) -> FilterExpr {
         match inst {
             FilterInst::Add(filter) => {
                 self.previous = self.current;
@@ -81,7 +98,7 @@ impl FilterState {
             FilterInst::Here => {
                 self.previous = self.current;
                 self.current += 1;
-                let filter = PaperFilter::default();
+                let filter = FilterExpr::default();
                 if self.current == self.history.len() {
                     self.history.push(filter);
                 } else {
@@ -109,7 +126,7 @@ impl FilterState {
     /// Observe the given filter instruction but do not record in history.
     /// Return a filter that would have been generated if the instruction
     /// were recorded in history.
-    pub fn observe(&self, inst: FilterInst) -> PaperFilter {
+    pub fn observe(&self, inst: FilterInst) -> FilterExpr {
         self.clone().record(inst)
     }
 }
@@ -117,9 +134,64 @@ impl FilterState {
 impl Default for FilterState {
     fn default() -> Self {
         Self {
-            history: vec![PaperFilter::default()],
+            history: vec![FilterExpr::default()],
             current: 0,
             previous: 0,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+
+    fn cd(filters: &mut FilterState, args: &[&str]) -> FilterExpr {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let config = FilterConfig::default();
+        let inst = FilterInst::from_args(&args, true, &config, &SavedFilters::new(), &[]).unwrap();
+        filters.record(inst)
+    }
+
+    #[test]
+    fn nested_levels() {
+        let config = Config::default();
+        let paper = |labels: &[&str], venue: &str| Paper {
+            title: "Reason".to_owned(),
+            authors: vec!["Chung".to_owned()],
+            venue: venue.to_owned(),
+            labels: labels.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let systems_osdi = paper(&["systems"], "OSDI");
+        let systems_icpp = paper(&["systems"], "ICPP");
+        let ml_osdi = paper(&["ml"], "OSDI");
+
+        let mut filters = FilterState::default();
+        let current = cd(&mut filters, &["is", "systems"]);
+        assert!(current.matches(&systems_osdi, &config));
+        assert!(current.matches(&systems_icpp, &config));
+        assert!(!current.matches(&ml_osdi, &config));
+
+        // Nested levels are AND'ed together.
+        let current = cd(&mut filters, &["at", "OSDI"]);
+        assert!(current.matches(&systems_osdi, &config));
+        assert!(!current.matches(&systems_icpp, &config));
+        assert!(!current.matches(&ml_osdi, &config));
+        cd(&mut filters, &["."]);
+        let stack: Vec<_> = filters.stack().iter().map(|f| f.to_string()).collect();
+        assert_eq!(stack.len(), 2);
+        assert!(stack[0].contains("systems"), "{:?}", stack);
+        assert!(stack[1].contains("OSDI"), "{:?}", stack);
+
+        // `cd ..` pops the empty level and then the venue.
+        cd(&mut filters, &[".."]);
+        let current = cd(&mut filters, &[".."]);
+        assert!(current.matches(&systems_icpp, &config));
+        assert_eq!(filters.stack().len(), 1);
+
+        let current = cd(&mut filters, &[]);
+        assert!(current.matches(&ml_osdi, &config));
+        assert!(filters.stack().is_empty());
+    }
+}
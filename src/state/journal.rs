@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Fallacy;
+use crate::paper::Paper;
+
+/// How many changes `undo` can revert by default.
+pub const DEFAULT_UNDO_DEPTH: usize = 10;
+
+/// Papers as they were right before a command changed them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The command that changed the papers, like 'mark'.
+    pub command: String,
+    /// The papers with their indices in the paperbase at the time.
+    pub papers: Vec<(usize, Paper)>,
+}
+
+/// Snapshots of recent changes to papers, newest last.
+#[derive(Debug)]
+pub struct Journal {
+    snapshots: VecDeque<Snapshot>,
+    /// The most snapshots kept. Zero turns off undo.
+    depth: usize,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_DEPTH)
+    }
+}
+
+impl Journal {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            depth,
+        }
+    }
+
+    /// Load snapshots stored by an earlier session. A missing file is an
+    /// empty journal.
+    pub fn load(path: &Path, depth: usize) -> Result<Self, Fallacy> {
+        let mut journal = Self::new(depth);
+        if !path.exists() {
+            return Ok(journal);
+        }
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(e) => return Err(Fallacy::StateLoadFailed(path.to_owned(), e)),
+        };
+        let snapshots: Option<Vec<Snapshot>> = match serde_yaml::from_reader(file) {
+            Ok(snapshots) => snapshots,
+            Err(e) => return Err(Fallacy::StateDeserializeFailed(path.to_owned(), e)),
+        };
+        for snapshot in snapshots.unwrap_or_default() {
+            journal.push(snapshot);
+        }
+        Ok(journal)
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), Fallacy> {
+        let file = match File::create(path) {
+            Ok(f) => f,
+            Err(e) => return Err(Fallacy::StateStoreFailed(path.to_owned(), e)),
+        };
+        match serde_yaml::to_writer(file, &self.snapshots) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Fallacy::StateSerializeFailed(path.to_owned(), e)),
+        }
+    }
+
+    /// Remember a snapshot, forgetting the oldest one beyond the depth.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.depth == 0 {
+            return;
+        }
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.depth {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Take the most recent snapshot.
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.snapshots.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
@@ -1,16 +1,53 @@
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
+
 mod filter;
+mod journal;
+mod trash;
 pub use crate::state::filter::{FilterInst, FilterState};
+pub use crate::state::journal::{Journal, Snapshot, DEFAULT_UNDO_DEPTH};
+use crate::state::trash::TrashedPaper;
 
 use crate::error::Fallacy;
-use crate::paper::Paper;
+use crate::filter::SavedFilters;
+use crate::paper::{Paper, PaperList};
 
 #[derive(Default, Debug)]
 pub struct State {
     pub papers: Vec<Paper>,
     pub filters: FilterState,
+    pub saved_filters: SavedFilters,
+    /// The ID given to the next paper added. IDs are never reused.
+    pub next_id: u64,
+    /// Papers before recent changes, for `undo`.
+    pub journal: Journal,
+    /// Papers removed by `rm`, oldest first.
+    pub trash: Vec<TrashedPaper>,
+}
+
+/// The on-disk representation of `State`.
+/// Older versions of reason stored only the list of papers, which is
+/// still accepted when loading.
+#[derive(Serialize, Deserialize, Default)]
+struct StateFile {
+    papers: Vec<Paper>,
+    #[serde(default)]
+    saved_filters: SavedFilters,
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    trash: Vec<TrashedPaper>,
+}
+
+#[derive(Serialize)]
+struct StateFileRef<'s> {
+    papers: &'s [Paper],
+    saved_filters: &'s SavedFilters,
+    next_id: u64,
+    trash: &'s [TrashedPaper],
 }
 
 impl State {
@@ -23,11 +60,40 @@ impl State {
             };
 
             // Load state from the file.
-            match serde_yaml::from_reader(file) {
-                Ok(papers) => Ok(Self {
-                    papers,
-                    filters: FilterState::default(),
-                }),
+            let value: serde_yaml::Value = match serde_yaml::from_reader(file) {
+                Ok(value) => value,
+                Err(e) => return Err(Fallacy::StateDeserializeFailed(state_path.to_owned(), e)),
+            };
+            let stored = match value {
+                // An empty file.
+                serde_yaml::Value::Null => Ok(StateFile::default()),
+                // Legacy format: just the list of papers.
+                serde_yaml::Value::Sequence(_) => {
+                    serde_yaml::from_value(value).map(|papers| StateFile {
+                        papers,
+                        ..Default::default()
+                    })
+                }
+                _ => serde_yaml::from_value(value),
+            };
+            match stored {
+                Ok(mut stored) => {
+                    // Older files do not have reading progress history.
+                    for paper in stored.papers.iter_mut() {
+                        paper.synthesize_history();
+                    }
+                    let mut state = Self {
+                        papers: stored.papers,
+                        filters: FilterState::default(),
+                        saved_filters: stored.saved_filters,
+                        next_id: stored.next_id,
+                        journal: Journal::default(),
+                        trash: stored.trash,
+                    };
+                    state.assign_ids();
+                    state.resolve_citekeys();
+                    Ok(state)
+                }
                 Err(e) => Err(Fallacy::StateDeserializeFailed(state_path.to_owned(), e)),
             }
         } else {
@@ -47,11 +113,181 @@ impl State {
         }
     }
 
+    /// Fail if a paper not in `except` already has the DOI, the nickname, an
+    /// alias, or the stored citation key of `paper`. DOIs are compared
+    /// case-insensitively.
+    pub fn check_unique(&self, paper: &Paper, except: &[usize]) -> Result<(), Fallacy> {
+        for (ind, other) in self.papers.iter().enumerate() {
+            if except.contains(&ind) {
+                continue;
+            }
+            if let Some(nickname) = paper
+                .nickname
+                .as_ref()
+                .filter(|nickname| other.nicknames().any(|name| name == nickname.as_str()))
+            {
+                return Err(Fallacy::PaperDuplicateNickname(
+                    nickname.clone(),
+                    other.title.clone(),
+                ));
+            }
+            if let (Some(doi), Some(other_doi)) = (paper.doi.as_ref(), other.doi.as_ref()) {
+                if doi.eq_ignore_ascii_case(other_doi) {
+                    return Err(Fallacy::PaperDuplicateDoi(doi.clone(), other.title.clone()));
+                }
+            }
+            if let Some(alias) = paper
+                .aliases
+                .iter()
+                .find(|alias| other.nicknames().any(|name| name == alias.as_str()))
+            {
+                return Err(Fallacy::PaperDuplicateAlias(
+                    alias.clone(),
+                    other.title.clone(),
+                ));
+            }
+            if let (Some(key), Some(other_key)) = (paper.citekey.as_ref(), other.citekey.as_ref()) {
+                if key == other_key {
+                    return Err(Fallacy::PaperDuplicateCitekey(
+                        key.clone(),
+                        other.title.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Give every paper without a stored citation key a generated one that
+    /// is unique in the paperbase. Colliding keys get a suffix 'a', 'b', ...
+    /// in paperbase order, so the result only depends on the papers.
+    pub fn resolve_citekeys(&mut self) {
+        let mut taken: HashSet<String> = self
+            .papers
+            .iter()
+            .filter_map(|p| p.citekey.clone())
+            .collect();
+        for paper in self.papers.iter_mut() {
+            if paper.citekey.is_some() {
+                paper.resolved_citekey = None;
+                continue;
+            }
+            let base = paper.base_citekey();
+            let mut key = base.clone();
+            let mut suffix = 0;
+            while taken.contains(&key) {
+                suffix += 1;
+                key = format!("{}{}", base, alphabetic_suffix(suffix));
+            }
+            taken.insert(key.clone());
+            paper.resolved_citekey = Some(key);
+        }
+    }
+
+    /// Build a state with the given papers, giving each an ID.
+    #[cfg(test)]
+    pub fn from_papers(papers: Vec<Paper>) -> Self {
+        let mut state = Self {
+            papers,
+            ..Default::default()
+        };
+        state.assign_ids();
+        state
+    }
+
+    /// Give IDs to papers stored before papers had IDs, and make sure
+    /// `next_id` is larger than any ID in use, even in the trash. IDs start
+    /// from 1.
+    fn assign_ids(&mut self) {
+        let max_id = self
+            .papers
+            .iter()
+            .chain(self.trash.iter().map(|t| &t.paper))
+            .map(|paper| paper.id)
+            .max()
+            .unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        for paper in self.papers.iter_mut().filter(|paper| paper.id == 0) {
+            paper.id = self.next_id;
+            self.next_id += 1;
+        }
+    }
+
+    /// Add a paper with a new ID and return its index.
+    pub fn add_paper(&mut self, mut paper: Paper) -> usize {
+        self.next_id = self.next_id.max(1);
+        paper.id = self.next_id;
+        self.next_id += 1;
+        self.papers.push(paper);
+        self.papers.len() - 1
+    }
+
+    /// Find the current indices of the papers in the list. Fails if a paper
+    /// was removed since the list was made.
+    pub fn resolve(&self, list: &PaperList) -> Result<Vec<usize>, Fallacy> {
+        let indices: HashMap<u64, usize> = self
+            .papers
+            .iter()
+            .enumerate()
+            .map(|(ind, paper)| (paper.id, ind))
+            .collect();
+        list.0
+            .iter()
+            .map(|id| indices.get(id).copied().ok_or(Fallacy::PaperNotFound(*id)))
+            .collect()
+    }
+
+    /// Remember the papers at the indices before `command` changes them.
+    pub fn snapshot(&mut self, command: &str, indices: &[usize]) {
+        let papers = indices
+            .iter()
+            .map(|&ind| (ind, self.papers[ind].clone()))
+            .collect();
+        self.journal.push(Snapshot {
+            command: command.to_owned(),
+            papers,
+        });
+    }
+
+    /// Revert the most recent change remembered in the journal. Changed
+    /// papers are overwritten, and removed papers are put back where they
+    /// were, out of the trash. Returns the snapshot that was restored.
+    pub fn undo(&mut self) -> Option<Snapshot> {
+        let mut snapshot = self.journal.pop()?;
+        snapshot.papers.sort_by_key(|(ind, _)| *ind);
+        for (ind, paper) in snapshot.papers.iter() {
+            match self.papers.iter().position(|p| p.id == paper.id) {
+                Some(current) => self.papers[current] = paper.clone(),
+                None => {
+                    let ind = (*ind).min(self.papers.len());
+                    self.papers.insert(ind, paper.clone());
+                }
+            }
+        }
+        self.trash
+            .retain(|t| !snapshot.papers.iter().any(|(_, p)| p.id == t.paper.id));
+        Some(snapshot)
+    }
+
+    /// Make a paper list out of indices into `papers`.
+    pub fn paper_list(&self, indices: impl IntoIterator<Item = usize>) -> PaperList {
+        PaperList(indices.into_iter().map(|ind| self.papers[ind].id).collect())
+    }
+
+    fn as_file(&self) -> StateFileRef<'_> {
+        StateFileRef {
+            papers: &self.papers,
+            saved_filters: &self.saved_filters,
+            next_id: self.next_id,
+            trash: &self.trash,
+        }
+    }
+
     pub fn store(&self, state_path: &Path) -> Result<(), Fallacy> {
         let emergency_button = |state: &State| {
             eprintln!("Could not save state. Dumping to stderr!");
             eprintln!("== Debug string ==\n{:#?}\n", state);
-            match serde_yaml::to_string(&state.papers) {
+            match serde_yaml::to_string(&state.as_file()) {
                 Ok(s) => eprintln!("== Serialized string ==\n{}", s),
                 Err(e) => eprintln!("== Serialization error ==\n{}", e),
             }
@@ -72,7 +308,7 @@ impl State {
         };
 
         // Store state into the file.
-        match serde_yaml::to_writer(file, &self.papers) {
+        match serde_yaml::to_writer(file, &self.as_file()) {
             Ok(()) => Ok(()),
             Err(e) => {
                 emergency_button(self);
@@ -81,3 +317,139 @@ impl State {
         }
     }
 }
+
+/// Suffixes for colliding citation keys: a, b, ..., z, aa, ab, ...
+fn alphabetic_suffix(mut n: usize) -> String {
+    let mut suffix = Vec::new();
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn load_legacy_and_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.yaml");
+
+        // Legacy format: a bare list of papers.
+        std::fs::write(
+            &path,
+            "---\n- title: Reason\n  nickname: ~\n  authors: [Chung]\n  venue: OSDI\n  year: \"2022\"\n  filepath: ~\n  labels: []\n  notepath: ~\n  wikipath: ~\n  progress: Read\n",
+        )
+        .unwrap();
+        let mut state = State::load(&path).unwrap();
+        assert_eq!(state.papers.len(), 1);
+        assert_eq!(state.papers[0].history.len(), 1);
+        assert_eq!(state.papers[0].id, 1);
+        assert!(state.saved_filters.is_empty());
+
+        state
+            .saved_filters
+            .insert("mine".to_owned(), vec!["by".to_owned(), "Chung".to_owned()]);
+        state.store(&path).unwrap();
+        let state = State::load(&path).unwrap();
+        assert_eq!(state.papers[0].title, "Reason");
+        assert_eq!(state.papers[0].history.len(), 1);
+        assert_eq!((state.papers[0].id, state.next_id), (1, 2));
+        assert_eq!(state.saved_filters["mine"], vec!["by", "Chung"]);
+    }
+
+    #[test]
+    fn migrate_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.yaml");
+
+        // Timestamps as written by earlier versions of reason.
+        std::fs::write(
+            &path,
+            "---\npapers:\n- title: Reason\n  authors: [Chung]\n  venue: OSDI\n  year: \"2022\"\n  labels: []\n  progress: Read\n  added: \"2022-03-04 05:06:07.123456 +09:00\"\n  read_at: \"2022-03-05T00:00:00+09:00\"\n- title: Shadowtutor\n  authors: [Chung]\n  venue: ICPP\n  year: \"2020\"\n  labels: []\n  added: sometime last winter\n",
+        )
+        .unwrap();
+        let state = State::load(&path).unwrap();
+        let added = state.papers[0].added.unwrap();
+        assert_eq!(
+            added,
+            chrono::DateTime::parse_from_rfc3339("2022-03-04T05:06:07.123456+09:00").unwrap()
+        );
+        assert!(state.papers[0].read_at.unwrap() > added);
+        assert!(state.papers[1].added.is_none());
+
+        // Stored back as RFC 3339.
+        state.store(&path).unwrap();
+        let stored = std::fs::read_to_string(&path).unwrap();
+        assert!(!stored.contains(" +09:00"));
+        let state = State::load(&path).unwrap();
+        assert_eq!(state.papers[0].added, Some(added));
+    }
+
+    #[test]
+    fn citekeys() {
+        let paper = |title: &str, author: &str| Paper {
+            title: title.to_owned(),
+            authors: vec![author.to_owned()],
+            year: "2021".to_owned(),
+            ..Default::default()
+        };
+        let mut state = State {
+            papers: vec![
+                paper("Efficient Training", "John Smith"),
+                paper("The Efficient Kernel", "Smith, Jane"),
+                paper("Efficient-Inference: Faster", "Ann Smith"),
+                paper("Reason: A Shell", "Jae-Won Chung"),
+            ],
+            ..Default::default()
+        };
+        let keys = |state: &mut State| {
+            state.resolve_citekeys();
+            state.papers.iter().map(Paper::citekey).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(&mut state),
+            vec![
+                "smith2021efficient",
+                "smith2021efficienta",
+                "smith2021efficientb",
+                "chung2021reason"
+            ]
+        );
+
+        // Renaming the first author hands the key over deterministically.
+        state.papers[0].authors = vec!["Kim".to_owned()];
+        assert_eq!(
+            keys(&mut state)[..3],
+            [
+                "kim2021efficient",
+                "smith2021efficient",
+                "smith2021efficienta"
+            ]
+        );
+
+        // Stored keys take precedence and must be unique.
+        state.papers[3].citekey = Some("smith2021efficient".to_owned());
+        assert_eq!(
+            keys(&mut state)[1..],
+            [
+                "smith2021efficienta",
+                "smith2021efficientb",
+                "smith2021efficient"
+            ]
+        );
+        let mut dup = paper("Other", "Lee");
+        dup.citekey = Some("smith2021efficient".to_owned());
+        assert!(matches!(
+            state.check_unique(&dup, &[]),
+            Err(Fallacy::PaperDuplicateCitekey(..))
+        ));
+        assert!(state.check_unique(&dup, &[3]).is_ok());
+        assert!(Paper::parse_citekey("has space").is_err());
+        assert_eq!(alphabetic_suffix(27), "aa");
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Fallacy;
+use crate::paper::Paper;
+use crate::state::State;
+
+/// A paper removed by `rm`, kept until the trash is emptied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedPaper {
+    pub paper: Paper,
+    /// When the paper was removed. Papers removed together share this.
+    pub removed_at: DateTime<Local>,
+}
+
+impl State {
+    /// Move the papers at the indices to the trash, and drop links to them
+    /// from other papers. Trashed papers keep their own links.
+    pub fn trash(&mut self, indices: &[usize]) {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        // Remove papers from the back so that indices stay valid.
+        let removed_at = Local::now();
+        let mut removed = Vec::with_capacity(indices.len());
+        for ind in indices.into_iter().rev() {
+            removed.push(self.papers.remove(ind));
+        }
+        for paper in self.papers.iter_mut() {
+            paper
+                .related
+                .retain(|id| !removed.iter().any(|removed| removed.id == *id));
+        }
+        self.trash.extend(
+            removed
+                .into_iter()
+                .rev()
+                .map(|paper| TrashedPaper { paper, removed_at }),
+        );
+    }
+
+    /// Put the papers trashed most recently back at the end of the
+    /// paperbase, along with links to them, and return their indices. Fails
+    /// without changing anything if they clash with papers added since.
+    pub fn restore_trash(&mut self) -> Result<Vec<usize>, Fallacy> {
+        let latest = match self.trash.iter().map(|t| t.removed_at).max() {
+            Some(latest) => latest,
+            None => return Ok(Vec::new()),
+        };
+        let (restored, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.trash)
+            .into_iter()
+            .partition(|t| t.removed_at == latest);
+        self.trash = kept;
+        if let Some(e) = restored
+            .iter()
+            .find_map(|t| self.check_unique(&t.paper, &[]).err())
+        {
+            self.trash.extend(restored);
+            return Err(e);
+        }
+
+        let first = self.papers.len();
+        self.papers.extend(restored.into_iter().map(|t| t.paper));
+        for ind in first..self.papers.len() {
+            let id = self.papers[ind].id;
+            for related in self.papers[ind].related.clone() {
+                if let Some(other) = self.papers.iter_mut().find(|p| p.id == related) {
+                    if !other.related.contains(&id) {
+                        other.related.push(id);
+                    }
+                }
+            }
+        }
+        Ok((first..self.papers.len()).collect())
+    }
+
+    /// Forget every trashed paper and return them, so that their files and
+    /// notes can be deleted.
+    pub fn empty_trash(&mut self) -> Vec<TrashedPaper> {
+        std::mem::take(&mut self.trash)
+    }
+}
@@ -0,0 +1,137 @@
+//! Templates with `{field}` placeholders that are filled in per paper, as
+//! used by `printf` and for new wiki pages.
+use crate::error::Fallacy;
+use crate::paper::Paper;
+use crate::utils::humanize_seconds;
+
+/// Placeholders that templates may use. `extra.<name>` gives extra metadata.
+const PLACEHOLDERS: [&str; 26] = [
+    "id",
+    "title",
+    "nickname",
+    "aliases",
+    "authors",
+    "first_author",
+    "venue",
+    "year",
+    "files",
+    "tags",
+    "labels",
+    "doi",
+    "citekey",
+    "key",
+    "url",
+    "abstract",
+    "rating",
+    "priority",
+    "due",
+    "citations",
+    "status",
+    "state",
+    "added",
+    "read_at",
+    "reading_time",
+    "related",
+];
+
+/// A part of a template: literal text or a placeholder.
+#[derive(Debug, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Field(String),
+}
+
+/// Split a template into text and placeholders, checking placeholder names
+/// so that errors show up before any paper is printed.
+pub fn parse(template: &str) -> Result<Vec<Piece>, Fallacy> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => {
+                            return Err(Fallacy::PrintfInvalidTemplate(format!(
+                                "'{{{}' is not closed",
+                                name
+                            )))
+                        }
+                        Some(c) => name.push(c),
+                    }
+                }
+                let name = name.trim().to_owned();
+                let extra = name.strip_prefix("extra.").is_some_and(|n| !n.is_empty());
+                if !extra && !PLACEHOLDERS.contains(&name.as_str()) {
+                    return Err(Fallacy::PrintfUnknownPlaceholder(name));
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Field(name));
+            }
+            '}' => {
+                return Err(Fallacy::PrintfInvalidTemplate(
+                    "'}' is not opened".to_owned(),
+                ))
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Fill in the placeholders of a parsed template with fields of the paper.
+pub fn expand(template: &[Piece], paper: &Paper) -> String {
+    let mut ret = String::new();
+    for piece in template {
+        match piece {
+            Piece::Text(text) => ret.push_str(text),
+            Piece::Field(name) => ret.push_str(&placeholder(paper, name)),
+        }
+    }
+    ret
+}
+
+/// The value of a placeholder, or an empty string if the paper lacks it.
+fn placeholder(paper: &Paper, name: &str) -> String {
+    let join = |items: Vec<String>| items.join(", ");
+    match name {
+        "id" => paper.id.to_string(),
+        "aliases" => paper.aliases.join(", "),
+        "files" => join(
+            paper
+                .files
+                .iter()
+                .map(|f| f.display().to_string())
+                .collect(),
+        ),
+        "citekey" => paper.citekey(),
+        "abstract" => paper.abstract_text.clone().unwrap_or_default(),
+        "status" => paper.progress.to_string(),
+        "read_at" => paper
+            .read_at
+            .map(|read_at| read_at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        "reading_time" => humanize_seconds(paper.reading_seconds),
+        "related" => join(paper.related.iter().map(u64::to_string).collect()),
+        _ => match name.strip_prefix("extra.") {
+            Some(key) => paper.extra.get(key).cloned().unwrap_or_default(),
+            None => paper.field_as_string(name),
+        },
+    }
+}
@@ -0,0 +1,304 @@
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::error::Fallacy;
+
+pub mod spawn;
+
+/// Expands the leading tilde (~) in the given `PathBuf` with the
+/// current user's home directory.
+pub fn expand_tilde(path: &Path) -> Result<PathBuf, Fallacy> {
+    if !path.starts_with("~") {
+        return Ok(path.to_path_buf());
+    }
+
+    let path_str = match path.to_str() {
+        Some(string) => string,
+        None => return Err(Fallacy::PathInvalidUTF8(path.to_path_buf())),
+    };
+
+    match home::home_dir() {
+        Some(mut home) => {
+            // Filter out '~' and '~/'.
+            if path_str.len() > 2 {
+                home.push(&path_str[2..]);
+            }
+            Ok(home)
+        }
+        None => Err(Fallacy::Homeless),
+    }
+}
+
+/// Expands the leading tilde (~) in the given `String` with the
+/// current user's home directory.
+pub fn expand_tilde_str(path: &str) -> Result<String, Fallacy> {
+    if !path.starts_with('~') {
+        return Ok(path.to_string());
+    }
+
+    match home::home_dir() {
+        Some(mut home) => {
+            // Filter out '~' and '~/'.
+            if path.len() >= 2 {
+                home.push(&path[2..]);
+            }
+            match home.to_str() {
+                Some(string) => Ok(string.to_string()),
+                None => Err(Fallacy::PathInvalidUTF8(home.clone())),
+            }
+        }
+        None => Err(Fallacy::Homeless),
+    }
+}
+
+/// Ask the user to input something.
+/// Automatically appends ": " to the prompt string.
+pub fn ask_for(prompt: &str, default: Option<String>) -> Result<String, Fallacy> {
+    let default_value = default.clone().unwrap_or_default();
+    Ok(ask_for_line(prompt, default)?.unwrap_or(default_value))
+}
+
+/// Same as `ask_for`, but `None` if the input has ended, so that callers
+/// asking again do not ask forever.
+pub fn ask_for_line(prompt: &str, default: Option<String>) -> Result<Option<String>, Fallacy> {
+    // Ask.
+    match default {
+        Some(ref value) => print!("{} (\"{}\"): ", prompt, value),
+        None => print!("{}: ", prompt),
+    };
+    stdout().flush()?;
+
+    // Get input.
+    let mut buffer = String::new();
+    if stdin().read_line(&mut buffer)? == 0 {
+        return Ok(None);
+    }
+    if buffer.trim().is_empty() {
+        if let Some(value) = default {
+            buffer = value;
+        }
+    }
+
+    Ok(Some(buffer.trim().to_string()))
+}
+
+/// Ask confirmation to the user.
+/// Fails right away if stdin is not a terminal, like in scripts.
+pub fn confirm(prompt: String, default: bool) -> Result<(), Fallacy> {
+    if !stdin().is_terminal() {
+        return Err(Fallacy::ConfirmWithoutTerminal(prompt));
+    }
+
+    // Ask.
+    let yn = if default { " [Y/n] " } else { " [y/N] " };
+    print!("{}", prompt + yn);
+    stdout().flush()?;
+
+    // Get input.
+    let mut buffer = String::new();
+    stdin().read_line(&mut buffer)?;
+
+    let default = if default {
+        Ok(())
+    } else {
+        Err(Fallacy::FailedUserInteraction("".to_owned()))
+    };
+
+    match buffer.to_ascii_lowercase().trim() {
+        "y" | "yes" => Ok(()),
+        "n" | "no" => Err(Fallacy::FailedUserInteraction("".to_owned())),
+        "" => default,
+        _ => Err(Fallacy::FailedUserInteraction("Invalid input.".to_owned())),
+    }
+}
+
+/// Take a `-y` or `yes` argument right after the command, which answers
+/// confirmation prompts with yes.
+pub fn take_yes(args: &mut Vec<String>) -> bool {
+    if matches!(args.get(1).map(String::as_str), Some("-y") | Some("yes")) {
+        args.remove(1);
+        true
+    } else {
+        false
+    }
+}
+
+/// Take a `dry-run` argument right after the command and `-y`, which
+/// previews a change without making it.
+pub fn take_dry_run(args: &mut Vec<String>) -> bool {
+    if matches!(
+        args.get(1).map(String::as_str),
+        Some("dry-run") | Some("--dry-run")
+    ) {
+        args.remove(1);
+        true
+    } else {
+        false
+    }
+}
+
+/// Ask the user to select among candidates.
+pub fn select<'i, I>(prompt: &str, candidate: I) -> Result<usize, Fallacy>
+where
+    I: Iterator<Item = &'i str>,
+{
+    // Ask.
+    print!("{}", prompt);
+    let mut len = 0;
+    for (index, cand) in candidate.enumerate() {
+        len += 1;
+        print!(" {}) {}.", index, cand);
+    }
+    print!("\n: ");
+    stdout().flush()?;
+
+    // Get input.
+    let mut buffer = String::new();
+    stdin().read_line(&mut buffer)?;
+
+    match buffer.trim().parse() {
+        Ok(num) => {
+            if num >= len {
+                Err(Fallacy::FailedUserInteraction(
+                    "Input out of range.".to_owned(),
+                ))
+            } else {
+                Ok(num)
+            }
+        }
+        Err(e) => Err(Fallacy::FailedUserInteraction(e.to_string())),
+    }
+}
+
+/// Score how well `needle` approximately appears somewhere in `haystack`,
+/// from 0 (nothing in common) to 1 (exact substring).
+/// The score is one minus the smallest edit distance between `needle` and
+/// any substring of `haystack`, normalized by the length of `needle`.
+pub fn fuzzy_score(needle: &str, haystack: &str) -> f64 {
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() {
+        return 1.0;
+    }
+    // dist[i] is the edit distance between needle[..i] and the best
+    // substring of haystack that ends at the current position.
+    let mut dist: Vec<usize> = (0..=needle.len()).collect();
+    let mut best = dist[needle.len()];
+    for h in haystack.chars() {
+        let mut prev_diag = dist[0];
+        dist[0] = 0;
+        for i in 1..=needle.len() {
+            let substitute = prev_diag + (needle[i - 1] != h) as usize;
+            prev_diag = dist[i];
+            dist[i] = substitute.min(dist[i] + 1).min(dist[i - 1] + 1);
+        }
+        best = best.min(dist[needle.len()]);
+    }
+    1.0 - best as f64 / needle.len() as f64
+}
+
+/// Replace XML entities like `&amp;` and `&#233;` with their characters.
+pub fn unescape_xml(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Generate an appropriate filename from a papaer title.
+/// Remove all non-alphanumeric characters and replace whitespaces to hyphens.
+pub fn as_filename(title: &str) -> String {
+    title
+        .replace(|c: char| c.is_whitespace(), "-")
+        .replace(|c: char| c != '-' && !c.is_ascii_alphanumeric(), "")
+}
+
+/// Append hyphen numbers at the end of the file path to find a path
+/// that doesn't already exist in the filesystem.
+pub fn make_unique_path(dir: &Path, name: &str, ext: &str) -> PathBuf {
+    let mut attempt = 0;
+    loop {
+        let mut path = PathBuf::from(dir);
+        let mut filename = name.to_owned();
+        if attempt == 0 {
+            filename.push_str(ext);
+        } else {
+            filename.push_str(&format!("-{}{}", attempt, ext));
+        }
+        path.push(&filename);
+
+        if !path.exists() {
+            return path;
+        } else {
+            attempt += 1;
+        }
+    }
+}
+
+/// Today's date in the local timezone.
+pub fn today() -> NaiveDate {
+    Local::now().naive_local().date()
+}
+
+/// Parse a timestamp written by older versions of reason.
+/// Accepts RFC 3339 as well as the formats produced by chrono's `Display`
+/// implementations and plain dates. Returns `None` if nothing matches.
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Local>> {
+    let timestamp = timestamp.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(datetime.with_timezone(&Local));
+    }
+    if let Ok(datetime) = DateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f %:z") {
+        return Some(datetime.with_timezone(&Local));
+    }
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .and_then(|datetime| Local.from_local_datetime(&datetime).earliest())
+}
+
+/// Format a number of seconds like '45s', '12m', or '3h 05m'.
+pub fn humanize_seconds(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
@@ -0,0 +1,232 @@
+//! Running the viewer, editor, and browser programs that commands open
+//! papers with.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::{Config, NoteFormat};
+use crate::error::Fallacy;
+
+/// Spawn the command, waiting for it to exit if `block` is set. Failures
+/// are reported rather than returned, and `false` is returned.
+pub fn spawn(mut command: Command, block: bool) -> bool {
+    match command.spawn() {
+        Ok(mut handle) => {
+            if block {
+                if let Err(e) = handle.wait() {
+                    println!("Failed to wait subprocess: {}", e);
+                }
+            }
+            true
+        }
+        Err(e) => {
+            report_spawn_error(e);
+            false
+        }
+    }
+}
+
+pub fn report_spawn_error(e: std::io::Error) {
+    if matches!(e.kind(), std::io::ErrorKind::NotFound) {
+        println!("Invalid viewer, editor, or browser command: '{:?}'", e);
+    } else {
+        println!("Failed to spawn subprocess: '{:?}'", e);
+    }
+}
+
+/// The command with '{}' replaced by the arguments, or with them appended
+/// if there is no '{}'.
+fn substitute<S: AsRef<std::ffi::OsStr>>(command: &[String], args: &[S]) -> Command {
+    let mut ret = Command::new(&command[0]);
+    let mut curly = false;
+    for part in &command[1..] {
+        if part == "{}" {
+            ret.args(args);
+            curly = true;
+        } else {
+            ret.arg(part);
+        }
+    }
+    if !curly {
+        ret.args(args);
+    }
+    ret
+}
+
+/// The pager, from config, `$PAGER`, or `less -R` in that order.
+pub fn pager_command(config: &Config) -> Command {
+    let mut command = config.output.pager_command.clone();
+    if command.is_empty() {
+        let pager = std::env::var("PAGER").unwrap_or_default();
+        command = pager.split_whitespace().map(str::to_owned).collect();
+    }
+    if command.is_empty() {
+        command = vec!["less".to_owned(), "-R".to_owned()];
+    }
+    let mut ret = Command::new(&command[0]);
+    ret.args(&command[1..]);
+    ret
+}
+
+/// Show the text through the pager and wait for it to exit. Returns
+/// `false` without printing anything if the pager could not be spawned.
+pub fn page(text: &str, config: &Config) -> bool {
+    let mut command = pager_command(config);
+    let mut handle = match command.stdin(Stdio::piped()).spawn() {
+        Ok(handle) => handle,
+        Err(_) => return false,
+    };
+    if let Some(mut stdin) = handle.stdin.take() {
+        // The pager may quit before reading everything.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    if let Err(e) = handle.wait() {
+        println!("Failed to wait subprocess: {}", e);
+    }
+    true
+}
+
+/// The viewer, which runs in the background without a terminal.
+pub fn viewer_command(viewer: &[String], files: &[&Path]) -> Command {
+    let mut ret = substitute(viewer, files);
+    ret.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    ret
+}
+
+/// The browser opening a URL or a local file, like the book of `printf`.
+pub fn browser_command<S: AsRef<std::ffi::OsStr>>(target: S, config: &Config) -> Command {
+    let mut ret = substitute(&config.output.browser_command, &[target]);
+    ret.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    ret
+}
+
+pub fn editor_command(notes: &[PathBuf], config: &Config) -> Command {
+    let command = &config.output.editor_command;
+    let mut ret = Command::new(&command[0]);
+    ret.args(&command[1..]).args(notes);
+    ret
+}
+
+/// The editor with VimWiki told where the wikis are. Arguments are passed
+/// to the editor as they are, so only the Vim string needs escaping.
+/// Markdown notes, and editors other than Vim, get the wikis alone.
+pub fn vimwiki_command(wikis: &[PathBuf], config: &Config) -> Result<Command, Fallacy> {
+    let command = &config.output.editor_command;
+    if config.storage.note_format == NoteFormat::Markdown || !is_vim(&command[0]) {
+        return Ok(editor_command(wikis, config));
+    }
+    let wiki_dir = &config.storage.wiki_dir;
+    let wiki_dir = wiki_dir
+        .to_str()
+        .ok_or_else(|| Fallacy::PathInvalidUTF8(wiki_dir.clone()))?;
+
+    // Single quotes are doubled in single-quoted Vim strings.
+    let register = format!(
+        "call add(g:vimwiki_list, {{'path': '{}'}})",
+        wiki_dir.replace('\'', "''")
+    );
+
+    let mut ret = Command::new(&command[0]);
+    ret.args(&command[1..]).arg("-c").arg(register).args(wikis);
+    Ok(ret)
+}
+
+/// Whether the program is Vim or Neovim, like '/usr/bin/nvim' or 'gvim'.
+fn is_vim(program: &str) -> bool {
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    ["vim", "nvim", "gvim", "mvim", "vimx", "nvim-qt"].contains(&name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vimwiki_arguments() {
+        let mut config = Config::default();
+        config.storage.wiki_dir = PathBuf::from("/home/me/my wiki's");
+        let wikis = [PathBuf::from("/home/me/my wiki's/a b.wiki")];
+        let command = vimwiki_command(&wikis, &config).unwrap();
+        assert_eq!(command.get_program(), "vim");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec![
+                "-p",
+                "-c",
+                "call add(g:vimwiki_list, {'path': '/home/me/my wiki''s'})",
+                "/home/me/my wiki's/a b.wiki",
+            ]
+        );
+    }
+
+    #[test]
+    fn editors() {
+        let args = |editor: &[&str]| {
+            let mut config = Config::default();
+            config.storage.wiki_dir = PathBuf::from("/wiki");
+            config.output.editor_command = editor.iter().map(|s| s.to_string()).collect();
+            let command = vimwiki_command(&[PathBuf::from("a.wiki")], &config).unwrap();
+            command
+                .get_args()
+                .map(|arg| arg.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let register = "call add(g:vimwiki_list, {'path': '/wiki'})";
+        assert_eq!(args(&["nvim"]), vec!["-c", register, "a.wiki"]);
+        assert_eq!(
+            args(&["/usr/bin/gvim", "-f"]),
+            vec!["-f", "-c", register, "a.wiki"]
+        );
+        assert_eq!(args(&["code", "--wait"]), vec!["--wait", "a.wiki"]);
+        assert_eq!(args(&["emacs"]), vec!["a.wiki"]);
+        assert_eq!(args(&["nano"]), vec!["a.wiki"]);
+        assert_eq!(args(&["vimdiff-ish"]), vec!["a.wiki"]);
+    }
+
+    #[test]
+    fn pager() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let mut config = Config::default();
+        config.output.pager_command = vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            "cat > \"$0\"".to_owned(),
+            out.to_str().unwrap().to_owned(),
+        ];
+        assert!(page("Usage: ls\n", &config));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "Usage: ls\n");
+
+        config.output.pager_command = vec!["reason-no-such-pager".to_owned()];
+        assert!(!page("Usage: ls\n", &config));
+    }
+
+    #[test]
+    fn markdown_arguments() {
+        let mut config = Config::default();
+        config.storage.note_format = NoteFormat::Markdown;
+        let command = vimwiki_command(&[PathBuf::from("a b.md")], &config).unwrap();
+        assert_eq!(command.get_args().collect::<Vec<_>>(), vec!["-p", "a b.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn vimwiki_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut config = Config::default();
+        config.storage.wiki_dir = PathBuf::from(OsStr::from_bytes(b"/wiki\xff"));
+        assert!(matches!(
+            vimwiki_command(&[], &config),
+            Err(Fallacy::PathInvalidUTF8(_))
+        ));
+    }
+}